@@ -78,9 +78,10 @@ pub async fn compile_main(args: CompileArgs) -> Result<()> {
         std::process::exit(1);
     }
 
-    // Exports the compiled project
+    // Exports the compiled project, which doesn't leave export manifests in the
+    // project like `typst compile`.
     let lock_dir = save_lock.then_some(lock_dir);
-    ExportTask::do_export(output.task, compiled, lock_dir).await?;
+    ExportTask::do_export(output.task, compiled, lock_dir, false).await?;
 
     Ok(())
 }
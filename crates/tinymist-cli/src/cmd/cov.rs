@@ -1,9 +1,12 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use reflexo_typst::TypstPagedDocument;
 use tinymist::CompileOnceArgs;
+use tinymist::project::LspWorld;
+use tinymist_debug::CoverageResult;
 use tinymist_project::WorldProvider;
 use tinymist_std::error::prelude::*;
+use tinymist_std::fs::paths::write_atomic;
 
 use crate::print_diag_or_error;
 
@@ -13,6 +16,35 @@ pub struct CovArgs {
     /// The argument to compile once.
     #[clap(flatten)]
     pub compile: CompileOnceArgs,
+
+    /// The format of the coverage report.
+    #[clap(long, default_value = "json")]
+    pub format: CovFormat,
+}
+
+/// The format of a coverage report.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum CovFormat {
+    /// The JSON format consumed by VS Cod(e,ium), written to
+    /// `target/coverage.json` under the project root.
+    Json,
+    /// The LCOV tracefile format, written to `target/lcov.info` under the
+    /// project root.
+    Lcov,
+    /// The Cobertura XML format, written to `target/cobertura.xml` under the
+    /// project root.
+    Cobertura,
+}
+
+impl CovFormat {
+    /// The path to write the coverage report to under the project `root`.
+    pub fn path(self, root: &Path) -> PathBuf {
+        root.join(match self {
+            CovFormat::Json => "target/coverage.json",
+            CovFormat::Lcov => "target/lcov.info",
+            CovFormat::Cobertura => "target/cobertura.xml",
+        })
+    }
 }
 
 /// Runs coverage test on a document
@@ -21,16 +53,39 @@ pub fn cov_main(args: CovArgs) -> Result<()> {
     let universe = args.compile.resolve()?;
     let world = universe.snapshot();
 
+    let root = universe.entry_state().root().map(Ok);
+    let root = root
+        .unwrap_or_else(|| std::env::current_dir().map(|p| p.into()))
+        .context("cannot find root")?;
+
     let result = Ok(()).and_then(|_| -> Result<()> {
         let res = tinymist_debug::collect_coverage::<TypstPagedDocument, _>(&world)?;
-        let cov_path = Path::new("target/coverage.json");
-        let res = serde_json::to_string(&res.to_json(&world)).context("coverage")?;
-
-        std::fs::create_dir_all(cov_path.parent().context("parent")?).context("create coverage")?;
-        std::fs::write(cov_path, res).context("write coverage")?;
+        write_coverage(&world, &res, args.format, &root)?;
 
         Ok(())
     });
 
     print_diag_or_error(&world, result)
 }
+
+/// Writes the coverage report in the format under the project `root`,
+/// returning the path written to. The paths in the report are relative to the
+/// `root`, and the package files are left out except in the JSON format.
+pub fn write_coverage(
+    world: &LspWorld,
+    cov: &CoverageResult,
+    format: CovFormat,
+    root: &Path,
+) -> Result<PathBuf> {
+    let content = match format {
+        CovFormat::Json => serde_json::to_string(&cov.to_json(world)).context("coverage")?,
+        CovFormat::Lcov => cov.to_lcov(world),
+        CovFormat::Cobertura => cov.to_cobertura(world, root),
+    };
+
+    let path = format.path(root);
+    std::fs::create_dir_all(path.parent().context("parent")?).context("create coverage")?;
+    write_atomic(&path, content).context("write coverage")?;
+
+    Ok(path)
+}
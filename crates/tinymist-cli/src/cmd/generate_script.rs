@@ -161,14 +161,24 @@ fn shell_build_script(shell: Shell) -> Result<String> {
                 ExportTransform::Pretty { .. } => {
                     cmd.push("--pretty");
                 }
+                ExportTransform::FirstPage => {
+                    cmd.push("--pages");
+                    cmd.push("1");
+                }
                 ExportTransform::Pages { ranges } => {
                     for r in ranges {
                         cmd.push("--pages");
                         cmd.push(r.to_string());
                     }
                 }
+                ExportTransform::Script {
+                    script: Some(script),
+                } => {
+                    cmd.push("--transform-script");
+                    cmd.push(quote(script));
+                }
                 // todo: export me
-                ExportTransform::Merge { .. } | ExportTransform::Script { .. } => {}
+                ExportTransform::Merge { .. } | ExportTransform::Script { script: None } => {}
             }
         }
 
@@ -206,6 +216,9 @@ fn shell_build_script(shell: Shell) -> Result<String> {
             ProjectTask::ExportText(..) => {
                 cmd.push("--format=txt");
             }
+            ProjectTask::ExportEpub(..) => {
+                cmd.push("--format=epub");
+            }
             ProjectTask::ExportHtml(..) => {
                 cmd.push("--format=html");
             }
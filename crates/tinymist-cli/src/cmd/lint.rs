@@ -0,0 +1,387 @@
+//! Linting utilities
+
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use codespan_reporting::term::termcolor::Buffer;
+use serde::Serialize;
+use tinymist::CompileOnceArgs;
+use tinymist::project::*;
+use tinymist::world::diag::print_diagnostics_to;
+use tinymist_lint::{LintDiagnostic, LintRule, LintSeverity};
+use tinymist_project::vfs::WorkspaceResolver;
+use tinymist_query::analysis::{Analysis, Fix, FixSet, LocalContext};
+use tinymist_std::path::unix_slash;
+use tinymist_std::{ImmutPath, error::prelude::*, typst::TypstPagedDocument};
+use typst::World;
+use typst::diag::{EcoString, eco_format};
+use typst::syntax::Source;
+
+/// Lint arguments
+#[derive(Debug, Clone, clap::Parser)]
+pub struct LintArgs {
+    /// The argument to compile once.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// The format to print the diagnostics in.
+    #[clap(long, default_value = "human")]
+    pub format: LintFormat,
+
+    /// Writes the diagnostics to the file instead of the terminal.
+    #[clap(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Exits with a nonzero code if any diagnostic reaches the severity.
+    #[clap(long, default_value = "error")]
+    pub fail_on: LintFailLevel,
+
+    /// Applies the machine-applicable fixes to the files and reports the
+    /// remaining diagnostics. Removals of unused parameters and exports are
+    /// not applied since they may break the callers.
+    #[clap(long)]
+    pub fix: bool,
+}
+
+/// The format of the lint diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LintFormat {
+    /// Prints the diagnostics like the compiler.
+    Human,
+    /// Prints the diagnostics as a JSON array.
+    Json,
+    /// Prints the diagnostics in the SARIF 2.1.0 format.
+    Sarif,
+}
+
+/// The least severity of diagnostics to fail the lint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LintFailLevel {
+    /// Fails if any hint, warning or error is reported.
+    Hint,
+    /// Fails if any warning or error is reported.
+    Warning,
+    /// Fails if any error is reported.
+    Error,
+    /// Never fails.
+    Never,
+}
+
+impl LintFailLevel {
+    /// Whether a diagnostic with the severity fails the lint.
+    fn fails(self, severity: LintSeverity) -> bool {
+        let least = match self {
+            LintFailLevel::Hint => LintSeverity::Hint,
+            LintFailLevel::Warning => LintSeverity::Warning,
+            LintFailLevel::Error => LintSeverity::Error,
+            LintFailLevel::Never => return false,
+        };
+
+        severity >= least
+    }
+}
+
+/// Lints a document and all its dependencies.
+pub fn lint_main(args: LintArgs) -> Result<()> {
+    // Prepares for the compilation
+    let verse = args.compile.resolve()?;
+    let mut world = verse.snapshot();
+
+    let root = world.entry_state().root().map(Ok);
+    let root = root
+        .unwrap_or_else(|| std::env::current_dir().map(|p| p.into()))
+        .context("cannot find root")?;
+
+    // Compiles the document to collect its dependencies.
+    let compiled = typst::compile::<TypstPagedDocument>(&world);
+    if compiled.output.is_err() {
+        log::warn!("compilation failed, lint results may be incomplete");
+    }
+
+    let lint_config = LockFile::read(&root)
+        .ok()
+        .and_then(|lock| lock.lint)
+        .unwrap_or_default();
+    let analysis = Analysis {
+        lint_config: Arc::new(lint_config),
+        ..Analysis::default()
+    };
+    let mut ctx = analysis.enter(world.clone());
+    let mut lints = collect_lints(&mut ctx, &world, &root);
+
+    if args.fix {
+        // The fixes that may break the code elsewhere, e.g. removing an unused
+        // parameter, are left to the quick fixes in the editor.
+        let mut fixes = FixSet::default();
+        let mut add = |fix: Fix| fix.is_machine_applicable() && fixes.add(&fix);
+        let compiler_diags = compiled.output.as_ref().err().into_iter().flatten();
+        for diag in compiler_diags.chain(compiled.warnings.iter()) {
+            if let Some(fix) = Fix::from_compiler(&ctx, diag) {
+                add(fix);
+            }
+        }
+        for entry in &lints {
+            if let Some(fix) = Fix::from_lint(&entry.lint) {
+                add(fix);
+            }
+        }
+        drop(ctx);
+
+        apply_fixes(&world, &fixes)?;
+        log::info!("applied {} fixes", fixes.len);
+
+        // Lints the fixed files again, since the fixes shift the ranges of the
+        // remaining diagnostics.
+        world = args.compile.resolve()?.snapshot();
+        let _ = typst::compile::<TypstPagedDocument>(&world);
+        let mut ctx = analysis.enter(world.clone());
+        lints = collect_lints(&mut ctx, &world, &root);
+    }
+
+    lints.sort_by(|a, b| {
+        (&a.path, a.range.start, a.range.end).cmp(&(&b.path, b.range.start, b.range.end))
+    });
+
+    match args.format {
+        LintFormat::Human => print_human(&world, &lints, args.output.as_deref())?,
+        LintFormat::Json => {
+            let json = serde_json::to_string_pretty(&lints).context("serialize diagnostics")?;
+            emit(args.output.as_deref(), json)?;
+        }
+        LintFormat::Sarif => {
+            let sarif =
+                serde_json::to_string_pretty(&to_sarif(&lints)).context("serialize diagnostics")?;
+            emit(args.output.as_deref(), sarif)?;
+        }
+    }
+
+    if lints.iter().any(|entry| args.fail_on.fails(entry.severity)) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Lints the files in the project that the compiled document depends on.
+fn collect_lints(ctx: &mut LocalContext, world: &LspWorld, root: &ImmutPath) -> Vec<LintEntry> {
+    let mut lints = vec![];
+    for dep in world.depended_files() {
+        if WorkspaceResolver::is_package_file(dep) {
+            continue;
+        }
+
+        let Ok(source) = world.source(dep) else {
+            continue;
+        };
+
+        for lint in ctx.lint(&source) {
+            lints.push(LintEntry::new(world, root, &source, lint));
+        }
+    }
+
+    lints
+}
+
+/// Prints the diagnostics like the compiler, labelling hints as `hint`.
+fn print_human(world: &LspWorld, lints: &[LintEntry], output: Option<&Path>) -> Result<()> {
+    let color = output.is_none() && std::io::stderr().is_terminal();
+    let buffer = || match color {
+        true => Buffer::ansi(),
+        false => Buffer::no_color(),
+    };
+
+    let mut human = vec![];
+    for entry in lints {
+        let mut diag = entry.lint.diag.clone();
+        diag.hint(eco_format!(
+            "this is reported by the `{}` lint rule",
+            entry.lint.rule
+        ));
+
+        let mut w = buffer();
+        print_diagnostics_to(
+            world,
+            std::iter::once(&diag),
+            &mut w,
+            DiagnosticFormat::Human,
+        )
+        .context_ut("print diagnostics")?;
+        let printed = String::from_utf8_lossy(w.as_slice());
+
+        // The compiler only knows errors and warnings, so the hints are printed
+        // as warnings and relabelled in the header.
+        match entry.severity {
+            LintSeverity::Hint => human.extend(printed.replacen("warning", "hint", 1).bytes()),
+            _ => human.extend_from_slice(w.as_slice()),
+        }
+    }
+
+    match output {
+        Some(path) => std::fs::write(path, human).context("write lint output"),
+        None => std::io::stderr()
+            .write_all(&human)
+            .context("write lint output"),
+    }
+}
+
+/// Writes the fixed files and creates the missing files.
+fn apply_fixes(world: &LspWorld, fixes: &FixSet) -> Result<()> {
+    for &id in fixes.edits.keys() {
+        let source = world.source(id).context_ut("read source to fix")?;
+        let path = world.path_for_id(id).context_ut("resolve path to fix")?;
+        std::fs::write(path.as_path(), fixes.apply(id, source.text()))
+            .context("write fixed file")?;
+    }
+
+    for &id in &fixes.creates {
+        let path = world.path_for_id(id).context_ut("resolve path to create")?;
+        let path = path.as_path();
+        if path.exists() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("create parent directory")?;
+        }
+        std::fs::write(path, "").context("create missing file")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the output to the file or stdout.
+fn emit(output: Option<&Path>, content: String) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, content).context("write lint output"),
+        None => {
+            println!("{content}");
+            Ok(())
+        }
+    }
+}
+
+/// A lint diagnostic located in a file.
+#[derive(Serialize)]
+struct LintEntry {
+    /// The ID of the rule.
+    rule: &'static str,
+    /// The severity of the diagnostic.
+    severity: LintSeverity,
+    /// The message of the diagnostic.
+    message: EcoString,
+    /// The hints of the diagnostic.
+    hints: Vec<EcoString>,
+    /// The path relative to the project root.
+    path: EcoString,
+    /// The range of the diagnostic.
+    range: LintRange,
+    #[serde(skip)]
+    lint: LintDiagnostic,
+}
+
+/// A range of one-based lines and columns.
+#[derive(Serialize, Clone, Copy)]
+struct LintRange {
+    start: LintPosition,
+    end: LintPosition,
+}
+
+/// A position of one-based line and column.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct LintPosition {
+    line: usize,
+    column: usize,
+}
+
+impl LintEntry {
+    fn new(world: &LspWorld, root: &ImmutPath, source: &Source, lint: LintDiagnostic) -> Self {
+        let path = match world.path_for_id(source.id()) {
+            Ok(path) => {
+                let path = path.as_path();
+                unix_slash(path.strip_prefix(root).unwrap_or(path)).into()
+            }
+            Err(..) => unix_slash(source.id().vpath().as_rooted_path()).into(),
+        };
+
+        let range = source.range(lint.diag.span).unwrap_or_default();
+        let position = |offset: usize| LintPosition {
+            line: source.byte_to_line(offset).unwrap_or_default() + 1,
+            column: source.byte_to_column(offset).unwrap_or_default() + 1,
+        };
+
+        Self {
+            rule: lint.rule.id(),
+            severity: lint.severity,
+            message: lint.diag.message.clone(),
+            hints: lint.diag.hints.iter().cloned().collect(),
+            path,
+            range: LintRange {
+                start: position(range.start),
+                end: position(range.end),
+            },
+            lint,
+        }
+    }
+}
+
+/// Converts the diagnostics to a SARIF log.
+fn to_sarif(lints: &[LintEntry]) -> serde_json::Value {
+    let rules = LintRule::ALL
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = lints
+        .iter()
+        .map(|entry| {
+            let level = match entry.severity {
+                LintSeverity::Error => "error",
+                LintSeverity::Warning => "warning",
+                LintSeverity::Hint | LintSeverity::Off => "note",
+            };
+            let mut message = entry.message.to_string();
+            for hint in &entry.hints {
+                message.push_str("\nhint: ");
+                message.push_str(hint);
+            }
+
+            serde_json::json!({
+                "ruleId": entry.rule,
+                "level": level,
+                "message": { "text": message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": entry.path },
+                        "region": {
+                            "startLine": entry.range.start.line,
+                            "startColumn": entry.range.start.column,
+                            "endLine": entry.range.end.line,
+                            "endColumn": entry.range.end.column,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "tinymist",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Myriad-Dreamin/tinymist",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
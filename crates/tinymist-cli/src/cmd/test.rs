@@ -6,7 +6,6 @@ use std::io::Write;
 use std::path::Path;
 use std::sync::{Arc, atomic::AtomicBool};
 
-use itertools::Either;
 use parking_lot::Mutex;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use tinymist::project::*;
@@ -15,21 +14,31 @@ use tinymist::world::{SourceWorld, with_main};
 use tinymist_debug::CoverageResult;
 use tinymist_project::world::{DiagnosticFormat, system::print_diagnostics};
 use tinymist_query::analysis::Analysis;
-use tinymist_query::syntax::{cast_include_expr, find_source_by_expr, node_ancestors};
-use tinymist_query::testing::{TestCaseKind, TestSuites};
+use tinymist_query::testing::{
+    ExampleRef, ImageTolerance, RefOutcome, TestCase, TestCaseKind, TestSuites, check_call,
+    check_diagnostics, example_file, example_name, image_tolerance, is_html_example,
+};
 use tinymist_std::ImmutPath;
+use tinymist_std::path::unix_slash;
 use tinymist_std::typst::{TypstDocument, TypstHtmlDocument};
-use tinymist_std::{bail, error::prelude::*, fs::paths::write_atomic, typst::TypstPagedDocument};
+use tinymist_std::{error::prelude::*, fs::paths::write_atomic, typst::TypstPagedDocument};
+use typst::World;
 use typst::diag::{Severity, SourceDiagnostic};
 use typst::ecow::EcoVec;
-use typst::foundations::{Context, Label};
-use typst::syntax::{FileId, LinkedNode, Source, Span, ast};
-use typst::{World, utils::PicoStr};
+use typst::foundations::Context;
+use typst::syntax::{FileId, Source};
 use typst_shim::eval::TypstEngine;
 
+use self::filter::{TestKind, is_selected};
+use self::report::{ReportArg, TestRecords};
+use crate::cov::{CovFormat, write_coverage};
 use crate::print_diag_or_error;
 use crate::utils::exit_on_ctrl_c;
 
+mod bench;
+mod filter;
+mod report;
+
 const TEST_EVICT_MAX_AGE: usize = 30;
 const PREFIX_LEN: usize = 7;
 
@@ -72,13 +81,69 @@ pub struct TestConfigArgs {
     #[clap(flatten)]
     pub png: PngExportArgs,
 
+    /// The maximum perceptual distance between the colors of two pixels that
+    /// are regarded as equal when comparing an example to its reference image,
+    /// from 0 to 1.
+    #[clap(long, default_value_t = 0.1)]
+    pub pixel_tolerance: f64,
+
+    /// The maximum ratio of the differing pixels when comparing an example to
+    /// its reference image, from 0 to 1.
+    #[clap(long, default_value_t = 0.0)]
+    pub diff_tolerance: f64,
+
     /// Whether to collect coverage.
     #[clap(long)]
     pub coverage: bool,
 
+    /// The format of the coverage report.
+    #[clap(long, default_value = "json")]
+    pub coverage_format: CovFormat,
+
     /// Style of printing coverage.
     #[clap(long, default_value = "short")]
     pub print_coverage: PrintCovStyle,
+
+    /// Runs only the test cases and examples whose names contain the pattern,
+    /// or match it if it is a glob pattern with `*` and `?`. Can be specified
+    /// multiple times.
+    #[clap(long = "filter", value_name = "PATTERN")]
+    pub filters: Vec<String>,
+
+    /// Runs only the test cases of the kind. Can be specified multiple times.
+    #[clap(long = "kind", value_enum)]
+    pub kinds: Vec<TestKind>,
+
+    /// Stops running the test cases after the first failure.
+    #[clap(long)]
+    pub fail_fast: bool,
+
+    /// Prints the discovered test cases and examples without running them.
+    #[clap(long)]
+    pub list: bool,
+
+    /// The number of threads running the test cases in parallel, which
+    /// defaults to the number of CPUs.
+    #[clap(long)]
+    pub jobs: Option<usize>,
+
+    /// Writes a report of the test results, e.g., `junit=target/junit.xml` or
+    /// `json=target/tests.json`. Can be specified multiple times.
+    #[clap(long = "report", value_name = "FORMAT=PATH")]
+    pub reports: Vec<ReportArg>,
+
+    /// The number of warm-up iterations of a benchmark.
+    #[clap(long, default_value_t = 3)]
+    pub bench_warmup: usize,
+
+    /// The number of measured iterations of a benchmark.
+    #[clap(long, default_value_t = 20)]
+    pub bench_samples: usize,
+
+    /// The slowdown of the median time of a benchmark over its baseline in
+    /// percent, beyond which the benchmark fails as a regression.
+    #[clap(long, default_value_t = 10.0)]
+    pub bench_threshold: f64,
 }
 
 /// Style of printing coverage.
@@ -229,16 +294,22 @@ fn test_once(world: &LspWorld, ctx: &TestContext) -> Result<bool> {
         tinymist_query::testing::test_suites(&mut actx, &TypstDocument::from(Arc::new(doc)))
             .context("failed to discover tests")?;
     log_info!(
-        "Found {} tests and {} examples",
+        "Found {} tests, {} examples and {} diagnostic documents",
         suites.tests.len(),
-        suites.examples.len()
+        suites.examples.len(),
+        suites.diagnostics.len()
     );
 
+    if ctx.args.list {
+        list_suites(ctx, &suites);
+        return Ok(true);
+    }
+
     let result = if ctx.args.coverage {
         let (cov, result) = tinymist_debug::with_cov(world, |world| {
             let suites = suites.recheck(world);
             let runner = TestRunner::new(ctx, world, &suites);
-            let result = print_diag_or_error(world, runner.run());
+            let result = print_diag_or_error(world, ctx.run_tests(runner));
             comemo::evict(TEST_EVICT_MAX_AGE);
             result
         });
@@ -248,7 +319,7 @@ fn test_once(world: &LspWorld, ctx: &TestContext) -> Result<bool> {
         let suites = suites.recheck(world);
         let runner = TestRunner::new(ctx, world, &suites);
         comemo::evict(TEST_EVICT_MAX_AGE);
-        runner.run()
+        ctx.run_tests(runner)
     };
 
     let passed = print_diag_or_error(world, result);
@@ -261,6 +332,33 @@ fn test_once(world: &LspWorld, ctx: &TestContext) -> Result<bool> {
     passed
 }
 
+/// Prints the selected test cases and examples.
+fn list_suites(ctx: &TestContext, suites: &TestSuites) {
+    let args = &ctx.args;
+    for example in &suites.examples {
+        let name = example_name(example.id());
+        if is_selected(&args.filters, &args.kinds, TestKind::Example, &name) {
+            let path = unix_slash(example.id().vpath().as_rooted_path());
+            println!("example({name}) at {path}");
+        }
+    }
+    for diagnostic in &suites.diagnostics {
+        let name = example_name(diagnostic.id());
+        if is_selected(&args.filters, &args.kinds, TestKind::Diagnostic, &name) {
+            let path = unix_slash(diagnostic.id().vpath().as_rooted_path());
+            println!("diagnostic({name}) at {path}");
+        }
+    }
+    for test in &suites.tests {
+        let kind = TestKind::from(test.kind);
+        if is_selected(&args.filters, &args.kinds, kind, &test.name) {
+            let kind = kind.name();
+            let path = unix_slash(test.location.vpath().as_rooted_path());
+            println!("{kind}({}) at {path}", test.name);
+        }
+    }
+}
+
 #[derive(Clone)]
 struct TestContext {
     analysis: Analysis,
@@ -270,10 +368,21 @@ struct TestContext {
 }
 
 impl TestContext {
+    /// Runs the tests, in a thread pool of `--jobs` threads if specified.
+    fn run_tests(&self, runner: TestRunner) -> Result<bool> {
+        let Some(jobs) = self.args.jobs else {
+            return runner.run();
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .context("build thread pool")?;
+        pool.install(|| runner.run())
+    }
+
     pub fn handle_cov(&self, world: &LspWorld, cov: CoverageResult) -> Result<()> {
-        let cov_path = Path::new("target/coverage.json");
-        let res = serde_json::to_string(&cov.to_json(world)).context("coverage")?;
-        write_atomic(cov_path, res).context("write coverage")?;
+        let cov_path = write_coverage(world, &cov, self.args.coverage_format, &self.root)?;
         log_info!("Written coverage to {} ...", cov_path.display());
 
         const COV_PREFIX: &str = "    \x1b[1;32mCov\x1b[0m ";
@@ -290,12 +399,21 @@ impl TestContext {
     }
 }
 
+/// A case to run in parallel.
+enum Case<'a> {
+    Example(&'a Source),
+    Diagnostic(&'a Source),
+    Test(&'a TestCase),
+}
+
 struct TestRunner<'a> {
     ctx: &'a TestContext,
     world: &'a dyn SourceWorld,
     suites: &'a TestSuites,
     diagnostics: Mutex<Vec<EcoVec<SourceDiagnostic>>>,
     examples: Mutex<HashSet<String>>,
+    benches: Mutex<Vec<&'a TestCase>>,
+    records: TestRecords,
     failed: AtomicBool,
 }
 
@@ -307,6 +425,8 @@ impl<'a> TestRunner<'a> {
             suites,
             diagnostics: Mutex::new(Vec::new()),
             examples: Mutex::new(HashSet::new()),
+            benches: Mutex::new(Vec::new()),
+            records: TestRecords::default(),
             failed: AtomicBool::new(false),
         }
     }
@@ -317,93 +437,119 @@ impl<'a> TestRunner<'a> {
         }
     }
 
-    fn running(&self, kind: &str, name: &str) {
-        test_info!("Running", "{kind}({name})");
-        self.put_log(format_args!("#running-{kind}({name:?})"));
+    fn running(&self, kind: TestKind, name: &str, file: FileId) {
+        let suite = kind.suite();
+        test_info!("Running", "{suite}({name})");
+        self.put_log(format_args!("#running-{suite}({name:?})"));
+        self.records.start(kind, name, file);
     }
 
-    fn mark_failed(&self, kind: &str, name: &str, args: impl fmt::Display) {
-        test_log!(Error, "Failed", "{kind}({name}): {args}");
-        self.put_log(format_args!("#failed-{kind}({name:?})"));
+    fn mark_failed(&self, kind: TestKind, name: &str, args: impl fmt::Display) {
+        let suite = kind.suite();
+        let message = args.to_string();
+        test_log!(Error, "Failed", "{suite}({name}): {message}");
+        self.put_log(format_args!("#failed-{suite}({name:?})"));
+        self.records.finish(kind, name, Some(message));
         self.failed.store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
-    fn mark_passed(&self, kind: &str, name: &str) {
-        test_info!("Passed", "{kind}({name})");
-        self.put_log(format_args!("#passed-{kind}({name:?})"));
+    fn mark_passed(&self, kind: TestKind, name: &str) {
+        let suite = kind.suite();
+        test_info!("Passed", "{suite}({name})");
+        self.put_log(format_args!("#passed-{suite}({name:?})"));
+        self.records.finish(kind, name, None);
     }
 
-    fn failed_example(&self, name: &str, args: impl fmt::Display) {
-        self.mark_failed("example", name, args);
+    /// Collects the diagnostics of a case to print them after the run.
+    fn push_diagnostics(&self, kind: TestKind, name: &str, diagnostics: EcoVec<SourceDiagnostic>) {
+        self.records
+            .attach(self.world.as_world(), kind, name, &diagnostics);
+        self.diagnostics.lock().push(diagnostics);
     }
 
-    fn failed_test(&self, name: &str, args: impl fmt::Display) {
-        self.mark_failed("test", name, args);
+    fn failed_example(&self, name: &str, args: impl fmt::Display) {
+        self.mark_failed(TestKind::Example, name, args);
     }
 
     /// Runs the tests and returns whether all tests passed.
     fn run(self) -> Result<bool> {
+        let instant = std::time::Instant::now();
         self.put_log(format_args!(
             "#reset();\n#running-tests({}, {})",
             self.suites.tests.len(),
-            self.suites.examples.len()
+            self.suites.examples.len() + self.suites.diagnostics.len()
         ));
 
-        let examples = self.suites.examples.par_iter().map(Either::Left);
-        let tests = self.suites.tests.par_iter().map(Either::Right);
+        let examples = self.suites.examples.par_iter().map(Case::Example);
+        let diagnostics = self.suites.diagnostics.par_iter().map(Case::Diagnostic);
+        let tests = self.suites.tests.par_iter().map(Case::Test);
+
+        examples.chain(diagnostics).chain(tests).for_each(|case| {
+            if self.should_stop() {
+                return;
+            }
 
-        examples.chain(tests).for_each(|case| {
             let test = match case {
-                Either::Left(test) => {
-                    self.run_example(test);
+                Case::Example(test) => {
+                    if self.is_selected(TestKind::Example, &example_name(test.id())) {
+                        self.run_example(test);
+                    }
+                    return;
+                }
+                Case::Diagnostic(test) => {
+                    if self.is_selected(TestKind::Diagnostic, &example_name(test.id())) {
+                        self.run_diagnostic(test);
+                    }
                     return;
                 }
-                Either::Right(test) => test,
+                Case::Test(test) => test,
             };
+            if !self.is_selected(test.kind.into(), &test.name) {
+                return;
+            }
 
             let name = &test.name;
             let func = &test.function;
+            let kind = TestKind::from(test.kind);
 
             let world = with_main(self.world.as_world(), test.location);
             let mut engine = TypstEngine::new(&world);
 
             // Executes the function
             match test.kind {
-                TestCaseKind::Test | TestCaseKind::Bench => {
-                    self.running("test", name);
-                    if let Err(err) = engine.call(func, Context::default()) {
-                        self.diagnostics.lock().push(err);
-                        self.failed_test(name, format_args!("call error"));
-                    } else {
-                        self.mark_passed("test", name);
-                    }
+                // Benchmarks are timed one by one after the other test cases,
+                // unless they are only called to collect coverage.
+                TestCaseKind::Bench if !self.ctx.args.coverage => {
+                    self.benches.lock().push(test);
                 }
-                TestCaseKind::Panic => {
-                    self.running("test", name);
-                    match engine.call(func, Context::default()) {
-                        Ok(..) => {
-                            self.failed_test(name, "exited normally, expected panic");
-                        }
-                        Err(err) => {
-                            let all_panic = err.iter().all(|p| p.message.contains("panic"));
-                            if !all_panic {
-                                self.diagnostics.lock().push(err);
-                                self.failed_test(name, "exited with error, expected panic");
-                            } else {
-                                self.mark_passed("test", name);
+                TestCaseKind::Test | TestCaseKind::Bench | TestCaseKind::Panic => {
+                    self.running(kind, name, test.location);
+                    match check_call(test, engine.call(func, Context::default())) {
+                        Ok(()) => self.mark_passed(kind, name),
+                        Err(failure) => {
+                            if !failure.diagnostics.is_empty() {
+                                self.push_diagnostics(kind, name, failure.diagnostics);
                             }
+                            self.mark_failed(kind, name, failure.message);
                         }
                     }
                 }
                 TestCaseKind::Example => {
-                    match get_example_file(&world, name, test.location, func.span()) {
+                    match example_file(&world, name, test.location, func.span()) {
                         Ok(example) => self.run_example(&example),
-                        Err(err) => self.failed_test(name, format_args!("not found: {err}")),
+                        Err(err) => self.mark_failed(kind, name, format_args!("not found: {err}")),
                     };
                 }
             }
         });
 
+        self.run_benches()?;
+        if self.should_stop() {
+            log_hint!("Skipped the remaining test cases after the first failure");
+        }
+        self.records
+            .write(&self.ctx.args.reports, instant.elapsed())?;
+
         {
             let diagnostics = self.diagnostics.into_inner();
             if !diagnostics.is_empty() {
@@ -421,10 +567,93 @@ impl<'a> TestRunner<'a> {
         Ok(!self.failed.load(std::sync::atomic::Ordering::SeqCst))
     }
 
+    /// Runs the benchmarks sequentially, since the timings are disturbed by
+    /// the other test cases and the benchmarks evict the caches.
+    fn run_benches(&self) -> Result<()> {
+        let mut benches = std::mem::take(&mut *self.benches.lock());
+        if benches.is_empty() {
+            return Ok(());
+        }
+        benches.sort_by(|a, b| (a.location.vpath(), &a.name).cmp(&(b.location.vpath(), &b.name)));
+
+        let args = &self.ctx.args;
+        let baseline_path = self.ctx.root.join(bench::BASELINE_PATH);
+        let mut baselines = bench::Baselines::load(&baseline_path)?;
+        let mut changed = false;
+
+        for test in benches {
+            if self.should_stop() {
+                break;
+            }
+            let name = &test.name;
+            let kind = TestKind::from(test.kind);
+            self.running(kind, name, test.location);
+
+            let world = with_main(self.world.as_world(), test.location);
+            let mut engine = TypstEngine::new(&world);
+            let stats = bench::bench(
+                &mut engine,
+                &test.function,
+                args.bench_warmup,
+                args.bench_samples,
+            );
+            let stats = match stats {
+                Ok(stats) => stats,
+                Err(err) => {
+                    self.push_diagnostics(kind, name, err);
+                    self.mark_failed(kind, name, format_args!("call error"));
+                    continue;
+                }
+            };
+            test_info!("Bench", "{name}: {stats}");
+
+            let path = unix_slash(test.location.vpath().as_rooted_path());
+            let key = format!("{path}@{name}");
+            match baselines.compare(&key, &stats, args.bench_threshold) {
+                bench::Comparison::Regressed(change) if !args.update => {
+                    self.mark_failed(
+                        kind,
+                        name,
+                        format_args!(
+                            "regressed by {change:.2}% over the baseline, beyond {:.2}%",
+                            args.bench_threshold
+                        ),
+                    );
+                    log_hint!("test({name}): accept the timings as the baseline with --update");
+                    continue;
+                }
+                bench::Comparison::Within(change) if !args.update => {
+                    log_info!("test({name}): changed by {change:+.2}% over the baseline");
+                }
+                _ => {
+                    baselines.insert(key, stats);
+                    changed = true;
+                }
+            }
+            self.mark_passed(kind, name);
+        }
+
+        if changed {
+            baselines.save(&baseline_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a case is selected by the filters.
+    fn is_selected(&self, kind: TestKind, name: &str) -> bool {
+        let args = &self.ctx.args;
+        is_selected(&args.filters, &args.kinds, kind, name)
+    }
+
+    /// Whether to stop running the remaining cases due to `--fail-fast`.
+    fn should_stop(&self) -> bool {
+        self.ctx.args.fail_fast && self.failed.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
     fn run_example(&self, test: &Source) {
-        let id = test.id().vpath().as_rooted_path().with_extension("");
-        let name = id.file_name().and_then(|s| s.to_str()).unwrap_or_default();
-        self.running("example", name);
+        let name = &example_name(test.id());
+        self.running(TestKind::Example, name, test.id());
 
         if !self.examples.lock().insert(name.to_string()) {
             self.failed_example(name, "duplicate");
@@ -433,31 +662,70 @@ impl<'a> TestRunner<'a> {
 
         let world = with_main(self.world.as_world(), test.id());
         let mut has_err = false;
-        let (has_err_, doc) = self.build_example::<TypstPagedDocument>(&world);
+        let (has_err_, doc) = self.build_example::<TypstPagedDocument>(name, &world);
         has_err |= has_err_ || self.render_paged(name, doc.as_ref());
 
         if self.can_html(doc.as_ref()) {
-            let (has_err_, doc) = self.build_example::<TypstHtmlDocument>(&world);
+            let (has_err_, doc) = self.build_example::<TypstHtmlDocument>(name, &world);
             has_err |= has_err_ || self.render_html(name, doc.as_ref());
         }
 
         if has_err {
             self.failed_example(name, "has error");
         } else {
-            self.mark_passed("example", name);
+            self.mark_passed(TestKind::Example, name);
+        }
+    }
+
+    /// Runs a diagnostic document, whose diagnostics are checked against the
+    /// annotations in it rather than failing the run.
+    fn run_diagnostic(&self, test: &Source) {
+        let name = &example_name(test.id());
+        self.running(TestKind::Diagnostic, name, test.id());
+
+        let world = with_main(self.world.as_world(), test.id());
+        let result = typst::compile::<TypstPagedDocument>(&world);
+        let mut diagnostics = result.warnings;
+        if let Err(errors) = result.output {
+            diagnostics.extend(errors);
+        }
+
+        let mismatches = check_diagnostics(self.world.as_world(), test, &diagnostics);
+        if mismatches.is_empty() {
+            self.mark_passed(TestKind::Diagnostic, name);
+            return;
         }
+
+        self.records.attach(
+            self.world.as_world(),
+            TestKind::Diagnostic,
+            name,
+            &diagnostics,
+        );
+        for mismatch in &mismatches {
+            log_hint!("diagnostic({name}): {mismatch}");
+        }
+        self.mark_failed(
+            TestKind::Diagnostic,
+            name,
+            format_args!("{} mismatched diagnostics", mismatches.len()),
+        );
     }
 
-    fn build_example<T: typst::Document>(&self, world: &dyn World) -> (bool, Option<T>) {
+    fn build_example<T: typst::Document>(
+        &self,
+        example: &str,
+        world: &dyn World,
+    ) -> (bool, Option<T>) {
         let result = typst::compile::<T>(world);
         if !result.warnings.is_empty() {
-            self.diagnostics.lock().push(result.warnings);
+            self.push_diagnostics(TestKind::Example, example, result.warnings);
         }
 
         match result.output {
             Ok(v) => (false, Some(v)),
             Err(e) => {
-                self.diagnostics.lock().push(e);
+                self.push_diagnostics(TestKind::Example, example, e);
                 (true, None)
             }
         }
@@ -471,7 +739,10 @@ impl<'a> TestRunner<'a> {
         let ppp = self.ctx.args.png.ppi / 72.0;
         let pixmap = typst_render::render_merged(doc, ppp, Default::default(), None);
         let output = pixmap.encode_png().context_ut("cannot encode pixmap");
-        let output = output.and_then(|output| self.update_example(example, &output, "paged"));
+        let output = output.and_then(|output| {
+            let tolerance = self.image_tolerance(doc)?;
+            self.update_example(example, &output, "paged", Some(tolerance))
+        });
         self.check_result(example, output, "paged")
     }
 
@@ -481,9 +752,9 @@ impl<'a> TestRunner<'a> {
         };
 
         let output = match typst_html::html(doc) {
-            Ok(output) => self.update_example(example, output.as_bytes(), "html"),
+            Ok(output) => self.update_example(example, output.as_bytes(), "html", None),
             Err(err) => {
-                self.diagnostics.lock().push(err);
+                self.push_diagnostics(TestKind::Example, example, err);
                 Err(error_once!("render error"))
             }
         };
@@ -499,80 +770,79 @@ impl<'a> TestRunner<'a> {
         }
     }
 
-    fn update_example(&self, example: &str, data: &[u8], kind: &str) -> Result<()> {
-        let ext = if kind == "paged" { "png" } else { "html" };
-        let refs_path = self.ctx.root.join("refs");
-        let path = refs_path.join(kind).join(example).with_extension(ext);
-        let tmp_path = &path.with_extension(format!("tmp.{ext}"));
-        let hash_path = &path.with_extension("hash");
-
-        let hash = &format!("siphash128_13:{:x}", tinymist_std::hash::hash128(&data));
-        let existing_hash = if std::fs::exists(hash_path).context("exists hash ref")? {
-            Some(std::fs::read(hash_path).context("read hash ref")?)
-        } else {
-            None
+    /// Gets the tolerance of comparing the rendered images of an example,
+    /// defaulting to the tolerance given in the arguments.
+    fn image_tolerance(&self, doc: &TypstPagedDocument) -> Result<ImageTolerance> {
+        let tolerance = ImageTolerance {
+            pixel: self.ctx.args.pixel_tolerance,
+            ratio: self.ctx.args.diff_tolerance,
         };
+        image_tolerance(doc, tolerance)
+    }
 
-        let equal = existing_hash.map(|existing| existing.as_slice() == hash.as_bytes());
-        match (self.ctx.args.update, equal) {
-            // Doesn't exist, create it
-            (_, None) => {}
-            (_, Some(true)) => log_info!("example({example}): {kind} matches"),
-            (true, Some(false)) => log_info!("example({example}): ref {kind}"),
-            (false, Some(false)) => {
-                write_atomic(tmp_path, data).context("write tmp ref")?;
-
-                self.failed_example(example, format_args!("mismatch {kind}"));
-                log_hint!("example({example}): compare {kind} at {}", path.display());
-                match path.strip_prefix(&self.ctx.root) {
-                    Ok(p) => self.put_log(format_args!("#mismatch-example({example:?}, {p:?})")),
-                    Err(_) => self.put_log(format_args!("#mismatch-example({example:?}, none)")),
+    fn update_example(
+        &self,
+        example: &str,
+        data: &[u8],
+        kind: &str,
+        tolerance: Option<ImageTolerance>,
+    ) -> Result<()> {
+        let refs = ExampleRef::new(&self.ctx.root, kind, example);
+        match refs.update(data, tolerance, self.ctx.args.update)? {
+            RefOutcome::Created => {}
+            RefOutcome::Matched(diff) => {
+                if let Some(diff) = diff {
+                    log_info!(
+                        "example({example}): {kind} matches within tolerance, {} pixels differ",
+                        diff.differing
+                    );
+                }
+                log_info!("example({example}): {kind} matches");
+            }
+            RefOutcome::Updated => log_info!("example({example}): ref {kind}"),
+            RefOutcome::Mismatched(diff) => {
+                if let Some(diff) = &diff {
+                    self.failed_example(
+                        example,
+                        format_args!(
+                            "mismatch {kind}, {} of {} pixels ({:.2}%) differ",
+                            diff.differing,
+                            diff.total,
+                            diff.ratio() * 100.0
+                        ),
+                    );
+                } else {
+                    self.failed_example(example, format_args!("mismatch {kind}"));
+                }
+                log_hint!(
+                    "example({example}): compare {kind} at {}",
+                    refs.path.display()
+                );
+                let root = &self.ctx.root;
+                match (
+                    refs.path.strip_prefix(root),
+                    refs.diff_path.strip_prefix(root),
+                ) {
+                    (Ok(p), Ok(d)) if diff.is_some() => self.put_log(format_args!(
+                        "#mismatch-example({example:?}, {p:?}, diff: {d:?})"
+                    )),
+                    (Ok(p), _) => {
+                        self.put_log(format_args!("#mismatch-example({example:?}, {p:?})"))
+                    }
+                    (Err(_), _) => {
+                        self.put_log(format_args!("#mismatch-example({example:?}, none)"))
+                    }
                 };
-
-                return Ok(());
             }
         }
 
-        if std::fs::exists(tmp_path).context("exists tmp")? {
-            std::fs::remove_file(tmp_path).context("remove tmp")?;
-        }
-
-        if matches!(equal, Some(true)) {
-            return Ok(());
-        }
-
-        std::fs::create_dir_all(path.parent().context("parent")?).context("create ref")?;
-        write_atomic(path, data).context("write ref")?;
-        write_atomic(hash_path, hash).context("write hash ref")?;
-
         Ok(())
     }
 
     fn can_html(&self, doc: Option<&TypstPagedDocument>) -> bool {
-        let Some(doc) = doc else {
-            return false;
-        };
-
-        let label = Label::new(PicoStr::intern("test-html-example"));
         // todo: error multiple times
-        doc.introspector.query_label(label).is_ok()
-    }
-}
-
-fn get_example_file(world: &dyn World, name: &str, id: FileId, span: Span) -> Result<Source> {
-    let source = world.source(id).context_ut("cannot find file")?;
-    let node = LinkedNode::new(source.root());
-    let leaf = node.find(span).context("cannot find example function")?;
-    let function = node_ancestors(&leaf)
-        .find(|n| n.is::<ast::Closure>())
-        .context("cannot find example function")?;
-    let closure = function.cast::<ast::Closure>().unwrap();
-    if closure.params().children().count() != 0 {
-        bail!("example function must not have parameters");
+        doc.is_some_and(is_html_example)
     }
-    let included =
-        cast_include_expr(name, closure.body()).context("cannot find example function")?;
-    find_source_by_expr(world, id, included).context("cannot find example file")
 }
 
 enum Level {
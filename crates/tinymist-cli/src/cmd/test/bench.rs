@@ -0,0 +1,236 @@
+//! Benchmarking of the `bench-*` test cases.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use tinymist_std::error::prelude::*;
+use tinymist_std::fs::paths::write_atomic;
+use typst::diag::SourceResult;
+use typst::foundations::{Context, Func};
+use typst_shim::eval::TypstEngine;
+
+/// The path to the baselines of the benchmarks, relative to the project root.
+pub(super) const BASELINE_PATH: &str = "target/bench-baselines.json";
+
+/// The statistics of the samples of a benchmark, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) struct BenchStats {
+    /// The number of samples.
+    pub samples: usize,
+    /// The mean of the samples.
+    pub mean: f64,
+    /// The median of the samples.
+    pub median: f64,
+    /// The standard deviation of the samples.
+    pub stddev: f64,
+}
+
+impl BenchStats {
+    /// Computes the statistics of the samples, which must not be empty.
+    fn new(samples: &[Duration]) -> Self {
+        let mut nanos = samples
+            .iter()
+            .map(|sample| sample.as_nanos() as f64)
+            .collect::<Vec<_>>();
+        nanos.sort_by(f64::total_cmp);
+
+        let len = nanos.len();
+        let mean = nanos.iter().sum::<f64>() / len as f64;
+        let median = if len % 2 == 0 {
+            (nanos[len / 2 - 1] + nanos[len / 2]) / 2.0
+        } else {
+            nanos[len / 2]
+        };
+        let variance = if len > 1 {
+            nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (len - 1) as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            samples: len,
+            mean,
+            median,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    /// Gets the change of the median in percent relative to a baseline.
+    pub fn change(&self, baseline: &BenchStats) -> f64 {
+        if baseline.median == 0.0 {
+            return 0.0;
+        }
+        (self.median - baseline.median) / baseline.median * 100.0
+    }
+}
+
+impl std::fmt::Display for BenchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = |nanos: f64| Duration::from_nanos(nanos as u64);
+        write!(
+            f,
+            "mean {:.2?}, median {:.2?}, stddev {:.2?} ({} samples)",
+            time(self.mean),
+            time(self.median),
+            time(self.stddev),
+            self.samples
+        )
+    }
+}
+
+/// Runs a benchmark function repeatedly, evicting the caches before each
+/// iteration so that every iteration does the full work.
+pub(super) fn bench(
+    engine: &mut TypstEngine,
+    func: &Func,
+    warmup: usize,
+    samples: usize,
+) -> SourceResult<BenchStats> {
+    for _ in 0..warmup {
+        comemo::evict(0);
+        engine.call(func, Context::default())?;
+    }
+
+    let mut timings = Vec::with_capacity(samples.max(1));
+    for _ in 0..samples.max(1) {
+        comemo::evict(0);
+        let instant = std::time::Instant::now();
+        engine.call(func, Context::default())?;
+        timings.push(instant.elapsed());
+    }
+
+    Ok(BenchStats::new(&timings))
+}
+
+/// The comparison of a benchmark with its baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Comparison {
+    /// There is no baseline for the benchmark.
+    Missing,
+    /// The median changed by the percent, which is within the threshold.
+    Within(f64),
+    /// The median regressed by the percent, which is beyond the threshold.
+    Regressed(f64),
+}
+
+/// The baselines of the benchmarks, keyed by `path@name`.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(super) struct Baselines(BTreeMap<String, BenchStats>);
+
+impl Baselines {
+    /// Loads the baselines, which are empty if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !std::fs::exists(path).context("exists baselines")? {
+            return Ok(Self::default());
+        }
+
+        let data = std::fs::read(path).context("read baselines")?;
+        serde_json::from_slice(&data).context("parse baselines")
+    }
+
+    /// Saves the baselines.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self).context("serialize baselines")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("create baselines dir")?;
+        }
+        write_atomic(path, data).context("write baselines")
+    }
+
+    /// Gets the baseline of a benchmark.
+    pub fn get(&self, key: &str) -> Option<&BenchStats> {
+        self.0.get(key)
+    }
+
+    /// Compares a benchmark with its baseline, which regresses if the median
+    /// grows by more than `threshold` percent.
+    pub fn compare(&self, key: &str, stats: &BenchStats, threshold: f64) -> Comparison {
+        match self.get(key).map(|baseline| stats.change(baseline)) {
+            None => Comparison::Missing,
+            Some(change) if change > threshold => Comparison::Regressed(change),
+            Some(change) => Comparison::Within(change),
+        }
+    }
+
+    /// Sets the baseline of a benchmark.
+    pub fn insert(&mut self, key: String, stats: BenchStats) {
+        self.0.insert(key, stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(samples: &[u64]) -> BenchStats {
+        let samples = samples.iter().copied().map(Duration::from_nanos);
+        BenchStats::new(&samples.collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_stats_odd() {
+        let stats = stats(&[30, 10, 20]);
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.mean, 20.0);
+        assert_eq!(stats.median, 20.0);
+        assert_eq!(stats.stddev, 10.0);
+    }
+
+    #[test]
+    fn test_stats_even() {
+        let stats = stats(&[4, 1, 2, 1]);
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.mean, 2.0);
+        assert_eq!(stats.median, 1.5);
+        assert_eq!(stats.stddev, 2f64.sqrt());
+    }
+
+    #[test]
+    fn test_stats_single() {
+        let stats = stats(&[7]);
+        assert_eq!(stats.mean, 7.0);
+        assert_eq!(stats.median, 7.0);
+        assert_eq!(stats.stddev, 0.0);
+    }
+
+    #[test]
+    fn test_change() {
+        let baseline = stats(&[100]);
+        assert_eq!(stats(&[110]).change(&baseline), 10.0);
+        assert_eq!(stats(&[50]).change(&baseline), -50.0);
+        assert_eq!(stats(&[50]).change(&stats(&[0])), 0.0);
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut baselines = Baselines::default();
+        baselines.insert("/main.typ@bench-a".into(), stats(&[100]));
+
+        let compare = |key: &str, median: u64| baselines.compare(key, &stats(&[median]), 5.0);
+        assert_eq!(compare("/main.typ@bench-b", 100), Comparison::Missing);
+        assert_eq!(compare("/main.typ@bench-a", 105), Comparison::Within(5.0));
+        assert_eq!(compare("/main.typ@bench-a", 90), Comparison::Within(-10.0));
+        assert_eq!(
+            compare("/main.typ@bench-a", 106),
+            Comparison::Regressed(6.0)
+        );
+    }
+
+    #[test]
+    fn test_baselines_json() {
+        let mut baselines = Baselines::default();
+        baselines.insert("/main.typ@bench-a".into(), stats(&[1, 3]));
+
+        let json = serde_json::to_string(&baselines).unwrap();
+        assert_eq!(
+            json,
+            r#"{"/main.typ@bench-a":{"samples":2,"mean":2.0,"median":2.0,"stddev":1.4142135623730951}}"#
+        );
+        let parsed: Baselines = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.get("/main.typ@bench-a"),
+            baselines.get("/main.typ@bench-a")
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Selection of the test cases and examples to run.
+
+use tinymist_query::testing::TestCaseKind;
+
+/// The kind of test cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TestKind {
+    /// The functions named `test-*`.
+    Test,
+    /// The functions named `panic-on-*`.
+    Panic,
+    /// The example documents and the functions named `example-*`.
+    Example,
+    /// The functions named `bench-*`.
+    Bench,
+    /// The documents named `diag-*`, whose diagnostics are checked against the
+    /// annotations in them.
+    Diagnostic,
+}
+
+impl TestKind {
+    /// Gets the name of the kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Test => "test",
+            Self::Panic => "panic",
+            Self::Example => "example",
+            Self::Bench => "bench",
+            Self::Diagnostic => "diagnostic",
+        }
+    }
+
+    /// Gets the kind of the cases shown in the dashboard, i.e., `test` or
+    /// `example`.
+    pub fn suite(self) -> &'static str {
+        match self {
+            Self::Test | Self::Panic | Self::Bench => "test",
+            Self::Example | Self::Diagnostic => "example",
+        }
+    }
+}
+
+impl From<TestCaseKind> for TestKind {
+    fn from(kind: TestCaseKind) -> Self {
+        match kind {
+            TestCaseKind::Test => Self::Test,
+            TestCaseKind::Panic => Self::Panic,
+            TestCaseKind::Example => Self::Example,
+            TestCaseKind::Bench => Self::Bench,
+        }
+    }
+}
+
+/// Checks whether a case is selected by the name patterns and the kinds, where
+/// empty patterns or kinds select all cases.
+pub(super) fn is_selected(
+    patterns: &[String],
+    kinds: &[TestKind],
+    kind: TestKind,
+    name: &str,
+) -> bool {
+    let kind_matches = kinds.is_empty() || kinds.contains(&kind);
+    let name_matches = patterns.is_empty() || patterns.iter().any(|p| name_matches(p, name));
+    kind_matches && name_matches
+}
+
+/// Checks whether a name matches a pattern, which is a glob pattern if it
+/// contains `*` or `?`, or a substring otherwise.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return name.contains(pattern);
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    // Matches greedily, backtracking to the last star on a mismatch.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substring() {
+        assert!(name_matches("table", "test-table-layout"));
+        assert!(name_matches("", "test-a"));
+        assert!(!name_matches("Table", "test-table"));
+        assert!(!name_matches("tables", "test-table"));
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(name_matches("test-*", "test-a"));
+        assert!(name_matches("test-*", "test-"));
+        assert!(name_matches("*-layout", "test-table-layout"));
+        assert!(name_matches("*table*", "test-table-layout"));
+        assert!(name_matches("test-?", "test-a"));
+        assert!(!name_matches("test-?", "test-"));
+        assert!(!name_matches("test-?", "test-ab"));
+        assert!(name_matches("t*t-*b", "test-a-b"));
+        assert!(name_matches("**", ""));
+        assert!(name_matches("*a*b*", "xaxxbx"));
+        assert!(!name_matches("*a*b", "xaxxbx"));
+    }
+
+    #[test]
+    fn test_glob_anchored() {
+        // A glob matches the whole name unlike a substring.
+        assert!(!name_matches("table*", "test-table"));
+        assert!(!name_matches("*table", "test-table-layout"));
+        assert!(!name_matches("test-?", "a-test-b"));
+    }
+
+    #[test]
+    fn test_is_selected() {
+        let patterns = ["table".to_owned(), "bench-*".to_owned()];
+
+        assert!(is_selected(&[], &[], TestKind::Test, "test-a"));
+        assert!(is_selected(&patterns, &[], TestKind::Test, "test-table"));
+        assert!(is_selected(&patterns, &[], TestKind::Bench, "bench-a"));
+        assert!(!is_selected(&patterns, &[], TestKind::Test, "test-a"));
+    }
+
+    #[test]
+    fn test_is_selected_by_kind() {
+        let kinds = [TestKind::Example, TestKind::Panic];
+
+        assert!(is_selected(&[], &kinds, TestKind::Example, "a"));
+        assert!(is_selected(&[], &kinds, TestKind::Panic, "panic-on-a"));
+        assert!(!is_selected(&[], &kinds, TestKind::Test, "test-a"));
+
+        // Both the patterns and the kinds must match.
+        let patterns = ["*-a".to_owned()];
+        assert!(is_selected(
+            &patterns,
+            &kinds,
+            TestKind::Panic,
+            "panic-on-a"
+        ));
+        assert!(!is_selected(
+            &patterns,
+            &kinds,
+            TestKind::Panic,
+            "panic-on-b"
+        ));
+        assert!(!is_selected(&patterns, &kinds, TestKind::Test, "test-a"));
+    }
+
+    #[test]
+    fn test_suite() {
+        assert_eq!(TestKind::Panic.suite(), "test");
+        assert_eq!(TestKind::Diagnostic.suite(), "example");
+        assert_eq!(TestKind::Bench.name(), "bench");
+    }
+}
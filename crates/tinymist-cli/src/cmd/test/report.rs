@@ -0,0 +1,514 @@
+//! Reports of the test results in the formats of the CI tools.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tinymist_std::error::prelude::*;
+use tinymist_std::fs::paths::write_atomic;
+use tinymist_std::path::unix_slash;
+use typst::World;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::syntax::FileId;
+
+use super::filter::TestKind;
+
+/// The format of a test report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A JUnit XML report.
+    Junit,
+    /// A JSON report.
+    Json,
+}
+
+/// A test report to write, specified as `<format>=<path>`, e.g.,
+/// `junit=target/junit.xml`.
+#[derive(Debug, Clone)]
+pub struct ReportArg {
+    /// The format of the report.
+    pub format: ReportFormat,
+    /// The path to write the report to.
+    pub path: PathBuf,
+}
+
+impl FromStr for ReportArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((format, path)) = s.split_once('=') else {
+            return Err(format!("expected `<format>=<path>`, found `{s}`"));
+        };
+        let format = match format {
+            "junit" => ReportFormat::Junit,
+            "json" => ReportFormat::Json,
+            _ => {
+                return Err(format!(
+                    "unknown report format `{format}`, expected junit or json"
+                ));
+            }
+        };
+        if path.is_empty() {
+            return Err("the path of the report is empty".to_owned());
+        }
+
+        Ok(Self {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// The status of a test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum TestStatus {
+    Running,
+    Passed,
+    Failed,
+}
+
+impl TestStatus {
+    /// Whether the case is reported as a failure. A case still running when
+    /// the report is written has been interrupted, so it fails as well.
+    fn is_failure(self) -> bool {
+        match self {
+            Self::Passed => false,
+            Self::Running | Self::Failed => true,
+        }
+    }
+}
+
+/// The result of a test case or an example.
+#[derive(Debug, serde::Serialize)]
+struct TestRecord {
+    /// The kind of the case, e.g., `test` or `example`.
+    #[serde(serialize_with = "serialize_kind")]
+    kind: TestKind,
+    /// The name of the case.
+    name: String,
+    /// The path of the file defining the case.
+    file: Option<String>,
+    /// The duration of the case in seconds.
+    duration: f64,
+    status: TestStatus,
+    /// The failure messages of the case.
+    messages: Vec<String>,
+    /// The diagnostics attached to the case.
+    diagnostics: Vec<String>,
+    #[serde(skip)]
+    start: Instant,
+}
+
+impl TestRecord {
+    fn new(kind: TestKind, name: &str, file: Option<String>) -> Self {
+        Self {
+            kind,
+            name: name.to_owned(),
+            file,
+            duration: 0.0,
+            status: TestStatus::Running,
+            messages: Vec::new(),
+            diagnostics: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+}
+
+/// The results of the test cases and examples in a run.
+#[derive(Default)]
+pub(super) struct TestRecords {
+    records: Mutex<Vec<TestRecord>>,
+}
+
+impl TestRecords {
+    /// Starts to record a case.
+    pub fn start(&self, kind: TestKind, name: &str, file: FileId) {
+        let file = unix_slash(file.vpath().as_rootless_path());
+        self.records
+            .lock()
+            .push(TestRecord::new(kind, name, Some(file)));
+    }
+
+    /// Finishes a case, which keeps failed once it fails.
+    pub fn finish(&self, kind: TestKind, name: &str, message: Option<String>) {
+        self.with_record(kind, name, |record| {
+            record.duration = record.start.elapsed().as_secs_f64();
+            match message {
+                Some(message) => {
+                    record.status = TestStatus::Failed;
+                    record.messages.push(message);
+                }
+                None if record.status == TestStatus::Running => {
+                    record.status = TestStatus::Passed;
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// Attaches the diagnostics to a case.
+    pub fn attach(
+        &self,
+        world: &dyn World,
+        kind: TestKind,
+        name: &str,
+        diagnostics: &[SourceDiagnostic],
+    ) {
+        let diagnostics = diagnostics.iter().map(|diag| diagnostic_text(world, diag));
+        self.with_record(kind, name, |record| record.diagnostics.extend(diagnostics));
+    }
+
+    /// Updates the record of a case, which is created if the case fails
+    /// before starting.
+    fn with_record(&self, kind: TestKind, name: &str, f: impl FnOnce(&mut TestRecord)) {
+        let mut records = self.records.lock();
+        let idx = records
+            .iter()
+            .rposition(|record| record.kind == kind && record.name == name);
+        let idx = idx.unwrap_or_else(|| {
+            records.push(TestRecord::new(kind, name, None));
+            records.len() - 1
+        });
+        f(&mut records[idx]);
+    }
+
+    /// Writes the reports.
+    pub fn write(&self, reports: &[ReportArg], elapsed: Duration) -> Result<()> {
+        if reports.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = self.records.lock();
+        records.sort_by(|a, b| (&a.file, &a.name).cmp(&(&b.file, &b.name)));
+
+        for report in reports {
+            let data = match report.format {
+                ReportFormat::Junit => to_junit(&records, elapsed),
+                ReportFormat::Json => to_json(&records, elapsed)?,
+            };
+            if let Some(parent) = report.path.parent() {
+                std::fs::create_dir_all(parent).context("create report dir")?;
+            }
+            write_atomic(&report.path, data).context("write report")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serializes the kind of a case by its name.
+fn serialize_kind<S: serde::Serializer>(kind: &TestKind, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(kind.name())
+}
+
+/// Formats a diagnostic as `path:line:column: severity: message`.
+fn diagnostic_text(world: &dyn World, diag: &SourceDiagnostic) -> String {
+    let severity = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let location = diag.span.id().and_then(|id| {
+        let source = world.source(id).ok()?;
+        let range = source.range(diag.span)?;
+        let line = source.byte_to_line(range.start)?;
+        let column = source.byte_to_column(range.start)?;
+        let path = unix_slash(id.vpath().as_rootless_path());
+        Some(format!("{path}:{}:{}: ", line + 1, column + 1))
+    });
+
+    let mut text = format!(
+        "{}{severity}: {}",
+        location.unwrap_or_default(),
+        diag.message
+    );
+    for hint in &diag.hints {
+        let _ = write!(text, "\n  hint: {hint}");
+    }
+    text
+}
+
+fn to_json(records: &[TestRecord], elapsed: Duration) -> Result<String> {
+    let failed = records.iter().filter(|r| r.status.is_failure()).count();
+    let report = serde_json::json!({
+        "tests": records.len(),
+        "passed": records.len() - failed,
+        "failed": failed,
+        "duration": elapsed.as_secs_f64(),
+        "cases": records,
+    });
+    serde_json::to_string_pretty(&report).context("serialize report")
+}
+
+fn to_junit(records: &[TestRecord], elapsed: Duration) -> String {
+    let failures = records.iter().filter(|r| r.status.is_failure()).count();
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<testsuites name="tinymist" tests="{}" failures="{failures}" errors="0" time="{:.3}">"#,
+        records.len(),
+        elapsed.as_secs_f64()
+    );
+
+    for suite in records.chunk_by(|a, b| a.file == b.file) {
+        let name = escape_xml(suite[0].file.as_deref().unwrap_or("<unknown>"));
+        let failures = suite.iter().filter(|r| r.status.is_failure()).count();
+        let time = suite.iter().map(|r| r.duration).sum::<f64>();
+        let _ = writeln!(
+            out,
+            r#"  <testsuite name="{name}" tests="{}" failures="{failures}" errors="0" skipped="0" time="{time:.3}">"#,
+            suite.len()
+        );
+
+        for record in suite {
+            let case = escape_xml(&record.name);
+            let _ = writeln!(
+                out,
+                r#"    <testcase name="{case}" classname="{name}" time="{:.3}">"#,
+                record.duration
+            );
+            let _ = writeln!(out, "      <properties>");
+            let _ = writeln!(
+                out,
+                r#"        <property name="kind" value="{}"/>"#,
+                record.kind.name()
+            );
+            let _ = writeln!(out, "      </properties>");
+
+            let diagnostics = escape_xml(&record.diagnostics.join("\n"));
+            match record.status.is_failure() {
+                false => {
+                    if !diagnostics.is_empty() {
+                        let _ = writeln!(out, "      <system-err>{diagnostics}</system-err>");
+                    }
+                }
+                true => {
+                    let message = if record.messages.is_empty() {
+                        "not finished".to_owned()
+                    } else {
+                        record.messages.join("; ")
+                    };
+                    let _ = writeln!(
+                        out,
+                        r#"      <failure message="{}">{diagnostics}</failure>"#,
+                        escape_xml(&message)
+                    );
+                }
+            }
+            let _ = writeln!(out, "    </testcase>");
+        }
+        let _ = writeln!(out, "  </testsuite>");
+    }
+    let _ = writeln!(out, "</testsuites>");
+
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        kind: TestKind,
+        name: &str,
+        file: Option<&str>,
+        duration: f64,
+        status: TestStatus,
+        messages: &[&str],
+        diagnostics: &[&str],
+    ) -> TestRecord {
+        TestRecord {
+            duration,
+            status,
+            messages: messages.iter().map(|s| s.to_string()).collect(),
+            diagnostics: diagnostics.iter().map(|s| s.to_string()).collect(),
+            ..TestRecord::new(kind, name, file.map(str::to_owned))
+        }
+    }
+
+    /// The records ordered as they are written.
+    fn records() -> Vec<TestRecord> {
+        vec![
+            record(
+                TestKind::Test,
+                "test-b",
+                None,
+                0.0,
+                TestStatus::Running,
+                &[],
+                &[],
+            ),
+            record(
+                TestKind::Example,
+                "ex<1>",
+                Some("tests/a.typ"),
+                0.25,
+                TestStatus::Failed,
+                &["mismatch \"a\" & 'b'", "diff"],
+                &["tests/a.typ:3:1: error: oops"],
+            ),
+            record(
+                TestKind::Panic,
+                "panic-on-a",
+                Some("tests/a.typ"),
+                0.5,
+                TestStatus::Passed,
+                &[],
+                &["tests/a.typ:1:2: warning: unused <x>"],
+            ),
+        ]
+    }
+
+    /// Sorts the keys of the objects, which keep their insertion order if
+    /// `serde_json` preserves the order.
+    fn sorted(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries = map.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                let entries = entries.into_iter().map(|(key, value)| (key, sorted(value)));
+                serde_json::Value::Object(entries.collect())
+            }
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.into_iter().map(sorted).collect())
+            }
+            value => value,
+        }
+    }
+
+    #[test]
+    fn test_junit() {
+        let junit = to_junit(&records(), Duration::from_millis(1500));
+        insta::assert_snapshot!(junit, @r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="tinymist" tests="3" failures="2" errors="0" time="1.500">
+          <testsuite name="&lt;unknown&gt;" tests="1" failures="1" errors="0" skipped="0" time="0.000">
+            <testcase name="test-b" classname="&lt;unknown&gt;" time="0.000">
+              <properties>
+                <property name="kind" value="test"/>
+              </properties>
+              <failure message="not finished"></failure>
+            </testcase>
+          </testsuite>
+          <testsuite name="tests/a.typ" tests="2" failures="1" errors="0" skipped="0" time="0.750">
+            <testcase name="ex&lt;1&gt;" classname="tests/a.typ" time="0.250">
+              <properties>
+                <property name="kind" value="example"/>
+              </properties>
+              <failure message="mismatch &quot;a&quot; &amp; &apos;b&apos;; diff">tests/a.typ:3:1: error: oops</failure>
+            </testcase>
+            <testcase name="panic-on-a" classname="tests/a.typ" time="0.500">
+              <properties>
+                <property name="kind" value="panic"/>
+              </properties>
+              <system-err>tests/a.typ:1:2: warning: unused &lt;x&gt;</system-err>
+            </testcase>
+          </testsuite>
+        </testsuites>
+        "#);
+    }
+
+    #[test]
+    fn test_json() {
+        let json = to_json(&records(), Duration::from_millis(1500)).unwrap();
+        let json = sorted(serde_json::from_str(&json).unwrap());
+        insta::assert_snapshot!(serde_json::to_string_pretty(&json).unwrap(), @r#"
+        {
+          "cases": [
+            {
+              "diagnostics": [],
+              "duration": 0.0,
+              "file": null,
+              "kind": "test",
+              "messages": [],
+              "name": "test-b",
+              "status": "running"
+            },
+            {
+              "diagnostics": [
+                "tests/a.typ:3:1: error: oops"
+              ],
+              "duration": 0.25,
+              "file": "tests/a.typ",
+              "kind": "example",
+              "messages": [
+                "mismatch \"a\" & 'b'",
+                "diff"
+              ],
+              "name": "ex<1>",
+              "status": "failed"
+            },
+            {
+              "diagnostics": [
+                "tests/a.typ:1:2: warning: unused <x>"
+              ],
+              "duration": 0.5,
+              "file": "tests/a.typ",
+              "kind": "panic",
+              "messages": [],
+              "name": "panic-on-a",
+              "status": "passed"
+            }
+          ],
+          "duration": 1.5,
+          "failed": 2,
+          "passed": 1,
+          "tests": 3
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_finish_keeps_failure() {
+        let records = TestRecords::default();
+        let file = FileId::new(None, typst::syntax::VirtualPath::new("tests/a.typ"));
+        records.start(TestKind::Example, "ex", file);
+        records.finish(TestKind::Example, "ex", Some("mismatch".into()));
+        records.finish(TestKind::Example, "ex", None);
+        // A case failing before starting is recorded without a file.
+        records.finish(TestKind::Test, "early", Some("compile error".into()));
+        // The cases of different kinds are recorded separately.
+        records.start(TestKind::Diagnostic, "ex", file);
+        records.finish(TestKind::Diagnostic, "ex", None);
+
+        let records = records.records.lock();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].status, TestStatus::Failed);
+        assert_eq!(records[0].file.as_deref(), Some("tests/a.typ"));
+        assert_eq!(records[1].kind, TestKind::Test);
+        assert_eq!(records[1].file, None);
+        assert_eq!(records[1].messages, ["compile error"]);
+        assert_eq!(records[2].kind, TestKind::Diagnostic);
+        assert_eq!(records[2].status, TestStatus::Passed);
+    }
+
+    #[test]
+    fn test_report_arg() {
+        let arg = "junit=target/junit.xml".parse::<ReportArg>().unwrap();
+        assert_eq!(arg.format, ReportFormat::Junit);
+        assert_eq!(arg.path, PathBuf::from("target/junit.xml"));
+
+        assert!("json=".parse::<ReportArg>().is_err());
+        assert!("xml=out.xml".parse::<ReportArg>().is_err());
+        assert!("out.xml".parse::<ReportArg>().is_err());
+    }
+}
@@ -67,13 +67,13 @@
   })
 }
 
-#let mismatch-example(example, hint) = {
+#let mismatch-example(example, hint, diff: none) = {
   example-set.update(it => {
     it.insert(example, "failed")
     it
   })
   ref-paths.update(it => {
-    it.insert(example, hint)
+    it.insert(example, (path: hint, diff-path: diff))
     it
   })
 }
@@ -125,17 +125,30 @@
 
     set page(height: auto)
 
-    for (example, hint) in ref-paths.pairs() {
+    for (example, record) in ref-paths.pairs() {
+      let hint = record.path
       page[
         == Hint #text(fill: blue.darken(10%), example) #label("hint-" + example)
 
-        #text(fill: red)[compare image at #text(fill: blue.darken(10%), "/" + hint)]
-        #grid(
-          align: center,
-          columns: (1fr, 1fr),
-          [Ref], [Got],
-          image("/" + hint), image("/" + hint.slice(0, hint.len() - 4) + ".tmp.png"),
-        )
+        #if hint != none [
+          #text(fill: red)[compare image at #text(fill: blue.darken(10%), "/" + hint)]
+        ]
+        #if hint != none and hint.ends-with(".png") {
+          let got = image("/" + hint.slice(0, hint.len() - 4) + ".tmp.png")
+          // The images are only diffed if the reference image exists, which
+          // may be ignored in favor of the hash file.
+          if record.diff-path == none {
+            grid(align: center, [Got], got)
+          } else {
+            grid(
+              align: center,
+              columns: (1fr, 1fr, 1fr),
+              column-gutter: 4pt,
+              [Ref], [Got], [Diff],
+              image("/" + hint), got, image("/" + record.diff-path),
+            )
+          }
+        }
       ]
     }
   }
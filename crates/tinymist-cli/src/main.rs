@@ -10,6 +10,7 @@ mod cmd {
     #[cfg(feature = "dap")]
     pub mod dap;
     pub mod generate_script;
+    pub mod lint;
     pub mod lsp;
     #[cfg(feature = "preview")]
     pub mod preview;
@@ -112,6 +113,8 @@ enum Commands {
     Cov(crate::cov::CovArgs),
     /// Test a document and gives summary
     Test(crate::test::TestArgs),
+    /// Lint a document and all its dependencies
+    Lint(crate::lint::LintArgs),
 }
 
 /// The main entry point.
@@ -134,7 +137,7 @@ fn main() -> Result<()> {
     set_translations(load_translations(tinymist_assets::L10N_DATA)?);
     // Starts logging
     let _ = tinymist::init_log(tinymist::InitLogOpts {
-        is_transient_cmd: matches!(cmd, Commands::Compile(..)),
+        is_transient_cmd: matches!(cmd, Commands::Compile(..) | Commands::Lint(..)),
         is_test_no_verbose: matches!(&cmd, Commands::Test(test) if !test.verbose),
         output: None,
     });
@@ -163,5 +166,6 @@ fn main() -> Result<()> {
 
         Commands::Cov(args) => crate::cov::cov_main(args),
         Commands::Test(args) => block_on(crate::test::test_main(args)),
+        Commands::Lint(args) => crate::lint::lint_main(args),
     }
 }
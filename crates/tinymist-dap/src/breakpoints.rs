@@ -0,0 +1,478 @@
+//! Conditions, hit counts and log messages of breakpoints.
+
+use std::cell::Cell;
+use std::sync::LazyLock;
+
+use ecow::{EcoString, eco_format};
+use parking_lot::Mutex;
+use tinymist_debug::{BreakpointItem, BreakpointKind, ResolvedBreakpoint};
+use tinymist_std::hash::FxHashMap;
+use tinymist_world::vfs::FileId;
+use typst::diag::SourceResult;
+use typst::foundations::{Repr, Value};
+
+use crate::{BreakpointContext, DebugAdaptor};
+
+/// The options deciding what happens when a breakpoint is hit.
+#[derive(Debug, Clone, Default)]
+pub struct BreakpointOptions {
+    /// An expression that must evaluate to `true` for the breakpoint to be
+    /// hit.
+    pub condition: Option<String>,
+    /// A condition on the number of hits, e.g., `3`, `== 3`, `> 3` or `% 3`.
+    /// A bare number is the same as `>=`.
+    pub hit_condition: Option<String>,
+    /// A message to log instead of stopping, in which the expressions in
+    /// braces are interpolated, e.g., `x = {x}`.
+    pub log_message: Option<String>,
+}
+
+/// A source breakpoint with its options.
+#[derive(Debug, Clone)]
+pub struct SourceBreakpoint {
+    /// The resolved breakpoint.
+    pub resolved: ResolvedBreakpoint,
+    /// The options of the breakpoint.
+    pub options: BreakpointOptions,
+}
+
+/// A function breakpoint with its options.
+#[derive(Debug, Clone)]
+pub struct FunctionBreakpoint {
+    /// The name of the function to break on.
+    pub name: EcoString,
+    /// The options of the breakpoint.
+    pub options: BreakpointOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BreakpointKey {
+    Source(FileId, usize),
+    Function(EcoString),
+}
+
+impl BreakpointKey {
+    fn new(id: usize, item: &BreakpointItem) -> Option<Self> {
+        // The function breakpoints stop at the call starts of the named
+        // functions.
+        match (&item.kind, &item.name) {
+            (BreakpointKind::CallStart | BreakpointKind::Function, Some(name)) => {
+                Some(Self::Function(name.clone()))
+            }
+            _ => Some(Self::Source(item.origin_span.id()?, id)),
+        }
+    }
+}
+
+struct BreakpointState {
+    options: BreakpointOptions,
+    hits: usize,
+}
+
+impl BreakpointState {
+    fn new(options: BreakpointOptions) -> Self {
+        Self { options, hits: 0 }
+    }
+}
+
+static BREAKPOINTS: LazyLock<Mutex<FxHashMap<BreakpointKey, BreakpointState>>> =
+    LazyLock::new(Mutex::default);
+
+thread_local! {
+    /// Whether a condition or a log message is being evaluated, in which case
+    /// the breakpoints are ignored.
+    static EVALUATING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Replaces the options of the source breakpoints in the file.
+pub(crate) fn set_source_options(fid: FileId, breakpoints: &[SourceBreakpoint]) {
+    let mut table = BREAKPOINTS.lock();
+    table.retain(|key, _| !matches!(key, BreakpointKey::Source(id, _) if *id == fid));
+    for bp in breakpoints {
+        let key = BreakpointKey::Source(bp.resolved.fid, bp.resolved.id);
+        table.insert(key, BreakpointState::new(bp.options.clone()));
+    }
+}
+
+/// Replaces the options of the function breakpoints.
+pub(crate) fn set_function_options(breakpoints: &[FunctionBreakpoint]) {
+    let mut table = BREAKPOINTS.lock();
+    table.retain(|key, _| !matches!(key, BreakpointKey::Function(..)));
+    for bp in breakpoints {
+        let key = BreakpointKey::Function(bp.name.clone());
+        table.insert(key, BreakpointState::new(bp.options.clone()));
+    }
+}
+
+/// Removes the options of all breakpoints.
+pub(crate) fn clear_options() {
+    BREAKPOINTS.lock().clear();
+}
+
+/// Whether the breakpoints are ignored on the current thread.
+pub(crate) fn is_evaluating() -> bool {
+    EVALUATING.get()
+}
+
+/// Checks the options of a hit breakpoint, logging the message of a logpoint.
+/// Returns whether the execution should stop.
+pub(crate) fn should_stop(
+    ctx: &BreakpointContext,
+    id: usize,
+    item: &BreakpointItem,
+    adaptor: &dyn DebugAdaptor,
+) -> bool {
+    let Some(key) = BreakpointKey::new(id, item) else {
+        return true;
+    };
+    check(&key, &|expr| evaluate(ctx, expr), &mut |message| {
+        adaptor.log(ctx, message)
+    })
+}
+
+/// Checks the options of the breakpoint with the key, evaluating the
+/// expressions with `eval` and reporting the messages with `log`.
+fn check(
+    key: &BreakpointKey,
+    eval: &dyn Fn(&str) -> SourceResult<Value>,
+    log: &mut dyn FnMut(EcoString),
+) -> bool {
+    let Some(options) = BREAKPOINTS.lock().get(key).map(|bp| bp.options.clone()) else {
+        return true;
+    };
+
+    if let Some(condition) = &options.condition {
+        match eval(condition) {
+            Ok(Value::Bool(true)) => {}
+            Ok(Value::Bool(false)) => return false,
+            Ok(value) => {
+                let ty = value.ty().repr();
+                log(eco_format!("condition `{condition}` is {ty}, not bool"));
+                return true;
+            }
+            Err(errors) => {
+                let message = error_message(&errors);
+                log(eco_format!("condition `{condition}` failed: {message}"));
+                return true;
+            }
+        }
+    }
+
+    let hits = {
+        let mut table = BREAKPOINTS.lock();
+        let Some(bp) = table.get_mut(key) else {
+            return true;
+        };
+        bp.hits += 1;
+        bp.hits
+    };
+
+    if let Some(hit_condition) = &options.hit_condition {
+        match hit_matches(hit_condition, hits) {
+            Some(true) => {}
+            Some(false) => return false,
+            None => {
+                log(eco_format!("invalid hit condition `{hit_condition}`"));
+                return true;
+            }
+        }
+    }
+
+    if let Some(log_message) = &options.log_message {
+        log(interpolate(eval, log_message));
+        return false;
+    }
+
+    true
+}
+
+/// Evaluates an expression, ignoring the breakpoints hit by the evaluation.
+fn evaluate(ctx: &BreakpointContext, expr: &str) -> SourceResult<Value> {
+    let evaluating = EVALUATING.replace(true);
+    let result = ctx.evaluate(expr);
+    EVALUATING.set(evaluating);
+    result
+}
+
+fn error_message(errors: &[typst::diag::SourceDiagnostic]) -> EcoString {
+    errors
+        .first()
+        .map(|error| error.message.clone())
+        .unwrap_or_else(|| "unknown error".into())
+}
+
+/// Checks the number of hits against a hit condition. Returns `None` if the
+/// condition is invalid.
+fn hit_matches(condition: &str, hits: usize) -> Option<bool> {
+    let condition = condition.trim();
+    let (op, count) = ["==", ">=", "<=", "=", ">", "<", "%"]
+        .into_iter()
+        .find_map(|op| Some((op, condition.strip_prefix(op)?)))
+        .unwrap_or((">=", condition));
+    let count = count.trim().parse::<usize>().ok()?;
+
+    Some(match op {
+        "==" | "=" => hits == count,
+        ">=" => hits >= count,
+        "<=" => hits <= count,
+        ">" => hits > count,
+        "<" => hits < count,
+        _ => count != 0 && hits % count == 0,
+    })
+}
+
+/// Interpolates the expressions in braces of a log message.
+fn interpolate(eval: &dyn Fn(&str) -> SourceResult<Value>, message: &str) -> EcoString {
+    let mut output = EcoString::new();
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+
+        let mut depth = 0usize;
+        let end = tail.char_indices().find_map(|(idx, ch)| match ch {
+            '{' => {
+                depth += 1;
+                None
+            }
+            '}' if depth == 0 => Some(idx),
+            '}' => {
+                depth -= 1;
+                None
+            }
+            _ => None,
+        });
+        let Some(end) = end else {
+            rest = &rest[start..];
+            break;
+        };
+
+        match eval(&tail[..end]) {
+            Ok(Value::Str(value)) => output.push_str(&value),
+            Ok(value) => output.push_str(&value.repr()),
+            Err(errors) => {
+                output.push_str("<error: ");
+                output.push_str(&error_message(&errors));
+                output.push('>');
+            }
+        }
+        rest = &tail[end + 1..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use ecow::eco_vec;
+    use typst::diag::SourceDiagnostic;
+    use typst::syntax::{Span, VirtualPath};
+
+    use super::*;
+
+    /// Evaluates the expressions with the bindings `x = 1`, `s = "hi"`,
+    /// `yes = true` and `no = false`.
+    fn eval(expr: &str) -> SourceResult<Value> {
+        Ok(match expr.trim() {
+            "x" => Value::Int(1),
+            "s" => Value::Str("hi".into()),
+            "yes" => Value::Bool(true),
+            "no" => Value::Bool(false),
+            expr => {
+                let message = eco_format!("unknown variable: {expr}");
+                return Err(eco_vec![SourceDiagnostic::error(Span::detached(), message)]);
+            }
+        })
+    }
+
+    /// Registers a function breakpoint with the options, which is named
+    /// uniquely by the test.
+    fn breakpoint(name: &str, options: BreakpointOptions) -> BreakpointKey {
+        let mut table = BREAKPOINTS.lock();
+        let key = BreakpointKey::Function(name.into());
+        table.insert(key.clone(), BreakpointState::new(options));
+        key
+    }
+
+    /// Hits the breakpoint, returning whether it stops and the logged
+    /// messages.
+    fn hit(key: &BreakpointKey) -> (bool, Vec<EcoString>) {
+        let mut logs = Vec::new();
+        let stop = check(key, &eval, &mut |message| logs.push(message));
+        (stop, logs)
+    }
+
+    #[test]
+    fn test_key_of_item() {
+        let fid = FileId::new(None, VirtualPath::new("/main.typ"));
+        let item = |kind, name: Option<&str>| BreakpointItem {
+            origin_span: Span::from_range(fid, 0..1),
+            kind,
+            name: name.map(EcoString::from),
+        };
+
+        // The options of a function breakpoint are checked at the call start.
+        let key = breakpoint(
+            "call_start_cond",
+            BreakpointOptions {
+                condition: Some("no".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        let call = item(BreakpointKind::CallStart, Some("call_start_cond"));
+        assert_eq!(BreakpointKey::new(1, &call), Some(key.clone()));
+        assert_eq!(hit(&key), (false, vec![]));
+
+        let anonymous = item(BreakpointKind::CallStart, None);
+        assert_eq!(
+            BreakpointKey::new(1, &anonymous),
+            Some(BreakpointKey::Source(fid, 1))
+        );
+        let line = item(BreakpointKind::Line, None);
+        assert_eq!(
+            BreakpointKey::new(2, &line),
+            Some(BreakpointKey::Source(fid, 2))
+        );
+    }
+
+    #[test]
+    fn test_hit_condition() {
+        assert_eq!(hit_matches("3", 2), Some(false));
+        assert_eq!(hit_matches("3", 4), Some(true));
+        assert_eq!(hit_matches("== 3", 3), Some(true));
+        assert_eq!(hit_matches("==3", 4), Some(false));
+        assert_eq!(hit_matches("> 3", 3), Some(false));
+        assert_eq!(hit_matches("<= 3", 3), Some(true));
+        assert_eq!(hit_matches("% 2", 4), Some(true));
+        assert_eq!(hit_matches("% 2", 5), Some(false));
+        assert_eq!(hit_matches("% 0", 5), Some(false));
+        assert_eq!(hit_matches("often", 1), None);
+    }
+
+    #[test]
+    fn test_no_options() {
+        let key = breakpoint("no_options", BreakpointOptions::default());
+        assert_eq!(hit(&key), (true, vec![]));
+
+        let unknown = BreakpointKey::Function("unknown".into());
+        assert_eq!(hit(&unknown), (true, vec![]));
+    }
+
+    #[test]
+    fn test_hit_count_operators() {
+        let stops = |name: &str, hit_condition: &str| {
+            let key = breakpoint(
+                name,
+                BreakpointOptions {
+                    hit_condition: Some(hit_condition.into()),
+                    ..BreakpointOptions::default()
+                },
+            );
+            (0..5).map(|_| hit(&key).0).collect::<Vec<_>>()
+        };
+
+        assert_eq!(stops("hit_ge", "3"), [false, false, true, true, true]);
+        assert_eq!(stops("hit_eq", "== 2"), [false, true, false, false, false]);
+        assert_eq!(stops("hit_gt", "> 3"), [false, false, false, true, true]);
+        assert_eq!(stops("hit_lt", "< 3"), [true, true, false, false, false]);
+        assert_eq!(stops("hit_mod", "% 2"), [false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_invalid_hit_condition() {
+        let key = breakpoint(
+            "hit_invalid",
+            BreakpointOptions {
+                hit_condition: Some("often".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        let logs = vec![EcoString::from("invalid hit condition `often`")];
+        assert_eq!(hit(&key), (true, logs));
+    }
+
+    #[test]
+    fn test_condition() {
+        let condition = |name: &str, condition: &str| {
+            breakpoint(
+                name,
+                BreakpointOptions {
+                    condition: Some(condition.into()),
+                    ..BreakpointOptions::default()
+                },
+            )
+        };
+
+        assert_eq!(hit(&condition("cond_true", "yes")), (true, vec![]));
+        assert_eq!(hit(&condition("cond_false", "no")), (false, vec![]));
+    }
+
+    #[test]
+    fn test_condition_errors() {
+        let key = breakpoint(
+            "cond_not_bool",
+            BreakpointOptions {
+                condition: Some("x".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        let logs = vec![EcoString::from("condition `x` is int, not bool")];
+        assert_eq!(hit(&key), (true, logs));
+
+        let key = breakpoint(
+            "cond_failed",
+            BreakpointOptions {
+                condition: Some("y".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        let logs = vec![EcoString::from("condition `y` failed: unknown variable: y")];
+        assert_eq!(hit(&key), (true, logs));
+    }
+
+    #[test]
+    fn test_condition_before_hit_count() {
+        // The hits are only counted when the condition holds.
+        let key = breakpoint(
+            "cond_hits",
+            BreakpointOptions {
+                condition: Some("no".into()),
+                hit_condition: Some("== 1".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        assert!(!hit(&key).0);
+
+        BREAKPOINTS.lock().get_mut(&key).unwrap().options.condition = None;
+        assert!(hit(&key).0);
+        assert!(!hit(&key).0);
+    }
+
+    #[test]
+    fn test_logpoint() {
+        let key = breakpoint(
+            "logpoint",
+            BreakpointOptions {
+                log_message: Some("x = {x}, s = {s}, y = {y}, open {x".into()),
+                ..BreakpointOptions::default()
+            },
+        );
+        let logs = vec![EcoString::from(
+            "x = 1, s = hi, y = <error: unknown variable: y>, open {x",
+        )];
+        assert_eq!(hit(&key), (false, logs));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(interpolate(&eval, "plain"), "plain");
+        assert_eq!(interpolate(&eval, "{x}{s}"), "1hi");
+        assert_eq!(interpolate(&eval, "{ yes }!"), "true!");
+        assert_eq!(
+            interpolate(&eval, "{(a: {x})}"),
+            "<error: unknown variable: (a: {x})>"
+        );
+        assert_eq!(interpolate(&eval, "}{"), "}{");
+    }
+}
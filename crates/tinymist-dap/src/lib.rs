@@ -14,21 +14,31 @@
 //         this.sendEvent(new TerminatedEvent());
 //       });
 
-pub use tinymist_debug::BreakpointKind;
+mod breakpoints;
+mod variables;
+pub use breakpoints::{BreakpointOptions, FunctionBreakpoint, SourceBreakpoint};
+pub use variables::*;
 
+pub use tinymist_debug::{
+    BreakpointKind, CallFrame, ResolvedBreakpoint, breakpoint_locations, resolve_breakpoint,
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, mpsc};
 
 use comemo::Track;
 use comemo::Tracked;
+use ecow::EcoString;
 use parking_lot::Mutex;
-use tinymist_debug::{DebugSession, DebugSessionHandler, set_debug_session};
+use tinymist_debug::{BreakpointItem, DebugSession, DebugSessionHandler, set_debug_session};
+use tinymist_std::hash::FxHashMap;
 use tinymist_std::typst_shim::eval::{Eval, Vm};
-use tinymist_world::{CompilerFeat, CompilerWorld};
+use tinymist_world::{CompilerFeat, CompilerWorld, vfs::FileId};
 use typst::{
     __bail as bail, World,
     diag::{SourceResult, Warned},
     engine::{Engine, Route, Sink, Traced},
-    foundations::{Context, Scopes, Value},
+    foundations::{Context, Scopes, Styles, Value},
     introspection::Introspector,
     layout::PagedDocument,
     syntax::{Span, ast, parse_code},
@@ -42,6 +52,25 @@ pub enum DebugRequest {
     Evaluate(RequestId, String),
     /// Continues the execution.
     Continue,
+    /// Steps the execution to the next statement. Only the first statements
+    /// on lines are stopped at if `by_line` is set.
+    Step {
+        /// The kind of the step.
+        kind: StepKind,
+        /// Whether to step by lines rather than statements.
+        by_line: bool,
+    },
+}
+
+/// The kind of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    /// Steps to the next statement, stepping over the function calls.
+    Over,
+    /// Steps to the next statement, stepping into the function calls.
+    In,
+    /// Steps to the next statement after the current function returns.
+    Out,
 }
 
 /// A handler for debug events.
@@ -56,40 +85,174 @@ pub trait DebugAdaptor: Send + Sync {
     fn stopped(&self, ctx: &BreakpointContext);
     /// Responds to a debug request.
     fn respond(&self, id: RequestId, result: SourceResult<Value>);
+    /// Logs a message of a logpoint or an error of a breakpoint condition.
+    fn log(&self, ctx: &BreakpointContext, message: EcoString);
+}
+
+/// The options of a debug session.
+#[derive(Debug, Default)]
+pub struct SessionOptions {
+    /// Whether to stop before the compilation.
+    pub stop_on_entry: bool,
+    /// The source breakpoints to enable, grouped by file.
+    pub breakpoints: FxHashMap<FileId, Vec<SourceBreakpoint>>,
+    /// The function breakpoints to enable.
+    pub functions: Vec<FunctionBreakpoint>,
 }
 
-/// Starts a debug session.
+/// Starts a debug session, which compiles the document in a thread.
 pub fn start_session<F: CompilerFeat>(
     base: CompilerWorld<F>,
     adaptor: Arc<dyn DebugAdaptor>,
-    rx: mpsc::Receiver<DebugRequest>,
-) {
-    let context = Arc::new(DebugContext {});
+    options: SessionOptions,
+) -> SessionHandle {
+    let (tx, rx) = mpsc::channel();
+    let aborted = Arc::new(AtomicBool::new(false));
+    let context = Arc::new(DebugContext {
+        aborted: aborted.clone(),
+    });
+
+    let is_aborted = aborted.clone();
+    let previous = ABORTED_SESSION.lock().take();
+    let thread = std::thread::spawn(move || {
+        if let Some(previous) = previous {
+            // A panic of the thread has been reported by the panic hook.
+            let _ = previous.join();
+        }
 
-    std::thread::spawn(move || {
+        let is_aborted = || is_aborted.load(Ordering::SeqCst);
         let world = tinymist_debug::instr_breakpoints(&base);
 
-        if !set_debug_session(Some(DebugSession::new(context))) {
+        let mut session = DebugSession::new(context);
+        for (fid, breakpoints) in &options.breakpoints {
+            let resolved = breakpoints.iter().map(|bp| bp.resolved.clone());
+            session.set_source_breakpoints(*fid, &resolved.collect::<Vec<_>>());
+        }
+        session.set_function_breakpoints(options.functions.iter().map(|bp| bp.name.clone()));
+        if !set_debug_session(Some(session)) {
             adaptor.terminate();
-            return None;
+            return;
+        }
+
+        let guard = SessionGuard;
+        breakpoints::clear_options();
+        for (fid, breakpoints) in &options.breakpoints {
+            breakpoints::set_source_options(*fid, breakpoints);
         }
+        breakpoints::set_function_options(&options.functions);
 
-        let _lock = ResourceLock::new(adaptor.clone(), rx);
+        let lock = ResourceLock::new(adaptor.clone(), rx);
 
         adaptor.before_compile();
-        step_global(BreakpointKind::BeforeCompile, &world);
+        if options.stop_on_entry && !is_aborted() {
+            step_global(BreakpointKind::BeforeCompile, &world);
+        }
 
         let result = typst::compile::<PagedDocument>(&world);
 
+        // An aborted session has been torn down by the client, which expects
+        // no more events.
+        if is_aborted() {
+            return;
+        }
         adaptor.after_compile(result);
         step_global(BreakpointKind::AfterCompile, &world);
 
-        *RESOURCES.lock() = None;
-        set_debug_session(None);
-
+        drop(lock);
+        drop(guard);
         adaptor.terminate();
-        Some(())
     });
+
+    SessionHandle {
+        aborted,
+        tx: Some(tx),
+        thread: Some(thread),
+    }
+}
+
+/// A handle to a running debug session. The session is aborted when the
+/// handle is dropped.
+pub struct SessionHandle {
+    /// Whether the session is aborted.
+    aborted: Arc<AtomicBool>,
+    /// The sender of the requests to the debugger.
+    tx: Option<mpsc::Sender<DebugRequest>>,
+    /// The thread compiling the document.
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl SessionHandle {
+    /// Sends a request to the debugger. Returns `false` if the session has
+    /// terminated.
+    pub fn send(&self, request: DebugRequest) -> bool {
+        self.tx.as_ref().is_some_and(|tx| tx.send(request).is_ok())
+    }
+
+    /// Aborts the session without waiting for its thread. The breakpoints are
+    /// no longer hit, so the compilation runs to the end without sending
+    /// events. The next session waits for the thread to exit before it
+    /// starts.
+    pub fn abort(&mut self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        // Wakes the debugger up if it is stopped.
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            *ABORTED_SESSION.lock() = Some(thread);
+        }
+    }
+}
+
+impl Drop for SessionHandle {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+/// The thread of the last aborted session, which may still be running. Since
+/// each session waits for the previous one, it is the only thread to wait for.
+static ABORTED_SESSION: Mutex<Option<std::thread::JoinHandle<()>>> = Mutex::new(None);
+
+/// Resets the global state of the debug session when the session thread exits,
+/// even if it panics.
+struct SessionGuard;
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        set_debug_session(None);
+        breakpoints::clear_options();
+    }
+}
+
+/// Replaces the source breakpoints of the file in the running debug session.
+/// Returns `false` if no session is running.
+pub fn set_breakpoints(fid: FileId, breakpoints: &[SourceBreakpoint]) -> bool {
+    let resolved = breakpoints
+        .iter()
+        .map(|bp| bp.resolved.clone())
+        .collect::<Vec<_>>();
+    let running = tinymist_debug::with_debug_session_mut(|session| {
+        session.set_source_breakpoints(fid, &resolved);
+    })
+    .is_some();
+
+    if running {
+        breakpoints::set_source_options(fid, breakpoints);
+    }
+    running
+}
+
+/// Replaces the function breakpoints in the running debug session. Returns
+/// `false` if no session is running.
+pub fn set_function_breakpoints(breakpoints: &[FunctionBreakpoint]) -> bool {
+    let running = tinymist_debug::with_debug_session_mut(|session| {
+        session.set_function_breakpoints(breakpoints.iter().map(|bp| bp.name.clone()));
+    })
+    .is_some();
+
+    if running {
+        breakpoints::set_function_options(breakpoints);
+    }
+    running
 }
 
 static RESOURCES: Mutex<Option<Resource>> = Mutex::new(None);
@@ -97,13 +260,45 @@ static RESOURCES: Mutex<Option<Resource>> = Mutex::new(None);
 struct Resource {
     adaptor: Arc<dyn DebugAdaptor>,
     rx: mpsc::Receiver<DebugRequest>,
+    /// The step in progress.
+    step: Option<Step>,
+}
+
+/// A step in progress, started at the call depth.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    kind: StepKind,
+    depth: usize,
+    by_line: bool,
+}
+
+impl Step {
+    /// Whether the step stops at the breakpoint hit at the call depth.
+    fn is_hit(&self, kind: BreakpointKind, depth: usize) -> bool {
+        let is_statement = match kind {
+            BreakpointKind::Line => true,
+            BreakpointKind::Expr => !self.by_line,
+            _ => false,
+        };
+
+        is_statement
+            && match self.kind {
+                StepKind::Over => depth <= self.depth,
+                StepKind::In => true,
+                StepKind::Out => depth < self.depth,
+            }
+    }
 }
 
 struct ResourceLock;
 
 impl ResourceLock {
     fn new(adaptor: Arc<dyn DebugAdaptor>, rx: mpsc::Receiver<DebugRequest>) -> Self {
-        RESOURCES.lock().replace(Resource { adaptor, rx });
+        RESOURCES.lock().replace(Resource {
+            adaptor,
+            rx,
+            step: None,
+        });
 
         Self
     }
@@ -142,6 +337,7 @@ fn step_global(kind: BreakpointKind, world: &dyn World) {
         scopes: Scopes::new(Some(world.library())),
         span,
         kind,
+        stepped: false,
     };
 
     step(&context, resource.as_mut().unwrap());
@@ -151,6 +347,8 @@ fn step_global(kind: BreakpointKind, world: &dyn World) {
 pub struct BreakpointContext<'a, 'b, 'c> {
     /// The breakpoint kind.
     pub kind: BreakpointKind,
+    /// Whether the execution stops after a step rather than at a breakpoint.
+    pub stepped: bool,
 
     engine: &'a Engine<'c>,
     context: Tracked<'a, Context<'b>>,
@@ -159,6 +357,41 @@ pub struct BreakpointContext<'a, 'b, 'c> {
 }
 
 impl BreakpointContext<'_, '_, '_> {
+    /// The span where the execution stops, which is detached for the global
+    /// breakpoints.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The function calls being evaluated, innermost first. It is empty for
+    /// the global breakpoints.
+    pub fn frames(&self) -> Vec<CallFrame> {
+        if self.span.is_detached() {
+            return Vec::new();
+        }
+
+        let mut frames = tinymist_debug::call_stack();
+        if let Some(top) = frames.first_mut() {
+            top.span = self.span;
+        }
+        frames
+    }
+
+    /// The local bindings in scope where the execution stops, innermost
+    /// first.
+    pub fn locals(&self) -> Vec<(EcoString, Value)> {
+        self.scopes
+            .top
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding.read().clone()))
+            .collect()
+    }
+
+    /// The styles in effect where the execution stops, if they are known.
+    pub fn styles(&self) -> Option<Styles> {
+        self.context.styles().ok().map(|chain| chain.to_map())
+    }
+
     fn evaluate(&self, expr: &str) -> SourceResult<Value> {
         let mut root = parse_code(expr);
         root.synthesize(self.span);
@@ -181,8 +414,13 @@ impl BreakpointContext<'_, '_, '_> {
         };
         let mut vm = Vm::new(engine, self.context, self.scopes.clone(), root.span());
 
-        // Evaluate the code.
-        let output = root.cast::<ast::Code>().unwrap().eval(&mut vm)?;
+        // Evaluate the code, leaving the calls aborted by an error.
+        let depth = tinymist_debug::call_depth();
+        let output = root.cast::<ast::Code>().unwrap().eval(&mut vm);
+        if output.is_err() {
+            tinymist_debug::unwind_calls(depth);
+        }
+        let output = output?;
 
         // Handle control flow.
         if let Some(flow) = vm.flow {
@@ -199,12 +437,20 @@ fn step(ctx: &BreakpointContext, resource: &mut Resource) {
         match resource.rx.recv() {
             Ok(DebugRequest::Evaluate(id, expr)) => {
                 let res = ctx.evaluate(&expr);
-                eprintln!("evaluate: {expr} => {res:?}");
                 resource.adaptor.respond(id, res);
             }
             Ok(DebugRequest::Continue) => {
                 break;
             }
+            Ok(DebugRequest::Step { kind, by_line }) => {
+                resource.step = Some(Step {
+                    kind,
+                    depth: tinymist_debug::call_depth(),
+                    by_line,
+                });
+                tinymist_debug::with_debug_session_mut(|session| session.set_stepping(true));
+                break;
+            }
             Err(mpsc::RecvError) => {
                 break;
             }
@@ -212,7 +458,10 @@ fn step(ctx: &BreakpointContext, resource: &mut Resource) {
     }
 }
 
-struct DebugContext {}
+struct DebugContext {
+    /// Whether the session is aborted, after which no breakpoint is hit.
+    aborted: Arc<AtomicBool>,
+}
 
 impl DebugSessionHandler for DebugContext {
     fn on_breakpoint(
@@ -220,17 +469,91 @@ impl DebugSessionHandler for DebugContext {
         engine: &Engine,
         context: Tracked<Context>,
         scopes: Scopes,
-        span: Span,
-        kind: BreakpointKind,
+        id: usize,
+        item: &BreakpointItem,
+        enabled: bool,
     ) {
-        let mut resource = RESOURCES.lock();
-        let context = BreakpointContext {
+        if breakpoints::is_evaluating() || self.aborted.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut context = BreakpointContext {
             engine,
             context,
             scopes,
-            span,
-            kind,
+            span: item.origin_span,
+            kind: item.kind,
+            stepped: false,
+        };
+
+        let resource = RESOURCES.lock();
+        let Some((adaptor, pending)) = resource.as_ref().map(|res| (res.adaptor.clone(), res.step))
+        else {
+            return;
+        };
+        drop(resource);
+
+        let hit = enabled && breakpoints::should_stop(&context, id, item, adaptor.as_ref());
+        let depth = tinymist_debug::call_depth();
+        let stepped = pending.is_some_and(|pending| pending.is_hit(item.kind, depth));
+        if !hit && !stepped {
+            return;
+        }
+        context.stepped = !hit;
+
+        let mut resource = RESOURCES.lock();
+        let Some(resource) = resource.as_mut() else {
+            return;
         };
-        step(&context, resource.as_mut().unwrap());
+        resource.step = None;
+        tinymist_debug::with_debug_session_mut(|session| session.set_stepping(false));
+        step(&context, resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A step started at the call depth 1.
+    fn start(kind: StepKind, by_line: bool) -> Step {
+        Step {
+            kind,
+            depth: 1,
+            by_line,
+        }
+    }
+
+    #[test]
+    fn test_step_over() {
+        let step = start(StepKind::Over, false);
+        assert!(step.is_hit(BreakpointKind::Line, 1));
+        assert!(step.is_hit(BreakpointKind::Expr, 1));
+        assert!(step.is_hit(BreakpointKind::Line, 0));
+        assert!(!step.is_hit(BreakpointKind::Line, 2));
+        assert!(!step.is_hit(BreakpointKind::CallStart, 1));
+    }
+
+    #[test]
+    fn test_step_in() {
+        let step = start(StepKind::In, false);
+        assert!(step.is_hit(BreakpointKind::Line, 2));
+        assert!(step.is_hit(BreakpointKind::Expr, 0));
+        assert!(!step.is_hit(BreakpointKind::CallEnd, 1));
+    }
+
+    #[test]
+    fn test_step_out() {
+        let step = start(StepKind::Out, false);
+        assert!(step.is_hit(BreakpointKind::Line, 0));
+        assert!(!step.is_hit(BreakpointKind::Line, 1));
+        assert!(!step.is_hit(BreakpointKind::Expr, 2));
+    }
+
+    #[test]
+    fn test_step_by_line() {
+        let step = start(StepKind::Over, true);
+        assert!(step.is_hit(BreakpointKind::Line, 1));
+        assert!(!step.is_hit(BreakpointKind::Expr, 1));
     }
 }
@@ -0,0 +1,201 @@
+//! Inspection of the values in a stopped debug session.
+
+use ecow::{EcoString, eco_format};
+use tinymist_analysis::func_signature;
+use tinymist_analysis::upstream::truncated_repr_;
+use typst::foundations::{Repr, Styles, Value};
+
+/// The maximum length of the representation shown for a value.
+const REPR_LIMIT: usize = 1024;
+
+/// A variable shown in the variables view of a debugger.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    /// The name of the variable.
+    pub name: EcoString,
+    /// The representation of the value.
+    pub value: EcoString,
+    /// The type of the value.
+    pub ty: Option<EcoString>,
+    /// The value to list children of when the variable is expanded, which is
+    /// `None` if the variable has no children.
+    pub expand: Option<Value>,
+}
+
+/// Creates a variable from a value.
+pub fn variable(name: EcoString, value: &Value) -> Variable {
+    let expandable = match value {
+        Value::Array(array) => !array.is_empty(),
+        Value::Dict(dict) => !dict.is_empty(),
+        Value::Content(..) | Value::Func(..) | Value::Module(..) => true,
+        _ => false,
+    };
+
+    Variable {
+        name,
+        value: truncated_repr_::<REPR_LIMIT>(value),
+        ty: Some(value.ty().repr()),
+        expand: expandable.then(|| value.clone()),
+    }
+}
+
+/// Lists the children of a value: the items of an array, the pairs of a
+/// dictionary, the fields of a content, the parameters of a function, or the
+/// bindings of a module.
+pub fn children(value: &Value) -> Vec<Variable> {
+    match value {
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| variable(eco_format!("{idx}"), item))
+            .collect(),
+        Value::Dict(dict) => dict
+            .iter()
+            .map(|(key, value)| variable(key.as_str().into(), value))
+            .collect(),
+        Value::Content(content) => content
+            .fields()
+            .iter()
+            .map(|(key, value)| variable(key.as_str().into(), value))
+            .collect(),
+        Value::Func(func) => func_signature(func.clone())
+            .params()
+            .map(|(param, _)| {
+                let mut value = param.ty.describe().unwrap_or_else(|| "any".into());
+                if let Some(default) = &param.default {
+                    value = eco_format!("{value} = {default}");
+                }
+                let name: &str = param.name.as_ref();
+                let name = if param.attrs.variadic {
+                    eco_format!("..{name}")
+                } else {
+                    name.into()
+                };
+
+                Variable {
+                    name,
+                    value,
+                    ty: None,
+                    expand: None,
+                }
+            })
+            .collect(),
+        Value::Module(module) => module
+            .scope()
+            .iter()
+            .map(|(name, binding)| variable(name.clone(), binding.read()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Lists the style rules in a style chain, outermost first.
+pub fn style_variables(styles: &Styles) -> Vec<Variable> {
+    styles
+        .iter()
+        .enumerate()
+        .map(|(idx, style)| Variable {
+            name: eco_format!("{idx}"),
+            value: eco_format!("{style:?}"),
+            ty: None,
+            expand: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::{Element, Module, Scope, array, dict};
+    use typst::layout::HElem;
+    use typst::text::TextElem;
+
+    use super::*;
+
+    fn names(variables: &[Variable]) -> Vec<&str> {
+        variables.iter().map(|var| var.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_scalar() {
+        let var = variable("x".into(), &Value::Int(1));
+        assert_eq!(var.name, "x");
+        assert_eq!(var.value, "1");
+        assert_eq!(var.ty.as_deref(), Some("int"));
+        assert!(var.expand.is_none());
+        assert!(children(&Value::Int(1)).is_empty());
+    }
+
+    #[test]
+    fn test_array() {
+        let empty = variable("a".into(), &Value::Array(array![]));
+        assert!(empty.expand.is_none());
+
+        let value = Value::Array(array![1, "b", array![2]]);
+        let var = variable("a".into(), &value);
+        assert_eq!(var.value, r#"(1, "b", (2,))"#);
+
+        let items = children(var.expand.as_ref().unwrap());
+        assert_eq!(names(&items), ["0", "1", "2"]);
+        assert_eq!(items[1].value, r#""b""#);
+        assert!(items[1].expand.is_none());
+        let nested = children(items[2].expand.as_ref().unwrap());
+        assert_eq!(names(&nested), ["0"]);
+        assert_eq!(nested[0].value, "2");
+    }
+
+    #[test]
+    fn test_dict() {
+        let empty = variable("d".into(), &Value::Dict(dict! {}));
+        assert!(empty.expand.is_none());
+
+        let value = Value::Dict(dict! { "x" => 1, "y" => dict! { "z" => true } });
+        let var = variable("d".into(), &value);
+        assert_eq!(var.ty.as_deref(), Some("dictionary"));
+
+        let pairs = children(var.expand.as_ref().unwrap());
+        assert_eq!(names(&pairs), ["x", "y"]);
+        assert_eq!(pairs[0].value, "1");
+        let nested = children(pairs[1].expand.as_ref().unwrap());
+        assert_eq!(names(&nested), ["z"]);
+        assert_eq!(nested[0].value, "true");
+    }
+
+    #[test]
+    fn test_content() {
+        let var = variable("c".into(), &Value::Content(TextElem::packed("hi")));
+        assert_eq!(var.ty.as_deref(), Some("content"));
+
+        let fields = children(var.expand.as_ref().unwrap());
+        let text = fields.iter().find(|var| var.name == "text").unwrap();
+        assert_eq!(text.value, r#""hi""#);
+    }
+
+    #[test]
+    fn test_func() {
+        let var = variable("h".into(), &Value::Func(Element::of::<HElem>().into()));
+        assert!(var.expand.is_some());
+
+        let params = children(var.expand.as_ref().unwrap());
+        assert_eq!(names(&params), ["amount", "weak"]);
+        assert!(params[1].value.ends_with("= false"), "{}", params[1].value);
+        assert!(params.iter().all(|param| param.expand.is_none()));
+    }
+
+    #[test]
+    fn test_module() {
+        let mut scope = Scope::new();
+        scope.define("x", 1);
+        scope.define("y", "z");
+        let var = variable("m".into(), &Value::Module(Module::new("m", scope)));
+        assert_eq!(var.ty.as_deref(), Some("module"));
+
+        let bindings = children(var.expand.as_ref().unwrap());
+        assert_eq!(names(&bindings), ["x", "y"]);
+        assert_eq!(bindings[1].value, r#""z""#);
+    }
+
+    #[test]
+    fn test_styles() {
+        assert!(style_variables(&Styles::new()).is_empty());
+    }
+}
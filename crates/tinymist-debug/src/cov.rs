@@ -18,6 +18,8 @@ use typst::{World, WorldExt};
 
 use crate::instrument::Instrumenter;
 
+mod report;
+
 /// The coverage result.
 pub struct CoverageResult {
     /// The coverage meta.
@@ -50,21 +52,50 @@ impl CoverageResult {
                 continue;
             };
 
-            let hits = region.hits.lock();
-            for (idx, (span, _kind)) in meta.meta.iter().enumerate() {
-                let Some(typst_range) = w.range(*span) else {
-                    continue;
-                };
-
-                let rng = tinymist_analysis::location::to_lsp_range(
+            let to_lsp_range = |span: Span| {
+                let typst_range = w.range(span)?;
+                Some(tinymist_analysis::location::to_lsp_range(
                     typst_range,
                     &typst_source,
                     lsp_position_encoding,
-                );
+                ))
+            };
+
+            let hits = region.hits.lock();
+            for (idx, (span, kind)) in meta.meta.iter().enumerate() {
+                if *kind == Kind::Branch {
+                    continue;
+                }
+                let Some(rng) = to_lsp_range(*span) else {
+                    continue;
+                };
 
                 details.push(VscodeFileCoverageDetail {
                     executed: hits[idx] > 0,
                     location: rng,
+                    branches: Vec::new(),
+                });
+            }
+
+            for branch in &meta.branches {
+                let Some(rng) = to_lsp_range(branch.span) else {
+                    continue;
+                };
+
+                let branches = branch
+                    .arms
+                    .iter()
+                    .map(|arm| VscodeBranchCoverageDetail {
+                        executed: hits[arm.region] > 0,
+                        location: to_lsp_range(meta.meta[arm.region].0),
+                        label: arm.label.clone(),
+                    })
+                    .collect::<Vec<_>>();
+
+                details.push(VscodeFileCoverageDetail {
+                    executed: branches.iter().any(|arm| arm.executed),
+                    location: rng,
+                    branches,
                 });
             }
 
@@ -114,6 +145,15 @@ impl SummarizedCoverage<'_> {
     }
 }
 
+/// The coverage of a file in the summary.
+struct FileSummary<'a> {
+    id: &'a FileId,
+    region_covered: usize,
+    region_total: usize,
+    arm_covered: usize,
+    arm_total: usize,
+}
+
 impl fmt::Display for SummarizedCoverage<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut ids = self.result.regions.keys().collect::<Vec<_>>();
@@ -131,22 +171,44 @@ impl fmt::Display for SummarizedCoverage<'_> {
                 let meta = self.result.meta.get(id)?;
 
                 let hits = region.hits.lock();
-                let region_covered = hits.par_iter().filter(|&&x| x > 0).count();
-
-                Some((id, region_covered, meta.meta.len()))
+                let regions = meta
+                    .meta
+                    .iter()
+                    .zip(hits.iter())
+                    .filter(|((_, kind), _)| *kind != Kind::Branch);
+                let region_total = regions.clone().count();
+                let region_covered = regions.filter(|(_, hits)| **hits > 0).count();
+
+                let arms = meta.branches.iter().flat_map(|branch| &branch.arms);
+                let arm_total = arms.clone().count();
+                let arm_covered = arms.filter(|arm| hits[arm.region] > 0).count();
+
+                Some(FileSummary {
+                    id,
+                    region_covered,
+                    region_total,
+                    arm_covered,
+                    arm_total,
+                })
             })
             .collect::<Vec<_>>();
 
-        let total = summary.iter().map(|(_, _, l)| l).sum::<usize>();
-        let covered = summary.iter().map(|(_, c, _)| c).sum::<usize>();
+        let total = summary.iter().map(|s| s.region_total).sum::<usize>();
+        let covered = summary.iter().map(|s| s.region_covered).sum::<usize>();
+        let arm_total = summary.iter().map(|s| s.arm_total).sum::<usize>();
+        let arm_covered = summary.iter().map(|s| s.arm_covered).sum::<usize>();
 
         if !self.short {
-            for (id, covered, total) in summary {
-                let id = format!("{:?}", WorkspaceResolver::display(Some(*id)));
-                self.line(f, &id, total, covered, false)?;
+            for s in &summary {
+                let id = format!("{:?}", WorkspaceResolver::display(Some(*s.id)));
+                self.line(f, &id, s.region_total, s.region_covered, false)?;
             }
         }
         self.line(f, "Coverage Summary", total, covered, true)?;
+        if arm_total > 0 {
+            writeln!(f)?;
+            self.line(f, "Branch Summary", arm_total, arm_covered, true)?;
+        }
 
         Ok(())
     }
@@ -162,6 +224,20 @@ pub struct VscodeFileCoverageDetail {
     pub executed: bool,
     /// The location of the coverage.
     pub location: LspRange,
+    /// The arms of the branch at the location.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<VscodeBranchCoverageDetail>,
+}
+
+/// The coverage of an arm of a branch in the VSCode coverage data.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VscodeBranchCoverageDetail {
+    /// Whether the arm is being executed
+    pub executed: bool,
+    /// The location of the arm.
+    pub location: Option<LspRange>,
+    /// The label of the arm.
+    pub label: String,
 }
 
 #[derive(Default)]
@@ -234,11 +310,41 @@ pub enum Kind {
     OpenBrace,
     CloseBrace,
     Show,
+    /// A region hit when an arm of a branch is taken, which is not a statement.
+    Branch,
 }
 
 #[derive(Default)]
 pub struct InstrumentMeta {
     pub meta: Vec<(Span, Kind)>,
+    /// The branches, whose arms are indexed into the regions.
+    pub branches: Vec<BranchMeta>,
+}
+
+/// A branching expression: a conditional, a loop or a show rule with an `or`
+/// selector.
+pub struct BranchMeta {
+    /// The span of the branching expression.
+    pub span: Span,
+    /// The arms of the branch.
+    pub arms: Vec<BranchArm>,
+}
+
+/// An arm of a branching expression.
+pub struct BranchArm {
+    /// The region hit when the arm is taken.
+    pub region: usize,
+    /// The label of the arm, e.g., `then` or `else`.
+    pub label: String,
+}
+
+impl BranchArm {
+    fn new(region: usize, label: impl Into<String>) -> Self {
+        Self {
+            region,
+            label: label.into(),
+        }
+    }
 }
 
 #[comemo::memoize]
@@ -261,14 +367,18 @@ struct InstrumentWorker {
 }
 
 impl InstrumentWorker {
-    fn instrument_block_child(&mut self, container: &SyntaxNode, b1: Span, b2: Span) {
+    /// Instruments the block children, returning the regions entering them.
+    fn instrument_block_child(&mut self, container: &SyntaxNode, b1: Span, b2: Span) -> Vec<usize> {
+        let mut entries = Vec::new();
         for child in container.children() {
             if b1 == child.span() || b2 == child.span() {
+                entries.push(self.meta.meta.len());
                 self.instrument_block(child);
             } else {
                 self.visit_node(child);
             }
         }
+        entries
     }
 
     fn visit_node(&mut self, node: &SyntaxNode) {
@@ -279,19 +389,43 @@ impl InstrumentWorker {
                     return;
                 }
                 ast::Expr::While(while_expr) => {
-                    self.instrument_block_child(node, while_expr.body().span(), Span::detached());
+                    self.instrument_loop(node, while_expr.body().span());
                     return;
                 }
                 ast::Expr::For(for_expr) => {
-                    self.instrument_block_child(node, for_expr.body().span(), Span::detached());
+                    self.instrument_loop(node, for_expr.body().span());
                     return;
                 }
                 ast::Expr::Conditional(cond_expr) => {
-                    self.instrument_block_child(
+                    let else_body = cond_expr.else_body();
+                    let entries = self.instrument_block_child(
                         node,
                         cond_expr.if_body().span(),
-                        cond_expr.else_body().unwrap_or_default().span(),
+                        else_body.unwrap_or_default().span(),
                     );
+                    let mut arms = entries.into_iter();
+                    let mut branch = BranchMeta {
+                        span: node.span(),
+                        arms: arms
+                            .next()
+                            .map(|then| BranchArm::new(then, "then"))
+                            .into_iter()
+                            .collect(),
+                    };
+                    // Counts the implicit `else` arm of a conditional without one.
+                    let else_region = match else_body {
+                        Some(..) => arms.next(),
+                        None => {
+                            self.instrumented.push_str(" else {\n");
+                            let region = self.make_cov(node.span(), Kind::Branch);
+                            self.instrumented.push('}');
+                            Some(region)
+                        }
+                    };
+                    branch
+                        .arms
+                        .extend(else_region.map(|region| BranchArm::new(region, "else")));
+                    self.meta.branches.push(branch);
                     return;
                 }
                 ast::Expr::Closure(closure) => {
@@ -301,13 +435,23 @@ impl InstrumentWorker {
                 ast::Expr::Show(show_rule) => {
                     let transform = show_rule.transform().to_untyped().span();
                     let is_set = matches!(show_rule.transform(), ast::Expr::Set(..));
+                    let arms = show_rule
+                        .selector()
+                        .and_then(selector_alternatives)
+                        .and_then(|alternatives| {
+                            alternatives
+                                .into_iter()
+                                .map(|alt| Some((alt, element_test(alt)?)))
+                                .collect::<Option<Vec<_>>>()
+                        })
+                        .unwrap_or_default();
 
                     for child in node.children() {
                         if transform == child.span() {
                             if is_set {
-                                self.instrument_show_set(child);
+                                self.instrument_show_set(node, child, &arms);
                             } else {
-                                self.instrument_show_transform(child);
+                                self.instrument_show_transform(node, child, &arms);
                             }
                         } else {
                             self.visit_node(child);
@@ -390,12 +534,13 @@ impl InstrumentWorker {
         }
     }
 
-    fn make_cov(&mut self, span: Span, kind: Kind) {
+    fn make_cov(&mut self, span: Span, kind: Kind) -> usize {
         let it = self.meta.meta.len();
         self.meta.meta.push((span, kind));
         self.instrumented.push_str("__cov_pc(");
         self.instrumented.push_str(&it.to_string());
         self.instrumented.push_str(");\n");
+        it
     }
 
     fn instrument_block(&mut self, child: &SyntaxNode) {
@@ -420,24 +565,156 @@ impl InstrumentWorker {
         self.instrumented.push('}');
     }
 
-    fn instrument_show_set(&mut self, child: &SyntaxNode) {
+    /// Instruments a loop, counting whether its body is entered or skipped
+    /// on each evaluation.
+    fn instrument_loop(&mut self, node: &SyntaxNode, body: Span) {
+        self.instrumented.push_str("{\nlet __cov_entered = false\n");
+        let mut entered = None;
+        for child in node.children() {
+            if body == child.span() {
+                self.instrumented.push_str("{\nif not __cov_entered {\n");
+                entered = Some(self.make_cov(body, Kind::Branch));
+                self.instrumented.push_str("__cov_entered = true\n}\n");
+                self.instrument_block(child);
+                self.instrumented.push_str("\n}");
+            } else {
+                self.visit_node(child);
+            }
+        }
+        self.instrumented.push_str("\nif not __cov_entered {\n");
+        let skipped = self.make_cov(node.span(), Kind::Branch);
+        self.instrumented.push_str("}\n}");
+
+        let mut arms = Vec::new();
+        arms.extend(entered.map(|region| BranchArm::new(region, "entered")));
+        arms.push(BranchArm::new(skipped, "skipped"));
+        self.meta.branches.push(BranchMeta {
+            span: node.span(),
+            arms,
+        });
+    }
+
+    /// Counts the alternatives of the selector matching the shown element
+    /// `__it`.
+    fn instrument_show_arms(&mut self, show: &SyntaxNode, arms: &[(ast::Expr, String)]) {
+        if arms.len() < 2 {
+            return;
+        }
+
+        let mut branch = BranchMeta {
+            span: show.span(),
+            arms: Vec::with_capacity(arms.len()),
+        };
+        for (alt, test) in arms {
+            self.instrumented.push_str("if (");
+            self.instrumented.push_str(test);
+            self.instrumented.push_str(") {\n");
+            let region = self.make_cov(alt.span(), Kind::Branch);
+            self.instrumented.push_str("}\n");
+            branch.arms.push(BranchArm::new(region, expr_text(*alt)));
+        }
+        self.meta.branches.push(branch);
+    }
+
+    fn instrument_show_set(
+        &mut self,
+        show: &SyntaxNode,
+        child: &SyntaxNode,
+        arms: &[(ast::Expr, String)],
+    ) {
         self.instrumented.push_str("__it => {");
         self.make_cov(child.span(), Kind::Show);
+        self.instrument_show_arms(show, arms);
         self.visit_node(child);
         self.instrumented.push_str("\n__it; }\n");
     }
 
-    fn instrument_show_transform(&mut self, child: &SyntaxNode) {
+    fn instrument_show_transform(
+        &mut self,
+        show: &SyntaxNode,
+        child: &SyntaxNode,
+        arms: &[(ast::Expr, String)],
+    ) {
         self.instrumented.push_str("{\nlet __cov_show_body = ");
         let s = child.span();
         self.visit_node(child);
         self.instrumented.push_str("\n__it => {");
         self.make_cov(s, Kind::Show);
+        self.instrument_show_arms(show, arms);
         self.instrumented
             .push_str("if type(__cov_show_body) == function { __cov_show_body(__it); } else { __cov_show_body } } }\n");
     }
 }
 
+/// Splits an `or` selector, e.g., `heading.or(figure)` or
+/// `selector.or(heading, figure)`, into its alternatives.
+fn selector_alternatives(selector: ast::Expr) -> Option<Vec<ast::Expr>> {
+    let ast::Expr::FuncCall(call) = selector else {
+        return None;
+    };
+    let ast::Expr::FieldAccess(access) = call.callee() else {
+        return None;
+    };
+    if access.field().as_str() != "or" {
+        return None;
+    }
+
+    let mut alternatives = match access.target() {
+        ast::Expr::Ident(ident) if ident.as_str() == "selector" => Vec::new(),
+        target => selector_alternatives(target).unwrap_or_else(|| vec![target]),
+    };
+    for arg in call.args().items() {
+        match arg {
+            ast::Arg::Pos(expr) => alternatives.push(expr),
+            ast::Arg::Named(..) | ast::Arg::Spread(..) => return None,
+        }
+    }
+
+    Some(alternatives)
+}
+
+/// Generates an expression checking whether the shown element `__it` is
+/// matched by an element selector, e.g., `heading` or
+/// `heading.where(level: 1)`. Returns `None` for the other selectors.
+fn element_test(selector: ast::Expr) -> Option<String> {
+    match selector {
+        ast::Expr::Ident(..) | ast::Expr::FieldAccess(..) => {
+            Some(format!("__it.func() == {}", expr_text(selector)))
+        }
+        ast::Expr::FuncCall(call) => {
+            let mut args = call.args().items();
+            match call.callee() {
+                ast::Expr::Ident(ident) if ident.as_str() == "selector" => {
+                    match (args.next(), args.next()) {
+                        (Some(ast::Arg::Pos(inner)), None) => element_test(inner),
+                        _ => None,
+                    }
+                }
+                ast::Expr::FieldAccess(access) if access.field().as_str() == "where" => {
+                    let mut test = element_test(access.target())?;
+                    for arg in args {
+                        let ast::Arg::Named(named) = arg else {
+                            return None;
+                        };
+                        let field = named.name().as_str();
+                        let value = expr_text(named.expr());
+                        test.push_str(&format!(
+                            " and __it.at(\"{field}\", default: none) == ({value})"
+                        ));
+                    }
+                    Some(test)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn expr_text(expr: ast::Expr) -> String {
+    expr.to_untyped().clone().into_text().to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -567,6 +844,93 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_instrument_if_branches() {
+        let arms = |input: &str| {
+            let source = Source::detached(input);
+            let (_new, meta) = instrument_coverage(source).unwrap();
+            meta.branches
+                .iter()
+                .map(|branch| {
+                    let arms = branch.arms.iter();
+                    arms.map(|arm| (arm.region, arm.label.as_str())).collect()
+                })
+                .collect::<Vec<Vec<_>>>()
+        };
+
+        assert_eq!(
+            arms("#if a { 1 } else { 2 }"),
+            vec![vec![(0, "then"), (2, "else")]]
+        );
+        assert_eq!(arms("#if a { 1 }"), vec![vec![(0, "then"), (2, "else")]]);
+        assert_eq!(
+            arms("#for x in xs { x }"),
+            vec![vec![(0, "entered"), (3, "skipped")]]
+        );
+        assert_eq!(
+            arms("#show heading.or(figure.where(kind: table)): it => it"),
+            vec![vec![(3, "heading"), (4, "figure.where(kind: table)")]]
+        );
+        assert!(arms("#show heading.or(<a>): it => it").is_empty());
+    }
+
+    #[test]
+    fn test_instrument_if_without_else() {
+        let source = Source::detached("#if a [b]");
+        let (new, _meta) = instrument_coverage(source).unwrap();
+        insta::assert_snapshot!(new.text(), @r###"
+        #if a {
+        __cov_pc(0);
+        [b]
+        __cov_pc(1);
+        } else {
+        __cov_pc(2);
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_instrument_loop() {
+        let source = Source::detached("#for x in xs [#x]");
+        let (new, _meta) = instrument_coverage(source).unwrap();
+        insta::assert_snapshot!(new.text(), @r###"
+        #{
+        let __cov_entered = false
+        for x in xs {
+        if not __cov_entered {
+        __cov_pc(0);
+        __cov_entered = true
+        }
+        {
+        __cov_pc(1);
+        [#x]
+        __cov_pc(2);
+        }
+        }
+        if not __cov_entered {
+        __cov_pc(3);
+        }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_instrument_show_selector_arms() {
+        let source = Source::detached("#show heading.where(level: 1).or(figure): set text(red)");
+        let (new, _meta) = instrument_coverage(source).unwrap();
+        insta::assert_snapshot!(new.text(), @r###"
+        #show heading.where(level: 1).or(figure): __it => {__cov_pc(0);
+        if (__it.func() == heading and __it.at("level", default: none) == (1)) {
+        __cov_pc(1);
+        }
+        if (__it.func() == figure) {
+        __cov_pc(2);
+        }
+        set text(red)
+        __it; }
+        "###);
+    }
+
     #[test]
     fn test_instrument_coverage_nested() {
         let source = Source::detached("#let a = {1};");
@@ -0,0 +1,276 @@
+//! Coverage reports in the formats of the standard coverage tools.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use tinymist_std::path::unix_slash;
+use tinymist_world::{CompilerFeat, CompilerWorld};
+use typst::World;
+
+use super::{CoverageResult, Kind};
+
+/// The line and branch hits of a file.
+struct FileCoverage {
+    /// The path of the file relative to the project root.
+    path: String,
+    /// The hits of the one-based lines.
+    lines: BTreeMap<usize, u32>,
+    /// The hits of the branches.
+    branches: Vec<BranchCoverage>,
+}
+
+/// The hits of the arms of a branch.
+struct BranchCoverage {
+    /// The one-based line of the branching expression.
+    line: usize,
+    /// The hits of the arms.
+    arms: Vec<u32>,
+}
+
+impl BranchCoverage {
+    /// Whether the branching expression is evaluated.
+    fn is_taken(&self) -> bool {
+        self.arms.iter().any(|&hits| hits > 0)
+    }
+}
+
+impl FileCoverage {
+    fn lines_covered(&self) -> usize {
+        self.lines.values().filter(|&&hits| hits > 0).count()
+    }
+
+    fn arms(&self) -> impl Iterator<Item = u32> + '_ {
+        self.branches
+            .iter()
+            .flat_map(|branch| branch.arms.iter().copied())
+    }
+
+    fn branches_valid(&self) -> usize {
+        self.arms().count()
+    }
+
+    fn branches_covered(&self) -> usize {
+        self.arms().filter(|&hits| hits > 0).count()
+    }
+}
+
+impl CoverageResult {
+    /// Collects the hits of the files in the project, excluding the package
+    /// files, ordered by path.
+    fn file_coverages<F: CompilerFeat>(&self, w: &CompilerWorld<F>) -> Vec<FileCoverage> {
+        let mut files = Vec::new();
+
+        for (file_id, region) in &self.regions {
+            if file_id.package().is_some() {
+                continue;
+            }
+            let Some(meta) = self.meta.get(file_id) else {
+                continue;
+            };
+            let Ok(source) = w.source(*file_id) else {
+                continue;
+            };
+            let line_of = |span| {
+                let range = source.range(span)?;
+                Some(source.byte_to_line(range.start)? + 1)
+            };
+
+            let hits = region.hits.lock();
+            let mut lines = BTreeMap::new();
+            for (idx, (span, kind)) in meta.meta.iter().enumerate() {
+                if *kind == Kind::Branch {
+                    continue;
+                }
+                let Some(line) = line_of(*span) else {
+                    continue;
+                };
+                let line_hits = lines.entry(line).or_insert(0);
+                *line_hits = (*line_hits).max(u32::from(hits[idx]));
+            }
+
+            let branches = meta
+                .branches
+                .iter()
+                .filter_map(|branch| {
+                    Some(BranchCoverage {
+                        line: line_of(branch.span)?,
+                        arms: branch
+                            .arms
+                            .iter()
+                            .map(|arm| hits[arm.region].into())
+                            .collect(),
+                    })
+                })
+                .collect();
+
+            files.push(FileCoverage {
+                path: unix_slash(file_id.vpath().as_rootless_path()),
+                lines,
+                branches,
+            });
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    /// Converts the coverage result to an LCOV tracefile, whose paths are
+    /// relative to the project root.
+    pub fn to_lcov<F: CompilerFeat>(&self, w: &CompilerWorld<F>) -> String {
+        let mut out = String::new();
+
+        for file in self.file_coverages(w) {
+            let _ = writeln!(out, "TN:");
+            let _ = writeln!(out, "SF:{}", file.path);
+            for (block, branch) in file.branches.iter().enumerate() {
+                let taken = branch.is_taken();
+                for (arm, hits) in branch.arms.iter().enumerate() {
+                    let line = branch.line;
+                    if taken {
+                        let _ = writeln!(out, "BRDA:{line},{block},{arm},{hits}");
+                    } else {
+                        let _ = writeln!(out, "BRDA:{line},{block},{arm},-");
+                    }
+                }
+            }
+            let _ = writeln!(out, "BRF:{}", file.branches_valid());
+            let _ = writeln!(out, "BRH:{}", file.branches_covered());
+            for (line, hits) in &file.lines {
+                let _ = writeln!(out, "DA:{line},{hits}");
+            }
+            let _ = writeln!(out, "LF:{}", file.lines.len());
+            let _ = writeln!(out, "LH:{}", file.lines_covered());
+            let _ = writeln!(out, "end_of_record");
+        }
+
+        out
+    }
+
+    /// Converts the coverage result to a Cobertura XML report, whose paths are
+    /// relative to the project `root`.
+    pub fn to_cobertura<F: CompilerFeat>(&self, w: &CompilerWorld<F>, root: &Path) -> String {
+        let files = self.file_coverages(w);
+
+        let lines_valid = files.iter().map(|file| file.lines.len()).sum::<usize>();
+        let lines_covered = files.iter().map(FileCoverage::lines_covered).sum::<usize>();
+        let branches_valid = files
+            .iter()
+            .map(FileCoverage::branches_valid)
+            .sum::<usize>();
+        let branches_covered = files
+            .iter()
+            .map(FileCoverage::branches_covered)
+            .sum::<usize>();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        let line_rate = rate(lines_covered, lines_valid);
+        let branch_rate = rate(branches_covered, branches_valid);
+
+        let mut out = String::new();
+        let _ = writeln!(out, r#"<?xml version="1.0" ?>"#);
+        let _ = writeln!(
+            out,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        );
+        let _ = writeln!(
+            out,
+            r#"<coverage line-rate="{line_rate}" branch-rate="{branch_rate}" lines-covered="{lines_covered}" lines-valid="{lines_valid}" branches-covered="{branches_covered}" branches-valid="{branches_valid}" complexity="0" version="tinymist" timestamp="{timestamp}">"#
+        );
+        let _ = writeln!(out, "  <sources>");
+        let _ = writeln!(
+            out,
+            "    <source>{}</source>",
+            escape_xml(&unix_slash(root))
+        );
+        let _ = writeln!(out, "  </sources>");
+        let _ = writeln!(out, "  <packages>");
+        let _ = writeln!(
+            out,
+            r#"    <package name="" line-rate="{line_rate}" branch-rate="{branch_rate}" complexity="0">"#
+        );
+        let _ = writeln!(out, "      <classes>");
+        for file in &files {
+            let line_rate = rate(file.lines_covered(), file.lines.len());
+            let branch_rate = rate(file.branches_covered(), file.branches_valid());
+            let path = escape_xml(&file.path);
+            let _ = writeln!(
+                out,
+                r#"        <class name="{path}" filename="{path}" line-rate="{line_rate}" branch-rate="{branch_rate}" complexity="0">"#
+            );
+            let _ = writeln!(out, "          <methods/>");
+            let _ = writeln!(out, "          <lines>");
+            for (line, hits) in &file.lines {
+                let arms = file
+                    .branches
+                    .iter()
+                    .filter(|branch| branch.line == *line)
+                    .flat_map(|branch| branch.arms.iter())
+                    .collect::<Vec<_>>();
+                if arms.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        r#"            <line number="{line}" hits="{hits}" branch="false"/>"#
+                    );
+                } else {
+                    let covered = arms.iter().filter(|&&hits| hits > 0).count();
+                    let valid = arms.len();
+                    let percent = covered * 100 / valid;
+                    let _ = writeln!(
+                        out,
+                        r#"            <line number="{line}" hits="{hits}" branch="true" condition-coverage="{percent}% ({covered}/{valid})"/>"#
+                    );
+                }
+            }
+            let _ = writeln!(out, "          </lines>");
+            let _ = writeln!(out, "        </class>");
+        }
+        let _ = writeln!(out, "      </classes>");
+        let _ = writeln!(out, "    </package>");
+        let _ = writeln!(out, "  </packages>");
+        let _ = writeln!(out, "</coverage>");
+
+        out
+    }
+}
+
+/// Gets the rate of the covered items, which is `1` if there is no item.
+fn rate(covered: usize, valid: usize) -> String {
+    if valid == 0 {
+        return "1".to_owned();
+    }
+    format!("{:.4}", covered as f64 / valid as f64)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&apos;");
+    }
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(rate(0, 0), "1");
+        assert_eq!(rate(1, 3), "0.3333");
+    }
+}
@@ -2,19 +2,23 @@
 
 mod instr;
 
+use std::cell::RefCell;
+use std::ops::Range;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use comemo::Tracked;
 use parking_lot::RwLock;
 use tinymist_std::hash::{FxHashMap, FxHashSet};
 use tinymist_world::vfs::FileId;
 use typst::World;
-use typst::diag::FileResult;
+use typst::diag::{EcoString, FileResult};
 use typst::engine::Engine;
 use typst::foundations::{Binding, Context, Dict, Scopes, func};
 use typst::syntax::{Source, Span};
 
 use crate::instrument::Instrumenter;
+use instr::instrument_breakpoints;
 
 #[derive(Default)]
 pub struct BreakpointInstr {}
@@ -22,11 +26,13 @@ pub struct BreakpointInstr {}
 /// The kind of breakpoint.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BreakpointKind {
-    // Expr,
-    // Line,
-    /// A call breakpoint.
+    /// An expression breakpoint, placed before each statement.
+    Expr,
+    /// A line breakpoint, placed before the first statement on each line.
+    Line,
+    /// A call breakpoint at the start of a function body.
     CallStart,
-    /// A call breakpoint.
+    /// A call breakpoint at the end of a function body.
     CallEnd,
     /// A function breakpoint.
     Function,
@@ -58,6 +64,8 @@ impl BreakpointKind {
     /// Converts the breakpoint kind to a string.
     pub fn to_str(self) -> &'static str {
         match self {
+            BreakpointKind::Expr => "expr",
+            BreakpointKind::Line => "line",
             BreakpointKind::CallStart => "call_start",
             BreakpointKind::CallEnd => "call_end",
             BreakpointKind::Function => "function",
@@ -81,28 +89,95 @@ pub struct BreakpointInfo {
     pub meta: Vec<BreakpointItem>,
 }
 
+/// An instrumented breakpoint.
 pub struct BreakpointItem {
+    /// The span of the breakpoint in the original source.
     pub origin_span: Span,
+    /// The kind of the breakpoint.
+    pub kind: BreakpointKind,
+    /// The name of the function, if it is the call start of a named function.
+    pub name: Option<EcoString>,
+}
+
+/// A source breakpoint resolved to an instrumented statement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResolvedBreakpoint {
+    /// The file of the breakpoint.
+    pub fid: FileId,
+    /// The index of the instrumented breakpoint in the file.
+    pub id: usize,
+    /// The kind of the breakpoint, either [`BreakpointKind::Line`] or
+    /// [`BreakpointKind::Expr`].
+    pub kind: BreakpointKind,
+    /// The byte range of the statement in the source.
+    pub range: Range<usize>,
+}
+
+/// Lists the statements where source breakpoints can be set in the zero-based
+/// lines, ordered by position.
+pub fn breakpoint_locations(source: &Source, lines: Range<usize>) -> Vec<ResolvedBreakpoint> {
+    let Ok((_, info)) = instrument_breakpoints(source.clone()) else {
+        return Vec::new();
+    };
+
+    let mut locations = info
+        .meta
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches!(item.kind, BreakpointKind::Line | BreakpointKind::Expr))
+        .filter_map(|(id, item)| {
+            let range = source.range(item.origin_span)?;
+            let line = source.byte_to_line(range.start)?;
+            lines.contains(&line).then(|| ResolvedBreakpoint {
+                fid: source.id(),
+                id,
+                kind: item.kind,
+                range,
+            })
+        })
+        .collect::<Vec<_>>();
+    locations.sort_by_key(|bp| bp.range.start);
+    locations
+}
+
+/// Resolves a source breakpoint at the zero-based line to the first statement
+/// starting on the line, or to the first one starting at or after the byte
+/// `offset` if it is given. Returns `None` if no statement maps there.
+pub fn resolve_breakpoint(
+    source: &Source,
+    line: usize,
+    offset: Option<usize>,
+) -> Option<ResolvedBreakpoint> {
+    breakpoint_locations(source, line..line + 1)
+        .into_iter()
+        .find(|bp| offset.is_none_or(|offset| bp.range.start >= offset))
 }
 
 static DEBUG_SESSION: RwLock<Option<DebugSession>> = RwLock::new(None);
 
 /// The debug session handler.
 pub trait DebugSessionHandler: Send + Sync {
-    /// Called when a breakpoint is hit.
+    /// Called when a breakpoint is hit. The `id` is the index of the
+    /// breakpoint in the file of its span. The breakpoint is either enabled,
+    /// or a statement hit while stepping.
     fn on_breakpoint(
         &self,
         engine: &Engine,
         context: Tracked<Context>,
         scopes: Scopes,
-        span: Span,
-        kind: BreakpointKind,
+        id: usize,
+        item: &BreakpointItem,
+        enabled: bool,
     );
 }
 
 /// The debug session.
 pub struct DebugSession {
     enabled: FxHashSet<(FileId, usize, BreakpointKind)>,
+    /// The names of the functions to break on.
+    functions: FxHashSet<EcoString>,
+    /// Whether all statements are hit, to step through them.
+    stepping: bool,
     /// The breakpoint meta.
     breakpoints: FxHashMap<FileId, Arc<BreakpointInfo>>,
 
@@ -115,10 +190,190 @@ impl DebugSession {
     pub fn new(handler: Arc<dyn DebugSessionHandler>) -> Self {
         Self {
             enabled: FxHashSet::default(),
+            functions: FxHashSet::default(),
+            stepping: false,
             breakpoints: FxHashMap::default(),
             handler,
         }
     }
+
+    /// Replaces the line and expression breakpoints enabled in the file.
+    pub fn set_source_breakpoints(&mut self, fid: FileId, breakpoints: &[ResolvedBreakpoint]) {
+        self.enabled.retain(|(id, _, kind)| {
+            *id != fid || !matches!(kind, BreakpointKind::Line | BreakpointKind::Expr)
+        });
+        self.enabled
+            .extend(breakpoints.iter().map(|bp| (bp.fid, bp.id, bp.kind)));
+    }
+
+    /// Replaces the functions to break on by their names. The breakpoints are
+    /// also enabled in the files instrumented later.
+    pub fn set_function_breakpoints(&mut self, names: impl IntoIterator<Item = EcoString>) {
+        self.functions = names.into_iter().collect();
+        self.enabled
+            .retain(|(_, _, kind)| !matches!(kind, BreakpointKind::CallStart));
+
+        let fids = self.breakpoints.keys().copied().collect::<Vec<_>>();
+        for fid in fids {
+            self.enable_function_breakpoints(fid);
+        }
+    }
+
+    /// Enables the breakpoints of the functions to break on in the file.
+    fn enable_function_breakpoints(&mut self, fid: FileId) {
+        let Some(info) = self.breakpoints.get(&fid) else {
+            return;
+        };
+
+        for (id, item) in info.meta.iter().enumerate() {
+            let is_target = item.kind == BreakpointKind::CallStart
+                && item
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| self.functions.contains(name));
+            if is_target {
+                self.enabled.insert((fid, id, BreakpointKind::CallStart));
+            }
+        }
+    }
+
+    /// Sets whether all statements are hit, to step through them.
+    pub fn set_stepping(&mut self, stepping: bool) {
+        self.stepping = stepping;
+    }
+
+    /// Whether the breakpoint is enabled.
+    fn is_enabled(&self, fid: FileId, id: usize, kind: BreakpointKind) -> bool {
+        self.enabled.contains(&(fid, id, kind))
+    }
+
+    /// Whether the breakpoint is hit, either enabled or a statement while
+    /// stepping.
+    fn is_hit(&self, fid: FileId, id: usize, kind: BreakpointKind) -> bool {
+        let stepped = self.stepping && matches!(kind, BreakpointKind::Line | BreakpointKind::Expr);
+        stepped || self.is_enabled(fid, id, kind)
+    }
+
+    fn breakpoint_span(&self, (fid, id): (FileId, usize)) -> Option<Span> {
+        Some(self.breakpoints.get(&fid)?.meta.get(id)?.origin_span)
+    }
+}
+
+/// A frame of the function calls in a debug session.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    /// The name of the function, which is `None` for the anonymous functions
+    /// and the root frame.
+    pub name: Option<EcoString>,
+    /// Whether the frame is the root one, which evaluates the document.
+    pub is_root: bool,
+    /// The span of the statement evaluated in the frame, which is the start of
+    /// the function body if no statement is evaluated yet.
+    pub span: Span,
+}
+
+/// The function calls being evaluated, indicated by the call breakpoints.
+#[derive(Default)]
+struct FrameState {
+    /// The call start of the function, which is `None` for the root frame.
+    call: Option<(FileId, usize)>,
+    /// The last statement evaluated in the frame.
+    current: Option<(FileId, usize)>,
+}
+
+/// The number of the debug sessions started, which tells the frames left by
+/// the previous sessions.
+static SESSION_EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+/// The stack of the function calls on a thread, whose bottom is the root
+/// frame.
+struct Frames {
+    /// The session epoch when the stack is used.
+    epoch: usize,
+    stack: Vec<FrameState>,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Frames> = RefCell::new(Frames {
+        epoch: 0,
+        stack: vec![FrameState::default()],
+    });
+}
+
+/// Runs function with the stack of the function calls on the thread, which
+/// is reset if it is left by a previous session, e.g., on a worker thread.
+fn with_frames<R>(f: impl FnOnce(&mut Vec<FrameState>) -> R) -> R {
+    let epoch = SESSION_EPOCH.load(Ordering::SeqCst);
+    FRAMES.with_borrow_mut(|frames| {
+        if frames.epoch != epoch {
+            frames.epoch = epoch;
+            frames.stack = vec![FrameState::default()];
+        }
+        f(&mut frames.stack)
+    })
+}
+
+/// Gets the number of the function calls being evaluated on the thread.
+pub fn call_depth() -> usize {
+    with_frames(|frames| frames.len().saturating_sub(1))
+}
+
+/// Leaves the function calls deeper than the call depth on the thread. The
+/// calls aborted by an error are never ended by their call end breakpoints,
+/// so the caller unwinds to its depth once an evaluation fails.
+pub fn unwind_calls(depth: usize) {
+    with_frames(|frames| frames.truncate(depth + 1));
+}
+
+/// Gets the function calls being evaluated on the thread, innermost first.
+pub fn call_stack() -> Vec<CallFrame> {
+    let session = DEBUG_SESSION.read();
+    let Some(session) = session.as_ref() else {
+        return Vec::new();
+    };
+
+    with_frames(|frames| {
+        frames
+            .iter()
+            .rev()
+            .map(|frame| {
+                let call = frame.call.and_then(|(fid, id)| {
+                    let item = session.breakpoints.get(&fid)?.meta.get(id)?;
+                    Some((item.name.clone(), item.origin_span))
+                });
+                let current = frame.current.and_then(|bp| session.breakpoint_span(bp));
+                let (name, entry) = call.unwrap_or((None, Span::detached()));
+
+                CallFrame {
+                    name,
+                    is_root: frame.call.is_none(),
+                    span: current.unwrap_or(entry),
+                }
+            })
+            .collect()
+    })
+}
+
+/// Tracks the function calls by the breakpoints, which are checked even if
+/// they are not enabled.
+fn track_frames(fid: FileId, id: usize, kind: BreakpointKind) {
+    with_frames(|frames| match kind {
+        BreakpointKind::Line | BreakpointKind::Expr => {
+            if let Some(frame) = frames.last_mut() {
+                frame.current = Some((fid, id));
+            }
+        }
+        BreakpointKind::CallStart => frames.push(FrameState {
+            call: Some((fid, id)),
+            current: None,
+        }),
+        BreakpointKind::CallEnd | BreakpointKind::Return => {
+            if frames.len() > 1 {
+                frames.pop();
+            }
+        }
+        _ => {}
+    });
 }
 
 /// Runs function with the debug session.
@@ -129,14 +384,25 @@ where
     Some(f(DEBUG_SESSION.read().as_ref()?))
 }
 
+/// Runs function with the mutable debug session.
+pub fn with_debug_session_mut<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut DebugSession) -> R,
+{
+    Some(f(DEBUG_SESSION.write().as_mut()?))
+}
+
 /// Sets the debug session.
 pub fn set_debug_session(session: Option<DebugSession>) -> bool {
     let mut lock = DEBUG_SESSION.write();
 
-    if session.is_some() {
+    if session.is_some() && lock.is_some() {
         return false;
     }
 
+    if session.is_some() {
+        SESSION_EPOCH.fetch_add(1, Ordering::SeqCst);
+    }
     let _ = std::mem::replace(&mut *lock, session);
     true
 }
@@ -148,8 +414,8 @@ fn check_soft_breakpoint(span: Span, id: usize, kind: BreakpointKind) -> Option<
     let session = DEBUG_SESSION.read();
     let session = session.as_ref()?;
 
-    let bp_feature = (fid, id, kind);
-    Some(session.enabled.contains(&bp_feature))
+    track_frames(fid, id, kind);
+    Some(session.is_hit(fid, id, kind))
 }
 
 /// Software breakpoints
@@ -163,18 +429,19 @@ fn soft_breakpoint_handle(
 ) -> Option<()> {
     let fid = span.id()?;
 
-    let (handler, origin_span) = {
+    let (handler, info, enabled) = {
         let session = DEBUG_SESSION.read();
         let session = session.as_ref()?;
 
-        let bp_feature = (fid, id, kind);
-        if !session.enabled.contains(&bp_feature) {
+        if !session.is_hit(fid, id, kind) {
             return None;
         }
 
-        let item = session.breakpoints.get(&fid)?.meta.get(id)?;
-        (session.handler.clone(), item.origin_span)
+        let info = session.breakpoints.get(&fid)?.clone();
+        let enabled = session.is_enabled(fid, id, kind);
+        (session.handler.clone(), info, enabled)
     };
+    let item = info.meta.get(id)?;
 
     let mut scopes = Scopes::new(Some(engine.world.library()));
     if let Some(scope) = scope {
@@ -183,7 +450,7 @@ fn soft_breakpoint_handle(
         }
     }
 
-    handler.on_breakpoint(engine, context, scopes, origin_span, kind);
+    handler.on_breakpoint(engine, context, scopes, id, item, enabled);
     Some(())
 }
 
@@ -210,6 +477,22 @@ pub mod breakpoints {
         };
     }
 
+    bp_handler!(
+        __breakpoint_expr,
+        "__breakpoint_expr",
+        __breakpoint_expr_handle,
+        "__breakpoint_expr_handle",
+        "A Software Breakpoint before a statement.",
+        Expr
+    );
+    bp_handler!(
+        __breakpoint_line,
+        "__breakpoint_line",
+        __breakpoint_line_handle,
+        "__breakpoint_line_handle",
+        "A Software Breakpoint before the first statement on a line.",
+        Line
+    );
     bp_handler!(
         __breakpoint_call_start,
         "__breakpoint_call_start",
@@ -323,3 +606,63 @@ pub mod breakpoints {
         AfterCompile
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::VirtualPath;
+
+    use super::*;
+
+    #[test]
+    fn test_call_depth() {
+        let fid = FileId::new(None, VirtualPath::new("main.typ"));
+        let track = |id, kind| track_frames(fid, id, kind);
+        let current = || with_frames(|frames| frames.last().and_then(|frame| frame.current));
+
+        assert_eq!(call_depth(), 0);
+        track(0, BreakpointKind::Line);
+        assert_eq!(current(), Some((fid, 0)));
+
+        // Enters a call and returns by the call end.
+        track(1, BreakpointKind::CallStart);
+        assert_eq!(call_depth(), 1);
+        assert_eq!(current(), None);
+        track(2, BreakpointKind::Expr);
+        track(3, BreakpointKind::CallEnd);
+        assert_eq!(call_depth(), 0);
+        assert_eq!(current(), Some((fid, 0)));
+
+        // Enters nested calls and returns early from the inner one.
+        track(1, BreakpointKind::CallStart);
+        track(1, BreakpointKind::CallStart);
+        assert_eq!(call_depth(), 2);
+        track(4, BreakpointKind::Return);
+        assert_eq!(call_depth(), 1);
+        track(3, BreakpointKind::CallEnd);
+        assert_eq!(call_depth(), 0);
+
+        // The root frame is never left.
+        track(3, BreakpointKind::CallEnd);
+        assert_eq!(call_depth(), 0);
+
+        // The other breakpoints don't change the depth.
+        track(5, BreakpointKind::BlockStart);
+        track(6, BreakpointKind::ShowStart);
+        assert_eq!(call_depth(), 0);
+
+        // Unwinds the calls aborted by an error.
+        track(1, BreakpointKind::CallStart);
+        let depth = call_depth();
+        track(1, BreakpointKind::CallStart);
+        track(1, BreakpointKind::CallStart);
+        unwind_calls(depth);
+        assert_eq!(call_depth(), 1);
+        unwind_calls(3);
+        assert_eq!(call_depth(), 1);
+
+        // A new session resets the calls left by the previous one.
+        SESSION_EPOCH.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(call_depth(), 0);
+        assert_eq!(current(), None);
+    }
+}
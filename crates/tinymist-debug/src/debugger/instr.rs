@@ -1,6 +1,6 @@
-use typst::diag::FileError;
-use typst::syntax::SyntaxNode;
+use typst::diag::{EcoString, FileError};
 use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{SyntaxKind, SyntaxNode};
 
 use super::*;
 
@@ -14,17 +14,21 @@ impl Instrumenter for BreakpointInstr {
             .ok_or_else(|| FileError::Other(Some("No active debug session".into())))?;
 
         session.breakpoints.insert(new.id(), meta);
+        session.enable_function_breakpoints(new.id());
 
         Ok(new)
     }
 }
 
 #[comemo::memoize]
-fn instrument_breakpoints(source: Source) -> FileResult<(Source, Arc<BreakpointInfo>)> {
+pub(super) fn instrument_breakpoints(source: Source) -> FileResult<(Source, Arc<BreakpointInfo>)> {
     let node = source.root();
     let mut worker = InstrumentWorker {
+        source: &source,
         meta: BreakpointInfo::default(),
         instrumented: String::new(),
+        last_line: None,
+        scopes: Vec::new(),
     };
 
     worker.visit_node(node);
@@ -33,16 +37,31 @@ fn instrument_breakpoints(source: Source) -> FileResult<(Source, Arc<BreakpointI
     Ok((new_source, Arc::new(worker.meta)))
 }
 
-struct InstrumentWorker {
+struct InstrumentWorker<'a> {
+    source: &'a Source,
     meta: BreakpointInfo,
     instrumented: String,
+    /// The line of the last instrumented statement.
+    last_line: Option<usize>,
+    /// The names bound in the lexical scopes, from the outermost to the
+    /// innermost.
+    scopes: Vec<Vec<EcoString>>,
 }
 
-impl InstrumentWorker {
-    fn instrument_block_child(&mut self, container: &SyntaxNode, b1: Span, b2: Span) {
+impl InstrumentWorker<'_> {
+    fn instrument_block_child(
+        &mut self,
+        container: &SyntaxNode,
+        b1: Span,
+        b2: Span,
+        bindings: Vec<EcoString>,
+        closure: Option<ast::Closure>,
+    ) {
         for child in container.children() {
             if b1 == child.span() || b2 == child.span() {
-                self.instrument_block(child);
+                self.scopes.push(bindings.clone());
+                self.instrument_block(child, closure);
+                self.scopes.pop();
             } else {
                 self.visit_node(child);
             }
@@ -50,18 +69,35 @@ impl InstrumentWorker {
     }
 
     fn visit_node(&mut self, node: &SyntaxNode) {
+        match node.kind() {
+            SyntaxKind::Markup => {
+                self.instrument_markup(node);
+                return;
+            }
+            SyntaxKind::Code => {
+                self.instrument_code(node);
+                return;
+            }
+            _ => {}
+        }
+
         if let Some(expr) = node.cast::<ast::Expr>() {
             match expr {
                 ast::Expr::Code(..) => {
-                    self.instrument_block(node);
+                    self.instrument_block(node, None);
                     return;
                 }
                 ast::Expr::While(while_expr) => {
-                    self.instrument_block_child(node, while_expr.body().span(), Span::detached());
+                    let body = while_expr.body().span();
+                    self.instrument_block_child(node, body, Span::detached(), Vec::new(), None);
                     return;
                 }
                 ast::Expr::For(for_expr) => {
-                    self.instrument_block_child(node, for_expr.body().span(), Span::detached());
+                    let bindings = for_expr.pattern().bindings();
+                    let bindings = bindings.iter().map(|ident| ident.get().clone());
+                    let body = for_expr.body().span();
+                    let bindings = bindings.collect();
+                    self.instrument_block_child(node, body, Span::detached(), bindings, None);
                     return;
                 }
                 ast::Expr::Conditional(cond_expr) => {
@@ -69,11 +105,22 @@ impl InstrumentWorker {
                         node,
                         cond_expr.if_body().span(),
                         cond_expr.else_body().unwrap_or_default().span(),
+                        Vec::new(),
+                        None,
                     );
                     return;
                 }
                 ast::Expr::Closure(closure) => {
-                    self.instrument_block_child(node, closure.body().span(), Span::detached());
+                    let bindings = closure.params().children().flat_map(|param| match param {
+                        ast::Param::Pos(pattern) => pattern.bindings(),
+                        ast::Param::Named(named) => vec![named.name()],
+                        ast::Param::Spread(spread) => spread.sink_ident().into_iter().collect(),
+                    });
+                    let bindings = bindings.map(|ident| ident.get().clone());
+                    let bindings = bindings.collect();
+                    let body = closure.body().span();
+                    let closure = Some(closure);
+                    self.instrument_block_child(node, body, Span::detached(), bindings, closure);
                     return;
                 }
                 ast::Expr::Show(show_rule) => {
@@ -158,9 +205,13 @@ impl InstrumentWorker {
         }
     }
 
-    fn make_cov(&mut self, span: Span, kind: BreakpointKind) {
+    fn make_bp(&mut self, span: Span, kind: BreakpointKind, scope: &str) {
         let it = self.meta.meta.len();
-        self.meta.meta.push(BreakpointItem { origin_span: span });
+        self.meta.meta.push(BreakpointItem {
+            origin_span: span,
+            kind,
+            name: None,
+        });
         self.instrumented.push_str("if __breakpoint_");
         self.instrumented.push_str(kind.to_str());
         self.instrumented.push('(');
@@ -170,11 +221,120 @@ impl InstrumentWorker {
         self.instrumented.push_str(kind.to_str());
         self.instrumented.push_str("_handle(");
         self.instrumented.push_str(&it.to_string());
-        self.instrumented.push_str(", (:)); ");
-        self.instrumented.push_str("};\n");
+        self.instrumented.push_str(", ");
+        self.instrumented.push_str(scope);
+        self.instrumented.push_str("); ");
+        self.instrumented.push_str("};");
+    }
+
+    fn make_cov(&mut self, span: Span, kind: BreakpointKind) {
+        self.make_bp(span, kind, "(:)");
+        self.instrumented.push('\n');
+    }
+
+    /// Instruments a breakpoint before a statement, which is a line breakpoint
+    /// if the statement is the first one on its line.
+    fn make_stmt_bp(&mut self, span: Span) {
+        let range = self.source.range(span);
+        let line = range.and_then(|range| self.source.byte_to_line(range.start));
+        let kind = if line.is_some() && line == self.last_line {
+            BreakpointKind::Expr
+        } else {
+            BreakpointKind::Line
+        };
+        self.last_line = line;
+        let locals = self.locals();
+        self.make_bp(span, kind, &locals);
+    }
+
+    /// Gets the dictionary of the local variables visible to a statement,
+    /// from the innermost scope. The top-level bindings of the module are
+    /// excluded.
+    fn locals(&self) -> String {
+        let mut names = Vec::<&EcoString>::new();
+        for name in self
+            .scopes
+            .iter()
+            .skip(1)
+            .rev()
+            .flat_map(|s| s.iter().rev())
+        {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        if names.is_empty() {
+            return "(:)".into();
+        }
+        let entries = names.iter().map(|name| format!("{name}: {name}"));
+        format!("({})", entries.collect::<Vec<_>>().join(", "))
+    }
+
+    /// Adds the names bound by a statement to the innermost scope.
+    fn bind(&mut self, stmt: &SyntaxNode) {
+        let names = match stmt.cast::<ast::Expr>() {
+            Some(ast::Expr::Let(binding)) => binding.kind().bindings(),
+            Some(ast::Expr::Import(import)) => {
+                let items = match import.imports() {
+                    Some(ast::Imports::Items(items)) => {
+                        items.iter().map(|item| item.bound_name()).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                import.new_name().into_iter().chain(items).collect()
+            }
+            _ => return,
+        };
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.extend(names.iter().map(|ident| ident.get().clone()));
+        }
     }
 
-    fn instrument_block(&mut self, child: &SyntaxNode) {
+    fn instrument_code(&mut self, node: &SyntaxNode) {
+        self.scopes.push(Vec::new());
+        for child in node.children() {
+            if !child.kind().is_trivia() && child.cast::<ast::Expr>().is_some() {
+                self.make_stmt_bp(child.span());
+                self.instrumented.push('\n');
+                if child.kind() == SyntaxKind::FuncReturn {
+                    // Leaves the call before returning, which skips the call end.
+                    self.make_cov(child.span(), BreakpointKind::Return);
+                }
+            }
+            self.visit_node(child);
+            self.bind(child);
+        }
+        self.scopes.pop();
+    }
+
+    fn instrument_markup(&mut self, node: &SyntaxNode) {
+        // Names bound in markup are conservatively scoped to the markup node,
+        // even if it doesn't open a scope, e.g., the body of a heading.
+        self.scopes.push(Vec::new());
+        let mut children = node.children().peekable();
+        while let Some(child) = children.next() {
+            let embedded = children
+                .peek()
+                .filter(|next| next.cast::<ast::Expr>().is_some());
+            if let Some(expr) = embedded.filter(|_| child.kind() == SyntaxKind::Hash) {
+                // No line break is inserted since it is significant in markup.
+                self.instrumented.push('#');
+                self.make_stmt_bp(expr.span());
+                if expr.kind() == SyntaxKind::FuncReturn {
+                    self.instrumented.push('#');
+                    self.make_bp(expr.span(), BreakpointKind::Return, "(:)");
+                }
+            }
+            self.visit_node(child);
+            self.bind(child);
+        }
+        self.scopes.pop();
+    }
+
+    /// Instruments a block, which is the body of the `closure` if it is given.
+    fn instrument_block(&mut self, child: &SyntaxNode, closure: Option<ast::Closure>) {
         self.instrumented.push_str("{\n");
         let (first, last) = {
             let mut children = child.children();
@@ -189,17 +349,40 @@ impl InstrumentWorker {
 
             (first, last)
         };
-        self.make_cov(first, BreakpointKind::BlockStart);
+        match closure {
+            // The call breakpoints take the place of the block ones, where the
+            // call start captures the parameters of the function and serves as
+            // the function breakpoint.
+            Some(closure) => {
+                let locals = self.locals();
+                self.make_bp(first, BreakpointKind::CallStart, &locals);
+                self.instrumented.push('\n');
+                if let Some(item) = self.meta.meta.last_mut() {
+                    item.name = closure.name().map(|name| name.get().clone());
+                }
+            }
+            None => self.make_cov(first, BreakpointKind::BlockStart),
+        }
         self.visit_node_fallback(child);
         self.instrumented.push('\n');
-        self.make_cov(last, BreakpointKind::BlockEnd);
+        let end = if closure.is_some() {
+            BreakpointKind::CallEnd
+        } else {
+            BreakpointKind::BlockEnd
+        };
+        self.make_cov(last, end);
         self.instrumented.push_str("}\n");
     }
 
     fn instrument_functor(&mut self, child: &SyntaxNode) {
         self.instrumented.push_str("{\nlet __bp_functor = ");
         let s = child.span();
-        self.visit_node_fallback(child);
+        if child.kind() == SyntaxKind::Closure {
+            // Visits the closure to bind its parameters in the body.
+            self.visit_node(child);
+        } else {
+            self.visit_node_fallback(child);
+        }
         self.instrumented.push_str("\n__it => {");
         self.make_cov(s, BreakpointKind::ShowStart);
         self.instrumented.push_str("__bp_functor(__it); } }\n");
@@ -230,39 +413,44 @@ mod tests {
         //     #show: super-plus-as-dagger
         //     U^+U = U U^+ = I
         //   ]
-        #let super-plus-as-dagger(document) = {
-        if __breakpoint_block_start(0) {__breakpoint_block_start_handle(0, (:)); };
+        #if __breakpoint_line(0) {__breakpoint_line_handle(0, (:)); };#let super-plus-as-dagger(document) = {
+        if __breakpoint_call_start(1) {__breakpoint_call_start_handle(1, (document: document)); };
         {
-          show math.attach: {
+          if __breakpoint_line(2) {__breakpoint_line_handle(2, (document: document)); };
+        show math.attach: {
         let __bp_functor = elem => {
-        if __breakpoint_block_start(1) {__breakpoint_block_start_handle(1, (:)); };
+        if __breakpoint_call_start(3) {__breakpoint_call_start_handle(3, (elem: elem, document: document)); };
         {
-            if __eligible(elem.base) and elem.at("t", default: none) == [+] {
-        if __breakpoint_block_start(2) {__breakpoint_block_start_handle(2, (:)); };
+            if __breakpoint_line(4) {__breakpoint_line_handle(4, (elem: elem, document: document)); };
+        if __eligible(elem.base) and elem.at("t", default: none) == [+] {
+        if __breakpoint_block_start(5) {__breakpoint_block_start_handle(5, (:)); };
         {
-              $attach(elem.base, t: dagger, b: elem.at("b", default: #none))$
+              if __breakpoint_line(6) {__breakpoint_line_handle(6, (elem: elem, document: document)); };
+        $attach(elem.base, t: dagger, b: elem.at("b", default: #none))$
             }
-        if __breakpoint_block_end(3) {__breakpoint_block_end_handle(3, (:)); };
+        if __breakpoint_block_end(7) {__breakpoint_block_end_handle(7, (:)); };
         }
          else {
-        if __breakpoint_block_start(4) {__breakpoint_block_start_handle(4, (:)); };
+        if __breakpoint_block_start(8) {__breakpoint_block_start_handle(8, (:)); };
         {
-              elem
+              if __breakpoint_line(9) {__breakpoint_line_handle(9, (elem: elem, document: document)); };
+        elem
             }
-        if __breakpoint_block_end(5) {__breakpoint_block_end_handle(5, (:)); };
+        if __breakpoint_block_end(10) {__breakpoint_block_end_handle(10, (:)); };
         }
 
           }
-        if __breakpoint_block_end(6) {__breakpoint_block_end_handle(6, (:)); };
+        if __breakpoint_call_end(11) {__breakpoint_call_end_handle(11, (:)); };
         }
 
-        __it => {if __breakpoint_show_start(7) {__breakpoint_show_start_handle(7, (:)); };
+        __it => {if __breakpoint_show_start(12) {__breakpoint_show_start_handle(12, (:)); };
         __bp_functor(__it); } }
 
 
-          document
+          if __breakpoint_line(13) {__breakpoint_line_handle(13, (document: document)); };
+        document
         }
-        if __breakpoint_block_end(8) {__breakpoint_block_end_handle(8, (:)); };
+        if __breakpoint_call_end(14) {__breakpoint_call_end_handle(14, (:)); };
         }
         "###);
     }
@@ -277,7 +465,7 @@ mod tests {
     fn test_instrument_coverage() {
         let source = Source::detached("#let a = 1;");
         let (new, _meta) = instrument_breakpoints(source).unwrap();
-        insta::assert_snapshot!(new.text(), @"#let a = 1;");
+        insta::assert_snapshot!(new.text(), @"#if __breakpoint_line(0) {__breakpoint_line_handle(0, (:)); };#let a = 1;");
     }
 
     #[test]
@@ -285,10 +473,11 @@ mod tests {
         let source = Source::detached("#let a = {1};");
         let (new, _meta) = instrument_breakpoints(source).unwrap();
         insta::assert_snapshot!(new.text(), @r###"
-        #let a = {
-        if __breakpoint_block_start(0) {__breakpoint_block_start_handle(0, (:)); };
-        {1}
-        if __breakpoint_block_end(1) {__breakpoint_block_end_handle(1, (:)); };
+        #if __breakpoint_line(0) {__breakpoint_line_handle(0, (:)); };#let a = {
+        if __breakpoint_block_start(1) {__breakpoint_block_start_handle(1, (:)); };
+        {if __breakpoint_expr(2) {__breakpoint_expr_handle(2, (:)); };
+        1}
+        if __breakpoint_block_end(3) {__breakpoint_block_end_handle(3, (:)); };
         }
         ;
         "###);
@@ -299,10 +488,58 @@ mod tests {
         let source = Source::detached("#show: main");
         let (new, _meta) = instrument_breakpoints(source).unwrap();
         insta::assert_snapshot!(new.text(), @r###"
-        #show: {
+        #if __breakpoint_line(0) {__breakpoint_line_handle(0, (:)); };#show: {
         let __bp_functor = main
-        __it => {if __breakpoint_show_start(0) {__breakpoint_show_start_handle(0, (:)); };
+        __it => {if __breakpoint_show_start(1) {__breakpoint_show_start_handle(1, (:)); };
         __bp_functor(__it); } }
         "###);
     }
+
+    #[test]
+    fn test_function_breakpoint() {
+        let source = Source::detached("#let f(x) = x\n#let g = y => y");
+        let (_, meta) = instrument_breakpoints(source).unwrap();
+        let functions = meta
+            .meta
+            .iter()
+            .filter(|item| item.kind == BreakpointKind::CallStart)
+            .map(|item| item.name.as_deref())
+            .collect::<Vec<_>>();
+
+        assert_eq!(functions, vec![Some("f"), None]);
+    }
+
+    #[test]
+    fn test_instrument_return() {
+        let source = Source::detached("#let f(x) = { return x }");
+        let (new, meta) = instrument_breakpoints(source).unwrap();
+        let kinds = meta.meta.iter().map(|item| item.kind).collect::<Vec<_>>();
+
+        assert!(new.root().errors().is_empty());
+        assert_eq!(
+            kinds,
+            vec![
+                BreakpointKind::Line,
+                BreakpointKind::CallStart,
+                BreakpointKind::Expr,
+                BreakpointKind::Return,
+                BreakpointKind::CallEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_breakpoint() {
+        let source = Source::detached("#let a = 1\n\n#let f(x) = {\n  x; x + 1\n}\n");
+        let resolve = |line, offset| {
+            let bp = resolve_breakpoint(&source, line, offset)?;
+            Some((bp.kind, bp.range))
+        };
+
+        assert_eq!(resolve(0, None), Some((BreakpointKind::Line, 1..10)));
+        assert_eq!(resolve(1, None), None);
+        assert_eq!(resolve(3, None), Some((BreakpointKind::Line, 28..29)));
+        assert_eq!(resolve(3, Some(29)), Some((BreakpointKind::Expr, 31..36)));
+        assert_eq!(resolve(4, None), None);
+    }
 }
@@ -2,7 +2,9 @@
 
 pub use cov::CoverageResult;
 pub use debugger::{
-    BreakpointKind, DebugSession, DebugSessionHandler, set_debug_session, with_debug_session,
+    BreakpointItem, BreakpointKind, CallFrame, DebugSession, DebugSessionHandler,
+    ResolvedBreakpoint, breakpoint_locations, call_depth, call_stack, resolve_breakpoint,
+    set_debug_session, unwind_calls, with_debug_session, with_debug_session_mut,
 };
 
 mod cov;
@@ -85,6 +87,8 @@ fn instrument_library(library: &Arc<LazyHash<Library>>) -> Arc<LazyHash<Library>
 
     let scope = library.global.scope_mut();
     scope.define_func::<__cov_pc>();
+    scope.define_func::<__breakpoint_expr>();
+    scope.define_func::<__breakpoint_line>();
     scope.define_func::<__breakpoint_call_start>();
     scope.define_func::<__breakpoint_call_end>();
     scope.define_func::<__breakpoint_function>();
@@ -98,6 +102,8 @@ fn instrument_library(library: &Arc<LazyHash<Library>>) -> Arc<LazyHash<Library>
     scope.define_func::<__breakpoint_doc_start>();
     scope.define_func::<__breakpoint_doc_end>();
 
+    scope.define_func::<__breakpoint_expr_handle>();
+    scope.define_func::<__breakpoint_line_handle>();
     scope.define_func::<__breakpoint_call_start_handle>();
     scope.define_func::<__breakpoint_call_end_handle>();
     scope.define_func::<__breakpoint_function_handle>();
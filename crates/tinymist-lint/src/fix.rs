@@ -0,0 +1,108 @@
+//! Machine-applicable fixes of lint diagnostics.
+
+use std::ops::Range;
+
+use typst::{
+    diag::EcoString,
+    ecow::{EcoVec, eco_vec},
+    syntax::{LinkedNode, Source, SyntaxKind},
+};
+
+/// A machine-applicable fix of a lint diagnostic.
+#[derive(Debug, Clone)]
+pub struct LintFix {
+    /// The title of the fix.
+    pub title: EcoString,
+    /// The non-overlapping edits to apply to the linted source.
+    pub edits: EcoVec<LintEdit>,
+    /// Whether the fix can be applied without reviewing it.
+    pub applicability: Applicability,
+}
+
+/// Whether a fix can be applied without reviewing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    /// The fix keeps the code working and can be applied in bulk, e.g. by
+    /// `tinymist lint --fix`.
+    MachineApplicable,
+    /// The fix may break the code elsewhere, e.g. the callers of a function
+    /// losing a parameter, so it is only applied on request.
+    MaybeIncorrect,
+}
+
+/// A text edit of a lint fix.
+#[derive(Debug, Clone)]
+pub struct LintEdit {
+    /// The byte range to replace.
+    pub range: Range<usize>,
+    /// The text to replace with.
+    pub text: EcoString,
+}
+
+impl LintFix {
+    /// Creates a fix replacing a range with the text.
+    pub fn replace(title: EcoString, range: Range<usize>, text: impl Into<EcoString>) -> Self {
+        Self {
+            title,
+            edits: eco_vec![LintEdit {
+                range,
+                text: text.into(),
+            }],
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    /// Marks the fix as one that may break the code elsewhere.
+    pub fn maybe_incorrect(mut self) -> Self {
+        self.applicability = Applicability::MaybeIncorrect;
+        self
+    }
+}
+
+/// Gets the range of a statement, including the leading hash and the trailing
+/// semicolon and line break.
+pub(crate) fn statement_range(source: &Source, stmt: &LinkedNode) -> Range<usize> {
+    let start = match stmt.prev_sibling() {
+        Some(prev) if prev.kind() == SyntaxKind::Hash => prev.offset(),
+        _ => stmt.offset(),
+    };
+
+    let end = stmt.range().end;
+    let rest = &source.text()[end..];
+    let trimmed = rest.trim_start_matches([' ', '\t']);
+    let trimmed = trimmed.strip_prefix(';').unwrap_or(trimmed);
+    let trimmed = trimmed.trim_start_matches([' ', '\t']);
+    let end = match trimmed
+        .strip_prefix("\r\n")
+        .or_else(|| trimmed.strip_prefix('\n'))
+    {
+        Some(after) => end + rest.len() - after.len(),
+        None => end + rest.len() - trimmed.len(),
+    };
+
+    start..end
+}
+
+/// Gets the range of an item in a comma-separated list, including one of the
+/// adjacent commas.
+pub(crate) fn list_item_range(item: &LinkedNode) -> Range<usize> {
+    if let Some(comma) = item
+        .next_sibling()
+        .filter(|it| it.kind() == SyntaxKind::Comma)
+    {
+        let end = match comma.next_sibling() {
+            Some(next) => next.offset(),
+            None => comma.range().end,
+        };
+        return item.offset()..end;
+    }
+
+    if let Some(comma) = item
+        .prev_sibling()
+        .filter(|it| it.kind() == SyntaxKind::Comma)
+    {
+        return comma.offset()..item.range().end;
+    }
+
+    item.range()
+}
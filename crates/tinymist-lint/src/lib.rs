@@ -1,5 +1,15 @@
 //! A linter for Typst.
 
+mod fix;
+mod rule;
+mod suppress;
+mod unused;
+
+pub use fix::{Applicability, LintEdit, LintFix};
+pub use rule::*;
+pub use tinymist_project::{LintConfig, LintSeverity};
+pub use unused::ExportUsage;
+
 use std::sync::Arc;
 
 use tinymist_analysis::{
@@ -8,16 +18,18 @@ use tinymist_analysis::{
 };
 use tinymist_project::LspWorld;
 use typst::{
-    diag::{EcoString, SourceDiagnostic, Tracepoint, eco_format},
+    diag::{EcoString, Severity, SourceDiagnostic, Tracepoint, eco_format},
     ecow::EcoVec,
     syntax::{
-        FileId, Span, Spanned, SyntaxNode,
+        FileId, Source, Span, Spanned, SyntaxNode,
         ast::{self, AstNode},
     },
 };
 
+use suppress::Suppressions;
+
 /// A type alias for a vector of diagnostics.
-type DiagnosticVec = EcoVec<SourceDiagnostic>;
+type DiagnosticVec = EcoVec<LintDiagnostic>;
 
 /// The lint information about a file.
 #[derive(Debug, Clone)]
@@ -30,9 +42,31 @@ pub struct LintInfo {
     pub diagnostics: DiagnosticVec,
 }
 
+/// A diagnostic reported by a lint rule.
+#[derive(Debug, Clone)]
+pub struct LintDiagnostic {
+    /// The rule reporting the diagnostic.
+    pub rule: LintRule,
+    /// The configured severity of the rule.
+    pub severity: LintSeverity,
+    /// The diagnostic.
+    pub diag: SourceDiagnostic,
+    /// The machine-applicable fix of the diagnostic.
+    pub fix: Option<LintFix>,
+}
+
 /// Performs linting check on file and returns a vector of diagnostics.
-pub fn lint_file(world: &LspWorld, expr: &ExprInfo, ti: Arc<TypeInfo>) -> LintInfo {
-    let diagnostics = Linter::new(world, ti).lint(expr.source.root());
+///
+/// The `exports` tells how the exports of the file are used by other modules,
+/// which is used to check unused exports.
+pub fn lint_file(
+    world: &LspWorld,
+    expr: &ExprInfo,
+    ti: Arc<TypeInfo>,
+    config: &LintConfig,
+    exports: &ExportUsage,
+) -> LintInfo {
+    let diagnostics = Linter::new(world, ti, config, &expr.source).lint(expr, exports);
     LintInfo {
         revision: expr.revision,
         fid: expr.fid,
@@ -43,16 +77,27 @@ pub fn lint_file(world: &LspWorld, expr: &ExprInfo, ti: Arc<TypeInfo>) -> LintIn
 struct Linter<'w> {
     world: &'w LspWorld,
     ti: Arc<TypeInfo>,
+    config: &'w LintConfig,
+    source: &'w Source,
+    suppressions: Suppressions,
     diag: DiagnosticVec,
     loop_info: Option<LoopInfo>,
     func_info: Option<FuncInfo>,
 }
 
 impl<'w> Linter<'w> {
-    fn new(world: &'w LspWorld, ti: Arc<TypeInfo>) -> Self {
+    fn new(
+        world: &'w LspWorld,
+        ti: Arc<TypeInfo>,
+        config: &'w LintConfig,
+        source: &'w Source,
+    ) -> Self {
         Self {
             world,
             ti,
+            config,
+            source,
+            suppressions: Suppressions::collect(source),
             diag: EcoVec::new(),
             loop_info: None,
             func_info: None,
@@ -63,16 +108,55 @@ impl<'w> Linter<'w> {
         self.ti.as_ref()
     }
 
-    fn lint(mut self, node: &SyntaxNode) -> DiagnosticVec {
+    fn lint(mut self, ei: &ExprInfo, exports: &ExportUsage) -> DiagnosticVec {
+        let node = ei.source.root();
         if let Some(markup) = node.cast::<ast::Markup>() {
             self.exprs(markup.exprs());
         } else if let Some(expr) = node.cast() {
             self.expr(expr);
         }
+        self.unused(ei, exports);
 
         self.diag
     }
 
+    /// Whether the rule is enabled at the given span.
+    fn is_enabled(&self, rule: LintRule, span: Span) -> bool {
+        if rule.severity(self.config) == LintSeverity::Off {
+            return false;
+        }
+
+        match self.source.range(span) {
+            Some(range) => !self.suppressions.is_suppressed(rule, &range),
+            None => true,
+        }
+    }
+
+    /// Reports a diagnostic of the rule unless the rule is disabled or
+    /// suppressed.
+    fn report(&mut self, rule: LintRule, diag: SourceDiagnostic) {
+        self.report_fix(rule, diag, None);
+    }
+
+    /// Reports a diagnostic of the rule along with its fix.
+    fn report_fix(&mut self, rule: LintRule, mut diag: SourceDiagnostic, fix: Option<LintFix>) {
+        if !self.is_enabled(rule, diag.span) {
+            return;
+        }
+
+        let severity = rule.severity(self.config);
+        diag.severity = match severity {
+            LintSeverity::Error => Severity::Error,
+            LintSeverity::Off | LintSeverity::Hint | LintSeverity::Warning => Severity::Warning,
+        };
+        self.diag.push(LintDiagnostic {
+            rule,
+            severity,
+            diag,
+            fix,
+        });
+    }
+
     fn with_loop_info<F>(&mut self, span: Span, f: F) -> Option<()>
     where
         F: FnOnce(&mut Self) -> Option<()>,
@@ -134,7 +218,7 @@ impl<'w> Linter<'w> {
             diag.trace
                 .push(Spanned::new(Tracepoint::Call(None), func_info.span));
         }
-        self.diag.push(diag);
+        self.report(LintRule::BadBranchStmt, diag);
 
         Some(())
     }
@@ -154,11 +238,19 @@ impl<'w> Linter<'w> {
                     _ => continue,
                 };
                 let mut warning = SourceDiagnostic::warning(set.span(), msg);
+                let mut fix = None;
                 if first {
                     first = false;
                     warning.hint(loc.hint(set));
+                    if block.iter().count() == 1 {
+                        fix = loc.fix(set).and_then(|(span, text)| {
+                            let range = self.source.range(span)?;
+                            let title = EcoString::inline("Move the statement out of the block");
+                            Some(LintFix::replace(title, range, text))
+                        });
+                    }
                 }
-                self.diag.push(warning);
+                self.report_fix(LintRule::BuggyBlock, warning, fix);
             }
 
             return None;
@@ -204,7 +296,15 @@ impl<'w> Linter<'w> {
                     "compare with the literal type instead".into(),
                     "this comparison will always return `false` since typst v0.14".into(),
                 ]);
-                self.diag.push(diag);
+                let fix = match rhs.expr {
+                    ast::Expr::Str(lit) => type_of_name(&lit.get()).and_then(|ty| {
+                        let range = self.source.range(lit.span())?;
+                        let title = eco_format!("Compare with the type `{ty}`");
+                        Some(LintFix::replace(title, range, ty))
+                    }),
+                    _ => None,
+                };
+                self.report_fix(LintRule::TypeCompare, diag, fix);
             }
         }
     }
@@ -254,7 +354,7 @@ impl<'w> Linter<'w> {
         let diag =
             SourceDiagnostic::warning(expr.span(), "variable font is not supported by typst yet");
         let diag = diag.with_hint("consider using a static font instead. For more information, see https://github.com/typst/typst/issues/185");
-        self.diag.push(diag);
+        self.report(LintRule::VariableFont, diag);
 
         Some(())
     }
@@ -367,10 +467,13 @@ impl DataFlowVisitor for Linter<'_> {
             info.has_return = true;
             info.has_return_value = expr.body().is_some();
         } else {
-            self.diag.push(SourceDiagnostic::warning(
-                expr.span(),
-                "`return` statement in a non-function context",
-            ));
+            self.report(
+                LintRule::BadReturnStmt,
+                SourceDiagnostic::warning(
+                    expr.span(),
+                    "`return` statement in a non-function context",
+                ),
+            );
         }
         Some(())
     }
@@ -598,7 +701,7 @@ impl DataFlowVisitor for LateFuncLinter<'_, '_> {
                 )),
                 _ => diag,
             };
-            self.linter.diag.push(diag);
+            self.linter.report(LintRule::ImplicitDiscard, diag);
         } else if ri.return_none && matches!(expr, ast::Expr::Show(..) | ast::Expr::Set(..)) {
             ri.warned = true;
             let diag = SourceDiagnostic::warning(
@@ -608,7 +711,7 @@ impl DataFlowVisitor for LateFuncLinter<'_, '_> {
                     expr.to_untyped().kind().name()
                 ),
             );
-            self.linter.diag.push(diag);
+            self.linter.report(LintRule::ImplicitDiscard, diag);
         }
 
         Some(())
@@ -1004,6 +1107,73 @@ impl BuggyBlockLoc<'_> {
             }
         }
     }
+
+    /// Returns the replacement of the parent expression that makes the only
+    /// statement in the block take effect.
+    fn fix(&self, show_set: ast::Expr<'_>) -> Option<(Span, EcoString)> {
+        let ast::Expr::Set(set) = show_set else {
+            return None;
+        };
+        let set_text = set.to_untyped().clone().into_text();
+
+        match self {
+            BuggyBlockLoc::Show(show_parent) => {
+                let text = match show_parent.selector() {
+                    Some(selector) => {
+                        eco_format!(
+                            "show {}: {set_text}",
+                            selector.to_untyped().clone().into_text()
+                        )
+                    }
+                    None => eco_format!("show: {set_text}"),
+                };
+                Some((show_parent.span(), text))
+            }
+            BuggyBlockLoc::IfTrue(conditional)
+                if conditional.else_body().is_none() && set.condition().is_none() =>
+            {
+                let cond = conditional.condition().to_untyped().clone().into_text();
+                Some((conditional.span(), eco_format!("{set_text} if ({cond})")))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the name of the type that used to be compared with the string, e.g.
+/// `"string"` or `"str"` for `str`.
+fn type_of_name(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "string" | "str" => "str",
+        "integer" | "int" => "int",
+        "boolean" | "bool" => "bool",
+        "float" => "float",
+        "content" => "content",
+        "array" => "array",
+        "dictionary" => "dictionary",
+        "function" => "function",
+        "length" => "length",
+        "angle" => "angle",
+        "ratio" => "ratio",
+        "relative length" | "relative" => "relative",
+        "fraction" => "fraction",
+        "color" => "color",
+        "gradient" => "gradient",
+        "datetime" => "datetime",
+        "duration" => "duration",
+        "bytes" => "bytes",
+        "label" => "label",
+        "selector" => "selector",
+        "arguments" => "arguments",
+        "module" => "module",
+        "version" => "version",
+        "symbol" => "symbol",
+        "decimal" => "decimal",
+        "regex" => "regex",
+        "none" => "type(none)",
+        "auto" => "type(auto)",
+        _ => return None,
+    })
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
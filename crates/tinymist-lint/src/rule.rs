@@ -0,0 +1,126 @@
+//! The registry of lint rules.
+
+use std::str::FromStr;
+
+use tinymist_project::{LintConfig, LintSeverity};
+
+/// A lint rule checked by the linter. Each rule has a stable ID that is used
+/// in configurations and suppression comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    /// `break` or `continue` statements in a non-loop context.
+    BadBranchStmt,
+    /// `return` statements in a non-function context.
+    BadReturnStmt,
+    /// Set or show rules that don't take effect because the block ends right
+    /// after them.
+    BuggyBlock,
+    /// Comparisons between strings and types, which are deprecated.
+    TypeCompare,
+    /// Variable fonts, which are not supported by typst yet.
+    VariableFont,
+    /// Values that are implicitly discarded by a function return.
+    ImplicitDiscard,
+    /// Local bindings that are never used.
+    UnusedBinding,
+    /// Closure parameters that are never used.
+    UnusedParam,
+    /// Import items that are never used.
+    UnusedImport,
+    /// Exports of a non-entry module that are never used by the module itself
+    /// or other modules.
+    UnusedExport,
+}
+
+impl LintRule {
+    /// All the lint rules known by the linter.
+    pub const ALL: &'static [LintRule] = &[
+        LintRule::BadBranchStmt,
+        LintRule::BadReturnStmt,
+        LintRule::BuggyBlock,
+        LintRule::TypeCompare,
+        LintRule::VariableFont,
+        LintRule::ImplicitDiscard,
+        LintRule::UnusedBinding,
+        LintRule::UnusedParam,
+        LintRule::UnusedImport,
+        LintRule::UnusedExport,
+    ];
+
+    /// Returns the stable ID of the rule.
+    pub fn id(self) -> &'static str {
+        match self {
+            LintRule::BadBranchStmt => "bad-branch-stmt",
+            LintRule::BadReturnStmt => "bad-return-stmt",
+            LintRule::BuggyBlock => "buggy-block",
+            LintRule::TypeCompare => "type-compare",
+            LintRule::VariableFont => "variable-font",
+            LintRule::ImplicitDiscard => "implicit-discard",
+            LintRule::UnusedBinding => "unused-binding",
+            LintRule::UnusedParam => "unused-param",
+            LintRule::UnusedImport => "unused-import",
+            LintRule::UnusedExport => "unused-export",
+        }
+    }
+
+    /// Returns a short description of the rule.
+    pub fn description(self) -> &'static str {
+        match self {
+            LintRule::BadBranchStmt => "`break` or `continue` statements in a non-loop context",
+            LintRule::BadReturnStmt => "`return` statements in a non-function context",
+            LintRule::BuggyBlock => "set or show rules that don't take effect",
+            LintRule::TypeCompare => "comparisons between strings and types, which are deprecated",
+            LintRule::VariableFont => "variable fonts, which are not supported by typst yet",
+            LintRule::ImplicitDiscard => "values implicitly discarded by a function return",
+            LintRule::UnusedBinding => "local bindings that are never used",
+            LintRule::UnusedParam => "closure parameters that are never used",
+            LintRule::UnusedImport => "import items that are never used",
+            LintRule::UnusedExport => "exports of a non-entry module that are never used",
+        }
+    }
+
+    /// Returns the severity of the rule if it is not configured.
+    pub fn default_severity(self) -> LintSeverity {
+        if self.is_unused() {
+            LintSeverity::Hint
+        } else {
+            LintSeverity::Warning
+        }
+    }
+
+    /// Whether the rule reports unused code, which can be safely removed.
+    pub fn is_unused(self) -> bool {
+        matches!(
+            self,
+            LintRule::UnusedBinding
+                | LintRule::UnusedParam
+                | LintRule::UnusedImport
+                | LintRule::UnusedExport
+        )
+    }
+
+    /// Returns the effective severity of the rule under a configuration.
+    pub fn severity(self, config: &LintConfig) -> LintSeverity {
+        config
+            .severity(self.id())
+            .unwrap_or_else(|| self.default_severity())
+    }
+}
+
+impl FromStr for LintRule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LintRule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.id() == s)
+            .ok_or(())
+    }
+}
+
+impl std::fmt::Display for LintRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
@@ -0,0 +1,174 @@
+//! Inline suppression of lint rules.
+//!
+//! A comment in the form of `// tinymist-lint: allow(rule-a, rule-b)`
+//! suppresses the listed rules in a scope determined by its position:
+//! - at the start of a file, the whole file;
+//! - at the start of a code or content block, the whole block;
+//! - at the end of a line, the statement on that line;
+//! - otherwise, the next statement.
+
+use std::ops::Range;
+
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+
+use crate::LintRule;
+
+/// The prefix of a suppression comment.
+const DIRECTIVE_PREFIX: &str = "tinymist-lint:";
+
+/// The suppressed lint rules in a source file.
+#[derive(Debug, Default)]
+pub(crate) struct Suppressions {
+    items: Vec<Suppression>,
+}
+
+#[derive(Debug)]
+struct Suppression {
+    rules: Vec<LintRule>,
+    range: Range<usize>,
+}
+
+impl Suppressions {
+    /// Collects the suppression comments in a source file.
+    pub fn collect(source: &Source) -> Self {
+        let mut this = Self::default();
+        this.collect_node(source, &LinkedNode::new(source.root()));
+        this
+    }
+
+    /// Whether the rule is suppressed at the given range.
+    pub fn is_suppressed(&self, rule: LintRule, range: &Range<usize>) -> bool {
+        self.items.iter().any(|item| {
+            item.rules.contains(&rule)
+                && item.range.start <= range.start
+                && range.end <= item.range.end
+        })
+    }
+
+    fn collect_node(&mut self, source: &Source, node: &LinkedNode) {
+        let children = node.children().collect::<Vec<_>>();
+        for (idx, child) in children.iter().enumerate() {
+            if matches!(
+                child.kind(),
+                SyntaxKind::LineComment | SyntaxKind::BlockComment
+            ) {
+                if let Some(rules) = parse_directive(child.text()) {
+                    if let Some(range) = directive_scope(source, node, &children, idx) {
+                        self.items.push(Suppression { rules, range });
+                    }
+                }
+            } else {
+                self.collect_node(source, child);
+            }
+        }
+    }
+}
+
+/// Determines the range covered by the directive at `siblings[idx]`.
+fn directive_scope(
+    source: &Source,
+    parent: &LinkedNode,
+    siblings: &[LinkedNode],
+    idx: usize,
+) -> Option<Range<usize>> {
+    let Some(prev) = siblings[..idx].iter().rev().find(|it| !is_skipped(it)) else {
+        return Some(parent.range());
+    };
+
+    let between = source
+        .text()
+        .get(prev.range().end..siblings[idx].offset())?;
+    if !between.contains('\n') {
+        return Some(prev.range());
+    }
+
+    let next = siblings[idx + 1..].iter().find(|it| !is_skipped(it))?;
+    Some(next.range())
+}
+
+/// Whether the node is not a statement.
+fn is_skipped(node: &LinkedNode) -> bool {
+    node.kind().is_trivia() || node.kind() == SyntaxKind::Hash
+}
+
+/// Parses the rules listed in a suppression comment.
+fn parse_directive(comment: &str) -> Option<Vec<LintRule>> {
+    let content = if let Some(line) = comment.strip_prefix("//") {
+        line
+    } else {
+        comment.strip_prefix("/*")?.strip_suffix("*/")?
+    };
+
+    let args = content
+        .trim()
+        .strip_prefix(DIRECTIVE_PREFIX)?
+        .trim()
+        .strip_prefix("allow")?
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    Some(
+        args.split(',')
+            .filter_map(|rule| rule.trim().parse().ok())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive() {
+        assert_eq!(
+            parse_directive("// tinymist-lint: allow(type-compare)"),
+            Some(vec![LintRule::TypeCompare])
+        );
+        assert_eq!(
+            parse_directive("/* tinymist-lint: allow(buggy-block, variable-font) */"),
+            Some(vec![LintRule::BuggyBlock, LintRule::VariableFont])
+        );
+        assert_eq!(
+            parse_directive("// tinymist-lint: allow(unknown-rule)"),
+            Some(vec![])
+        );
+        assert_eq!(parse_directive("// tinymist-lint: deny(buggy-block)"), None);
+        assert_eq!(parse_directive("// a regular comment"), None);
+    }
+
+    #[test]
+    fn test_directive_scope() {
+        let source = Source::detached(
+            "// tinymist-lint: allow(type-compare)\n\
+             #let a = 1\n\
+             #{\n\
+             // tinymist-lint: allow(buggy-block)\n\
+             let b = 2\n\
+             let c = 3 // tinymist-lint: allow(variable-font)\n\
+             // tinymist-lint: allow(bad-branch-stmt)\n\
+             let d = 4\n\
+             }\n",
+        );
+        let suppressions = Suppressions::collect(&source);
+        let range_of = |text: &str| {
+            let start = source.text().find(text).unwrap();
+            start..start + text.len()
+        };
+
+        let file_wide = range_of("#let a = 1");
+        assert!(suppressions.is_suppressed(LintRule::TypeCompare, &file_wide));
+        assert!(!suppressions.is_suppressed(LintRule::BuggyBlock, &file_wide));
+
+        let in_block = range_of("let b = 2");
+        assert!(suppressions.is_suppressed(LintRule::BuggyBlock, &in_block));
+        assert!(!suppressions.is_suppressed(LintRule::VariableFont, &in_block));
+
+        let same_line = range_of("let c = 3");
+        assert!(suppressions.is_suppressed(LintRule::VariableFont, &same_line));
+        assert!(!suppressions.is_suppressed(LintRule::BadBranchStmt, &same_line));
+
+        let next_stmt = range_of("let d = 4");
+        assert!(suppressions.is_suppressed(LintRule::BadBranchStmt, &next_stmt));
+    }
+}
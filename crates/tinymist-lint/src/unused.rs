@@ -0,0 +1,280 @@
+//! Lints for unused bindings, parameters, imports and exports.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use tinymist_analysis::{
+    syntax::{Decl, DeclExpr, Expr, ExprInfo},
+    ty::Interned,
+};
+use tinymist_std::hash::FxHashSet;
+use typst::{
+    diag::{SourceDiagnostic, eco_format},
+    syntax::{FileId, LinkedNode, SyntaxKind},
+};
+
+use crate::fix::{list_item_range, statement_range};
+use crate::{Applicability, LintFix, LintRule, Linter};
+
+/// How the exports of a module are used by other modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ExportUsage {
+    /// The module is the entry of a document, of which the bindings are
+    /// public but the imports are not.
+    #[default]
+    Entry,
+    /// All the exports may be used, e.g. the module is bound to a variable by
+    /// some dependent.
+    All,
+    /// The names of the exports used by the dependents of the module.
+    Used(BTreeSet<Interned<str>>),
+}
+
+impl ExportUsage {
+    /// Collects the usage of the exports of the module `fid` from the
+    /// expression information of its dependents.
+    pub fn collect<'a>(fid: FileId, dependents: impl IntoIterator<Item = &'a ExprInfo>) -> Self {
+        let of_module = |expr: &Option<Expr>| match expr {
+            Some(Expr::Decl(decl)) if decl.file_id() == Some(fid) => Some(decl.clone()),
+            _ => None,
+        };
+
+        let mut used = BTreeSet::new();
+        for ei in dependents {
+            for r in ei.resolves.values() {
+                let root = of_module(&r.root);
+                let is_module_var =
+                    matches!(r.decl.as_ref(), Decl::ModuleAlias(..) | Decl::PathStem(..));
+                if is_module_var && matches!(root.as_deref(), Some(Decl::Module(..))) {
+                    // The fields accessed on a module variable are not tracked.
+                    return Self::All;
+                }
+
+                used.extend(
+                    root.into_iter()
+                        .chain(of_module(&r.step))
+                        .map(|decl| decl.name().clone()),
+                );
+            }
+        }
+
+        Self::Used(used)
+    }
+
+    /// Whether the export of the kind is used by other modules.
+    fn is_used(&self, kind: &DeclKind, name: &Interned<str>) -> bool {
+        match self {
+            Self::Entry => matches!(kind, DeclKind::Binding),
+            Self::All => true,
+            Self::Used(used) => used.contains(name),
+        }
+    }
+}
+
+/// Gets the text to replace an unused binding in a destructuring pattern.
+fn placeholder_of(parent: &LinkedNode) -> &'static str {
+    match parent.kind() {
+        // `..rest` becomes `..`
+        SyntaxKind::Spread => "",
+        _ => "_",
+    }
+}
+
+/// The kind of an unused declaration, determined by its syntax.
+enum DeclKind {
+    Binding,
+    Param,
+    Import,
+}
+
+impl Linter<'_> {
+    /// Reports the declarations that are never referenced in the file.
+    pub(crate) fn unused(&mut self, ei: &ExprInfo, exports: &ExportUsage) {
+        let mut used = FxHashSet::default();
+        let mut renamed = FxHashSet::default();
+        for r in ei.resolves.values() {
+            match (r.decl.as_ref(), &r.step) {
+                (Decl::IdentRef(..), Some(Expr::Decl(decl))) => {
+                    used.insert(decl.clone());
+                }
+                (Decl::ImportAlias(..), Some(Expr::Decl(decl))) => {
+                    renamed.insert(decl.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let mut unused = ei
+            .resolves
+            .values()
+            .map(|r| &r.decl)
+            .filter(|decl| !used.contains(*decl) && !renamed.contains(*decl))
+            .filter(|decl| !decl.name().starts_with('_'))
+            .filter_map(|decl| {
+                let range = self.source.range(decl.span())?;
+                Some((range.start, decl.clone()))
+            })
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(start, _)| *start);
+
+        for (_, decl) in unused {
+            let Some(kind) = self.decl_kind(&decl) else {
+                continue;
+            };
+
+            let exported = match kind {
+                DeclKind::Binding => ei.is_exported(&decl),
+                DeclKind::Param => false,
+                DeclKind::Import => matches!(
+                    ei.exports.get(decl.name()),
+                    Some(Expr::Ref(r)) if r.decl == decl
+                ),
+            };
+            if exported && exports.is_used(&kind, decl.name()) {
+                continue;
+            }
+
+            let name = decl.name();
+            let (rule, what) = match kind {
+                DeclKind::Binding if exported => (LintRule::UnusedExport, "export"),
+                DeclKind::Binding => (LintRule::UnusedBinding, "binding"),
+                DeclKind::Param => (LintRule::UnusedParam, "parameter"),
+                DeclKind::Import => (LintRule::UnusedImport, "import"),
+            };
+            let message = eco_format!("unused {what} `{name}`");
+
+            let diag = SourceDiagnostic::warning(decl.span(), message);
+            let diag = match kind {
+                _ if exported => diag
+                    .with_hint("it is neither used in this module nor imported by other modules"),
+                DeclKind::Import => diag,
+                DeclKind::Binding | DeclKind::Param => diag.with_hint(eco_format!(
+                    "if this is intentional, prefix it with an underscore: `_{name}`"
+                )),
+            };
+            let fix = self
+                .unused_fix(&decl, &kind)
+                .map(|(range, text, applicability)| {
+                    let title = eco_format!("Remove unused {what} `{name}`");
+                    let fix = LintFix::replace(title, range, text);
+                    // Removing an export may break the modules which are not
+                    // compiled along with this one.
+                    if exported || applicability == Applicability::MaybeIncorrect {
+                        fix.maybe_incorrect()
+                    } else {
+                        fix
+                    }
+                });
+            self.report_fix(rule, diag, fix);
+        }
+    }
+
+    /// Gets the edit removing an unused declaration.
+    fn unused_fix(
+        &self,
+        decl: &DeclExpr,
+        kind: &DeclKind,
+    ) -> Option<(Range<usize>, &'static str, Applicability)> {
+        use Applicability::*;
+
+        let node = self.source.find(decl.span())?;
+        let parent = node.parent()?;
+
+        match kind {
+            DeclKind::Import => Some((self.unused_import_range(&node)?, "", MachineApplicable)),
+            // Removing a parameter changes the signature of the function and
+            // breaks its callers.
+            DeclKind::Param => match parent.kind() {
+                SyntaxKind::Params => Some((list_item_range(&node), "", MaybeIncorrect)),
+                SyntaxKind::Named | SyntaxKind::Spread
+                    if parent.parent_kind() == Some(SyntaxKind::Params) =>
+                {
+                    Some((list_item_range(parent), "", MaybeIncorrect))
+                }
+                // A binding destructured from a parameter.
+                _ => Some((node.range(), placeholder_of(parent), MachineApplicable)),
+            },
+            DeclKind::Binding => match parent.kind() {
+                SyntaxKind::LetBinding => {
+                    Some((statement_range(self.source, parent), "", MachineApplicable))
+                }
+                SyntaxKind::Closure => Some((
+                    statement_range(self.source, parent.parent()?),
+                    "",
+                    MachineApplicable,
+                )),
+                // A binding destructured from a value.
+                _ => Some((node.range(), placeholder_of(parent), MachineApplicable)),
+            },
+        }
+    }
+
+    /// Gets the range to remove for an unused import item. The whole import is
+    /// removed if it is the only item.
+    fn unused_import_range(&self, node: &LinkedNode) -> Option<Range<usize>> {
+        let mut item = node.clone();
+        while item.parent_kind() != Some(SyntaxKind::ImportItems) {
+            item = item.parent()?.clone();
+        }
+        let items = item.parent()?;
+        let is_item = |node: &LinkedNode| {
+            matches!(
+                node.kind(),
+                SyntaxKind::ImportItemPath | SyntaxKind::RenamedImportItem
+            )
+        };
+        if items.children().filter(is_item).count() > 1 {
+            return Some(list_item_range(&item));
+        }
+
+        let mut import = items.clone();
+        while import.kind() != SyntaxKind::ModuleImport {
+            import = import.parent()?.clone();
+        }
+        if import
+            .children()
+            .any(|child| child.kind() == SyntaxKind::As)
+        {
+            // Keeps the module bound by `import "..." as name`.
+            let colon = import
+                .children()
+                .find(|child| child.kind() == SyntaxKind::Colon)?;
+            return Some(colon.offset()..import.range().end);
+        }
+
+        Some(statement_range(self.source, &import))
+    }
+
+    /// Determines the kind of a declaration, or `None` if it is not checked.
+    fn decl_kind(&self, decl: &DeclExpr) -> Option<DeclKind> {
+        match decl.as_ref() {
+            Decl::Import(..) | Decl::ImportAlias(..) => return Some(DeclKind::Import),
+            Decl::Var(..) | Decl::Func(..) => {}
+            _ => return None,
+        }
+
+        let node = self.source.find(decl.span())?;
+        let mut parent = node.parent()?.clone();
+        if parent.kind() == SyntaxKind::Closure {
+            // The name of a closure bound by `let f(..) = ..`.
+            parent = parent.parent()?.clone();
+        }
+        while matches!(
+            parent.kind(),
+            SyntaxKind::Destructuring
+                | SyntaxKind::Parenthesized
+                | SyntaxKind::Named
+                | SyntaxKind::Spread
+        ) {
+            parent = parent.parent()?.clone();
+        }
+
+        match parent.kind() {
+            SyntaxKind::LetBinding => Some(DeclKind::Binding),
+            SyntaxKind::Params => Some(DeclKind::Param),
+            // Loop variables and destructuring assignments are not bindings to
+            // remove.
+            _ => None,
+        }
+    }
+}
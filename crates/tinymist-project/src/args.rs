@@ -131,6 +131,11 @@ pub struct TaskCompileArgs {
     #[arg(long = "when")]
     pub when: Option<TaskWhen>,
 
+    /// The typst script deciding whether to run the task when `--when` is
+    /// `script`, e.g., `document.title != none`.
+    #[arg(long = "when-script", value_name = "SCRIPT")]
+    pub when_script: Option<String>,
+
     /// Path to output file (PDF, PNG, SVG, or HTML). Use `-` to write output to
     /// stdout.
     ///
@@ -159,6 +164,17 @@ pub struct TaskCompileArgs {
     #[arg(long = "pages", value_delimiter = ',')]
     pub pages: Option<Vec<Pages>>,
 
+    /// Typst scripts to run over the exported artifact before writing it,
+    /// in order.
+    ///
+    /// A script can read the exported artifact as `artifact`, its extension as
+    /// `format`, and the page count, labels, and metadata of the document as
+    /// `document`. It returns `none` to keep the artifact, `bytes` or `str` to
+    /// replace it, or an array of page numbers to keep for PDF, PNG, and SVG
+    /// outputs.
+    #[arg(long = "transform-script", value_name = "SCRIPT")]
+    pub transform_scripts: Vec<String>,
+
     /// The argument to export to PDF.
     #[clap(flatten)]
     pub pdf: PdfExportArgs,
@@ -209,6 +225,9 @@ impl TaskCompileArgs {
         });
 
         let when = self.when.unwrap_or(TaskWhen::Never);
+        if when == TaskWhen::Script && self.when_script.is_none() {
+            bail!("`--when-script` must be provided when `--when` is `script`");
+        }
 
         let mut transforms = vec![];
 
@@ -218,8 +237,15 @@ impl TaskCompileArgs {
             });
         }
 
+        for script in &self.transform_scripts {
+            transforms.push(ExportTransform::Script {
+                script: Some(script.clone()),
+            });
+        }
+
         let export = ExportTask {
             when,
+            when_script: self.when_script.clone(),
             output,
             transform: transforms,
         };
@@ -117,6 +117,15 @@ impl LockFile {
             }
         }
 
+        let lint = content.get("lint");
+        if let Some(lint) = lint {
+            let mut lint_table = toml::Table::new();
+            lint_table.insert("lint".to_string(), lint.clone());
+
+            out.push('\n');
+            out.push_str(&lint_table.to_string());
+        }
+
         return out;
 
         fn emit_document(input: &toml::Value, out: &mut String) {
@@ -165,6 +174,7 @@ impl LockFile {
                 document: vec![],
                 task: vec![],
                 route: eco_vec![],
+                lint: None,
             }
         } else {
             let old_state = toml::from_str::<LockFileCompat>(old_data)
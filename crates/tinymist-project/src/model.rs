@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 
@@ -67,6 +68,9 @@ pub struct LockFile {
     /// The project's task route.
     #[serde(skip_serializing_if = "EcoVec::is_empty", default)]
     pub route: EcoVec<ProjectRoute>,
+    /// The project's lint configuration.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub lint: Option<LintConfig>,
 }
 
 /// A project input specifier.
@@ -151,6 +155,58 @@ impl ProjectPathMaterial {
     }
 }
 
+/// A project lint configuration.
+///
+/// ## Examples
+///
+/// ```toml
+/// [lint.rules]
+/// type-compare = "error"
+/// variable-font = "off"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// The severity of each lint rule, keyed by the rule ID. Rules not listed
+    /// here use their default severity.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub rules: BTreeMap<EcoString, LintSeverity>,
+}
+
+impl LintConfig {
+    /// Returns the configured severity of a rule, if any.
+    pub fn severity(&self, rule: &str) -> Option<LintSeverity> {
+        self.rules.get(rule).copied()
+    }
+}
+
+/// The severity of a lint rule.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintSeverity {
+    /// The rule is disabled.
+    Off,
+    /// The rule reports hints.
+    Hint,
+    /// The rule reports warnings.
+    #[default]
+    Warning,
+    /// The rule reports errors.
+    Error,
+}
+
 /// A project route specifier.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
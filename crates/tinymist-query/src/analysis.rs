@@ -13,6 +13,8 @@ pub mod color_expr;
 pub use color_expr::*;
 pub mod doc_highlight;
 pub use doc_highlight::*;
+pub mod fix;
+pub use fix::*;
 pub mod link_expr;
 pub use link_expr::*;
 pub mod stats;
@@ -617,8 +619,19 @@ mod lint_tests {
         snapshot_testing("lint", &|ctx, path| {
             let source = ctx.source_by_path(&path).unwrap();
 
-            let result = ctx.lint(&source);
-            let result = crate::diagnostics::DiagWorker::new(ctx).convert_all(result.iter());
+            // Lints the other modules as well to check the usage of their exports.
+            let mut lints = ctx.lint(&source);
+            for fid in ctx.source_files().clone() {
+                if fid != source.id() {
+                    let module = ctx.source_by_id(fid).unwrap();
+                    lints.extend(ctx.lint(&module));
+                }
+            }
+            let mut worker = crate::diagnostics::DiagWorker::new(ctx);
+            for lint in lints.iter() {
+                worker.handle_lint(lint);
+            }
+            let result = worker.results;
             let result = result
                 .into_iter()
                 .map(|(k, v)| (file_path_(&k), v))
@@ -1,11 +1,17 @@
 //! Provides code actions for the document.
 
 use ecow::eco_format;
-use lsp_types::{ChangeAnnotation, CreateFile, CreateFileOptions};
+use lsp_types::{
+    ChangeAnnotation, CreateFile, CreateFileOptions, NumberOrString, OneOf,
+    OptionalVersionedTextDocumentIdentifier,
+};
 use regex::Regex;
 use tinymist_analysis::syntax::{
     PreviousItem, SyntaxClass, adjust_expr, node_ancestors, previous_items,
 };
+use tinymist_lint::LintRule;
+
+use super::fix::{Fix, FixSet, missing_file_id};
 use tinymist_std::path::{diff, unix_slash};
 use typst::syntax::Side;
 
@@ -14,6 +20,9 @@ use crate::analysis::LinkTarget;
 use crate::prelude::*;
 use crate::syntax::{InterpretMode, interpret_mode_at};
 
+/// The kind of the code action fixing all the auto-fixable problems.
+pub const FIX_ALL_KIND: &str = "source.fixAll.tinymist";
+
 /// Analyzes the document and provides code actions.
 pub struct CodeActionWorker<'a> {
     /// The local analysis context to work with.
@@ -77,6 +86,15 @@ impl<'a> CodeActionWorker<'a> {
                 continue;
             }
 
+            let rule = match &diag.code {
+                Some(NumberOrString::String(code)) => code.parse::<LintRule>().ok(),
+                _ => None,
+            };
+            if let Some(rule) = rule {
+                self.autofix_lint(diag, rule);
+                continue;
+            }
+
             match match_autofix_kind(diag.message.as_str()) {
                 Some(AutofixKind::UnknownVariable) => {
                     self.autofix_unknown_variable(root, range);
@@ -244,24 +262,13 @@ impl<'a> CodeActionWorker<'a> {
         let cursor = (range.start + 1).min(self.source.text().len());
         let node = root.leaf_at_compat(cursor)?;
 
-        let importing = node.cast::<ast::Str>()?.get();
-        if importing.starts_with('@') {
-            // todo: create local package?
-            // if importing.starts_with("@local") { return None; }
-
-            // This is a package import, not a file import.
-            return None;
-        }
-
-        let file_id = node.span().id()?;
-        let root_path = self.ctx.path_for_id(file_id.join("/")).ok()?;
-        let path_in_workspace = file_id.vpath().join(importing.as_str());
-        let new_path = path_in_workspace.resolve(root_path.as_path())?;
-        let new_file_url = path_to_url(&new_path).ok()?;
+        let new_id = missing_file_id(&node)?;
+        let new_path = self.ctx.path_for_id(new_id).ok()?;
+        let new_file_url = path_to_url(new_path.as_path()).ok()?;
 
         let edit = self.create_file(new_file_url, false);
 
-        let file_to_create = unix_slash(path_in_workspace.as_rooted_path());
+        let file_to_create = unix_slash(new_id.vpath().as_rooted_path());
         let action = CodeAction {
             title: format!("Create missing file at `{file_to_create}`"),
             kind: Some(CodeActionKind::QUICKFIX),
@@ -273,6 +280,129 @@ impl<'a> CodeActionWorker<'a> {
         Some(())
     }
 
+    /// Applies the fix carried by a lint diagnostic.
+    fn autofix_lint(&mut self, diag: &Diagnostic, rule: LintRule) -> Option<()> {
+        let range = self.ctx.to_typst_range(diag.range, &self.source)?;
+        let lints = self.ctx.lint(&self.source);
+        let lint = lints.iter().find(|lint| {
+            lint.rule == rule && self.source.range(lint.diag.span) == Some(range.clone())
+        })?;
+        let fix = Fix::from_lint(lint)?;
+
+        let mut fixes = FixSet::default();
+        fixes.add(&fix);
+        let action = CodeAction {
+            title: fix.title.to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(self.fix_edit(&fixes)?),
+            ..CodeAction::default()
+        };
+        self.actions.push(action);
+        Some(())
+    }
+
+    /// Fixes all the auto-fixable diagnostics in the document at once.
+    ///
+    /// It is only provided if the client requests the kind, e.g. by
+    /// `editor.codeActionsOnSave` in VS Code. The fixes that may break the code
+    /// elsewhere, e.g. removing an unused parameter, are left to the quick
+    /// fixes.
+    pub(crate) fn fix_all(
+        &mut self,
+        root: &LinkedNode,
+        context: &lsp_types::CodeActionContext,
+    ) -> Option<()> {
+        let only = context.only.as_ref()?;
+        let fix_all = CodeActionKind::from(FIX_ALL_KIND);
+        let requested = only.iter().any(|kind| {
+            let kind = kind.as_str();
+            FIX_ALL_KIND == kind || FIX_ALL_KIND.starts_with(&format!("{kind}."))
+        });
+        if !requested {
+            return None;
+        }
+
+        let mut fixes = FixSet::default();
+        let mut add = |fix: Fix| {
+            if fix.is_machine_applicable() {
+                fixes.add(&fix);
+            }
+        };
+
+        // The compiler diagnostics are only known from the client.
+        for diag in &context.diagnostics {
+            if diag.source.as_ref().is_none_or(|t| t != "typst") || diag.code.is_some() {
+                continue;
+            }
+            let kind = match_autofix_kind(diag.message.as_str());
+            if !matches!(kind, Some(AutofixKind::FileNotFound)) {
+                continue;
+            }
+            let Some(range) = self.ctx.to_typst_range(diag.range, &self.source) else {
+                continue;
+            };
+            let cursor = (range.start + 1).min(self.source.text().len());
+            let node = root.leaf_at_compat(cursor);
+            if let Some(fix) = node.and_then(|node| Fix::create_missing_file(&node)) {
+                add(fix);
+            }
+        }
+        for lint in self.ctx.lint(&self.source).iter() {
+            if let Some(fix) = Fix::from_lint(lint) {
+                add(fix);
+            }
+        }
+        if fixes.len == 0 {
+            return None;
+        }
+
+        let action = CodeAction {
+            title: "Fix all auto-fixable problems".to_string(),
+            kind: Some(fix_all),
+            edit: Some(self.fix_edit(&fixes)?),
+            ..CodeAction::default()
+        };
+        self.actions.push(action);
+        Some(())
+    }
+
+    /// Converts the fixes in the document to a workspace edit.
+    fn fix_edit(&self, fixes: &FixSet) -> Option<EcoWorkspaceEdit> {
+        let mut edits = fixes
+            .edits
+            .get(&self.source.id())
+            .cloned()
+            .unwrap_or_default();
+        edits.sort_by_key(|(range, _)| (range.start, range.end));
+        let edits = edits.into_iter().map(|(range, text)| {
+            let range = self.ctx.to_lsp_range(range, &self.source);
+            EcoSnippetTextEdit::new_plain(range, text)
+        });
+        let edits = edits.collect::<Vec<_>>();
+        if fixes.creates.is_empty() {
+            return self.local_edits(edits);
+        }
+
+        let uris = fixes.creates.iter().map(|&id| {
+            let path = self.ctx.path_for_id(id).ok()?;
+            path_to_url(path.as_path()).ok()
+        });
+        let mut edit = self.create_files(uris.collect::<Option<_>>()?, false);
+        if let Some(EcoDocumentChanges::Operations(ops)) = &mut edit.document_changes {
+            // The text edits are applied after the files are created.
+            if !edits.is_empty() {
+                ops.push(EcoDocumentChangeOperation::Edit(EcoTextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: self.local_url()?.clone(),
+                        version: None,
+                    },
+                    edits: edits.into_iter().map(OneOf::Left).collect(),
+                }));
+            }
+        }
+        Some(edit)
+    }
+
     /// Starts to work.
     pub fn scoped(&mut self, root: &LinkedNode, range: &Range<usize>) -> Option<()> {
         let cursor = (range.start + 1).min(self.source.text().len());
@@ -577,16 +707,23 @@ impl<'a> CodeActionWorker<'a> {
     }
 
     fn create_file(&self, uri: Url, needs_confirmation: bool) -> EcoWorkspaceEdit {
+        self.create_files(vec![uri], needs_confirmation)
+    }
+
+    fn create_files(&self, uris: Vec<Url>, needs_confirmation: bool) -> EcoWorkspaceEdit {
         let change_id = "Typst Create Missing Files".to_string();
 
-        let create_op = EcoDocumentChangeOperation::Op(lsp_types::ResourceOp::Create(CreateFile {
-            uri,
-            options: Some(CreateFileOptions {
-                overwrite: Some(false),
-                ignore_if_exists: None,
-            }),
-            annotation_id: Some(change_id.clone()),
-        }));
+        let create_ops = uris.into_iter().map(|uri| {
+            EcoDocumentChangeOperation::Op(lsp_types::ResourceOp::Create(CreateFile {
+                uri,
+                options: Some(CreateFileOptions {
+                    overwrite: Some(false),
+                    ignore_if_exists: None,
+                }),
+                annotation_id: Some(change_id.clone()),
+            }))
+        });
+        let create_ops = create_ops.collect();
 
         let mut change_annotations = HashMap::new();
         change_annotations.insert(
@@ -600,19 +737,19 @@ impl<'a> CodeActionWorker<'a> {
 
         EcoWorkspaceEdit {
             changes: None,
-            document_changes: Some(EcoDocumentChanges::Operations(vec![create_op])),
+            document_changes: Some(EcoDocumentChanges::Operations(create_ops)),
             change_annotations: Some(change_annotations),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-enum AutofixKind {
+pub(crate) enum AutofixKind {
     UnknownVariable,
     FileNotFound,
 }
 
-fn match_autofix_kind(msg: &str) -> Option<AutofixKind> {
+pub(crate) fn match_autofix_kind(msg: &str) -> Option<AutofixKind> {
     static PATTERNS: &[(&str, AutofixKind)] = &[
         ("unknown variable", AutofixKind::UnknownVariable),
         ("file not found", AutofixKind::FileNotFound),
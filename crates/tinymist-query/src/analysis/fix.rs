@@ -0,0 +1,196 @@
+//! Machine-applicable fixes of compiler and lint diagnostics.
+
+use tinymist_lint::{Applicability, LintDiagnostic};
+use tinymist_std::path::unix_slash;
+use typst::diag::SourceDiagnostic;
+
+use super::code_action::{AutofixKind, match_autofix_kind};
+use super::prelude::*;
+
+/// A machine-applicable fix of a diagnostic.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    /// The title of the fix.
+    pub title: EcoString,
+    /// The edits of the fix.
+    pub edits: EcoVec<FixEdit>,
+    /// Whether the fix can be applied without reviewing it.
+    pub applicability: Applicability,
+}
+
+/// An edit of a fix.
+#[derive(Debug, Clone)]
+pub enum FixEdit {
+    /// Replaces a byte range of a source file with the text.
+    Replace {
+        /// The file to edit.
+        id: TypstFileId,
+        /// The byte range to replace.
+        range: Range<usize>,
+        /// The text to replace with.
+        text: EcoString,
+    },
+    /// Creates an empty file if it doesn't exist.
+    CreateFile(TypstFileId),
+}
+
+impl Fix {
+    /// Gets the fix carried by a lint diagnostic.
+    pub fn from_lint(lint: &LintDiagnostic) -> Option<Self> {
+        let fix = lint.fix.as_ref()?;
+        let id = lint.diag.span.id()?;
+        let edits = fix.edits.iter().map(|edit| FixEdit::Replace {
+            id,
+            range: edit.range.clone(),
+            text: edit.text.clone(),
+        });
+
+        Some(Self {
+            title: fix.title.clone(),
+            edits: edits.collect(),
+            applicability: fix.applicability,
+        })
+    }
+
+    /// Gets the fix of a compiler diagnostic.
+    pub fn from_compiler(ctx: &LocalContext, diag: &SourceDiagnostic) -> Option<Self> {
+        match match_autofix_kind(diag.message.as_str())? {
+            AutofixKind::FileNotFound => {
+                let source = ctx.source_by_id(diag.span.id()?).ok()?;
+                Self::create_missing_file(&source.find(diag.span)?)
+            }
+            AutofixKind::UnknownVariable => None,
+        }
+    }
+
+    /// Gets the fix creating the missing file imported or included by a
+    /// string.
+    pub(crate) fn create_missing_file(node: &LinkedNode) -> Option<Self> {
+        let id = missing_file_id(node)?;
+        let path = unix_slash(id.vpath().as_rooted_path());
+        Some(Self {
+            title: eco_format!("Create missing file at `{path}`"),
+            edits: eco_vec![FixEdit::CreateFile(id)],
+            applicability: Applicability::MachineApplicable,
+        })
+    }
+
+    /// Whether the fix can be applied in bulk, e.g. by `tinymist lint --fix`.
+    pub fn is_machine_applicable(&self) -> bool {
+        self.applicability == Applicability::MachineApplicable
+    }
+}
+
+/// Gets the file id of a missing file imported or included by a string.
+pub(crate) fn missing_file_id(node: &LinkedNode) -> Option<TypstFileId> {
+    let importing = node.cast::<ast::Str>()?.get();
+    if importing.starts_with('@') {
+        // todo: create local package?
+        // if importing.starts_with("@local") { return None; }
+
+        // This is a package import, not a file import.
+        return None;
+    }
+
+    Some(node.span().id()?.join(importing.as_str()))
+}
+
+/// A set of fixes that can be applied together.
+#[derive(Debug, Default)]
+pub struct FixSet {
+    /// The text edits to apply, grouped by file.
+    pub edits: HashMap<TypstFileId, Vec<(Range<usize>, EcoString)>>,
+    /// The files to create.
+    pub creates: Vec<TypstFileId>,
+    /// The number of fixes in the set.
+    pub len: usize,
+}
+
+impl FixSet {
+    /// Adds a fix to the set unless it conflicts with the fixes already in the
+    /// set. Returns whether the fix is added.
+    pub fn add(&mut self, fix: &Fix) -> bool {
+        let conflicts = fix.edits.iter().any(|edit| match edit {
+            FixEdit::Replace { id, range, .. } => self
+                .edits
+                .get(id)
+                .is_some_and(|edits| edits.iter().any(|(applied, _)| overlaps(applied, range))),
+            FixEdit::CreateFile(..) => false,
+        });
+        if conflicts {
+            return false;
+        }
+
+        for edit in fix.edits.iter() {
+            match edit {
+                FixEdit::Replace { id, range, text } => {
+                    let edits = self.edits.entry(*id).or_default();
+                    edits.push((range.clone(), text.clone()));
+                }
+                FixEdit::CreateFile(id) => {
+                    if !self.creates.contains(id) {
+                        self.creates.push(*id);
+                    }
+                }
+            }
+        }
+        self.len += 1;
+
+        true
+    }
+
+    /// Applies the text edits of a file to its content.
+    pub fn apply(&self, id: TypstFileId, text: &str) -> String {
+        let mut edits = self.edits.get(&id).cloned().unwrap_or_default();
+        edits.sort_by_key(|(range, _)| (range.start, range.end));
+
+        let mut result = String::with_capacity(text.len());
+        let mut cursor = 0;
+        for (range, new_text) in edits {
+            result.push_str(&text[cursor..range.start]);
+            result.push_str(&new_text);
+            cursor = range.end;
+        }
+        result.push_str(&text[cursor..]);
+
+        result
+    }
+}
+
+/// Whether two edits overlap. Two insertions at the same position also
+/// conflict since their order is undetermined.
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    (a.start < b.end && b.start < a.end) || a.start == b.start
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::VirtualPath;
+
+    use super::*;
+
+    fn replace(id: TypstFileId, range: Range<usize>, text: &str) -> Fix {
+        Fix {
+            title: "test".into(),
+            edits: eco_vec![FixEdit::Replace {
+                id,
+                range,
+                text: text.into(),
+            }],
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    #[test]
+    fn test_fix_set() {
+        let id = TypstFileId::new(None, VirtualPath::new("main.typ"));
+        let text = "#let a = 1\n#let b = 2\n#a";
+
+        let mut set = FixSet::default();
+        assert!(set.add(&replace(id, 0..11, "")));
+        assert!(!set.add(&replace(id, 5..6, "c")));
+        assert!(set.add(&replace(id, 16..17, "_")));
+        assert_eq!(set.len, 2);
+        assert_eq!(set.apply(id, text), "#let _ = 2\n#a");
+    }
+}
@@ -13,14 +13,14 @@ use tinymist_analysis::stats::AllocStats;
 use tinymist_analysis::syntax::classify_def_loosely;
 use tinymist_analysis::ty::term_value;
 use tinymist_analysis::{analyze_expr_, analyze_import_};
-use tinymist_lint::LintInfo;
-use tinymist_project::{LspComputeGraph, LspWorld, TaskWhen};
+use tinymist_lint::{ExportUsage, LintDiagnostic, LintInfo, LintRule, LintSeverity};
+use tinymist_project::{LintConfig, LspComputeGraph, LspWorld, TaskWhen};
 use tinymist_std::hash::{FxDashMap, hash128};
 use tinymist_std::typst::TypstDocument;
 use tinymist_world::debug_loc::DataSource;
 use tinymist_world::vfs::{PathResolution, WorkspaceResolver};
 use tinymist_world::{DETACHED_ENTRY, EntryReader};
-use typst::diag::{At, FileError, FileResult, SourceDiagnostic, SourceResult, StrResult};
+use typst::diag::{At, FileError, FileResult, SourceResult, StrResult};
 use typst::foundations::{Bytes, IntoValue, Module, StyleChain, Styles};
 use typst::introspection::Introspector;
 use typst::layout::Position;
@@ -79,6 +79,8 @@ pub struct Analysis {
     pub color_theme: ColorTheme,
     /// When to trigger the lint.
     pub lint: TaskWhen,
+    /// The severity of the lint rules.
+    pub lint_config: Arc<LintConfig>,
     /// The periscope provider.
     pub periscope: Option<Arc<dyn PeriscopeProvider + Send + Sync>>,
     /// The global worker resources for analysis.
@@ -475,8 +477,40 @@ impl LocalContext {
         cache.get_or_init(|| self.shared.type_check(source)).clone()
     }
 
-    pub(crate) fn lint(&mut self, source: &Source) -> EcoVec<SourceDiagnostic> {
-        self.shared.lint(source).diagnostics
+    /// Get the lint result of a source file.
+    pub fn lint(&mut self, source: &Source) -> EcoVec<LintDiagnostic> {
+        let exports = self.export_usage(source.id());
+        self.shared.lint(source, &exports).diagnostics
+    }
+
+    /// Get the usage of the exports of a module by its dependents.
+    fn export_usage(&mut self, fid: TypstFileId) -> ExportUsage {
+        let config = &self.analysis.lint_config;
+        if fid == self.world.main() || LintRule::UnusedExport.severity(config) == LintSeverity::Off
+        {
+            return ExportUsage::Entry;
+        }
+
+        // The modules that no project file imports, e.g. the roots of a library,
+        // are used by the documents outside the project.
+        let deps = self.module_dependencies().get(&fid);
+        let dependents = deps.map(|dep| dep.dependents.clone()).unwrap_or_default();
+        if dependents.is_empty() || self.is_package_entry(fid) {
+            return ExportUsage::Entry;
+        }
+
+        let dependents = dependents
+            .into_iter()
+            .filter_map(|dep| self.expr_stage_by_id(dep))
+            .collect::<Vec<_>>();
+        ExportUsage::collect(fid, dependents.iter())
+    }
+
+    /// Whether the file is the entrypoint of the package it belongs to.
+    fn is_package_entry(&self, fid: TypstFileId) -> bool {
+        let toml_id = fid.join("/typst.toml");
+        self.get_manifest(toml_id)
+            .is_ok_and(|manifest| toml_id.join(&manifest.package.entrypoint) == fid)
     }
 
     /// Get the type check information of a source file.
@@ -794,13 +828,15 @@ impl SharedContext {
 
     /// Get the lint result of a source file.
     #[typst_macros::time(span = source.root().span())]
-    pub(crate) fn lint(self: &Arc<Self>, source: &Source) -> LintInfo {
+    pub(crate) fn lint(self: &Arc<Self>, source: &Source, exports: &ExportUsage) -> LintInfo {
         let ei = self.expr_stage(source);
         let ti = self.type_check(source);
+        let config = &self.analysis.lint_config;
+        let key = hash128(&(&ei, &ti, config, exports));
         let guard = self.query_stat(source.id(), "lint");
-        self.slot.lint.compute(hash128(&(&ei, &ti)), |_prev| {
+        self.slot.lint.compute(key, |_prev| {
             guard.miss();
-            tinymist_lint::lint_file(&self.world, &ei, ti)
+            tinymist_lint::lint_file(&self.world, &ei, ti, config, exports)
         })
     }
 
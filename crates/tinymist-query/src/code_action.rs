@@ -85,6 +85,7 @@ impl SemanticRequest for CodeActionRequest {
         let root = LinkedNode::new(source.root());
         let mut worker = CodeActionWorker::new(ctx, source.clone());
         worker.autofix(&root, &range, &self.context);
+        worker.fix_all(&root, &self.context);
         worker.scoped(&root, &range);
 
         (!worker.actions.is_empty()).then_some(worker.actions)
@@ -102,9 +103,21 @@ mod tests {
     fn test() {
         snapshot_testing("code_action", &|ctx, path| {
             let source = ctx.source_by_path(&path).unwrap();
+            let docs = find_module_level_docs(&source).unwrap_or_default();
+            let properties = get_test_properties(&docs);
+            let only = properties
+                .get("only")
+                .map(|kind| vec![CodeActionKind::from(kind.to_string())]);
 
             let request_range = find_test_range(&source);
-            let code_action_ctx = compute_code_action_context(ctx, &source, &request_range);
+            // Fixing all the problems is requested with all the diagnostics in
+            // the document, e.g. on save.
+            let diag_range = match &only {
+                Some(..) => ctx.to_lsp_range(0..source.text().len(), &source),
+                None => request_range,
+            };
+            let mut code_action_ctx = compute_code_action_context(ctx, &source, &diag_range);
+            code_action_ctx.only = only;
             let request = CodeActionRequest {
                 path: path.clone(),
                 range: request_range,
@@ -133,13 +146,12 @@ mod tests {
         let compiler_errors = output.err().unwrap_or_default();
 
         let lint_warnings = ctx.lint(source);
-        let diagnostics = DiagWorker::new(ctx)
-            .convert_all(
-                compiler_errors
-                    .iter()
-                    .chain(compiler_warnings.iter())
-                    .chain(lint_warnings.iter()),
-            )
+        let mut worker = DiagWorker::new(ctx);
+        for lint in lint_warnings.iter() {
+            worker.handle_lint(lint);
+        }
+        let diagnostics = worker
+            .convert_all(compiler_errors.iter().chain(compiler_warnings.iter()))
             .into_values()
             .flatten();
 
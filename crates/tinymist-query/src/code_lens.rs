@@ -1,6 +1,7 @@
 use lsp_types::Command;
 
-use crate::{SemanticRequest, prelude::*};
+use crate::prelude::*;
+use crate::testing::{TestCaseKind, TestConfig};
 
 /// The [`textDocument/codeLens`] request is sent from the client to the server
 /// to compute code lenses for a given text document.
@@ -12,10 +13,10 @@ pub struct CodeLensRequest {
     pub path: PathBuf,
 }
 
-impl SemanticRequest for CodeLensRequest {
+impl StatefulRequest for CodeLensRequest {
     type Response = Vec<CodeLens>;
 
-    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+    fn request(self, ctx: &mut LocalContext, graph: LspComputeGraph) -> Option<Self::Response> {
         let source = ctx.source_by_path(&self.path).ok()?;
 
         let mut res = vec![];
@@ -59,6 +60,64 @@ impl SemanticRequest for CodeLensRequest {
             vec!["more".into()],
         ));
 
+        // The test patterns are configured in the last successfully compiled
+        // document, falling back to the default ones.
+        let config = graph.snap.success_doc.as_ref();
+        let config = config.and_then(|doc| TestConfig::extract(doc).ok());
+        res.extend(test_lenses(ctx, &source, &config.unwrap_or_default()));
+
         Some(res)
     }
 }
+
+/// Computes the lenses to run the test cases defined at the top level of a
+/// source, i.e. the functions named by the test, panic and example patterns of
+/// the configuration, which are `test-*`, `panic-on-*` and `example-*` by
+/// default.
+fn test_lenses(ctx: &LocalContext, source: &Source, config: &TestConfig) -> Vec<CodeLens> {
+    let mut res = vec![];
+
+    let root = LinkedNode::new(source.root());
+    for node in root.children() {
+        let Some(binding) = node.cast::<ast::LetBinding>() else {
+            continue;
+        };
+        let ast::LetBindingKind::Closure(name) = binding.kind() else {
+            continue;
+        };
+        let name = name.get();
+        let is_example = match config.match_test(name) {
+            Some(TestCaseKind::Test | TestCaseKind::Panic) => false,
+            Some(TestCaseKind::Example) => true,
+            Some(TestCaseKind::Bench) | None => continue,
+        };
+
+        let range = ctx.to_lsp_range(node.range(), source);
+        let test_lens = |title: String, action: &str| CodeLens {
+            range,
+            command: Some(Command {
+                title,
+                command: "tinymist.runCodeLens".to_string(),
+                arguments: Some(vec![action.into(), name.as_str().into()]),
+            }),
+            data: None,
+        };
+
+        res.push(test_lens(
+            tinymist_l10n::t!("tinymist-query.code-action.runTest", "Run test").into(),
+            "run-test",
+        ));
+        if is_example {
+            res.push(test_lens(
+                tinymist_l10n::t!(
+                    "tinymist-query.code-action.updateReference",
+                    "Update reference"
+                )
+                .into(),
+                "update-test",
+            ));
+        }
+    }
+
+    res
+}
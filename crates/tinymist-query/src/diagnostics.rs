@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 
+use lsp_types::{DiagnosticTag, NumberOrString};
+use tinymist_lint::{LintDiagnostic, LintSeverity};
 use tinymist_project::LspWorld;
 use tinymist_world::vfs::WorkspaceResolver;
 use typst::syntax::Span;
@@ -58,7 +60,7 @@ impl<'w> DiagWorker<'w> {
             };
 
             for diag in self.ctx.lint(&source) {
-                self.handle(&diag);
+                self.handle_lint(&diag);
             }
         }
 
@@ -89,6 +91,24 @@ impl<'w> DiagWorker<'w> {
         }
     }
 
+    /// Converts a lint diagnostic to LSP diagnostics, carrying the rule ID as
+    /// the diagnostic code.
+    pub fn handle_lint(&mut self, lint: &LintDiagnostic) {
+        match self.convert_diagnostic(&lint.diag) {
+            Ok((uri, mut diagnostic)) => {
+                diagnostic.severity = Some(lint_severity(lint.severity));
+                diagnostic.code = Some(NumberOrString::String(lint.rule.id().to_owned()));
+                if lint.rule.is_unused() {
+                    diagnostic.tags = Some(vec![DiagnosticTag::UNNECESSARY]);
+                }
+                self.results.entry(uri).or_default().push(diagnostic);
+            }
+            Err(error) => {
+                log::error!("Failed to convert lint diagnostic: {error:?}");
+            }
+        }
+    }
+
     fn convert_diagnostic(
         &self,
         typst_diagnostic: &TypstDiagnostic,
@@ -184,6 +204,14 @@ fn diagnostic_severity(typst_severity: TypstSeverity) -> DiagnosticSeverity {
     }
 }
 
+fn lint_severity(severity: LintSeverity) -> DiagnosticSeverity {
+    match severity {
+        LintSeverity::Error => DiagnosticSeverity::ERROR,
+        LintSeverity::Off | LintSeverity::Warning => DiagnosticSeverity::WARNING,
+        LintSeverity::Hint => DiagnosticSeverity::HINT,
+    }
+}
+
 fn diagnostic_message(typst_diagnostic: &TypstDiagnostic) -> String {
     let mut message = typst_diagnostic.message.to_string();
     for hint in &typst_diagnostic.hints {
@@ -0,0 +1,3 @@
+#if true {
+  set text(red)/* range -2..-2 */
+}
@@ -0,0 +1,14 @@
+/// only: source.fixAll.tinymist
+
+#let f(unused-param) = {
+  let unused = 1
+  if type(1) == "integer" { "yes" } else { "no" }
+}
+
+#if true {
+  set text(red)
+}
+
+#f(1)
+#include "missing.typ"
+/* range 0..0 */
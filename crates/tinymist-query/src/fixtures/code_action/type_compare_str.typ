@@ -0,0 +1 @@
+#let f() = type("") == "str"/* range -3..-3 */
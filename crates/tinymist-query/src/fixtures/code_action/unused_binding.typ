@@ -0,0 +1,6 @@
+#let f() = {
+  let unused/* range -2..-2 */ = 1
+  none
+}
+
+#f()
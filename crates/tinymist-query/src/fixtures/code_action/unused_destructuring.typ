@@ -0,0 +1,6 @@
+#let f() = {
+  let (first, second/* range -2..-2 */) = (1, 2)
+  first
+}
+
+#f()
@@ -0,0 +1,6 @@
+#let f() = {
+  let (first, ..rest/* range -2..-2 */) = (1, 2)
+  first
+}
+
+#f()
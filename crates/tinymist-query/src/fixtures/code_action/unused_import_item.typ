@@ -0,0 +1,4 @@
+#{
+  import calc: pow, odd/* range -1..-1 */
+  pow(2, 2)
+}
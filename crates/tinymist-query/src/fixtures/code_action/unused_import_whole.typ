@@ -0,0 +1,2 @@
+#import calc: odd/* range -1..-1 */
+#calc.pow(2, 2)
@@ -0,0 +1,3 @@
+#let f(x, unused/* range -2..-2 */) = x
+
+#f(1, 2)
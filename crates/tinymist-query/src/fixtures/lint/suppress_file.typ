@@ -0,0 +1,2 @@
+// tinymist-lint: allow(bad-branch-stmt)
+#break
@@ -0,0 +1,7 @@
+// a regular comment
+#set text(font: "Noto Sans VF") // tinymist-lint: allow(variable-font)
+
+// tinymist-lint: allow(variable-font)
+#text(font: ("Arial", "Noto Sans VF"))[123]
+
+#set text(font: "Noto Sans VF")
@@ -0,0 +1,7 @@
+#let f(x, y) = {
+  let a = 1
+  let (b, c) = (2, 3)
+  let _d = 4
+  for i in range(3) {}
+  x + c
+}
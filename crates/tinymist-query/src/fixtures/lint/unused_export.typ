@@ -0,0 +1,10 @@
+/// path: lib.typ
+#let used = 1
+#let helper = 2
+#let unused = helper
+-----
+/// path: orphan.typ
+#let orphan = 1
+-----
+#import "lib.typ": used
+#used
@@ -0,0 +1,4 @@
+#{
+  import calc: pow, floor as fl, odd
+  fl(1.5) + pow(2, 2)
+}
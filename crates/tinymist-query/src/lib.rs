@@ -39,6 +39,7 @@ pub use semantic_tokens_delta::*;
 pub use semantic_tokens_full::*;
 pub use signature_help::*;
 pub use symbol::*;
+pub use test_discovery::*;
 pub use will_rename_files::*;
 pub use workspace_label::*;
 
@@ -84,6 +85,7 @@ mod semantic_tokens_delta;
 mod semantic_tokens_full;
 mod signature_help;
 mod symbol;
+mod test_discovery;
 mod will_rename_files;
 mod workspace_label;
 
@@ -249,6 +251,8 @@ mod polymorphic {
         WorkspaceLabel(WorkspaceLabelRequest),
         /// A request to get the server info.
         ServerInfo(ServerInfoRequest),
+        /// A request to discover the tests.
+        TestDiscovery(TestDiscoveryRequest),
     }
 
     impl CompilerQueryRequest {
@@ -287,6 +291,7 @@ mod polymorphic {
 
                 Self::DocumentMetrics(..) => PinnedFirst,
                 Self::ServerInfo(..) => Mergeable,
+                Self::TestDiscovery(..) => PinnedFirst,
             }
         }
 
@@ -324,6 +329,7 @@ mod polymorphic {
 
                 Self::DocumentMetrics(req) => &req.path,
                 Self::ServerInfo(..) => return None,
+                Self::TestDiscovery(req) => &req.path,
             })
         }
     }
@@ -392,6 +398,8 @@ mod polymorphic {
         DocumentMetrics(Option<DocumentMetricsResponse>),
         /// The response to the server info request.
         ServerInfo(Option<HashMap<String, ServerInfoResponse>>),
+        /// The response to the test discovery request.
+        TestDiscovery(Option<Vec<TestItem>>),
     }
 }
 
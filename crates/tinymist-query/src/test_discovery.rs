@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+
+use crate::prelude::*;
+use crate::testing::{TestCaseKind, example_name, test_suites};
+
+/// The kind of a test item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestItemKind {
+    /// A function named `test-*`.
+    Test,
+    /// A function named `panic-on-*`.
+    Panic,
+    /// A function named `bench-*`.
+    Bench,
+    /// A function named `example-*` or an example document.
+    Example,
+    /// A document whose diagnostics are checked against its annotations.
+    Diagnostic,
+}
+
+impl From<TestCaseKind> for TestItemKind {
+    fn from(kind: TestCaseKind) -> Self {
+        match kind {
+            TestCaseKind::Test => Self::Test,
+            TestCaseKind::Panic => Self::Panic,
+            TestCaseKind::Bench => Self::Bench,
+            TestCaseKind::Example => Self::Example,
+        }
+    }
+}
+
+/// A test case or a test document discovered in a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestItem {
+    /// The name of the test item.
+    pub name: String,
+    /// The kind of the test item.
+    pub kind: TestItemKind,
+    /// The uri of the file defining the test item.
+    pub uri: Url,
+    /// The range of the function of a test case, or the start of a test
+    /// document.
+    pub range: LspRange,
+}
+
+/// A request to discover the test cases and test documents that the document
+/// depends on.
+///
+/// This is not part of the LSP protocol.
+#[derive(Debug, Clone)]
+pub struct TestDiscoveryRequest {
+    /// The path of the document to discover tests from.
+    pub path: PathBuf,
+}
+
+impl SemanticRequest for TestDiscoveryRequest {
+    type Response = Vec<TestItem>;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let doc = typst::compile::<TypstPagedDocument>(&ctx.world)
+            .output
+            .ok()?;
+        let suites = match test_suites(ctx, &TypstDocument::from(Arc::new(doc))) {
+            Ok(suites) => suites,
+            Err(err) => {
+                log::error!("failed to discover tests: {err:?}");
+                return None;
+            }
+        };
+
+        let mut items = vec![];
+        for test in &suites.tests {
+            let Ok(source) = ctx.source_by_id(test.location) else {
+                continue;
+            };
+            let Ok(uri) = ctx.uri_for_id(test.location) else {
+                continue;
+            };
+            let range = source.range(test.function.span()).unwrap_or_default();
+            items.push(TestItem {
+                name: test.name.to_string(),
+                kind: test.kind.into(),
+                uri,
+                range: ctx.to_lsp_range(range, &source),
+            });
+        }
+
+        let documents = [
+            (TestItemKind::Example, &suites.examples),
+            (TestItemKind::Diagnostic, &suites.diagnostics),
+        ];
+        for (kind, sources) in documents {
+            for source in sources {
+                let Ok(uri) = ctx.uri_for_id(source.id()) else {
+                    continue;
+                };
+                items.push(TestItem {
+                    name: example_name(source.id()),
+                    kind,
+                    uri,
+                    range: ctx.to_lsp_range(0..0, source),
+                });
+            }
+        }
+
+        Some(items)
+    }
+}
@@ -0,0 +1,266 @@
+//! Perceptual comparison of the rendered images of examples.
+
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use tinymist_std::error::prelude::*;
+use typst::foundations::{Dict, Value};
+
+/// The maximum squared YIQ distance between two colors.
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// The tolerance of comparing a rendered image to its reference.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageTolerance {
+    /// The maximum perceptual distance between the colors of two pixels that
+    /// are regarded as equal, from `0` to `1`.
+    pub pixel: f64,
+    /// The maximum ratio of the differing pixels, from `0` to `1`.
+    pub ratio: f64,
+}
+
+impl Default for ImageTolerance {
+    fn default() -> Self {
+        Self {
+            pixel: 0.1,
+            ratio: 0.0,
+        }
+    }
+}
+
+impl ImageTolerance {
+    /// Overrides the tolerance with the fields of a dictionary, e.g.,
+    /// `(pixel: 0.2, ratio: 0.01)`.
+    pub fn merge(self, dict: &Dict) -> Result<Self> {
+        let field = |name: &str, default: f64| match dict.get(name) {
+            Ok(Value::Float(value)) => Ok(*value),
+            Ok(Value::Int(value)) => Ok(*value as f64),
+            Ok(value) => bail!(
+                "tolerance `{name}` must be a number, found {}",
+                value.ty().short_name()
+            ),
+            Err(..) => Ok(default),
+        };
+
+        Ok(Self {
+            pixel: field("pixel", self.pixel)?,
+            ratio: field("ratio", self.ratio)?,
+        })
+    }
+}
+
+/// The difference between a rendered image and its reference.
+pub struct ImageDiff {
+    /// The number of the differing pixels.
+    pub differing: usize,
+    /// The number of all pixels.
+    pub total: usize,
+    /// The image highlighting the differing pixels in red over the faded
+    /// reference.
+    pub image: RgbaImage,
+}
+
+impl ImageDiff {
+    /// Gets the ratio of the differing pixels.
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.differing as f64 / self.total as f64
+    }
+
+    /// Whether the difference is within the tolerance.
+    pub fn is_within(&self, tolerance: &ImageTolerance) -> bool {
+        self.ratio() <= tolerance.ratio
+    }
+
+    /// Encodes the diff image to PNG.
+    pub fn encode_png(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .context("encode diff image")?;
+        Ok(data)
+    }
+}
+
+/// Compares a rendered PNG to its reference PNG. The pixels outside of the
+/// overlapping area of images of different sizes are regarded as differing.
+pub fn diff_png(reference: &[u8], actual: &[u8], pixel: f64) -> Result<ImageDiff> {
+    let decode = |data: &[u8]| {
+        image::load_from_memory_with_format(data, ImageFormat::Png).map(|image| image.to_rgba8())
+    };
+    let reference = decode(reference).context("decode reference image")?;
+    let actual = decode(actual).context("decode rendered image")?;
+
+    let width = reference.width().max(actual.width());
+    let height = reference.height().max(actual.height());
+    let max_delta = MAX_YIQ_DELTA * pixel * pixel;
+
+    let mut image = RgbaImage::new(width, height);
+    let mut differing = 0;
+    for (x, y, out) in image.enumerate_pixels_mut() {
+        let (Some(expected), Some(got)) = (
+            reference.get_pixel_checked(x, y),
+            actual.get_pixel_checked(x, y),
+        ) else {
+            differing += 1;
+            *out = Rgba([255, 0, 0, 255]);
+            continue;
+        };
+
+        if yiq_delta(expected, got) > max_delta {
+            differing += 1;
+            *out = Rgba([255, 0, 0, 255]);
+        } else {
+            let [r, g, b] = blend_white(expected);
+            let luma = r * 0.29889531 + g * 0.58662247 + b * 0.11448223;
+            let faded = (255.0 + (luma - 255.0) * 0.1) as u8;
+            *out = Rgba([faded, faded, faded, 255]);
+        }
+    }
+
+    Ok(ImageDiff {
+        differing,
+        total: width as usize * height as usize,
+        image,
+    })
+}
+
+/// Blends a pixel over a white background.
+fn blend_white(pixel: &Rgba<u8>) -> [f64; 3] {
+    let [r, g, b, a] = pixel.0.map(f64::from);
+    let a = a / 255.0;
+    [r, g, b].map(|c| 255.0 + (c - 255.0) * a)
+}
+
+/// Computes the squared perceptual distance between the colors of two pixels
+/// in the YIQ color space.
+fn yiq_delta(expected: &Rgba<u8>, got: &Rgba<u8>) -> f64 {
+    if expected == got {
+        return 0.0;
+    }
+
+    let yiq = |pixel: &Rgba<u8>| {
+        let [r, g, b] = blend_white(pixel);
+        let y = r * 0.29889531 + g * 0.58662247 + b * 0.11448223;
+        let i = r * 0.59597799 - g * 0.2741761 - b * 0.32180189;
+        let q = r * 0.21147017 - g * 0.52261711 + b * 0.31114694;
+        (y, i, q)
+    };
+    let (y1, i1, q1) = yiq(expected);
+    let (y2, i2, q2) = yiq(got);
+    let (y, i, q) = (y1 - y2, i1 - i2, q1 - q2);
+
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes an image filled with the color, whose first `marked` pixels
+    /// are of the marked color.
+    fn png(width: u32, height: u32, fill: [u8; 4], marked: usize, mark: [u8; 4]) -> Vec<u8> {
+        let mut image = RgbaImage::from_pixel(width, height, Rgba(fill));
+        for pixel in image.pixels_mut().take(marked) {
+            *pixel = Rgba(mark);
+        }
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+    const TOLERANCE: ImageTolerance = ImageTolerance {
+        pixel: 0.1,
+        ratio: 0.05,
+    };
+
+    #[test]
+    fn test_identical() {
+        let image = png(10, 10, WHITE, 3, BLACK);
+        let diff = diff_png(&image, &image, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 0);
+        assert_eq!(diff.total, 100);
+        assert_eq!(diff.ratio(), 0.0);
+        assert!(diff.is_within(&TOLERANCE));
+    }
+
+    #[test]
+    fn test_within_pixel_tolerance() {
+        let reference = png(10, 10, WHITE, 0, BLACK);
+        let actual = png(10, 10, [250, 250, 250, 255], 0, BLACK);
+        let diff = diff_png(&reference, &actual, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 0);
+
+        // A transparent pixel is blended over white.
+        let actual = png(10, 10, [0, 0, 0, 0], 0, BLACK);
+        let diff = diff_png(&reference, &actual, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 0);
+    }
+
+    #[test]
+    fn test_within_ratio() {
+        let reference = png(10, 10, WHITE, 0, BLACK);
+        let actual = png(10, 10, WHITE, 5, BLACK);
+        let diff = diff_png(&reference, &actual, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 5);
+        assert_eq!(diff.ratio(), 0.05);
+        assert!(diff.is_within(&TOLERANCE));
+    }
+
+    #[test]
+    fn test_over_threshold() {
+        let reference = png(10, 10, WHITE, 0, BLACK);
+        let actual = png(10, 10, WHITE, 6, BLACK);
+        let diff = diff_png(&reference, &actual, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 6);
+        assert!(!diff.is_within(&TOLERANCE));
+
+        // The differing pixels are highlighted in red.
+        assert_eq!(diff.image.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(diff.image.get_pixel(9, 9), &Rgba([255, 255, 255, 255]));
+        assert!(diff.encode_png().is_ok());
+
+        // The pixel tolerance is strict if it is zero.
+        let actual = png(10, 10, [250, 250, 250, 255], 0, BLACK);
+        let diff = diff_png(&reference, &actual, 0.0).unwrap();
+        assert_eq!(diff.differing, 100);
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let reference = png(10, 10, WHITE, 0, BLACK);
+        let actual = png(10, 8, WHITE, 0, BLACK);
+        let diff = diff_png(&reference, &actual, TOLERANCE.pixel).unwrap();
+        assert_eq!(diff.differing, 20);
+        assert_eq!(diff.total, 100);
+    }
+
+    #[test]
+    fn test_invalid_png() {
+        let reference = png(1, 1, WHITE, 0, BLACK);
+        assert!(diff_png(&reference, b"not a png", TOLERANCE.pixel).is_err());
+    }
+
+    #[test]
+    fn test_merge_tolerance() {
+        let mut dict = Dict::new();
+        dict.insert("pixel".into(), Value::Float(0.2));
+        dict.insert("ratio".into(), Value::Int(1));
+        let merged = TOLERANCE.merge(&dict).unwrap();
+        assert_eq!(merged.pixel, 0.2);
+        assert_eq!(merged.ratio, 1.0);
+
+        let merged = TOLERANCE.merge(&Dict::new()).unwrap();
+        assert_eq!(merged.pixel, TOLERANCE.pixel);
+
+        let mut dict = Dict::new();
+        dict.insert("ratio".into(), Value::Str("high".into()));
+        assert!(TOLERANCE.merge(&dict).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Checks of the diagnostics of documents against their annotations.
+//!
+//! A diagnostic is expected by an annotation comment at the end of a line,
+//! e.g., `//~ error: unknown variable`, or at a line below, with one `^` per
+//! line, e.g., `//~^ warning: unused`. A diagnostic matches the annotation if
+//! it has the same severity and line, and its message contains the pattern
+//! after the colon.
+
+use typst::World;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::syntax::Source;
+
+/// A diagnostic expected by an annotation.
+struct Expected {
+    /// The zero-based line of the diagnostic.
+    line: usize,
+    severity: Severity,
+    /// The pattern that the message must contain.
+    pattern: String,
+}
+
+/// Parses the annotations of a source, returning the invalid annotations as
+/// errors.
+fn parse_annotations(source: &Source) -> Result<Vec<Expected>, Vec<String>> {
+    let mut expected = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in source.text().lines().enumerate() {
+        let Some((_, annotation)) = line.split_once("//~") else {
+            continue;
+        };
+        let carets = annotation.chars().take_while(|&ch| ch == '^').count();
+        let annotation = annotation[carets..].trim();
+        let (severity, pattern) = annotation.split_once(':').unwrap_or((annotation, ""));
+        let severity = match severity.trim() {
+            "error" => Severity::Error,
+            "warning" => Severity::Warning,
+            _ => {
+                errors.push(format!(
+                    "line {}: invalid annotation, expected `//~ error: ..` or `//~ warning: ..`",
+                    idx + 1
+                ));
+                continue;
+            }
+        };
+        let Some(line) = idx.checked_sub(carets) else {
+            errors.push(format!(
+                "line {}: annotation points before the file",
+                idx + 1
+            ));
+            continue;
+        };
+
+        expected.push(Expected {
+            line,
+            severity,
+            pattern: pattern.trim().to_owned(),
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(expected)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks the diagnostics of a compiled source against its annotations,
+/// returning the mismatches.
+pub fn check_diagnostics(
+    world: &dyn World,
+    source: &Source,
+    diagnostics: &[SourceDiagnostic],
+) -> Vec<String> {
+    let mut expected = match parse_annotations(source) {
+        Ok(expected) => expected,
+        Err(errors) => return errors,
+    };
+
+    let severity_name = |severity: Severity| match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+
+    let mut mismatches = Vec::new();
+    for diag in diagnostics {
+        let line = diag.span.id().and_then(|id| {
+            let file = world.source(id).ok()?;
+            let range = file.range(diag.span)?;
+            Some((id, file.byte_to_line(range.start)?))
+        });
+        let severity = severity_name(diag.severity);
+
+        let line = match line {
+            Some((id, line)) if id == source.id() => line,
+            _ => {
+                mismatches.push(format!(
+                    "unexpected {severity} outside of the file: {}",
+                    diag.message
+                ));
+                continue;
+            }
+        };
+
+        let matched = expected.iter().position(|exp| {
+            exp.line == line && exp.severity == diag.severity && diag.message.contains(&exp.pattern)
+        });
+        match matched {
+            Some(idx) => {
+                expected.remove(idx);
+            }
+            None => mismatches.push(format!(
+                "line {}: unexpected {severity}: {}",
+                line + 1,
+                diag.message
+            )),
+        }
+    }
+
+    for exp in expected {
+        mismatches.push(format!(
+            "line {}: expected {} containing `{}`, found none",
+            exp.line + 1,
+            severity_name(exp.severity),
+            exp.pattern
+        ));
+    }
+
+    mismatches
+}
@@ -9,11 +9,20 @@ use typst::{
     World,
     foundations::{Func, Label, Module, Selector, Value},
     introspection::MetadataElem,
-    syntax::Source,
+    syntax::{LinkedNode, Source, Span, SyntaxKind, ast},
     utils::PicoStr,
 };
 
+pub use diff::{ImageDiff, ImageTolerance, diff_png};
+pub use expect::check_diagnostics;
+pub use run::{ExampleRef, RefOutcome, TestFailure, check_call, image_tolerance, is_html_example};
+
 use crate::LocalContext;
+use crate::syntax::{cast_include_expr, find_source_by_expr, node_ancestors};
+
+mod diff;
+mod expect;
+mod run;
 
 /// Test suites extracted from the document.
 pub struct TestSuites {
@@ -23,6 +32,9 @@ pub struct TestSuites {
     pub tests: Vec<TestCase>,
     /// Example documents in the current workspace.
     pub examples: Vec<Source>,
+    /// Documents in the current workspace whose diagnostics are checked
+    /// against the annotations in them.
+    pub diagnostics: Vec<Source>,
 }
 impl TestSuites {
     /// Rechecks the test suites.
@@ -42,20 +54,23 @@ impl TestSuites {
                     location: test.location,
                     function: function.clone(),
                     kind: test.kind,
+                    panic_patterns: test.panic_patterns.clone(),
                 })
             })
             .collect();
 
-        let examples = self
-            .examples
-            .iter()
-            .filter_map(|source| world.source(source.id()).ok())
-            .collect();
+        let recheck_sources = |sources: &[Source]| {
+            sources
+                .iter()
+                .filter_map(|source| world.source(source.id()).ok())
+                .collect()
+        };
 
         TestSuites {
             origin_files: self.origin_files.clone(),
             tests,
-            examples,
+            examples: recheck_sources(&self.examples),
+            diagnostics: recheck_sources(&self.diagnostics),
         }
     }
 }
@@ -83,6 +98,9 @@ pub struct TestCase {
     pub function: Func,
     /// Kind of the test case.
     pub kind: TestCaseKind,
+    /// Patterns that the error messages of a `panic-on-*` test case must
+    /// contain, declared by `/// should-panic: <pattern>` doc comments.
+    pub panic_patterns: Vec<EcoString>,
 }
 
 /// Extracts the test suites in the document
@@ -95,10 +113,28 @@ pub fn test_suites(ctx: &mut LocalContext, doc: &TypstDocument) -> Result<TestSu
         main_workspace,
         ctx.depended_source_files()
     );
-    let files = ctx
-        .depended_source_files()
+    let config = TestConfig::extract(doc)?;
+
+    // Documents checking diagnostics may fail to evaluate, so they are
+    // collected without evaluating them.
+    let is_diagnostic = |fid: &FileId| {
+        let vpath = fid.vpath().as_rooted_path();
+        let file_name = vpath.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        file_name.starts_with(config.diagnostic_pattern.as_str())
+    };
+    let depended = ctx.depended_source_files();
+    let diagnostics = depended
+        .iter()
+        .filter(|fid| fid.package() == main_workspace && is_diagnostic(fid))
+        .map(|fid| {
+            ctx.source_by_id(*fid)
+                .context_ut("failed to get source by id")
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let files = depended
         .par_iter()
-        .filter(|fid| fid.package() == main_workspace)
+        .filter(|fid| fid.package() == main_workspace && !is_diagnostic(fid))
         .map(|fid| {
             let source = ctx
                 .source_by_id(*fid)
@@ -108,8 +144,6 @@ pub fn test_suites(ctx: &mut LocalContext, doc: &TypstDocument) -> Result<TestSu
         })
         .collect::<Result<Vec<_>>>()?;
 
-    let config = extract_test_configuration(doc)?;
-
     let mut worker = TestSuitesWorker {
         files: &files,
         config,
@@ -122,16 +156,88 @@ pub fn test_suites(ctx: &mut LocalContext, doc: &TypstDocument) -> Result<TestSu
     Ok(TestSuites {
         tests: worker.tests,
         examples: worker.examples,
+        diagnostics,
         origin_files: files,
     })
 }
 
+/// The prefixes of the names of test cases, which can be configured by a
+/// dictionary in metadata labelled `<test-config>`, e.g.,
+/// `#metadata((test_pattern: "check-")) <test-config>`.
 #[derive(Debug, Clone)]
-struct TestConfig {
-    test_pattern: EcoString,
-    bench_pattern: EcoString,
-    panic_pattern: EcoString,
-    example_pattern: EcoString,
+pub struct TestConfig {
+    /// The prefix of the functions of normal test cases.
+    pub test_pattern: EcoString,
+    /// The prefix of the functions of benchmarks.
+    pub bench_pattern: EcoString,
+    /// The prefix of the functions of test cases that should panic.
+    pub panic_pattern: EcoString,
+    /// The prefix of the functions and documents of examples.
+    pub example_pattern: EcoString,
+    /// The prefix of the documents whose diagnostics are checked.
+    pub diagnostic_pattern: EcoString,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            test_pattern: "test-".into(),
+            bench_pattern: "bench-".into(),
+            panic_pattern: "panic-on-".into(),
+            example_pattern: "example-".into(),
+            diagnostic_pattern: "diag-".into(),
+        }
+    }
+}
+
+impl TestConfig {
+    /// Extracts the configuration from the document.
+    pub fn extract(doc: &TypstDocument) -> Result<Self> {
+        let selector = Label::new(PicoStr::intern("test-config"));
+        let metadata = doc.introspector().query(&Selector::Label(selector));
+        if metadata.len() > 1 {
+            // todo: attach source locations.
+            bail!("multiple test configurations found");
+        }
+
+        let config = if let Some(metadata) = metadata.first() {
+            let metadata = metadata
+                .to_packed::<MetadataElem>()
+                .context("test configuration is not a metadata element")?;
+
+            let value =
+                serde_json::to_value(&metadata.value).context("failed to serialize metadata")?;
+            serde_json::from_value(value).context("failed to deserialize metadata")?
+        } else {
+            UserTestConfig::default()
+        };
+
+        let default = Self::default();
+        Ok(Self {
+            test_pattern: config.test_pattern.unwrap_or(default.test_pattern),
+            bench_pattern: config.bench_pattern.unwrap_or(default.bench_pattern),
+            panic_pattern: config.panic_pattern.unwrap_or(default.panic_pattern),
+            example_pattern: config.example_pattern.unwrap_or(default.example_pattern),
+            diagnostic_pattern: config
+                .diagnostic_pattern
+                .unwrap_or(default.diagnostic_pattern),
+        })
+    }
+
+    /// Matches the kind of a test case by the name of its function.
+    pub fn match_test(&self, name: &str) -> Option<TestCaseKind> {
+        if name.starts_with(self.test_pattern.as_str()) {
+            Some(TestCaseKind::Test)
+        } else if name.starts_with(self.bench_pattern.as_str()) {
+            Some(TestCaseKind::Bench)
+        } else if name.starts_with(self.panic_pattern.as_str()) {
+            Some(TestCaseKind::Panic)
+        } else if name.starts_with(self.example_pattern.as_str()) {
+            Some(TestCaseKind::Example)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize)]
@@ -140,34 +246,7 @@ struct UserTestConfig {
     bench_pattern: Option<EcoString>,
     panic_pattern: Option<EcoString>,
     example_pattern: Option<EcoString>,
-}
-
-fn extract_test_configuration(doc: &TypstDocument) -> Result<TestConfig> {
-    let selector = Label::new(PicoStr::intern("test-config"));
-    let metadata = doc.introspector().query(&Selector::Label(selector));
-    if metadata.len() > 1 {
-        // todo: attach source locations.
-        bail!("multiple test configurations found");
-    }
-
-    let config = if let Some(metadata) = metadata.first() {
-        let metadata = metadata
-            .to_packed::<MetadataElem>()
-            .context("test configuration is not a metadata element")?;
-
-        let value =
-            serde_json::to_value(&metadata.value).context("failed to serialize metadata")?;
-        serde_json::from_value(value).context("failed to deserialize metadata")?
-    } else {
-        UserTestConfig::default()
-    };
-
-    Ok(TestConfig {
-        test_pattern: config.test_pattern.unwrap_or_else(|| "test-".into()),
-        bench_pattern: config.bench_pattern.unwrap_or_else(|| "bench-".into()),
-        panic_pattern: config.panic_pattern.unwrap_or_else(|| "panic-on-".into()),
-        example_pattern: config.example_pattern.unwrap_or_else(|| "example-".into()),
-    })
+    diagnostic_pattern: Option<EcoString>,
 }
 
 struct TestSuitesWorker<'a> {
@@ -178,20 +257,6 @@ struct TestSuitesWorker<'a> {
 }
 
 impl TestSuitesWorker<'_> {
-    fn match_test(&self, name: &str) -> Option<TestCaseKind> {
-        if name.starts_with(self.config.test_pattern.as_str()) {
-            Some(TestCaseKind::Test)
-        } else if name.starts_with(self.config.bench_pattern.as_str()) {
-            Some(TestCaseKind::Bench)
-        } else if name.starts_with(self.config.panic_pattern.as_str()) {
-            Some(TestCaseKind::Panic)
-        } else if name.starts_with(self.config.example_pattern.as_str()) {
-            Some(TestCaseKind::Example)
-        } else {
-            None
-        }
-    }
-
     fn discover_tests(&mut self) -> Result<()> {
         for (source, module) in self.files.iter() {
             let vpath = source.id().vpath().as_rooted_path();
@@ -213,12 +278,17 @@ impl TestSuitesWorker<'_> {
                     continue;
                 }
 
-                if let Some(kind) = self.match_test(name.as_str()) {
+                if let Some(kind) = self.config.match_test(name.as_str()) {
+                    let panic_patterns = match kind {
+                        TestCaseKind::Panic => panic_patterns(source, span),
+                        _ => Vec::new(),
+                    };
                     self.tests.push(TestCase {
                         name: name.clone(),
                         location: source.id(),
                         function: function.clone(),
                         kind,
+                        panic_patterns,
                     });
                 }
             }
@@ -227,3 +297,202 @@ impl TestSuitesWorker<'_> {
         Ok(())
     }
 }
+
+/// Extracts the patterns of the expected panic messages from the doc comments
+/// right before the binding of a test case.
+fn panic_patterns(source: &Source, span: Span) -> Vec<EcoString> {
+    let root = LinkedNode::new(source.root());
+    let Some(leaf) = root.find(span) else {
+        return Vec::new();
+    };
+    let Some(binding) = node_ancestors(&leaf).find(|n| n.is::<ast::LetBinding>()) else {
+        return Vec::new();
+    };
+    let Some(parent) = binding.parent() else {
+        return Vec::new();
+    };
+
+    let mut patterns = Vec::new();
+    for node in parent.children().take(binding.index()).rev() {
+        match node.kind() {
+            SyntaxKind::Hash => {}
+            SyntaxKind::Space if node.text().matches('\n').count() <= 1 => {}
+            SyntaxKind::LineComment => {
+                let Some(docs) = node.text().strip_prefix("///") else {
+                    break;
+                };
+                if let Some(pattern) = docs.trim().strip_prefix("should-panic:") {
+                    patterns.push(pattern.trim().into());
+                }
+            }
+            _ => break,
+        }
+    }
+
+    patterns.reverse();
+    patterns
+}
+
+/// Gets the name of an example document, i.e., the stem of its file name.
+pub fn example_name(id: FileId) -> String {
+    let path = id.vpath().as_rooted_path().with_extension("");
+    let name = path.file_name().and_then(|s| s.to_str());
+    name.unwrap_or_default().to_owned()
+}
+
+/// Finds the example document included by the function of an `example-*`
+/// test case.
+pub fn example_file(world: &dyn World, name: &str, id: FileId, span: Span) -> Result<Source> {
+    let source = world.source(id).context_ut("cannot find file")?;
+    let node = LinkedNode::new(source.root());
+    let leaf = node.find(span).context("cannot find example function")?;
+    let function = node_ancestors(&leaf)
+        .find(|n| n.is::<ast::Closure>())
+        .context("cannot find example function")?;
+    let closure = function.cast::<ast::Closure>().unwrap();
+    if closure.params().children().count() != 0 {
+        bail!("example function must not have parameters");
+    }
+    let included =
+        cast_include_expr(name, closure.body()).context("cannot find example function")?;
+    find_source_by_expr(world, id, included).context("cannot find example file")
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::diag::SourceDiagnostic;
+
+    use super::*;
+    use crate::tests::*;
+
+    /// Runs the function with the world and the source of the text.
+    fn with_source<T>(text: &str, f: impl FnOnce(&dyn World, &Source) -> T) -> T {
+        run_with_sources(text, |verse, path| {
+            let world = verse.snapshot();
+            let source = world.source_by_path(&path).unwrap();
+            f(&world, &source)
+        })
+    }
+
+    /// Gets the span of the first leaf with the text.
+    fn span_of(source: &Source, text: &str) -> Span {
+        fn find(node: &LinkedNode, text: &str) -> Option<Span> {
+            if node.text() == text {
+                return Some(node.span());
+            }
+            node.children().find_map(|child| find(&child, text))
+        }
+
+        find(&LinkedNode::new(source.root()), text).unwrap()
+    }
+
+    fn patterns(text: &str, name: &str) -> Vec<EcoString> {
+        with_source(text, |_, source| {
+            panic_patterns(source, span_of(source, name))
+        })
+    }
+
+    #[test]
+    fn test_panic_patterns() {
+        let text = "/// Docs of the case.
+/// should-panic: out of bounds
+///should-panic:index 2
+#let panic-on-index() = (1,).at(2)";
+        assert_eq!(
+            patterns(text, "panic-on-index"),
+            vec!["out of bounds", "index 2"]
+        );
+    }
+
+    #[test]
+    fn test_panic_patterns_detached() {
+        // The doc comments are separated by a blank line.
+        let text = "/// should-panic: out of bounds
+
+#let panic-on-index() = (1,).at(2)";
+        assert!(patterns(text, "panic-on-index").is_empty());
+
+        // A regular comment ends the doc comments.
+        let text = "/// should-panic: out of bounds
+// should-panic: index 2
+#let panic-on-index() = (1,).at(2)";
+        assert!(patterns(text, "panic-on-index").is_empty());
+    }
+
+    #[test]
+    fn test_panic_patterns_of_next_binding() {
+        // The patterns only belong to the binding right after them.
+        let text = "/// should-panic: out of bounds
+#let panic-on-a() = (1,).at(2)
+#let panic-on-b() = panic(\"b\")";
+        assert_eq!(patterns(text, "panic-on-a"), vec!["out of bounds"]);
+        assert!(patterns(text, "panic-on-b").is_empty());
+    }
+
+    #[test]
+    fn test_check_diagnostics() {
+        let text = "#let a = xvar //~ error: unknown variable
+#let bvar = 1
+//~^ warning: unused
+#let c = yvar
+#none //~ error: never";
+        let mismatches = with_source(text, |world, source| {
+            let diagnostics = [
+                SourceDiagnostic::error(span_of(source, "xvar"), "unknown variable: xvar"),
+                SourceDiagnostic::warning(span_of(source, "bvar"), "unused variable"),
+                SourceDiagnostic::error(span_of(source, "yvar"), "unknown variable: yvar"),
+                SourceDiagnostic::error(Span::detached(), "boom"),
+            ];
+            check_diagnostics(world, source, &diagnostics)
+        });
+
+        assert_eq!(
+            mismatches,
+            vec![
+                "line 4: unexpected error: unknown variable: yvar",
+                "unexpected error outside of the file: boom",
+                "line 5: expected error containing `never`, found none",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_diagnostics_severity() {
+        // The annotation matches neither a diagnostic of another severity nor
+        // one on another line.
+        let text = "#let a = xvar //~ warning: unknown variable
+#let b = yvar
+//~^^ error: unknown variable";
+        let mismatches = with_source(text, |world, source| {
+            let diagnostics = [
+                SourceDiagnostic::error(span_of(source, "xvar"), "unknown variable: xvar"),
+                SourceDiagnostic::error(span_of(source, "yvar"), "unknown variable: yvar"),
+            ];
+            check_diagnostics(world, source, &diagnostics)
+        });
+
+        assert_eq!(
+            mismatches,
+            vec![
+                "line 2: unexpected error: unknown variable: yvar",
+                "line 1: expected warning containing `unknown variable`, found none",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_annotations() {
+        let text = "//~^ error: before the file
+#let a = 1 //~ note: unknown severity
+#let b = 2 //~ error: valid";
+        let mismatches = with_source(text, |world, source| check_diagnostics(world, source, &[]));
+
+        assert_eq!(
+            mismatches,
+            vec![
+                "line 1: annotation points before the file",
+                "line 2: invalid annotation, expected `//~ error: ..` or `//~ warning: ..`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Checks the results of test cases and examples, shared by the test runners
+//! of the CLI and the language server.
+
+use std::path::{Path, PathBuf};
+
+use ecow::EcoVec;
+use tinymist_std::error::prelude::*;
+use tinymist_std::typst::TypstPagedDocument;
+use typst::diag::{SourceDiagnostic, SourceResult};
+use typst::foundations::{Label, Value};
+use typst::utils::PicoStr;
+
+use super::diff::{ImageDiff, ImageTolerance, diff_png};
+use super::{TestCase, TestCaseKind};
+
+/// The failure of a test case.
+#[derive(Debug, Clone)]
+pub struct TestFailure {
+    /// The reason of the failure.
+    pub message: String,
+    /// The diagnostics of the failure.
+    pub diagnostics: EcoVec<SourceDiagnostic>,
+}
+
+impl TestFailure {
+    fn new(message: impl Into<String>, diagnostics: EcoVec<SourceDiagnostic>) -> Self {
+        Self {
+            message: message.into(),
+            diagnostics,
+        }
+    }
+}
+
+/// Checks the result of calling the function of a test case. A `panic-on-*`
+/// test case must panic with the messages containing its patterns, and the
+/// others must return normally.
+pub fn check_call(test: &TestCase, result: SourceResult<Value>) -> Result<(), TestFailure> {
+    match (test.kind, result) {
+        (TestCaseKind::Panic, Ok(..)) => Err(TestFailure::new(
+            "exited normally, expected panic",
+            EcoVec::new(),
+        )),
+        (TestCaseKind::Panic, Err(err)) => {
+            let all_panic = err.iter().all(|p| p.message.contains("panic"));
+            let unmatched = test
+                .panic_patterns
+                .iter()
+                .find(|pattern| !err.iter().any(|p| p.message.contains(pattern.as_str())));
+            if !all_panic {
+                Err(TestFailure::new("exited with error, expected panic", err))
+            } else if let Some(pattern) = unmatched {
+                let message = format!("panicked without message containing `{pattern}`");
+                Err(TestFailure::new(message, err))
+            } else {
+                Ok(())
+            }
+        }
+        (_, Ok(..)) => Ok(()),
+        (_, Err(err)) => Err(TestFailure::new("call error", err)),
+    }
+}
+
+/// Gets the tolerance of comparing the rendered images of an example, which
+/// can be overridden in the example by a dictionary in metadata, e.g.,
+/// `#metadata((pixel: 0.2, ratio: 0.01)) <test-image-tolerance>`.
+pub fn image_tolerance(
+    doc: &TypstPagedDocument,
+    tolerance: ImageTolerance,
+) -> Result<ImageTolerance> {
+    let label = Label::new(PicoStr::intern("test-image-tolerance"));
+    let Ok(elem) = doc.introspector.query_label(label) else {
+        return Ok(tolerance);
+    };
+    match elem.get_by_name("value") {
+        Ok(Value::Dict(dict)) => tolerance.merge(&dict),
+        _ => bail!("<test-image-tolerance> must label a metadata of a dictionary"),
+    }
+}
+
+/// Whether an example is also rendered to HTML, which is marked by the label
+/// `<test-html-example>`.
+pub fn is_html_example(doc: &TypstPagedDocument) -> bool {
+    let label = Label::new(PicoStr::intern("test-html-example"));
+    doc.introspector.query_label(label).is_ok()
+}
+
+/// The outcome of comparing a rendered example to its reference.
+pub enum RefOutcome {
+    /// The reference didn't exist and has been created.
+    Created,
+    /// The rendered example matches the reference, exactly or within the
+    /// tolerance of the difference.
+    Matched(Option<ImageDiff>),
+    /// The reference has been updated to the rendered example.
+    Updated,
+    /// The rendered example mismatches the reference, and has been written
+    /// to [`ExampleRef::tmp_path`] along with the difference to
+    /// [`ExampleRef::diff_path`].
+    Mismatched(Option<ImageDiff>),
+}
+
+/// The reference files of an example, i.e., `refs/<kind>/<name>.<ext>` under
+/// the root, where the kind is `paged` or `html`.
+pub struct ExampleRef {
+    /// The reference file.
+    pub path: PathBuf,
+    /// The rendered file on a mismatch.
+    pub tmp_path: PathBuf,
+    /// The image of the difference on a mismatch.
+    pub diff_path: PathBuf,
+    /// The hash of the reference file.
+    pub hash_path: PathBuf,
+}
+
+impl ExampleRef {
+    /// Creates the reference files of an example rendered to the kind.
+    pub fn new(root: &Path, kind: &str, name: &str) -> Self {
+        let ext = if kind == "paged" { "png" } else { "html" };
+        let path = root.join("refs").join(kind).join(name).with_extension(ext);
+        Self {
+            tmp_path: path.with_extension(format!("tmp.{ext}")),
+            diff_path: path.with_extension(format!("diff.{ext}")),
+            hash_path: path.with_extension("hash"),
+            path,
+        }
+    }
+
+    /// Compares the rendered example to the reference by hashes, or
+    /// perceptually if the tolerance is given and the reference image exists.
+    /// The reference is updated on a mismatch if `update` is set.
+    pub fn update(
+        &self,
+        data: &[u8],
+        tolerance: Option<ImageTolerance>,
+        update: bool,
+    ) -> Result<RefOutcome> {
+        let hash = &format!("siphash128_13:{:x}", tinymist_std::hash::hash128(&data));
+        let existing_hash = if std::fs::exists(&self.hash_path).context("exists hash ref")? {
+            Some(std::fs::read(&self.hash_path).context("read hash ref")?)
+        } else {
+            None
+        };
+
+        let equal = existing_hash.map(|existing| existing.as_slice() == hash.as_bytes());
+
+        // Compares the images perceptually if the hashes differ.
+        let mut image_diff = None;
+        let equal = match (equal, tolerance) {
+            (Some(false), Some(tolerance))
+                if std::fs::exists(&self.path).context("exists ref")? =>
+            {
+                let reference = std::fs::read(&self.path).context("read ref")?;
+                let diff = diff_png(&reference, data, tolerance.pixel)?;
+                let within = diff.is_within(&tolerance);
+                image_diff = Some(diff);
+                Some(within)
+            }
+            _ => equal,
+        };
+
+        if equal == Some(false) && !update {
+            std::fs::write(&self.tmp_path, data).context("write tmp ref")?;
+            if let Some(diff) = &image_diff {
+                std::fs::write(&self.diff_path, diff.encode_png()?).context("write diff")?;
+            }
+            return Ok(RefOutcome::Mismatched(image_diff));
+        }
+
+        for stale_path in [&self.tmp_path, &self.diff_path] {
+            if std::fs::exists(stale_path).context("exists tmp")? {
+                std::fs::remove_file(stale_path).context("remove tmp")?;
+            }
+        }
+
+        if equal == Some(true) {
+            return Ok(RefOutcome::Matched(image_diff));
+        }
+
+        std::fs::create_dir_all(self.path.parent().context("parent")?).context("create ref")?;
+        std::fs::write(&self.path, data).context("write ref")?;
+        std::fs::write(&self.hash_path, hash).context("write hash ref")?;
+
+        Ok(match equal {
+            None => RefOutcome::Created,
+            Some(_) => RefOutcome::Updated,
+        })
+    }
+}
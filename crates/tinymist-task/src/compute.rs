@@ -1,5 +1,6 @@
 //! The computations for the tasks.
 
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -19,8 +20,10 @@ use typst_eval::eval_string;
 
 use crate::model::{ExportHtmlTask, ExportPngTask, ExportSvgTask};
 use crate::primitives::TaskWhen;
-use crate::{ExportTransform, Pages, QueryTask};
+use crate::{ExportTransform, QueryTask};
 
+pub mod script;
+pub use script::*;
 #[cfg(feature = "pdf")]
 pub mod pdf;
 #[cfg(feature = "pdf")]
@@ -50,6 +53,35 @@ impl ExportTimings {
         snap.signal
             .should_run_task(timing.unwrap_or(&TaskWhen::Never), docs)
     }
+
+    /// Checks if the export task is needed, which also evaluates the script of
+    /// the task if it is run by [`TaskWhen::Script`].
+    ///
+    /// Note: the script is evaluated synchronously on the calling thread, so
+    /// the language server calls it on the export task instead of the compile
+    /// handler.
+    pub fn needs_run_task<F: CompilerFeat, D: typst::Document>(
+        snap: &CompileSnapshot<F>,
+        task: &crate::ExportTask,
+        doc: Option<&D>,
+    ) -> Option<bool> {
+        let needs_run = Self::needs_run(snap, Some(&task.when), doc)?;
+        if !needs_run || task.when != TaskWhen::Script {
+            return Some(needs_run);
+        }
+
+        let Some(script) = task.when_script.as_deref() else {
+            log::warn!("the task is run by a script, but no script is given");
+            return Some(false);
+        };
+        match ExportScript::check(&snap.world, doc?, script) {
+            Ok(needs_run) => Some(needs_run),
+            Err(err) => {
+                log::error!("failed to check whether to run the task: {err}");
+                Some(false)
+            }
+        }
+    }
 }
 
 /// The computation for svg export.
@@ -75,7 +107,7 @@ impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for SvgExport {
                 typst_svg::svg_merged(doc, merged_gap)
             }
         } else {
-            typst_svg::svg_merged(doc, merged_gap)
+            typst_svg::svg_merged(&select_pages(&config.export, doc)?, merged_gap)
         })
     }
 }
@@ -121,7 +153,8 @@ impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PngExport {
                 typst_render::render_merged(doc, ppp, merged_gap, Some(fill))
             }
         } else {
-            typst_render::render_merged(doc, ppp, merged_gap, Some(fill))
+            let doc = select_pages(&config.export, doc)?;
+            typst_render::render_merged(&doc, ppp, merged_gap, Some(fill))
         };
 
         pixmap
@@ -312,7 +345,7 @@ pub fn get_page_selection(task: &crate::ExportTask) -> Result<(bool, Abs)> {
     let is_first = task
         .transform
         .iter()
-        .any(|t| matches!(t, ExportTransform::Pages { ranges, .. } if ranges == &[Pages::FIRST]));
+        .any(|t| matches!(t, ExportTransform::FirstPage));
 
     let mut gap_res = Abs::default();
     if !is_first {
@@ -331,6 +364,145 @@ pub fn get_page_selection(task: &crate::ExportTask) -> Result<(bool, Abs)> {
     Ok((is_first, gap_res))
 }
 
+/// Checks whether the output path contains a page number template, i.e.,
+/// `{p}`, `{0p}`, or `{t}`, in which case one file is exported per page.
+pub fn has_page_template(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    ["{p}", "{0p}", "{t}"]
+        .into_iter()
+        .any(|template| path.contains(template))
+}
+
+/// Substitutes the page number templates in the output path with the
+/// one-indexed page number and the page count.
+///
+/// `{0p}` is padded with zeros to the width of the page count.
+pub fn page_output_path(path: &Path, number: usize, total: usize) -> PathBuf {
+    let width = total.to_string().len();
+    let path = path
+        .to_string_lossy()
+        .replace("{p}", &number.to_string())
+        .replace("{0p}", &format!("{number:0width$}"))
+        .replace("{t}", &total.to_string());
+    PathBuf::from(path)
+}
+
+/// Checks whether the path is produced by [`page_output_path`] from the output
+/// path with some page number and page count.
+pub fn is_page_output_path(template: &Path, path: &Path) -> bool {
+    let check = |captures: &[(&str, &str)]| {
+        let capture = |templates: &[&str]| {
+            captures
+                .iter()
+                .find(|(name, _)| templates.contains(name))
+                .map(|(_, digits)| *digits)
+        };
+
+        let number = capture(&["{p}", "{0p}"]).map_or(Some(1), |digits| digits.parse().ok());
+        // The narrowest page count giving the padded width is enough to
+        // reproduce the path.
+        let width = capture(&["{0p}"]).map_or(0, str::len) as u32;
+        let total = match capture(&["{t}"]) {
+            Some(digits) => digits.parse().ok(),
+            None => number.map(|number| number.max(10usize.saturating_pow(width.max(1) - 1))),
+        };
+
+        match (number, total) {
+            (Some(number), Some(total)) if 0 < number && number <= total => {
+                page_output_path(template, number, total) == path
+            }
+            _ => false,
+        }
+    };
+
+    match_page_template(
+        &template.to_string_lossy(),
+        &path.to_string_lossy(),
+        &mut vec![],
+        &check,
+    )
+}
+
+/// Matches the path against the output path, capturing the digits substituted
+/// for the page number templates.
+fn match_page_template<'a>(
+    template: &str,
+    path: &'a str,
+    captures: &mut Vec<(&'static str, &'a str)>,
+    check: &dyn Fn(&[(&'static str, &'a str)]) -> bool,
+) -> bool {
+    for placeholder in ["{p}", "{0p}", "{t}"] {
+        let Some(rest) = template.strip_prefix(placeholder) else {
+            continue;
+        };
+
+        let digits = path.bytes().take_while(u8::is_ascii_digit).count();
+        for len in (1..=digits).rev() {
+            captures.push((placeholder, &path[..len]));
+            if match_page_template(rest, &path[len..], captures, check) {
+                return true;
+            }
+            captures.pop();
+        }
+        return false;
+    }
+
+    let mut chars = template.chars();
+    match chars.next() {
+        Some(ch) => path
+            .strip_prefix(ch)
+            .is_some_and(|path| match_page_template(chars.as_str(), path, captures, check)),
+        None => path.is_empty() && check(captures),
+    }
+}
+
+/// Gets the one-indexed numbers of the pages selected by the `FirstPage` and
+/// `Pages` transforms, which are all pages if there is no such transform.
+pub fn get_page_numbers(task: &crate::ExportTask, total: usize) -> Vec<usize> {
+    let is_first = task
+        .transform
+        .iter()
+        .any(|t| matches!(t, ExportTransform::FirstPage));
+    let selections = task
+        .transform
+        .iter()
+        .filter_map(|t| match t {
+            ExportTransform::Pages { ranges } => Some(ranges),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    (1..=total)
+        .filter(|&number| !is_first || number == 1)
+        .filter(|&number| {
+            selections
+                .iter()
+                .all(|ranges| ranges.iter().any(|range| range.contains(number)))
+        })
+        .collect()
+}
+
+/// Keeps the pages selected by the `Pages` transforms in the document, which
+/// are merged into a single artifact.
+pub fn select_pages(
+    task: &crate::ExportTask,
+    doc: &Arc<TypstPagedDocument>,
+) -> Result<Arc<TypstPagedDocument>> {
+    let has_ranges = task
+        .transform
+        .iter()
+        .any(|t| matches!(t, ExportTransform::Pages { .. }));
+    if !has_ranges {
+        return Ok(doc.clone());
+    }
+
+    let numbers = get_page_numbers(task, doc.pages.len());
+    if numbers.is_empty() {
+        bail!("no pages to export");
+    }
+    Ok(Arc::new(ExportScript::filter_pages(doc, &numbers)))
+}
+
 fn parse_length(gap: &str) -> Result<Abs> {
     let length = typst::syntax::parse_code(gap);
     if length.erroneous() {
@@ -380,8 +552,10 @@ fn parse_color(fill: String) -> anyhow::Result<Color> {
 
 #[cfg(test)]
 mod tests {
+    use tinymist_world::CompileSignal;
 
     use super::*;
+    use crate::tests::compile;
 
     #[test]
     fn test_parse_color() {
@@ -414,4 +588,134 @@ mod tests {
         assert!(parse_length("1").is_err());
         assert!(parse_length("1px").is_err());
     }
+
+    #[test]
+    fn test_page_output_path() {
+        let path = Path::new("out/page-{p}.png");
+        assert!(has_page_template(path));
+        assert!(!has_page_template(Path::new("out/page.png")));
+        assert_eq!(
+            page_output_path(path, 3, 12),
+            PathBuf::from("out/page-3.png")
+        );
+        assert_eq!(
+            page_output_path(Path::new("page-{0p}-of-{t}.svg"), 3, 12),
+            PathBuf::from("page-03-of-12.svg")
+        );
+    }
+
+    fn needs_run_task(when: TaskWhen, script: Option<&str>, by_fs_events: bool) -> Option<bool> {
+        let (world, doc) = compile(
+            r#"#set document(title: "Report")
+#metadata(true) <final>"#,
+        );
+        let mut snap = CompileSnapshot::from_world(world);
+        snap.signal = CompileSignal {
+            by_entry_update: false,
+            by_fs_events,
+            by_mem_events: false,
+        };
+
+        let mut task = crate::ExportTask::new(when);
+        task.when_script = script.map(str::to_owned);
+        ExportTimings::needs_run_task(&snap, &task, Some(doc.as_ref()))
+    }
+
+    #[test]
+    fn test_needs_run_task_by_script() {
+        let script = Some("document.title != none");
+        assert_eq!(needs_run_task(TaskWhen::Script, script, true), Some(true));
+        assert_eq!(needs_run_task(TaskWhen::Script, script, false), Some(false));
+
+        let script = Some("document.metadata.at(\"final\", default: false)");
+        assert_eq!(needs_run_task(TaskWhen::Script, script, true), Some(true));
+
+        let script = Some("document.pages > 1");
+        assert_eq!(needs_run_task(TaskWhen::Script, script, true), Some(false));
+    }
+
+    #[test]
+    fn test_needs_run_task_by_bad_script() {
+        assert_eq!(needs_run_task(TaskWhen::Script, None, true), Some(false));
+        assert_eq!(
+            needs_run_task(TaskWhen::Script, Some("document.pages"), true),
+            Some(false)
+        );
+        assert_eq!(
+            needs_run_task(TaskWhen::Script, Some("undefined-variable"), true),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_needs_run_task_ignores_script() {
+        let script = Some("false");
+        assert_eq!(needs_run_task(TaskWhen::OnSave, script, true), Some(true));
+        assert_eq!(needs_run_task(TaskWhen::Never, script, true), Some(false));
+    }
+
+    #[test]
+    fn test_is_page_output_path() {
+        let is_output =
+            |template: &str, path: &str| is_page_output_path(Path::new(template), Path::new(path));
+
+        assert!(is_output("out/page-{p}.png", "out/page-3.png"));
+        assert!(is_output("out/page-{p}.png", "out/page-12.png"));
+        assert!(!is_output("out/page-{p}.png", "out/page-0.png"));
+        assert!(!is_output("out/page-{p}.png", "out/page-03.png"));
+        assert!(!is_output("out/page-{p}.png", "out/page-.png"));
+        assert!(!is_output("out/page-{p}.png", "out/page-x.png"));
+        assert!(!is_output("out/page-{p}.png", "out/notes.png"));
+        assert!(!is_output("out/page-{p}.png", "other/page-3.png"));
+
+        assert!(is_output("page-{0p}-of-{t}.svg", "page-03-of-12.svg"));
+        assert!(is_output("page-{0p}-of-{t}.svg", "page-3-of-9.svg"));
+        assert!(!is_output("page-{0p}-of-{t}.svg", "page-3-of-12.svg"));
+        assert!(!is_output("page-{0p}-of-{t}.svg", "page-13-of-12.svg"));
+        assert!(is_output("page-{0p}.svg", "page-003.svg"));
+
+        assert!(is_output("{p}1.png", "121.png"));
+        assert!(is_output("{p}-{p}.png", "2-2.png"));
+        assert!(!is_output("{p}-{p}.png", "2-3.png"));
+
+        assert!(is_output("out/page.png", "out/page.png"));
+        assert!(!is_output("out/page.png", "out/page-1.png"));
+    }
+
+    #[test]
+    fn test_get_page_numbers() {
+        let mut task = crate::ExportTask::new(TaskWhen::Never);
+        assert_eq!(get_page_numbers(&task, 3), vec![1, 2, 3]);
+
+        task.transform.push(ExportTransform::Pages {
+            ranges: vec!["2".parse().unwrap(), "4-".parse().unwrap()],
+        });
+        assert_eq!(get_page_numbers(&task, 5), vec![2, 4, 5]);
+
+        task.transform.push(ExportTransform::Pages {
+            ranges: vec!["-4".parse().unwrap()],
+        });
+        assert_eq!(get_page_numbers(&task, 5), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_get_page_numbers_ranges() {
+        let pages = |ranges: &[&str]| {
+            let mut task = crate::ExportTask::new(TaskWhen::Never);
+            task.transform.push(ExportTransform::Pages {
+                ranges: ranges.iter().map(|r| r.parse().unwrap()).collect(),
+            });
+            task
+        };
+
+        assert_eq!(get_page_numbers(&pages(&["2-3"]), 5), vec![2, 3]);
+        // `1-` selects all pages rather than the first page.
+        assert_eq!(get_page_numbers(&pages(&["1-"]), 3), vec![1, 2, 3]);
+
+        let mut task = crate::ExportTask::new(TaskWhen::Never);
+        task.transform.push(ExportTransform::FirstPage);
+        assert_eq!(get_page_numbers(&task, 3), vec![1]);
+        assert!(get_page_selection(&task).unwrap().0);
+        assert!(!get_page_selection(&pages(&["1-"])).unwrap().0);
+    }
 }
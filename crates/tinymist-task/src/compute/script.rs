@@ -0,0 +1,365 @@
+//! The computation for export transform scripts.
+
+use std::collections::BTreeMap;
+
+use typst::foundations::{Array, Binding, Dict, IntoValue, NativeElement, Str};
+use typst::introspection::MetadataElem;
+
+use super::*;
+
+/// The output of an export transform script.
+#[derive(Debug, Clone)]
+pub enum ScriptOutput {
+    /// The artifact to write in place of the exported one.
+    Artifact(Bytes),
+    /// The one-indexed page numbers to keep, which requires exporting the
+    /// document again with only these pages.
+    Pages(Vec<usize>),
+}
+
+/// The computation for export transform scripts.
+///
+/// A script is evaluated in code mode with the following variables in scope:
+/// - `artifact`: the exported artifact in bytes.
+/// - `format`: the extension of the exported artifact, e.g. `"pdf"`.
+/// - `document`: a dictionary describing the document, containing `title`,
+///   the title of the document, `pages`, the number of pages, `labels`, the
+///   page numbers of the labelled elements keyed by label, and `metadata`, the
+///   values of the labelled metadata elements keyed by label.
+///
+/// The script returns `none` to keep the artifact, `bytes` or `str` to replace
+/// it, or, for the paged formats, an array of page numbers to keep.
+pub struct ExportScript;
+
+impl ExportScript {
+    /// Runs a transform script over the exported artifact.
+    pub fn run(
+        world: &dyn World,
+        doc: &TypstDocument,
+        script: &str,
+        format: &str,
+        artifact: Bytes,
+    ) -> Result<ScriptOutput> {
+        let document = match doc {
+            TypstDocument::Paged(doc) => Self::describe(doc.as_ref()),
+            TypstDocument::Html(doc) => Self::describe(doc.as_ref()),
+        };
+
+        let mut scope = Scope::new();
+        let mut define = |name: &str, value: Value| {
+            scope.bind(name.into(), Binding::detached(value));
+        };
+        define("artifact", Value::Bytes(artifact.clone()));
+        define("format", Value::Str(format.into()));
+        define("document", Value::Dict(document));
+
+        let value = Self::eval(world, script, scope, "transform script")?;
+
+        Ok(match value {
+            Value::None => ScriptOutput::Artifact(artifact),
+            Value::Bytes(bytes) => ScriptOutput::Artifact(bytes),
+            Value::Str(text) => ScriptOutput::Artifact(Bytes::from_string(text.to_string())),
+            Value::Array(pages) if matches!(format, "pdf" | "png" | "svg") => {
+                let pages = pages
+                    .into_iter()
+                    .map(|page| match page.cast::<usize>() {
+                        Ok(page) if page > 0 => Ok(page),
+                        _ => bail!("expected positive page numbers from transform script"),
+                    })
+                    .collect::<Result<_>>()?;
+                ScriptOutput::Pages(pages)
+            }
+            value => bail!(
+                "transform script returned {} for format {format}, expected none, bytes, or str",
+                value.ty()
+            ),
+        })
+    }
+
+    /// Evaluates a predicate script deciding whether to run a task, which has
+    /// the same `document` in scope as the transform scripts.
+    pub fn check<D: typst::Document>(world: &dyn World, doc: &D, script: &str) -> Result<bool> {
+        let mut scope = Scope::new();
+        let document = Value::Dict(Self::describe(doc));
+        scope.bind("document".into(), Binding::detached(document));
+
+        match Self::eval(world, script, scope, "when script")? {
+            Value::Bool(value) => Ok(value),
+            value => bail!("when script returned {}, expected bool", value.ty()),
+        }
+    }
+
+    /// Evaluates a script in code mode.
+    fn eval(world: &dyn World, script: &str, scope: Scope, kind: &str) -> Result<Value> {
+        eval_string(
+            &typst::ROUTINES,
+            world.track(),
+            script,
+            Span::detached(),
+            EvalMode::Code,
+            scope,
+        )
+        .map_err(|errors| {
+            let mut message = EcoString::from(format!("failed to evaluate {kind}"));
+            for (i, error) in errors.into_iter().enumerate() {
+                message.push_str(if i == 0 { ": " } else { ", " });
+                message.push_str(&error.message);
+            }
+            anyhow::anyhow!("{message}")
+        })
+    }
+
+    /// Keeps only the given one-indexed pages of a document.
+    ///
+    /// The kept pages stay in the order of the document, so the order and the
+    /// duplicates of the given page numbers are ignored.
+    pub fn filter_pages(doc: &TypstPagedDocument, pages: &[usize]) -> TypstPagedDocument {
+        let mut doc = doc.clone();
+        let mut number = 0;
+        doc.pages.retain(|_| {
+            number += 1;
+            pages.contains(&number)
+        });
+        doc
+    }
+
+    /// Describes the document for the scripts.
+    fn describe<D: typst::Document>(doc: &D) -> Dict {
+        let introspector = doc.introspector();
+
+        let mut labels = BTreeMap::<Str, Vec<usize>>::new();
+        for elem in introspector.all() {
+            let (Some(label), Some(loc)) = (elem.label(), elem.location()) else {
+                continue;
+            };
+            let page = introspector.page(loc).get();
+            let pages = labels.entry(label.resolve().as_str().into()).or_default();
+            if !pages.contains(&page) {
+                pages.push(page);
+            }
+        }
+        let labels = labels
+            .into_iter()
+            .map(|(label, pages)| {
+                (
+                    label,
+                    pages
+                        .into_iter()
+                        .map(IntoValue::into_value)
+                        .collect::<Array>()
+                        .into_value(),
+                )
+            })
+            .collect::<Dict>();
+
+        let metadata = introspector
+            .query(&MetadataElem::elem().select())
+            .into_iter()
+            .filter_map(|elem| {
+                let label = elem.label()?;
+                let value = elem.to_packed::<MetadataElem>()?.value.clone();
+                Some((label.resolve().as_str().into(), value))
+            })
+            .collect::<Dict>();
+
+        let mut dict = Dict::new();
+        dict.insert("title".into(), doc.info().title.clone().into_value());
+        dict.insert("pages".into(), introspector.pages().get().into_value());
+        dict.insert("labels".into(), labels.into_value());
+        dict.insert("metadata".into(), metadata.into_value());
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::array;
+
+    use super::*;
+    use crate::tests::compile;
+
+    const DOC: &str = r#"#set document(title: "Report")
+= Intro <intro>
+#metadata("v1") <final>
+#pagebreak()
+= Body <body>
+#pagebreak()
+= Outro <intro>
+"#;
+
+    fn run(script: &str, format: &str) -> Result<ScriptOutput> {
+        let (world, doc) = compile(DOC);
+        let doc = TypstDocument::Paged(doc);
+        ExportScript::run(&world, &doc, script, format, Bytes::new(b"artifact"))
+    }
+
+    fn artifact(output: ScriptOutput) -> Bytes {
+        match output {
+            ScriptOutput::Artifact(bytes) => bytes,
+            ScriptOutput::Pages(pages) => panic!("expected an artifact, got pages {pages:?}"),
+        }
+    }
+
+    #[test]
+    fn test_run_none() {
+        let output = artifact(run("none", "pdf").unwrap());
+        assert_eq!(output.as_slice(), b"artifact");
+    }
+
+    #[test]
+    fn test_run_bytes() {
+        let output = artifact(run("bytes((65, 66))", "pdf").unwrap());
+        assert_eq!(output.as_slice(), b"AB");
+
+        let output = artifact(run("artifact.slice(0, 3)", "pdf").unwrap());
+        assert_eq!(output.as_slice(), b"art");
+    }
+
+    #[test]
+    fn test_run_str() {
+        let output = artifact(run("format + \": \" + str(artifact)", "md").unwrap());
+        assert_eq!(output.as_slice(), b"md: artifact");
+    }
+
+    #[test]
+    fn test_run_pages() {
+        for format in ["pdf", "png", "svg"] {
+            let output = run("(3, 1)", format).unwrap();
+            assert!(
+                matches!(&output, ScriptOutput::Pages(pages) if pages == &[3, 1]),
+                "{format}: {output:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_errors() {
+        let err = run("(1, 2)", "html").unwrap_err().to_string();
+        assert!(
+            err.contains("transform script returned array for format html"),
+            "{err}"
+        );
+
+        let err = run("42", "pdf").unwrap_err().to_string();
+        assert!(
+            err.contains(
+                "transform script returned integer for format pdf, expected none, bytes, or str"
+            ),
+            "{err}"
+        );
+
+        for script in ["(1, 0)", "(-1,)", "(\"1\",)"] {
+            let err = run(script, "pdf").unwrap_err().to_string();
+            assert!(
+                err.contains("expected positive page numbers from transform script"),
+                "{script}: {err}"
+            );
+        }
+
+        let err = run("undefined-variable", "pdf").unwrap_err().to_string();
+        assert!(
+            err.contains("failed to evaluate transform script: unknown variable"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let (_, doc) = compile(DOC);
+        let document = ExportScript::describe(doc.as_ref());
+
+        assert_eq!(document.get("title").unwrap(), &Value::Str("Report".into()));
+        assert_eq!(document.get("pages").unwrap(), &Value::Int(3));
+
+        let Value::Dict(labels) = document.get("labels").unwrap() else {
+            panic!("expected labels to be a dictionary");
+        };
+        assert_eq!(labels.get("intro").unwrap(), &Value::Array(array![1, 3]));
+        assert_eq!(labels.get("body").unwrap(), &Value::Array(array![2]));
+        assert_eq!(labels.get("final").unwrap(), &Value::Array(array![1]));
+
+        let Value::Dict(metadata) = document.get("metadata").unwrap() else {
+            panic!("expected metadata to be a dictionary");
+        };
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata.get("final").unwrap(), &Value::Str("v1".into()));
+    }
+
+    #[test]
+    fn test_describe_untitled() {
+        let (_, doc) = compile("Hello");
+        let document = ExportScript::describe(doc.as_ref());
+
+        assert_eq!(document.get("title").unwrap(), &Value::None);
+        assert_eq!(document.get("pages").unwrap(), &Value::Int(1));
+        assert_eq!(document.get("labels").unwrap(), &Value::Dict(Dict::new()));
+        assert_eq!(document.get("metadata").unwrap(), &Value::Dict(Dict::new()));
+    }
+
+    #[test]
+    fn test_run_document() {
+        let script = "document.labels.intro.map(str).join(\",\") + \" of \" + str(document.pages)";
+        let output = artifact(run(script, "txt").unwrap());
+        assert_eq!(output.as_slice(), b"1,3 of 3");
+    }
+
+    fn check(source: &str, script: &str) -> Result<bool> {
+        let (world, doc) = compile(source);
+        ExportScript::check(&world, doc.as_ref(), script)
+    }
+
+    #[test]
+    fn test_check_title() {
+        let script = "document.title != none";
+        assert!(check(DOC, script).unwrap());
+        assert!(!check("Hello", script).unwrap());
+
+        assert!(check(DOC, "document.title == \"Report\"").unwrap());
+    }
+
+    #[test]
+    fn test_check_final_metadata() {
+        let script = "document.metadata.at(\"final\", default: none) == \"v1\"";
+        assert!(check(DOC, script).unwrap());
+        assert!(!check("#metadata(\"v0\") <final>", script).unwrap());
+        assert!(!check("#metadata(\"v1\") <draft>", script).unwrap());
+    }
+
+    #[test]
+    fn test_check_errors() {
+        let err = check(DOC, "document.pages").unwrap_err().to_string();
+        assert!(
+            err.contains("when script returned integer, expected bool"),
+            "{err}"
+        );
+
+        let err = check(DOC, "artifact == none").unwrap_err().to_string();
+        assert!(
+            err.contains("failed to evaluate when script: unknown variable: artifact"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_filter_pages() {
+        let (_, doc) = compile(DOC);
+
+        let filtered = ExportScript::filter_pages(&doc, &[3, 1, 1]);
+        let numbers = filtered
+            .pages
+            .iter()
+            .map(|page| page.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, [1, 3]);
+
+        let filtered = ExportScript::filter_pages(&doc, &[2, 4]);
+        let numbers = filtered
+            .pages
+            .iter()
+            .map(|page| page.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, [2]);
+
+        assert!(ExportScript::filter_pages(&doc, &[]).pages.is_empty());
+    }
+}
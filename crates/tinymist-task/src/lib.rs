@@ -6,5 +6,11 @@ pub use model::*;
 mod primitives;
 pub use primitives::*;
 
+mod manifest;
+pub use manifest::*;
+
 pub mod compute;
 pub use compute::*;
+
+#[cfg(test)]
+mod tests;
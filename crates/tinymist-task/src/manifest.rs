@@ -0,0 +1,176 @@
+//! The manifests of exported outputs, which are used to skip unchanged exports
+//! and to clean up stale outputs.
+
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tinymist_std::error::prelude::*;
+use tinymist_std::fs::paths::write_atomic;
+use tinymist_std::hash::hash128;
+use tinymist_std::path::unix_slash;
+use tinymist_std::typst::TypstDocument;
+
+use crate::is_page_output_path;
+
+/// The directory storing the export manifests, relative to the project root.
+pub const EXPORT_MANIFEST_DIR: &str = "target/tinymist/exports";
+
+/// A manifest of the outputs produced by an export task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportManifest {
+    /// The hash of the export task.
+    pub task: String,
+    /// The hash of the compiled document, if the outputs only depend on the
+    /// document.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub document: Option<String>,
+    /// The hashes of the produced outputs, keyed by their paths.
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+}
+
+impl ExportManifest {
+    /// Creates an empty manifest of an export task.
+    pub fn new(task: String, document: Option<String>) -> Self {
+        Self {
+            task,
+            document,
+            outputs: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the path to the manifest of the export task writing to the output
+    /// path, which is in the target directory of the project.
+    pub fn path(root: &Path, output: &Path) -> PathBuf {
+        let key = hash128(&unix_slash(output));
+        root.join(EXPORT_MANIFEST_DIR)
+            .join(format!("{key:032x}.json"))
+    }
+
+    /// Reads a manifest, which is empty if it doesn't exist or is corrupted.
+    pub fn read(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the manifest.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("create manifest dir")?;
+        }
+        let data = serde_json::to_string_pretty(self).context("serialize manifest")?;
+        write_atomic(path, data).context("write manifest")
+    }
+
+    /// Hashes a value, e.g., an export task, a page, or an artifact.
+    pub fn hash(value: &impl Hash) -> String {
+        format!("siphash128_13:{:x}", hash128(value))
+    }
+
+    /// Hashes a paged document. The outputs of other documents are not
+    /// decided by the hash, so it returns `None` for them.
+    pub fn hash_document(doc: &TypstDocument) -> Option<String> {
+        match doc {
+            TypstDocument::Paged(doc) => Some(Self::hash(&(&doc.pages, &doc.info))),
+            TypstDocument::Html(_) => None,
+        }
+    }
+
+    /// Checks whether all the outputs are produced by the same task from the
+    /// same document and still exist.
+    pub fn is_fresh(&self, task: &str, document: Option<&str>) -> bool {
+        document.is_some()
+            && self.task == task
+            && self.document.as_deref() == document
+            && !self.outputs.is_empty()
+            && self.outputs.keys().all(|path| Path::new(path).exists())
+    }
+
+    /// Checks whether an output is produced by the same task with the same
+    /// hash and still exists.
+    pub fn is_output_fresh(&self, task: &str, path: &Path, hash: &str) -> bool {
+        self.task == task
+            && self
+                .outputs
+                .get(path.to_string_lossy().as_ref())
+                .map(String::as_str)
+                == Some(hash)
+            && path.exists()
+    }
+
+    /// Adds an output to the manifest.
+    pub fn add_output(&mut self, path: &Path, hash: String) {
+        self.outputs
+            .insert(path.to_string_lossy().into_owned(), hash);
+    }
+
+    /// Gets the outputs in the manifest that are not produced by the current
+    /// export anymore.
+    ///
+    /// Only the paths that could be produced from the output path by
+    /// [`page_output_path`](crate::page_output_path) are considered, so a
+    /// corrupted or edited manifest never leads to removing other files.
+    pub fn stale_outputs<'a>(
+        &'a self,
+        current: &'a Self,
+        output: &'a Path,
+    ) -> impl Iterator<Item = &'a Path> {
+        self.outputs
+            .keys()
+            .filter(|path| !current.outputs.contains_key(*path))
+            .map(Path::new)
+            .filter(move |path| is_page_output_path(output, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_outputs() {
+        let mut old = ExportManifest::new("task".into(), Some("doc".into()));
+        old.add_output(Path::new("page-1.png"), "a".into());
+        old.add_output(Path::new("page-2.png"), "b".into());
+
+        let mut current = ExportManifest::new("task".into(), Some("doc2".into()));
+        current.add_output(Path::new("page-1.png"), "c".into());
+
+        let output = Path::new("page-{p}.png");
+        let stale = old.stale_outputs(&current, output).collect::<Vec<_>>();
+        assert_eq!(stale, vec![Path::new("page-2.png")]);
+    }
+
+    #[test]
+    fn test_stale_outputs_of_other_paths() {
+        let mut old = ExportManifest::new("task".into(), Some("doc".into()));
+        old.add_output(Path::new("page-2.png"), "a".into());
+        old.add_output(Path::new("main.typ"), "b".into());
+        old.add_output(Path::new("notes/page-3.png"), "c".into());
+
+        let current = ExportManifest::new("task".into(), Some("doc2".into()));
+
+        let output = Path::new("page-{p}.png");
+        let stale = old.stale_outputs(&current, output).collect::<Vec<_>>();
+        assert_eq!(stale, vec![Path::new("page-2.png")]);
+
+        let output = Path::new("main.png");
+        assert_eq!(old.stale_outputs(&current, output).count(), 0);
+    }
+
+    #[test]
+    fn test_fresh_outputs() {
+        let mut manifest = ExportManifest::new("task".into(), Some("doc".into()));
+        assert!(!manifest.is_fresh("task", Some("doc")));
+
+        let missing = Path::new("/tinymist/missing/page-1.png");
+        manifest.add_output(missing, "a".into());
+        assert!(!manifest.is_fresh("task", Some("doc")));
+        assert!(!manifest.is_output_fresh("task", missing, "a"));
+    }
+}
@@ -76,6 +76,8 @@ pub enum ProjectTask {
     ExportTeX(ExportTeXTask),
     /// An export Text task.
     ExportText(ExportTextTask),
+    /// An export EPUB task.
+    ExportEpub(ExportEpubTask),
     /// An query task.
     Query(QueryTask),
     // todo: compatibility
@@ -96,6 +98,7 @@ impl ProjectTask {
             | Self::ExportMd(..)
             | Self::ExportTeX(..)
             | Self::ExportText(..)
+            | Self::ExportEpub(..)
             | Self::Query(..) => &self.as_export()?.when,
         })
     }
@@ -112,6 +115,7 @@ impl ProjectTask {
             Self::ExportTeX(task) => &task.export,
             Self::ExportMd(task) => &task.export,
             Self::ExportText(task) => &task.export,
+            Self::ExportEpub(task) => &task.export,
             Self::Query(task) => &task.export,
         })
     }
@@ -128,6 +132,7 @@ impl ProjectTask {
             Self::ExportTeX(task) => &mut task.export,
             Self::ExportMd(task) => &mut task.export,
             Self::ExportText(task) => &mut task.export,
+            Self::ExportEpub(task) => &mut task.export,
             Self::Query(task) => &mut task.export,
         })
     }
@@ -140,6 +145,7 @@ impl ProjectTask {
             Self::ExportMd { .. } => "md",
             Self::ExportTeX { .. } => "tex",
             Self::ExportText { .. } => "txt",
+            Self::ExportEpub { .. } => "epub",
             Self::ExportSvg { .. } => "svg",
             Self::ExportPng { .. } => "png",
             Self::Query(QueryTask {
@@ -166,6 +172,10 @@ pub struct PreviewTask {
 pub struct ExportTask {
     /// When to run the task
     pub when: TaskWhen,
+    /// The typst script deciding whether to run the task, which is evaluated
+    /// when the task is run by [`TaskWhen::Script`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub when_script: Option<String>,
     /// The output path pattern.
     pub output: Option<PathPattern>,
     /// The task's transforms.
@@ -178,6 +188,7 @@ impl ExportTask {
     pub fn new(when: TaskWhen) -> Self {
         Self {
             when,
+            when_script: None,
             output: None,
             transform: Vec::new(),
         }
@@ -208,6 +219,9 @@ pub enum PageSelection {
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum ExportTransform {
+    /// Only pick the first page, which is the default page selection of the
+    /// export commands of the language server.
+    FirstPage,
     /// Only pick a subset of pages.
     Pages {
         /// The page ranges to export.
@@ -324,6 +338,20 @@ pub struct ExportTextTask {
     pub export: ExportTask,
 }
 
+/// An export EPUB task specifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportEpubTask {
+    /// The processor to use for the EPUB export.
+    pub processor: Option<String>,
+    /// The theme of the book, which is a stylesheet or a directory containing
+    /// a `style.css` and fonts.
+    pub theme: Option<PathBuf>,
+    /// The shared export arguments.
+    #[serde(flatten)]
+    pub export: ExportTask,
+}
+
 /// An export query task specifier.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
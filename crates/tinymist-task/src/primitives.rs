@@ -198,8 +198,12 @@ impl PathPattern {
 pub struct Pages(pub RangeInclusive<Option<NonZeroUsize>>);
 
 impl Pages {
-    /// Selects the first page.
-    pub const FIRST: Pages = Pages(NonZeroUsize::new(1)..=None);
+    /// Checks whether the range contains the one-indexed page number.
+    pub fn contains(&self, number: usize) -> bool {
+        let start = self.0.start().map_or(1, NonZeroUsize::get);
+        let end = self.0.end().map_or(usize::MAX, NonZeroUsize::get);
+        (start..=end).contains(&number)
+    }
 }
 
 impl FromStr for Pages {
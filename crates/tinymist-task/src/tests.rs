@@ -0,0 +1,55 @@
+//! Tests support for the task computations.
+
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock};
+
+use tinymist_std::typst::TypstPagedDocument;
+use tinymist_world::args::CompileFontArgs;
+use tinymist_world::font::FontResolverImpl;
+use tinymist_world::system::SystemUniverseBuilder;
+use tinymist_world::{EntryState, ShadowApi, TypstSystemWorld};
+use typst::foundations::Bytes;
+use typst::syntax::VirtualPath;
+
+/// The root of the documents compiled by [`compile`].
+pub fn root() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from("C:\\dummy-root")
+    } else {
+        PathBuf::from("/dummy-root")
+    }
+}
+
+/// Compiles a document from the source of its `main.typ`.
+pub fn compile(source: &str) -> (TypstSystemWorld, Arc<TypstPagedDocument>) {
+    static FONT_RESOLVER: LazyLock<Arc<FontResolverImpl>> = LazyLock::new(|| {
+        Arc::new(
+            SystemUniverseBuilder::resolve_fonts(CompileFontArgs {
+                ignore_system_fonts: true,
+                ..Default::default()
+            })
+            .unwrap(),
+        )
+    });
+
+    let root = root();
+    let entry = EntryState::new_rooted(root.as_path().into(), Some(VirtualPath::new("main.typ")));
+    let mut verse = SystemUniverseBuilder::build(
+        entry,
+        Default::default(),
+        FONT_RESOLVER.clone(),
+        SystemUniverseBuilder::resolve_package(None, None),
+    );
+    verse
+        .map_shadow(
+            &root.join("main.typ"),
+            Bytes::from_string(source.to_owned()),
+        )
+        .unwrap();
+
+    let world = verse.snapshot();
+    let doc = typst::compile::<TypstPagedDocument>(&world)
+        .output
+        .expect("failed to compile the document");
+    (world, Arc::new(doc))
+}
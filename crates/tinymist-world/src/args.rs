@@ -256,7 +256,8 @@ pub enum TaskWhen {
     ///
     /// Note: this is deprecating.
     OnDocumentHasTitle,
-    /// Checks by running a typst script.
+    /// Run task on saving or switching to the document if the typst script of
+    /// the task evaluates to `true`, which has the compiled document in scope.
     Script,
 }
 
@@ -87,8 +87,8 @@ impl CompileSignal {
     ) -> Option<bool> {
         match when {
             TaskWhen::Never => Some(false),
-            // todo: by script
-            TaskWhen::Script => Some(self.by_entry_update),
+            // The script is evaluated by the task itself.
+            TaskWhen::Script => Some(self.by_entry_update || self.by_fs_events),
             TaskWhen::OnType => Some(self.by_mem_events),
             TaskWhen::OnSave => Some(self.by_fs_events),
             TaskWhen::OnDocumentHasTitle if self.by_fs_events => {
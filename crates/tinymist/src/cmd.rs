@@ -10,8 +10,8 @@ use serde_json::Value as JsonValue;
 use task::TraceParams;
 use tinymist_assets::TYPST_PREVIEW_HTML;
 use tinymist_project::{
-    ExportHtmlTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask, ExportTeXTask,
-    ExportTextTask, ExportTransform, PageSelection, Pages, ProjectTask, QueryTask,
+    ExportEpubTask, ExportHtmlTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask,
+    ExportTeXTask, ExportTextTask, ExportTransform, PageSelection, Pages, ProjectTask, QueryTask,
 };
 use tinymist_query::package::PackageInfo;
 use tinymist_query::{LocalContextGuard, LspRange};
@@ -40,6 +40,10 @@ struct ExportOpts {
     ppi: Option<f32>,
     #[serde(default)]
     page: PageSelection,
+    /// The page ranges to export, e.g., `["1", "3-5"]`, which replace the
+    /// first page or are merged. When the output path contains `{p}`, `{0p}`,
+    /// or `{t}`, one file is exported per selected page.
+    pages: Option<Vec<Pages>>,
     /// Whether to open the exported file(s) after the export is done.
     open: Option<bool>,
     // todo: we made a mistake that they will be snakecase, but they should be camelCase
@@ -61,6 +65,18 @@ struct ExportTypliteOpts {
     assets_path: Option<PathBuf>,
 }
 
+/// See [`ProjectTask`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportEpubOpts {
+    /// Whether to open the exported file(s) after the export is done.
+    open: Option<bool>,
+    /// The processor to use for the typlite export.
+    processor: Option<String>,
+    /// The theme of the book, which is a stylesheet or a directory.
+    theme: Option<PathBuf>,
+}
+
 /// See [`ProjectTask`].
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -151,6 +167,21 @@ impl ServerState {
         )
     }
 
+    /// Export the current document as an EPUB file.
+    pub fn export_epub(&mut self, mut args: Vec<JsonValue>) -> ScheduleResult {
+        let opts = get_arg_or_default!(args[1] as ExportEpubOpts);
+        let export = self.config.export_task();
+        self.export(
+            ProjectTask::ExportEpub(ExportEpubTask {
+                processor: opts.processor,
+                theme: opts.theme,
+                export,
+            }),
+            opts.open.unwrap_or_default(),
+            args,
+        )
+    }
+
     /// Export the current document as Text file(s).
     pub fn export_text(&mut self, mut args: Vec<JsonValue>) -> ScheduleResult {
         let opts = get_arg_or_default!(args[1] as ExportOpts);
@@ -192,7 +223,7 @@ impl ServerState {
         let opts = get_arg_or_default!(args[1] as ExportOpts);
 
         let mut export = self.config.export_task();
-        select_page(&mut export, opts.page).map_err(invalid_params)?;
+        select_page(&mut export, opts.page, opts.pages).map_err(invalid_params)?;
 
         self.export(
             ProjectTask::ExportSvg(ExportSvgTask { export }),
@@ -212,7 +243,7 @@ impl ServerState {
             .map_err(invalid_params)?;
 
         let mut export = self.config.export_task();
-        select_page(&mut export, opts.page).map_err(invalid_params)?;
+        select_page(&mut export, opts.page, opts.pages).map_err(invalid_params)?;
 
         self.export(
             ProjectTask::ExportPng(ExportPngTask {
@@ -626,6 +657,43 @@ impl ServerState {
     pub fn get_server_info(&mut self, _arguments: Vec<JsonValue>) -> ScheduleResult {
         run_query!(self.ServerInfo())
     }
+
+    /// Discover the test cases and test documents that the document depends
+    /// on.
+    pub fn discover_tests(&mut self, mut args: Vec<JsonValue>) -> ScheduleResult {
+        let path = get_arg!(args[0] as PathBuf);
+        run_query!(self.TestDiscovery(path))
+    }
+
+    /// Run the test cases and test documents that the document depends on,
+    /// notifying the result of each test item by `tinymist/testResult`.
+    #[cfg(feature = "system")]
+    pub fn run_tests(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
+        use crate::tool::testing::{run_tests, RunTestsOpts, TestResultNotification};
+
+        let path = get_arg!(args[0] as PathBuf);
+        let opts = get_arg_or_default!(args[1] as RunTestsOpts);
+
+        let mut snap = self.query_snapshot().map_err(internal_error)?;
+        if !self.is_pinning() {
+            snap = snap.task(self.resolve_task(path.into()));
+        }
+        let client = self.client.clone();
+        let position_encoding = self.const_config().position_encoding;
+
+        just_future(async move {
+            let summary = snap
+                .run_analysis(|ctx| {
+                    run_tests(ctx, &opts, position_encoding, |result| {
+                        client.send_notification::<TestResultNotification>(&result);
+                    })
+                })
+                .map_err(internal_error)?
+                .map_err(internal_error)?;
+
+            serde_json::to_value(summary).map_err(internal_error)
+        })
+    }
 }
 
 impl ServerState {
@@ -791,13 +859,22 @@ impl ServerState {
     }
 }
 
-/// Applies page selection to the export task.
-fn select_page(task: &mut ExportTask, selection: PageSelection) -> Result<()> {
+/// Applies page selection to the export task, where the page ranges take
+/// precedence over selecting the first page.
+fn select_page(
+    task: &mut ExportTask,
+    selection: PageSelection,
+    pages: Option<Vec<Pages>>,
+) -> Result<()> {
     match selection {
-        PageSelection::First => task.transform.push(ExportTransform::Pages {
-            ranges: vec![Pages::FIRST],
+        PageSelection::First => task.transform.push(match pages {
+            Some(ranges) => ExportTransform::Pages { ranges },
+            None => ExportTransform::FirstPage,
         }),
         PageSelection::Merged { gap } => {
+            if let Some(ranges) = pages {
+                task.transform.push(ExportTransform::Pages { ranges });
+            }
             task.transform.push(ExportTransform::Merge { gap });
         }
     }
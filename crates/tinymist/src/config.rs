@@ -1,4 +1,5 @@
 use core::fmt;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, LazyLock, OnceLock};
 
@@ -19,6 +20,7 @@ use tinymist_query::{url_to_path, CompletionFeat, PositionEncoding};
 use tinymist_render::PeriscopeArgs;
 use tinymist_std::error::prelude::*;
 use tinymist_task::ExportTarget;
+use typst::diag::EcoString;
 use typst::foundations::IntoValue;
 use typst::Features;
 use typst_shim::utils::LazyHash;
@@ -26,7 +28,8 @@ use typst_shim::utils::LazyHash;
 use super::*;
 use crate::input::WatchAccessModel;
 use crate::project::{
-    EntryResolver, ExportTask, ImmutDict, PathPattern, ProjectResolutionKind, TaskWhen,
+    EntryResolver, ExportTask, ImmutDict, LintConfig, LintSeverity, LockFile, PathPattern,
+    ProjectResolutionKind, TaskWhen,
 };
 use crate::world::font::FontResolverImpl;
 
@@ -47,6 +50,7 @@ const CONFIG_ITEMS: &[&str] = &[
     "completion",
     "development",
     "exportPdf",
+    "exportPdfScript",
     "exportTarget",
     "fontPaths",
     "formatterMode",
@@ -133,6 +137,9 @@ pub struct Config {
     pub export_target: ExportTarget,
     /// The mode of PDF export.
     pub export_pdf: TaskWhen,
+    /// The typst script deciding whether to export PDF when the mode is
+    /// `script`.
+    pub export_pdf_script: Option<String>,
     /// The output directory for PDF export.
     pub output_path: PathPattern,
 
@@ -343,6 +350,7 @@ impl Config {
         assign_config!(customized_show_document := "customizedShowDocument"?: bool);
         assign_config!(entry_resolver.project_resolution := "projectResolution"?: ProjectResolutionKind);
         assign_config!(export_pdf := "exportPdf"?: TaskWhen);
+        assign_config!(export_pdf_script := "exportPdfScript"?: Option<String>);
         assign_config!(export_target := "exportTarget"?: ExportTarget);
         assign_config!(font_paths := "fontPaths"?: Vec<_>);
         assign_config!(formatter_mode := "formatterMode"?: FormatterMode);
@@ -492,6 +500,13 @@ impl Config {
     pub fn validate(&self) -> Result<()> {
         self.entry_resolver.validate()?;
 
+        if self.export_pdf == TaskWhen::Script && self.export_pdf_script.is_none() {
+            tinymist_l10n::bail!(
+                "tinymist.config.missingExportPdfScript",
+                "exportPdfScript must be set when exportPdf is `\"script\"`",
+            );
+        }
+
         Ok(())
     }
 
@@ -538,6 +553,7 @@ impl Config {
     pub(crate) fn export_task(&self) -> ExportTask {
         ExportTask {
             when: self.export_pdf.clone(),
+            when_script: self.export_pdf_script.clone(),
             output: Some(self.output_path.clone()),
             transform: vec![],
         }
@@ -608,6 +624,20 @@ impl Config {
         CompilePackageArgs::default()
     }
 
+    /// Determines the lint configuration. The rules in the editor settings
+    /// override the ones in the lock file of the project.
+    pub fn lint_config(&self) -> LintConfig {
+        let root = self
+            .entry_resolver
+            .root(self.entry_resolver.resolve_default().as_ref());
+        let mut config = root
+            .and_then(|root| LockFile::read(&root).ok())
+            .and_then(|lock| lock.lint)
+            .unwrap_or_default();
+        config.rules.extend(self.lint.rules.clone());
+        config
+    }
+
     /// Determines the font resolver.
     pub fn fonts(&self) -> Arc<FontResolverImpl> {
         // todo: on font resolving failure, downgrade to a fake font book
@@ -905,6 +935,9 @@ pub struct LintFeat {
     pub enabled: Option<bool>,
     /// When to trigger the lint checks.
     pub when: Option<TaskWhen>,
+    /// The severity of each lint rule, keyed by the rule ID.
+    #[serde(default, deserialize_with = "deserialize_null_default")]
+    pub rules: BTreeMap<EcoString, LintSeverity>,
 }
 
 impl LintFeat {
@@ -1165,6 +1198,7 @@ mod tests {
         test_good_config("customizedShowDocument");
         test_good_config("projectResolution");
         test_good_config("exportPdf");
+        test_good_config("exportPdfScript");
         test_good_config("exportTarget");
         test_good_config("fontPaths");
         test_good_config("formatterMode");
@@ -1194,6 +1228,7 @@ mod tests {
         test_good_config("lint");
         test_good_config("lint.enabled");
         test_good_config("lint.when");
+        test_good_config("lint.rules");
 
         test_good_config("preview");
         test_good_config("preview.browsing");
@@ -1305,6 +1340,27 @@ mod tests {
         assert!(err.contains("absolute path"), "unexpected error: {err}");
     }
 
+    #[test]
+    fn test_reject_missing_export_pdf_script() {
+        let mut config = Config::default();
+        let update = json!({
+            "exportPdf": "script",
+        });
+
+        let err = format!("{}", update_config(&mut config, &update).unwrap_err());
+        assert!(err.contains("exportPdfScript"), "unexpected error: {err}");
+
+        let mut config = Config::default();
+        let update = json!({
+            "exportPdf": "script",
+            "exportPdfScript": "document.title != none",
+        });
+        update_config(&mut config, &update).unwrap();
+        let task = config.export_task();
+        assert_eq!(task.when, TaskWhen::Script);
+        assert_eq!(task.when_script.as_deref(), Some("document.title != none"));
+    }
+
     #[test]
     fn test_entry_by_extra_args() {
         let simple_config = {
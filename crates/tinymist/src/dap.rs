@@ -6,19 +6,31 @@ mod request;
 
 pub use init::*;
 
+use std::sync::Arc;
+
+use parking_lot::Mutex;
 use reflexo_typst::vfs::PathResolution;
 use serde::{Deserialize, Serialize};
 use sync_ls::{invalid_request, LspResult};
-use tinymist_query::PositionEncoding;
+use tinymist_dap::{BreakpointKind, CallFrame, DebugRequest, SessionHandle};
+use tinymist_query::{LspPosition, PositionEncoding};
+use tinymist_std::hash::FxHashMap;
+use typst::diag::EcoString;
 // use sync_lsp::RequestId;
-use typst::syntax::{FileId, Source};
+use typst::foundations::{Styles, Value};
+use typst::syntax::{FileId, Source, Span};
 
-use crate::project::LspCompileSnapshot;
+use crate::project::{LspCompileSnapshot, LspWorld};
 use crate::{ConstDapConfig, ServerState};
 
 #[derive(Default)]
 pub(crate) struct DebugState {
     pub(crate) session: Option<DebugSession>,
+    /// The source breakpoints set by the client, keyed by the path of the
+    /// source. They are resolved again when a session is launched.
+    pub(crate) breakpoints: FxHashMap<String, Vec<dapts::SourceBreakpoint>>,
+    /// The function breakpoints set by the client.
+    pub(crate) functions: Vec<dapts::FunctionBreakpoint>,
 }
 
 impl DebugState {
@@ -27,6 +39,20 @@ impl DebugState {
             .as_ref()
             .ok_or_else(|| invalid_request("No active debug session"))
     }
+
+    pub(crate) fn session_mut(&mut self) -> LspResult<&mut DebugSession> {
+        self.session
+            .as_mut()
+            .ok_or_else(|| invalid_request("No active debug session"))
+    }
+
+    /// Terminates the debug session without waiting for its compilation to
+    /// exit. No more events are sent by the aborted session.
+    pub(crate) fn terminate_session(&mut self) {
+        if let Some(mut session) = self.session.take() {
+            session.debugger.abort();
+        }
+    }
 }
 
 pub(crate) struct DebugSession {
@@ -39,13 +65,51 @@ pub(crate) struct DebugSession {
     /// Whether the debugger should stop on entry.
     stop_on_entry: bool,
 
-    /// The current source file.
+    /// The main source file, where the REPL evaluates expressions if the
+    /// debugger doesn't stop in a source file.
     source: Source,
-    /// The current position.
+    /// The end of the main source file.
     position: usize,
+
+    /// The handle to the running debugger.
+    debugger: SessionHandle,
+    /// The adaptor of the running debugger, which receives the results of
+    /// the REPL evaluations.
+    adaptor: Arc<event::DapAdaptor>,
+    /// The id of the next REPL evaluation.
+    next_evaluation: i64,
+    /// The state where the debugger stops.
+    stopped: Arc<Mutex<Option<StopState>>>,
+    /// The values that can be expanded in the variables view, which are
+    /// referenced by their index plus [`VARIABLES_REF_START`]. They are cleared
+    /// once the execution continues.
+    variables: Vec<Value>,
+}
+
+/// The state where the debugger stops.
+#[derive(Debug, Clone)]
+pub(crate) struct StopState {
+    /// The span of the stopped expression, which is detached for the global
+    /// breakpoints.
+    pub span: Span,
+    /// The kind of the breakpoint.
+    pub kind: BreakpointKind,
+    /// The local bindings in scope, innermost first.
+    pub locals: Vec<(EcoString, Value)>,
+    /// The styles in effect, if they are known.
+    pub styles: Option<Styles>,
+    /// The function calls being evaluated, innermost first.
+    pub frames: Vec<CallFrame>,
 }
-// private _variableHandles = new Handles<"locals" | "globals" |
-// RuntimeVariable>();
+
+/// The variables reference of the locals scope.
+const LOCALS_REF: u64 = 1;
+/// The variables reference of the module scope.
+const MODULE_REF: u64 = 2;
+/// The variables reference of the styles scope.
+const STYLES_REF: u64 = 3;
+/// The first variables reference of the expandable values.
+const VARIABLES_REF_START: u64 = 4;
 
 //     private _valuesInHex = false;
 //     private _useInvalidatedEvent = false;
@@ -54,23 +118,55 @@ const DAP_POS_ENCODING: PositionEncoding = PositionEncoding::Utf16;
 
 impl DebugSession {
     pub fn to_dap_source(&self, id: FileId) -> dapts::Source {
-        use dapts::Source;
-        Source {
-            path: match self.snapshot.world.path_for_id(id).ok() {
-                Some(PathResolution::Resolved(path)) => Some(path.display().to_string()),
-                None | Some(PathResolution::Rootless(..)) => None,
-            },
-            ..Source::default()
+        to_dap_source(&self.snapshot.world, id)
+    }
+
+    pub fn to_dap_position(&self, pos: usize, source: &Source) -> DapPosition {
+        self.config.to_dap_position(pos, source)
+    }
+
+    /// Converts a variable to a DAP variable, allocating a reference for its
+    /// children if it is expandable.
+    pub fn to_dap_variable(&mut self, variable: tinymist_dap::Variable) -> dapts::Variable {
+        let variables_reference = match variable.expand {
+            Some(value) => {
+                self.variables.push(value);
+                self.variables.len() as u64 - 1 + VARIABLES_REF_START
+            }
+            None => 0,
+        };
+
+        dapts::Variable {
+            name: variable.name.into(),
+            value: variable.value.into(),
+            ty: variable.ty.map(Into::into),
+            variables_reference,
+            ..dapts::Variable::default()
         }
     }
+}
 
+/// Converts a file to a DAP source.
+pub(crate) fn to_dap_source(world: &LspWorld, id: FileId) -> dapts::Source {
+    use dapts::Source;
+    Source {
+        path: match world.path_for_id(id).ok() {
+            Some(PathResolution::Resolved(path)) => Some(path.display().to_string()),
+            None | Some(PathResolution::Rootless(..)) => None,
+        },
+        ..Source::default()
+    }
+}
+
+impl ConstDapConfig {
+    /// Converts a byte offset in the source to a DAP position.
     pub fn to_dap_position(&self, pos: usize, source: &Source) -> DapPosition {
         let mut lsp_pos = tinymist_query::to_lsp_position(pos, DAP_POS_ENCODING, source);
 
-        if self.config.lines_start_at1 {
+        if self.lines_start_at1 {
             lsp_pos.line += 1;
         }
-        if self.config.columns_start_at1 {
+        if self.columns_start_at1 {
             lsp_pos.character += 1;
         }
 
@@ -79,6 +175,32 @@ impl DebugSession {
             character: lsp_pos.character as u64,
         }
     }
+
+    /// Converts a DAP line to a zero-based line.
+    pub fn to_typst_line(&self, line: u64) -> usize {
+        let line = if self.lines_start_at1 {
+            line.saturating_sub(1)
+        } else {
+            line
+        };
+
+        line as usize
+    }
+
+    /// Converts a DAP position to a byte offset in the source.
+    pub fn to_typst_position(&self, pos: DapPosition, source: &Source) -> Option<usize> {
+        let character = if self.columns_start_at1 {
+            pos.character.saturating_sub(1)
+        } else {
+            pos.character
+        };
+        let lsp_pos = LspPosition {
+            line: self.to_typst_line(pos.line) as u32,
+            character: character as u32,
+        };
+
+        tinymist_query::to_typst_position(lsp_pos, DAP_POS_ENCODING, source)
+    }
 }
 
 /// Position in a text document expressed as line and character offset.
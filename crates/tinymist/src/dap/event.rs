@@ -1,3 +1,105 @@
+use dapts::StoppedEventReason;
+use sync_ls::TypedLspClient;
+use tinymist_dap::{BreakpointContext, DebugAdaptor};
+use tokio::sync::oneshot;
+use typst::diag::{SourceResult, Warned};
+use typst::layout::PagedDocument;
+use typst::World;
+
 use super::*;
 
-impl ServerState {}
+/// Forwards the events of a running debugger to the client.
+pub(crate) struct DapAdaptor {
+    /// The client to send events to.
+    pub client: TypedLspClient<ServerState>,
+    /// The faked thread id of the session.
+    pub thread_id: u64,
+    /// The state where the debugger stops.
+    pub stopped: Arc<Mutex<Option<StopState>>>,
+    /// The world being debugged, to locate the logged messages.
+    pub world: LspWorld,
+    /// The configuration of the client.
+    pub config: ConstDapConfig,
+    /// The pending REPL evaluations, keyed by their request ids.
+    pub evaluations: Mutex<FxHashMap<i64, oneshot::Sender<SourceResult<Value>>>>,
+}
+
+impl DebugAdaptor for DapAdaptor {
+    fn before_compile(&self) {}
+
+    fn after_compile(&self, result: Warned<SourceResult<PagedDocument>>) {
+        if let Err(errors) = result.output {
+            log::info!("debugged document compiled with {} errors", errors.len());
+        }
+    }
+
+    fn terminate(&self) {
+        *self.stopped.lock() = None;
+        self.client
+            .send_dap_event::<dapts::event::Terminated>(dapts::TerminatedEvent { restart: None });
+    }
+
+    fn stopped(&self, ctx: &BreakpointContext) {
+        *self.stopped.lock() = Some(StopState {
+            span: ctx.span(),
+            kind: ctx.kind,
+            locals: ctx.locals(),
+            styles: ctx.styles(),
+            frames: ctx.frames(),
+        });
+
+        let (reason, description) = match ctx.kind {
+            _ if ctx.stepped => (StoppedEventReason::Step, "Paused after step"),
+            BreakpointKind::BeforeCompile => (StoppedEventReason::Entry, "Paused on entry"),
+            BreakpointKind::AfterCompile => (
+                StoppedEventReason::Pause,
+                "Paused at the end of the document",
+            ),
+            BreakpointKind::Line | BreakpointKind::Expr => {
+                (StoppedEventReason::Breakpoint, "Paused on breakpoint")
+            }
+            BreakpointKind::CallStart => (
+                StoppedEventReason::Breakpoint,
+                "Paused on function breakpoint",
+            ),
+            _ => (StoppedEventReason::Step, "Paused"),
+        };
+
+        self.client
+            .send_dap_event::<dapts::event::Stopped>(dapts::StoppedEvent {
+                all_threads_stopped: Some(true),
+                reason,
+                description: Some(description.into()),
+                thread_id: Some(self.thread_id),
+                hit_breakpoint_ids: None,
+                preserve_focus_hint: Some(false),
+                text: None,
+            });
+    }
+
+    fn respond(&self, id: i64, result: SourceResult<Value>) {
+        if let Some(tx) = self.evaluations.lock().remove(&id) {
+            let _ = tx.send(result);
+        }
+    }
+
+    fn log(&self, ctx: &BreakpointContext, message: EcoString) {
+        let span = ctx.span();
+        let location = span.id().and_then(|fid| {
+            let source = self.world.source(fid).ok()?;
+            let pos = self
+                .config
+                .to_dap_position(source.range(span)?.start, &source);
+            Some((to_dap_source(&self.world, fid), pos))
+        });
+
+        self.client
+            .send_dap_event::<dapts::event::Output>(dapts::OutputEvent {
+                output: format!("{message}\n"),
+                source: location.as_ref().map(|(source, _)| source.clone()),
+                line: location.as_ref().map(|(_, pos)| pos.line),
+                column: location.as_ref().map(|(_, pos)| pos.character),
+                ..dapts::OutputEvent::default()
+            });
+    }
+}
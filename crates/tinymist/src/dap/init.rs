@@ -110,6 +110,8 @@ impl Initializer for SuperInit {
                 },
             ]),
 
+            // make client page the children of large arrays and dictionaries
+            supports_variable_paging: Some(true),
             supports_set_variable: Some(false),
             supports_set_expression: Some(false),
 
@@ -127,6 +129,9 @@ impl Initializer for SuperInit {
             support_terminate_debuggee: Some(true),
             // supports_terminate_request: Some(true),
             supports_function_breakpoints: Some(true),
+            supports_conditional_breakpoints: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
+            supports_log_points: Some(true),
             supports_delayed_stack_trace_loading: Some(true),
 
             ..Default::default()
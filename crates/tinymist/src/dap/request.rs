@@ -1,11 +1,19 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use comemo::Track;
-use dapts::{CompletionItem, ProcessEventStartMethod, StoppedEventReason, ThreadEventReason};
-use reflexo::ImmutPath;
+use dapts::{CompletionItem, ProcessEventStartMethod, ThreadEventReason};
+use reflexo::{path::unix_slash, ImmutPath};
 use reflexo_typst::{EntryReader, TaskInputs};
 use serde::Deserialize;
-use sync_ls::{internal_error, invalid_params, invalid_request, just_ok, SchedulableResponse};
+use sync_ls::{
+    internal_error, invalid_params, invalid_request, just_future, just_ok, LspResult,
+    SchedulableResponse,
+};
+use tinymist_dap::{
+    BreakpointOptions, DebugRequest, FunctionBreakpoint, ResolvedBreakpoint, SessionOptions,
+    StepKind,
+};
 use tinymist_std::error::prelude::*;
 use typst::{
     foundations::Repr,
@@ -15,7 +23,9 @@ use typst::{
 };
 use typst_shim::syntax::LinkedNodeExt;
 
+use super::event::DapAdaptor;
 use super::*;
+use crate::project::LspWorld;
 
 impl ServerState {
     /// Called at the end of the configuration sequence.
@@ -33,7 +43,7 @@ impl ServerState {
         &mut self,
         _args: dapts::DisconnectArguments,
     ) -> SchedulableResponse<()> {
-        let _ = self.debug.session.take();
+        self.debug.terminate_session();
 
         just_ok(())
     }
@@ -42,7 +52,7 @@ impl ServerState {
         &mut self,
         _args: dapts::TerminateArguments,
     ) -> SchedulableResponse<()> {
-        let _ = self.debug.session.take();
+        self.debug.terminate_session();
 
         self.client
             .send_dap_event::<dapts::event::Terminated>(dapts::TerminatedEvent { restart: None });
@@ -59,7 +69,7 @@ impl ServerState {
         }
         let terminate_thread_ok = args.thread_ids.into_iter().flatten().all(|id| id == 1);
         if terminate_thread_ok {
-            let _ = self.debug.session.take();
+            self.debug.terminate_session();
         }
 
         just_ok(())
@@ -118,15 +128,54 @@ impl ServerState {
         let main_eof = main_source.text().len();
         let source = main_source.clone();
 
+        let stop_on_entry = args.stop_on_entry.unwrap_or_default();
+        let mut breakpoints = FxHashMap::default();
+        for (path, source_breakpoints) in &self.debug.breakpoints {
+            let config = &self.config.const_dap_config;
+            if let Ok((source, resolved, _)) =
+                resolve_breakpoints(config, world, path, source_breakpoints)
+            {
+                breakpoints.insert(source.id(), resolved);
+            }
+        }
+
+        // Only one session can run at a time.
+        self.debug.terminate_session();
+
+        let stopped = Arc::new(Mutex::new(None));
+        let thread_id = 1;
+        let adaptor = Arc::new(DapAdaptor {
+            client: self.client.clone(),
+            thread_id,
+            stopped: stopped.clone(),
+            world: world.clone(),
+            config: self.config.const_dap_config.clone(),
+            evaluations: Mutex::default(),
+        });
+        let options = SessionOptions {
+            stop_on_entry,
+            breakpoints,
+            functions: self
+                .debug
+                .functions
+                .iter()
+                .map(function_breakpoint)
+                .collect(),
+        };
+        let debugger = tinymist_dap::start_session(world.clone(), adaptor.clone(), options);
+
         self.debug.session = Some(DebugSession {
             config: self.config.const_dap_config.clone(),
             snapshot,
-            stop_on_entry: args.stop_on_entry.unwrap_or_default(),
-            thread_id: 1,
-            // Since we haven't implemented breakpoints, we can only stop intermediately and
-            // response completions in repl console.
+            stop_on_entry,
+            thread_id,
             source,
             position: main_eof,
+            debugger,
+            adaptor,
+            next_evaluation: 0,
+            stopped,
+            variables: Vec::new(),
         });
 
         self.client
@@ -142,20 +191,6 @@ impl ServerState {
                 thread_id: self.debug.session()?.thread_id,
             });
 
-        // Since we haven't implemented breakpoints, we can only stop intermediately and
-        // response completions in repl console.
-        let _ = self.debug.session()?.stop_on_entry;
-        self.client
-            .send_dap_event::<dapts::event::Stopped>(dapts::StoppedEvent {
-                all_threads_stopped: Some(true),
-                reason: StoppedEventReason::Pause,
-                description: Some("Paused at the end of the document".into()),
-                thread_id: Some(self.debug.session()?.thread_id),
-                hit_breakpoint_ids: None,
-                preserve_focus_hint: Some(false),
-                text: None,
-            });
-
         just_ok(())
     }
 
@@ -178,6 +213,399 @@ struct LaunchDebugArguments {
     stop_on_entry: Option<bool>,
 }
 
+impl ServerState {
+    /// Sets the source breakpoints of a file, replacing the previous ones.
+    /// A breakpoint is unverified if no statement maps to its line.
+    pub(crate) fn set_breakpoints(
+        &mut self,
+        args: dapts::SetBreakpointsArguments,
+    ) -> SchedulableResponse<dapts::SetBreakpointsResponse> {
+        let path = args
+            .source
+            .path
+            .clone()
+            .ok_or_else(|| invalid_params("The source of breakpoints has no path"))?;
+        let source_breakpoints = args.breakpoints.unwrap_or_default();
+
+        let graph;
+        let world = match self.debug.session.as_ref() {
+            Some(session) => &session.snapshot.world,
+            None => {
+                graph = self.project.snapshot().map_err(internal_error)?;
+                &graph.snap.world
+            }
+        };
+        let config = &self.config.const_dap_config;
+        let (source, resolved, breakpoints) =
+            resolve_breakpoints(config, world, &path, &source_breakpoints)
+                .map_err(invalid_params)?;
+
+        if self.debug.session.is_some() {
+            tinymist_dap::set_breakpoints(source.id(), &resolved);
+        }
+        self.debug.breakpoints.insert(path, source_breakpoints);
+
+        let breakpoints = breakpoints
+            .into_iter()
+            .map(|bp| {
+                let Some(bp) = bp else {
+                    return dapts::Breakpoint {
+                        verified: false,
+                        message: Some("No expression is found on this line".into()),
+                        source: Some(args.source.clone()),
+                        ..dapts::Breakpoint::default()
+                    };
+                };
+
+                let start = config.to_dap_position(bp.range.start, &source);
+                let end = config.to_dap_position(bp.range.end, &source);
+                dapts::Breakpoint {
+                    verified: true,
+                    source: Some(args.source.clone()),
+                    line: Some(start.line),
+                    column: Some(start.character),
+                    end_line: Some(end.line),
+                    end_column: Some(end.character),
+                    ..dapts::Breakpoint::default()
+                }
+            })
+            .collect();
+
+        just_ok(dapts::SetBreakpointsResponse { breakpoints })
+    }
+
+    /// Sets the function breakpoints, replacing the previous ones. They are
+    /// verified even if no function is found with the names, since the
+    /// functions can be defined in files that are not yet compiled.
+    pub(crate) fn set_function_breakpoints(
+        &mut self,
+        args: dapts::SetFunctionBreakpointsArguments,
+    ) -> SchedulableResponse<dapts::SetFunctionBreakpointsResponse> {
+        if self.debug.session.is_some() {
+            let functions = args.breakpoints.iter().map(function_breakpoint);
+            tinymist_dap::set_function_breakpoints(&functions.collect::<Vec<_>>());
+        }
+
+        let breakpoints = args
+            .breakpoints
+            .iter()
+            .map(|_| dapts::Breakpoint {
+                verified: true,
+                ..dapts::Breakpoint::default()
+            })
+            .collect();
+        self.debug.functions = args.breakpoints;
+
+        just_ok(dapts::SetFunctionBreakpointsResponse { breakpoints })
+    }
+
+    /// Lists the locations where breakpoints can be set in the lines.
+    pub(crate) fn breakpoint_locations(
+        &mut self,
+        args: dapts::BreakpointLocationsArguments,
+    ) -> SchedulableResponse<dapts::BreakpointLocationsResponse> {
+        let path = args
+            .source
+            .path
+            .ok_or_else(|| invalid_params("The source of breakpoints has no path"))?;
+
+        let graph;
+        let world = match self.debug.session.as_ref() {
+            Some(session) => &session.snapshot.world,
+            None => {
+                graph = self.project.snapshot().map_err(internal_error)?;
+                &graph.snap.world
+            }
+        };
+        let source = world
+            .source_by_path(Path::new(&path))
+            .map_err(invalid_params)?;
+
+        let config = &self.config.const_dap_config;
+        let start = config.to_typst_line(args.line);
+        let end = config.to_typst_line(args.end_line.unwrap_or(args.line));
+        let breakpoints = tinymist_dap::breakpoint_locations(&source, start..end + 1)
+            .into_iter()
+            .map(|bp| {
+                let start = config.to_dap_position(bp.range.start, &source);
+                let end = config.to_dap_position(bp.range.end, &source);
+                dapts::BreakpointLocation {
+                    line: start.line,
+                    column: Some(start.character),
+                    end_line: Some(end.line),
+                    end_column: Some(end.character),
+                }
+            })
+            .collect();
+
+        just_ok(dapts::BreakpointLocationsResponse { breakpoints })
+    }
+
+    /// Resumes the execution until the next breakpoint.
+    pub(crate) fn continue_debug(
+        &mut self,
+        _args: dapts::ContinueArguments,
+    ) -> SchedulableResponse<dapts::ContinueResponse> {
+        self.resume_debug(DebugRequest::Continue)?;
+
+        just_ok(dapts::ContinueResponse {
+            all_threads_continued: Some(true),
+        })
+    }
+
+    /// Steps to the next statement, stepping over the function calls.
+    pub(crate) fn debug_next(&mut self, args: dapts::NextArguments) -> SchedulableResponse<()> {
+        self.step_debug(StepKind::Over, args.granularity)
+    }
+
+    /// Steps to the next statement, stepping into the function calls.
+    pub(crate) fn debug_step_in(
+        &mut self,
+        args: dapts::StepInArguments,
+    ) -> SchedulableResponse<()> {
+        self.step_debug(StepKind::In, args.granularity)
+    }
+
+    /// Steps to the next statement after the current function returns.
+    pub(crate) fn debug_step_out(
+        &mut self,
+        args: dapts::StepOutArguments,
+    ) -> SchedulableResponse<()> {
+        self.step_debug(StepKind::Out, args.granularity)
+    }
+
+    fn step_debug(
+        &mut self,
+        kind: StepKind,
+        granularity: Option<dapts::SteppingGranularity>,
+    ) -> SchedulableResponse<()> {
+        let by_line = matches!(granularity, Some(dapts::SteppingGranularity::Line));
+        self.resume_debug(DebugRequest::Step { kind, by_line })?;
+
+        just_ok(())
+    }
+
+    /// Sends a request resuming the execution to the debugger, after which the
+    /// state of the last stop is no longer available.
+    fn resume_debug(&mut self, request: DebugRequest) -> LspResult<()> {
+        let session = self.debug.session_mut()?;
+        *session.stopped.lock() = None;
+        session.variables.clear();
+        if !session.debugger.send(request) {
+            return Err(invalid_request("The debugger has terminated"));
+        }
+        Ok(())
+    }
+
+    /// Gets the function calls where the debugger stops, innermost first. The
+    /// id of a frame is its index plus one.
+    pub(crate) fn debug_stack_trace(
+        &mut self,
+        _args: dapts::StackTraceArguments,
+    ) -> SchedulableResponse<dapts::StackTraceResponse> {
+        let session = self.debug.session()?;
+        let frames = session
+            .stopped
+            .lock()
+            .as_ref()
+            .map(|stopped| stopped.frames.clone())
+            .unwrap_or_default();
+
+        let stack_frames = frames
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, frame)| {
+                let fid = frame.span.id()?;
+                let source = session.snapshot.world.source(fid).ok()?;
+                let range = source.range(frame.span)?;
+                let start = session.to_dap_position(range.start, &source);
+                let end = session.to_dap_position(range.end, &source);
+
+                let name = match &frame.name {
+                    Some(name) => name.to_string(),
+                    None if frame.is_root => unix_slash(fid.vpath().as_rootless_path()),
+                    None => "<anonymous>".to_owned(),
+                };
+
+                Some(dapts::StackFrame {
+                    id: idx as u64 + 1,
+                    name,
+                    source: Some(session.to_dap_source(fid)),
+                    line: start.line,
+                    column: start.character,
+                    end_line: Some(end.line),
+                    end_column: Some(end.character),
+                    ..dapts::StackFrame::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        just_ok(dapts::StackTraceResponse {
+            total_frames: Some(stack_frames.len() as u64),
+            stack_frames,
+        })
+    }
+
+    /// Lists the scopes of the stack frame where the debugger stops. Only the
+    /// innermost frame has scopes.
+    pub(crate) fn debug_scopes(
+        &mut self,
+        args: dapts::ScopesArguments,
+    ) -> SchedulableResponse<dapts::ScopesResponse> {
+        let session = self.debug.session()?;
+        let stopped = session.stopped.lock();
+        let Some(stopped) = stopped.as_ref().filter(|_| args.frame_id == 1) else {
+            return just_ok(dapts::ScopesResponse { scopes: vec![] });
+        };
+
+        let mut scopes = vec![dapts::Scope {
+            name: "Locals".into(),
+            variables_reference: LOCALS_REF,
+            named_variables: Some(stopped.locals.len() as u64),
+            expensive: false,
+            ..dapts::Scope::default()
+        }];
+        if stopped.span.id().is_some() {
+            scopes.push(dapts::Scope {
+                name: "Module".into(),
+                variables_reference: MODULE_REF,
+                // The module is evaluated again to list its bindings.
+                expensive: true,
+                ..dapts::Scope::default()
+            });
+        }
+        if let Some(styles) = &stopped.styles {
+            scopes.push(dapts::Scope {
+                name: "Styles".into(),
+                variables_reference: STYLES_REF,
+                named_variables: Some(styles.iter().count() as u64),
+                expensive: false,
+                ..dapts::Scope::default()
+            });
+        }
+
+        just_ok(dapts::ScopesResponse { scopes })
+    }
+
+    /// Lists the variables of a scope or the children of an expanded value.
+    pub(crate) fn debug_variables(
+        &mut self,
+        args: dapts::VariablesArguments,
+    ) -> SchedulableResponse<dapts::VariablesResponse> {
+        let session = self.debug.session_mut()?;
+
+        let variables: Vec<tinymist_dap::Variable> = match args.variables_reference {
+            LOCALS_REF => session
+                .stopped
+                .lock()
+                .iter()
+                .flat_map(|stopped| &stopped.locals)
+                .map(|(name, value)| tinymist_dap::variable(name.clone(), value))
+                .collect(),
+            MODULE_REF => {
+                let span = session.stopped.lock().as_ref().map(|stopped| stopped.span);
+                match span.and_then(|span| span.id()) {
+                    Some(fid) => {
+                        let world = &session.snapshot.world;
+                        let source = world.source(fid).map_err(invalid_request)?;
+                        let module = typst_shim::eval::eval_compat(&world, &source)
+                            .map_err(|e| invalid_request(format!("{e:?}")))?;
+                        tinymist_dap::children(&Value::Module(module))
+                    }
+                    None => Vec::new(),
+                }
+            }
+            STYLES_REF => session
+                .stopped
+                .lock()
+                .iter()
+                .flat_map(|stopped| &stopped.styles)
+                .flat_map(tinymist_dap::style_variables)
+                .collect(),
+            reference => {
+                let value = reference
+                    .checked_sub(VARIABLES_REF_START)
+                    .and_then(|idx| session.variables.get(idx as usize))
+                    .ok_or_else(|| invalid_params("The variable is no longer available"))?;
+                tinymist_dap::children(value)
+            }
+        };
+
+        let start = args.start.unwrap_or_default() as usize;
+        let count = args.count.map_or(usize::MAX, |count| count as usize);
+        let variables = variables
+            .into_iter()
+            .skip(start)
+            .take(count)
+            .map(|variable| session.to_dap_variable(variable))
+            .collect();
+
+        just_ok(dapts::VariablesResponse { variables })
+    }
+}
+
+/// The resolved breakpoints of a source, in which `None` stands for an
+/// unverified breakpoint.
+type ResolvedBreakpoints = (
+    Source,
+    Vec<tinymist_dap::SourceBreakpoint>,
+    Vec<Option<ResolvedBreakpoint>>,
+);
+
+/// Resolves the source breakpoints set in a file.
+fn resolve_breakpoints(
+    config: &ConstDapConfig,
+    world: &LspWorld,
+    path: &str,
+    source_breakpoints: &[dapts::SourceBreakpoint],
+) -> typst::diag::FileResult<ResolvedBreakpoints> {
+    let source = world.source_by_path(Path::new(path))?;
+
+    let breakpoints = source_breakpoints
+        .iter()
+        .map(|bp| {
+            let line = config.to_typst_line(bp.line);
+            let offset = bp.column.and_then(|character| {
+                let pos = DapPosition {
+                    line: bp.line,
+                    character,
+                };
+                config.to_typst_position(pos, &source)
+            });
+            tinymist_dap::resolve_breakpoint(&source, line, offset)
+        })
+        .collect::<Vec<_>>();
+    let resolved = breakpoints
+        .iter()
+        .zip(source_breakpoints)
+        .filter_map(|(resolved, bp)| {
+            Some(tinymist_dap::SourceBreakpoint {
+                resolved: resolved.clone()?,
+                options: BreakpointOptions {
+                    condition: bp.condition.clone(),
+                    hit_condition: bp.hit_condition.clone(),
+                    log_message: bp.log_message.clone(),
+                },
+            })
+        })
+        .collect();
+
+    Ok((source, resolved, breakpoints))
+}
+
+/// Converts a function breakpoint set by the client. The log messages are not
+/// supported on function breakpoints by the protocol.
+fn function_breakpoint(bp: &dapts::FunctionBreakpoint) -> FunctionBreakpoint {
+    FunctionBreakpoint {
+        name: bp.name.as_str().into(),
+        options: BreakpointOptions {
+            condition: bp.condition.clone(),
+            hit_condition: bp.hit_condition.clone(),
+            log_message: None,
+        },
+    }
+}
+
 impl ServerState {
     pub(crate) fn debug_threads(
         &mut self,
@@ -193,37 +621,68 @@ impl ServerState {
 }
 
 impl ServerState {
+    /// Evaluates an expression in the scope of the selected stack frame.
+    ///
+    /// The innermost frame evaluates in the scopes where the debugger stops,
+    /// so the local bindings are visible. The outer frames only evaluate in
+    /// the module scope of their source files, since their local bindings are
+    /// not kept. If the debugger doesn't stop in a source file, the
+    /// expression is evaluated at the end of the main file.
     pub(crate) fn evaluate_repl(
         &mut self,
         args: dapts::EvaluateArguments,
     ) -> SchedulableResponse<dapts::EvaluateResponse> {
-        let session = self.debug.session()?;
-        let world = &session.snapshot.world;
-        let library = &world.library;
-
-        let root = session.source.root();
-        let span = LinkedNode::new(root)
-            .leaf_at_compat(session.position)
-            .map(|node| node.span())
-            .unwrap_or_else(Span::detached);
-
-        let source = typst_shim::eval::eval_compat(&world, &session.source)
-            .map_err(|e| invalid_params(format!("{e:?}")))?;
-
-        let val = typst_shim::eval::eval_string(
-            &typst::ROUTINES,
-            (world as &dyn World).track(),
-            &args.expression,
-            span,
-            EvalMode::Code,
-            source.scope().clone(),
-        )
-        .map_err(|e| invalid_params(format!("{e:?}")))?;
+        let session = self.debug.session_mut()?;
+        let frame = args
+            .frame_id
+            .map_or(0, |id| (id as usize).saturating_sub(1));
+        let stopped = session.stopped.lock().clone();
+        let stopped = stopped.filter(|stopped| !stopped.span.is_detached());
+
+        let Some(stopped) = stopped else {
+            let source = session.source.clone();
+            let root = source.root();
+            let span = LinkedNode::new(root)
+                .leaf_at_compat(session.position)
+                .map(|node| node.span())
+                .unwrap_or_else(Span::detached);
+            let val = eval_in_module(&session.snapshot.world, &source, span, &args.expression)?;
+            return just_ok(evaluate_response(&val));
+        };
+
+        if frame > 0 {
+            let span = stopped
+                .frames
+                .get(frame)
+                .map(|frame| frame.span)
+                .ok_or_else(|| invalid_params("The stack frame is not found"))?;
+            let world = &session.snapshot.world;
+            let source = span
+                .id()
+                .and_then(|fid| world.source(fid).ok())
+                .ok_or_else(|| invalid_params("The stack frame has no source"))?;
+            let val = eval_in_module(world, &source, span, &args.expression)?;
+            return just_ok(evaluate_response(&val));
+        }
 
-        just_ok(dapts::EvaluateResponse {
-            result: format!("{}", val.repr()),
-            ty: Some(format!("{}", val.ty().repr())),
-            ..dapts::EvaluateResponse::default()
+        let id = session.next_evaluation;
+        session.next_evaluation += 1;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        session.adaptor.evaluations.lock().insert(id, tx);
+        if !session
+            .debugger
+            .send(DebugRequest::Evaluate(id, args.expression))
+        {
+            session.adaptor.evaluations.lock().remove(&id);
+            return Err(invalid_request("The debug session has ended"));
+        }
+
+        just_future(async move {
+            let val = rx
+                .await
+                .map_err(|_| invalid_request("The debug session has ended"))?
+                .map_err(|e| invalid_params(format!("{e:?}")))?;
+            Ok(evaluate_response(&val))
         })
     }
 
@@ -241,3 +700,27 @@ impl ServerState {
         just_ok(dapts::CompletionsResponse { targets: vec![] })
     }
 }
+
+/// Evaluates an expression in the module scope of a source file.
+fn eval_in_module(world: &LspWorld, source: &Source, span: Span, expr: &str) -> LspResult<Value> {
+    let module = typst_shim::eval::eval_compat(world, source)
+        .map_err(|e| invalid_params(format!("{e:?}")))?;
+
+    typst_shim::eval::eval_string(
+        &typst::ROUTINES,
+        (world as &dyn World).track(),
+        expr,
+        span,
+        EvalMode::Code,
+        module.scope().clone(),
+    )
+    .map_err(|e| invalid_params(format!("{e:?}")))
+}
+
+fn evaluate_response(val: &Value) -> dapts::EvaluateResponse {
+    dapts::EvaluateResponse {
+        result: format!("{}", val.repr()),
+        ty: Some(format!("{}", val.ty().repr())),
+        ..dapts::EvaluateResponse::default()
+    }
+}
@@ -295,7 +295,7 @@ impl ServerState {
                 DocumentColor(req) => snap.run_semantic(req, R::DocumentColor),
                 DocumentLink(req) => snap.run_semantic(req, R::DocumentLink),
                 CodeAction(req) => snap.run_semantic(req, R::CodeAction),
-                CodeLens(req) => snap.run_semantic(req, R::CodeLens),
+                CodeLens(req) => snap.run_stateful(req, R::CodeLens),
                 Completion(req) => snap.run_stateful(req, R::Completion),
                 SignatureHelp(req) => snap.run_semantic(req, R::SignatureHelp),
                 Rename(req) => snap.run_stateful(req, R::Rename),
@@ -304,6 +304,7 @@ impl ServerState {
                 Symbol(req) => snap.run_semantic(req, R::Symbol),
                 WorkspaceLabel(req) => snap.run_semantic(req, R::WorkspaceLabel),
                 DocumentMetrics(req) => snap.run_stateful(req, R::DocumentMetrics),
+                TestDiscovery(req) => snap.run_semantic(req, R::TestDiscovery),
                 _ => unreachable!(),
             };
 
@@ -264,6 +264,7 @@ impl ServerState {
         let provider = provider
             .with_command("tinymist.doInitTemplate", State::init_template)
             .with_command("tinymist.doGetTemplateEntry", State::get_template_entry)
+            .with_command("tinymist.runTests", State::run_tests)
             .with_resource("/package/by-namespace", State::resource_package_by_ns)
             .with_resource("/dir/package", State::resource_package_dirs)
             .with_resource("/dir/package/local", State::resource_local_package_dir);
@@ -325,6 +326,7 @@ impl ServerState {
             .with_command_("tinymist.exportHtml", State::export_html)
             .with_command_("tinymist.exportMarkdown", State::export_markdown)
             .with_command_("tinymist.exportTeX", State::export_tex)
+            .with_command_("tinymist.exportEpub", State::export_epub)
             .with_command_("tinymist.exportQuery", State::export_query)
             .with_command("tinymist.exportAnsiHighlight", State::export_ansi_hl)
             .with_command("tinymist.exportAst", State::export_ast)
@@ -335,6 +337,7 @@ impl ServerState {
             .with_command_("tinymist.getDocumentMetrics", State::get_document_metrics)
             .with_command_("tinymist.getWorkspaceLabels", State::get_workspace_labels)
             .with_command_("tinymist.getServerInfo", State::get_server_info)
+            .with_command_("tinymist.discoverTests", State::discover_tests)
             // resources
             .with_resource("/fonts", State::resource_fonts)
             .with_resource("/symbols", State::resource_symbols)
@@ -370,6 +373,16 @@ impl ServerState {
             .with_request::<request::TerminateThreads>(Self::terminate_debug_thread)
             .with_request::<request::Attach>(Self::attach_debug)
             .with_request::<request::Launch>(Self::launch_debug)
+            .with_request::<request::SetBreakpoints>(Self::set_breakpoints)
+            .with_request::<request::SetFunctionBreakpoints>(Self::set_function_breakpoints)
+            .with_request::<request::BreakpointLocations>(Self::breakpoint_locations)
+            .with_request::<request::Continue>(Self::continue_debug)
+            .with_request::<request::Next>(Self::debug_next)
+            .with_request::<request::StepIn>(Self::debug_step_in)
+            .with_request::<request::StepOut>(Self::debug_step_out)
+            .with_request::<request::StackTrace>(Self::debug_stack_trace)
+            .with_request::<request::Scopes>(Self::debug_scopes)
+            .with_request::<request::Variables>(Self::debug_variables)
             .with_request::<request::Evaluate>(Self::evaluate_repl)
             .with_request::<request::Completions>(Self::complete_repl)
             .with_request::<request::Threads>(Self::debug_threads)
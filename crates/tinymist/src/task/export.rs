@@ -14,11 +14,16 @@ use tinymist_query::OnExportRequest;
 use tinymist_std::error::prelude::*;
 use tinymist_std::fs::paths::write_atomic;
 use tinymist_std::path::PathClean;
-use tinymist_std::typst::TypstDocument;
-use tinymist_task::{get_page_selection, ExportMarkdownTask, ExportTarget, PdfExport, TextExport};
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+use tinymist_task::{
+    get_page_numbers, get_page_selection, has_page_template, page_output_path, select_pages,
+    ExportManifest, ExportMarkdownTask, ExportScript, ExportTarget, ExportTimings, ExportTransform,
+    PdfExport, ScriptOutput, TextExport,
+};
 use tokio::sync::mpsc;
 use typlite::{Format, Typlite};
 use typst::foundations::IntoValue;
+use typst::layout::Page;
 use typst::visualize::Color;
 
 use futures::Future;
@@ -29,9 +34,10 @@ use super::SyncTaskFactory;
 use crate::lsp::query::QueryFuture;
 use crate::project::{
     update_lock, ApplyProjectTask, CompiledArtifact, DevEvent, DevExportEvent, EntryReader,
-    ExportHtmlTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask as ProjectExportTask,
-    ExportTeXTask, ExportTextTask, LspCompiledArtifact, LspComputeGraph, ProjectClient,
-    ProjectTask, QueryTask, TaskWhen, PROJECT_ROUTE_USER_ACTION_PRIORITY,
+    ExportEpubTask, ExportHtmlTask, ExportPdfTask, ExportPngTask, ExportSvgTask,
+    ExportTask as ProjectExportTask, ExportTeXTask, ExportTextTask, LspCompiledArtifact,
+    LspComputeGraph, ProjectClient, ProjectTask, QueryTask, TaskWhen,
+    PROJECT_ROUTE_USER_ACTION_PRIORITY,
 };
 use crate::world::TaskInputs;
 use crate::ServerState;
@@ -71,7 +77,7 @@ impl ServerState {
             let is_html = matches!(task, ProjectTask::ExportHtml { .. });
             // todo: we may get some file missing errors here
             let artifact = CompiledArtifact::from_graph(snap.clone(), is_html);
-            let res = ExportTask::do_export(task, artifact, lock_dir)
+            let res = ExportTask::do_export(task, artifact, lock_dir, true)
                 .await
                 .map_err(internal_error)?;
             if let Some(update_dep) = update_dep {
@@ -160,50 +166,71 @@ impl ExportTask {
         let s = artifact.snap.signal;
 
         let when = config.task.when().unwrap_or(&TaskWhen::Never);
-        let need_export = match when {
-            TaskWhen::Never => false,
-            TaskWhen::Script => s.by_entry_update,
-            TaskWhen::OnType => s.by_mem_events,
-            TaskWhen::OnSave => s.by_fs_events,
-            TaskWhen::OnDocumentHasTitle => s.by_fs_events && doc.info().title.is_some(),
-        };
+        // The `when` script is evaluated on the export task rather than the
+        // compile handler, and at most once per revision.
+        let by_script = when == &TaskWhen::Script;
+        let need_export = config.task.as_export().and_then(|task| match doc {
+            TypstDocument::Paged(doc) => {
+                ExportTimings::needs_run(&artifact.snap, Some(&task.when), Some(doc.as_ref()))
+            }
+            TypstDocument::Html(doc) => {
+                ExportTimings::needs_run(&artifact.snap, Some(&task.when), Some(doc.as_ref()))
+            }
+        });
+        let need_export = need_export.unwrap_or_default();
 
-        let export_hook = config.development.then_some({
+        let export_hook = config.development.then(|| {
             let client = client.clone();
 
-            let event = DevEvent::Export(DevExportEvent {
-                id: artifact.id().to_string(),
-                when: when.clone(),
-                need_export,
-                signal: s,
-                path: config
-                    .task
-                    .as_export()
-                    .and_then(|t| t.output.clone())
-                    .map(|p| p.to_string()),
-            });
-
-            move || client.dev_event(event)
+            let id = artifact.id().to_string();
+            let when = when.clone();
+            let path = config
+                .task
+                .as_export()
+                .and_then(|t| t.output.clone())
+                .map(|p| p.to_string());
+
+            move |need_export: bool| {
+                client.dev_event(DevEvent::Export(DevExportEvent {
+                    id,
+                    when,
+                    need_export,
+                    signal: s,
+                    path,
+                }))
+            }
         });
 
         if !need_export {
             if let Some(f) = export_hook {
-                f()
+                f(false)
             }
             return None;
         }
-        log::info!(
-            "ExportTask(when={when:?}): export for {} with signal: {s:?}",
-            artifact.id()
-        );
         let rev = artifact.world().revision().get();
         let fut = self.export_folder.spawn(rev, || {
             let task = config.task.clone();
             let artifact = artifact.clone();
+            let when = when.clone();
             Box::pin(async move {
-                log_err(Self::do_export(task, artifact, None).await);
+                let need_export = if by_script {
+                    let (task, artifact) = (task.clone(), artifact.clone());
+                    let need_export =
+                        FutureFolder::compute(move |_| Self::needs_run_by_script(&task, &artifact));
+                    log_err(need_export.await).unwrap_or_default()
+                } else {
+                    true
+                };
+
+                if need_export {
+                    log::info!(
+                        "ExportTask(when={when:?}): export for {} with signal: {s:?}",
+                        artifact.id()
+                    );
+                    log_err(Self::do_export(task, artifact, None, true).await);
+                }
                 if let Some(f) = export_hook {
-                    f()
+                    f(need_export)
                 }
                 Some(())
             })
@@ -214,6 +241,24 @@ impl ExportTask {
         Some(())
     }
 
+    /// Checks whether the task is needed by evaluating its `when` script, which
+    /// may take a while.
+    fn needs_run_by_script(task: &ProjectTask, artifact: &LspCompiledArtifact) -> bool {
+        let (Some(task), Some(doc)) = (task.as_export(), artifact.doc.as_ref()) else {
+            return false;
+        };
+
+        let need_export = match doc {
+            TypstDocument::Paged(doc) => {
+                ExportTimings::needs_run_task(&artifact.snap, task, Some(doc.as_ref()))
+            }
+            TypstDocument::Html(doc) => {
+                ExportTimings::needs_run_task(&artifact.snap, task, Some(doc.as_ref()))
+            }
+        };
+        need_export.unwrap_or_default()
+    }
+
     fn signal_count_word(
         &self,
         artifact: &LspCompiledArtifact,
@@ -248,12 +293,16 @@ impl ExportTask {
     }
 
     /// Exports a document.
+    ///
+    /// If `manifest` is set, the export manifest in the target directory of the
+    /// project is used to skip the unchanged exports and to clean up the outputs
+    /// of the removed pages.
     pub async fn do_export(
         task: ProjectTask,
         artifact: LspCompiledArtifact,
         lock_dir: Option<ImmutPath>,
+        manifest: bool,
     ) -> Result<Option<PathBuf>> {
-        use reflexo_vec2svg::DefaultExportFeature;
         use ProjectTask::*;
 
         let CompiledArtifact { graph, doc, .. } = artifact;
@@ -312,172 +361,374 @@ impl ExportTask {
         // Prepare the document.
         let doc = doc.context("cannot export with compilation errors")?;
 
+        // Skip the export if the outputs are produced from the same document.
+        let manifest_path = manifest
+            .then(|| graph.world().entry_state().root())
+            .flatten()
+            .map(|root| ExportManifest::path(&root, &write_to));
+        let old_manifest = manifest_path
+            .as_deref()
+            .map(ExportManifest::read)
+            .unwrap_or_default();
+        let task_hash = ExportManifest::hash(&task);
+        let doc_hash = match task {
+            // The typlite exports and the html export don't depend on the paged document.
+            ExportMd(..) | ExportTeX(..) | ExportEpub(..) | ExportHtml(..) | Preview(..) => None,
+            _ => ExportManifest::hash_document(&doc),
+        };
+        let per_page =
+            matches!(task, ExportPng(..) | ExportSvg(..)) && has_page_template(&write_to);
+        if old_manifest.is_fresh(&task_hash, doc_hash.as_deref()) {
+            log::debug!("ExportTask({export_id}): skipped unchanged export");
+            if !per_page {
+                return Ok(Some(write_to));
+            }
+
+            // Reports the output of the first selected page, since the outputs in
+            // the manifest are sorted by paths, e.g., `page-10` before `page-2`.
+            let total = Self::paged_doc(&graph, &doc)?.pages.len();
+            let number = get_page_numbers(config, total).first().copied();
+            return Ok(number.map(|number| page_output_path(&write_to, number, total)));
+        }
+
         // Prepare data.
         let kind2 = task.clone();
-        let data = FutureFolder::compute(move |_| -> Result<Bytes> {
-            let doc = &doc;
-
-            // static BLANK: Lazy<Page> = Lazy::new(Page::default);
-            // todo: check warnings and errors inside
-            let html_once = OnceLock::new();
-            let html_doc = || -> Result<_> {
-                html_once
-                    .get_or_init(|| -> Result<_> {
-                        Ok(match &doc {
-                            TypstDocument::Html(html_doc) => html_doc.clone(),
-                            TypstDocument::Paged(_) => extra_compile_for_export(graph.world())?,
-                        })
-                    })
-                    .as_ref()
-                    .map_err(|e| e.clone())
-            };
-            let page_once = OnceLock::new();
-            let paged_doc = || {
-                page_once
-                    .get_or_init(|| -> Result<_> {
-                        Ok(match &doc {
-                            TypstDocument::Paged(paged_doc) => paged_doc.clone(),
-                            TypstDocument::Html(_) => extra_compile_for_export(graph.world())?,
-                        })
-                    })
-                    .as_ref()
-                    .map_err(|e| e.clone())
-            };
-            let first_page = || {
-                paged_doc()?
-                    .pages
-                    .first()
-                    .context("no first page to export")
-            };
-            Ok(match kind2 {
-                Preview(..) => Bytes::new([]),
-                // todo: more pdf flags
-                ExportPdf(config) => PdfExport::run(&graph, paged_doc()?, &config)?,
-                Query(QueryTask {
-                    export: _,
-                    output_extension: _,
-                    format,
-                    selector,
-                    field,
-                    one,
-                }) => {
-                    let pretty = false;
-                    let elements = reflexo_typst::query::retrieve(&graph.world(), &selector, doc)
-                        .map_err(|e| anyhow::anyhow!("failed to retrieve: {e}"))?;
-                    if one && elements.len() != 1 {
-                        bail!("expected exactly one element, found {}", elements.len());
-                    }
+        let export = config.clone();
+        let output_path = write_to.clone();
+        let mut manifest = ExportManifest::new(task_hash.clone(), doc_hash.clone());
+        let files = FutureFolder::compute(move |_| -> Result<_> {
+            let mut files = vec![];
+            if !per_page {
+                let data = Self::export_bytes(&graph, &kind2, &doc, export_id)?;
+                let data = Self::transform(&graph, &kind2, &doc, data, export_id, false)?;
+                let hash = ExportManifest::hash(&data);
+                let fresh = old_manifest.is_output_fresh(&task_hash, &write_to, &hash);
+                files.push((write_to, hash, (!fresh).then_some(data)));
+                return Ok((old_manifest, files));
+            }
 
-                    let mapped: Vec<_> = elements
-                        .into_iter()
-                        .filter_map(|c| match &field {
-                            Some(field) => c.get_by_name(field).ok(),
-                            _ => Some(c.into_value()),
-                        })
-                        .collect();
-
-                    if one {
-                        let Some(value) = mapped.first() else {
-                            bail!("no such field found for element");
-                        };
-                        serialize(value, &format, pretty).map(Bytes::from_string)?
-                    } else {
-                        serialize(&mapped, &format, pretty).map(Bytes::from_string)?
+            // Export one file per selected page, skipping the unchanged pages.
+            let paged_doc = Self::paged_doc(&graph, &doc)?;
+            let total = paged_doc.pages.len();
+            let numbers = get_page_numbers(&export, total);
+            if numbers.is_empty() {
+                bail!("no pages to export");
+            }
+
+            // The transform scripts may read the whole document.
+            let scripted = export
+                .transform
+                .iter()
+                .any(|t| matches!(t, ExportTransform::Script { script: Some(..) }));
+            for number in numbers {
+                let page = &paged_doc.pages[number - 1];
+                let path = page_output_path(&write_to, number, total);
+                let hash = if scripted {
+                    ExportManifest::hash(&(&doc_hash, number))
+                } else {
+                    ExportManifest::hash(&(page, total))
+                };
+                if old_manifest.is_output_fresh(&task_hash, &path, &hash) {
+                    files.push((path, hash, None));
+                    continue;
+                }
+
+                let data = Self::export_page(&kind2, page)?;
+                let data = Self::transform(&graph, &kind2, &doc, data, export_id, true)?;
+                files.push((path, hash, Some(data)));
+            }
+            Ok((old_manifest, files))
+        })
+        .await??;
+
+        let write_to = files.1.first().map(|(path, ..)| path.clone());
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let (old_manifest, files) = files;
+            for (path, hash, data) in files {
+                if let Some(data) = data {
+                    write_atomic(&path, data)?;
+                }
+                manifest.add_output(&path, hash);
+            }
+
+            // Clean up the outputs of the removed pages.
+            for path in old_manifest.stale_outputs(&manifest, &output_path) {
+                if let Err(err) = std::fs::remove_file(path) {
+                    if err.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("failed to remove stale output {path:?}: {err}");
                     }
                 }
-                ExportHtml(ExportHtmlTask { export: _ }) => Bytes::from_string(
-                    typst_html::html(html_doc()?)
-                        .map_err(|e| format!("export error: {e:?}"))
-                        .context_ut("failed to export to html")?,
-                ),
-                ExportSvgHtml(ExportHtmlTask { export: _ }) => Bytes::from_string(
-                    reflexo_vec2svg::render_svg_html::<DefaultExportFeature>(paged_doc()?),
-                ),
-                ExportText(ExportTextTask { export: _ }) => {
-                    Bytes::from_string(TextExport::run_on_doc(doc)?)
+            }
+            if let Some(manifest_path) = manifest_path {
+                manifest
+                    .write(&manifest_path)
+                    .log_error("ExportTask: write manifest error");
+            }
+            Ok(())
+        })
+        .await
+        .context_ut("failed to export")??;
+
+        log::debug!("ExportTask({export_id}): export complete");
+        Ok(write_to)
+    }
+
+    /// Runs the transform scripts over the exported artifact.
+    fn transform(
+        graph: &LspComputeGraph,
+        task: &ProjectTask,
+        doc: &TypstDocument,
+        mut data: Bytes,
+        export_id: usize,
+        per_page: bool,
+    ) -> Result<Bytes> {
+        let scripts = task.as_export().into_iter().flat_map(|e| &e.transform);
+        for script in scripts {
+            let ExportTransform::Script {
+                script: Some(script),
+            } = script
+            else {
+                continue;
+            };
+
+            let extension = task.extension();
+            data = match ExportScript::run(graph.world(), doc, script, extension, data)? {
+                ScriptOutput::Artifact(data) => data,
+                ScriptOutput::Pages(..) if per_page => {
+                    bail!(
+                        "cannot select pages by transform scripts when exporting one file per page"
+                    )
                 }
-                ExportMd(ExportMarkdownTask {
-                    processor,
-                    assets_path,
-                    export: _,
-                }) => {
-                    let conv = Typlite::new(Arc::new(graph.world().clone()))
-                        .with_format(Format::Md)
-                        .with_feature(typlite::TypliteFeat {
-                            processor,
-                            assets_path,
-                            ..Default::default()
-                        })
-                        .convert()
-                        .map_err(|e| anyhow::anyhow!("failed to convert to markdown: {e}"))?;
-
-                    Bytes::from_string(conv)
+                ScriptOutput::Pages(pages) => {
+                    let paged_doc = Self::paged_doc(graph, doc)?;
+                    let paged_doc = ExportScript::filter_pages(&paged_doc, &pages);
+                    let doc = TypstDocument::Paged(Arc::new(paged_doc));
+                    // The pages selected by the script replace the page ranges.
+                    let mut task = task.clone();
+                    if let Some(export) = task.as_export_mut() {
+                        export
+                            .transform
+                            .retain(|t| !matches!(t, ExportTransform::Pages { .. }));
+                    }
+                    Self::export_bytes(graph, &task, &doc, export_id)?
                 }
-                // todo: duplicated code with ExportMd
-                ExportTeX(ExportTeXTask {
-                    processor,
-                    assets_path,
-                    export: _,
-                }) => {
-                    log::info!("ExportTask({export_id}): exporting to TeX with processor {processor:?} and assets path {assets_path:?}");
-                    let conv = Typlite::new(Arc::new(graph.world().clone()))
-                        .with_format(Format::LaTeX)
-                        .with_feature(typlite::TypliteFeat {
-                            processor,
-                            assets_path,
-                            ..Default::default()
-                        })
-                        .convert()
-                        .map_err(|e| anyhow::anyhow!("failed to convert to latex: {e}"))?;
-
-                    Bytes::from_string(conv)
+            };
+        }
+
+        Ok(data)
+    }
+
+    /// Exports a page to bytes artifact, which is used when exporting one file
+    /// per page.
+    fn export_page(task: &ProjectTask, page: &Page) -> Result<Bytes> {
+        Ok(match task {
+            ProjectTask::ExportSvg(..) => Bytes::from_string(typst_svg::svg(page)),
+            ProjectTask::ExportPng(ExportPngTask { ppi, .. }) => {
+                let ppi = ppi.to_f32();
+                if ppi <= 1e-6 {
+                    bail!("invalid ppi: {ppi}");
                 }
-                ExportSvg(ExportSvgTask { export }) => {
-                    let (is_first, merged_gap) = get_page_selection(&export)?;
 
-                    Bytes::from_string(if is_first {
-                        typst_svg::svg(first_page()?)
-                    } else {
-                        typst_svg::svg_merged(paged_doc()?, merged_gap)
+                let pixmap = typst_render::render(page, ppi / 72.);
+                Bytes::new(
+                    pixmap
+                        .encode_png()
+                        .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?,
+                )
+            }
+            _ => bail!("cannot export one file per page for {task:?}"),
+        })
+    }
+
+    /// Gets the paged document, which is compiled again if the document is an
+    /// HTML document.
+    fn paged_doc(graph: &LspComputeGraph, doc: &TypstDocument) -> Result<Arc<TypstPagedDocument>> {
+        Ok(match doc {
+            TypstDocument::Paged(paged_doc) => paged_doc.clone(),
+            TypstDocument::Html(_) => extra_compile_for_export(graph.world())?,
+        })
+    }
+
+    /// Exports the document to bytes artifact.
+    fn export_bytes(
+        graph: &LspComputeGraph,
+        task: &ProjectTask,
+        doc: &TypstDocument,
+        export_id: usize,
+    ) -> Result<Bytes> {
+        use reflexo_vec2svg::DefaultExportFeature;
+        use ProjectTask::*;
+
+        // static BLANK: Lazy<Page> = Lazy::new(Page::default);
+        // todo: check warnings and errors inside
+        let html_once = OnceLock::new();
+        let html_doc = || -> Result<_> {
+            html_once
+                .get_or_init(|| -> Result<_> {
+                    Ok(match &doc {
+                        TypstDocument::Html(html_doc) => html_doc.clone(),
+                        TypstDocument::Paged(_) => extra_compile_for_export(graph.world())?,
                     })
+                })
+                .as_ref()
+                .map_err(|e| e.clone())
+        };
+        let page_once = OnceLock::new();
+        let paged_doc = || {
+            page_once
+                .get_or_init(|| -> Result<_> {
+                    Ok(match &doc {
+                        TypstDocument::Paged(paged_doc) => paged_doc.clone(),
+                        TypstDocument::Html(_) => extra_compile_for_export(graph.world())?,
+                    })
+                })
+                .as_ref()
+                .map_err(|e| e.clone())
+        };
+        let first_page = || {
+            paged_doc()?
+                .pages
+                .first()
+                .context("no first page to export")
+        };
+        Ok(match task.clone() {
+            Preview(..) => Bytes::new([]),
+            // todo: more pdf flags
+            ExportPdf(config) => PdfExport::run(graph, paged_doc()?, &config)?,
+            Query(QueryTask {
+                export: _,
+                output_extension: _,
+                format,
+                selector,
+                field,
+                one,
+            }) => {
+                let pretty = false;
+                let elements = reflexo_typst::query::retrieve(&graph.world(), &selector, doc)
+                    .map_err(|e| anyhow::anyhow!("failed to retrieve: {e}"))?;
+                if one && elements.len() != 1 {
+                    bail!("expected exactly one element, found {}", elements.len());
                 }
-                ExportPng(ExportPngTask { export, ppi, fill }) => {
-                    let ppi = ppi.to_f32();
-                    if ppi <= 1e-6 {
-                        bail!("invalid ppi: {ppi}");
-                    }
 
-                    let fill = if let Some(fill) = fill {
-                        parse_color(fill).map_err(|err| anyhow::anyhow!("invalid fill ({err})"))?
-                    } else {
-                        Color::WHITE
+                let mapped: Vec<_> = elements
+                    .into_iter()
+                    .filter_map(|c| match &field {
+                        Some(field) => c.get_by_name(field).ok(),
+                        _ => Some(c.into_value()),
+                    })
+                    .collect();
+
+                if one {
+                    let Some(value) = mapped.first() else {
+                        bail!("no such field found for element");
                     };
+                    serialize(value, &format, pretty).map(Bytes::from_string)?
+                } else {
+                    serialize(&mapped, &format, pretty).map(Bytes::from_string)?
+                }
+            }
+            ExportHtml(ExportHtmlTask { export: _ }) => Bytes::from_string(
+                typst_html::html(html_doc()?)
+                    .map_err(|e| format!("export error: {e:?}"))
+                    .context_ut("failed to export to html")?,
+            ),
+            ExportSvgHtml(ExportHtmlTask { export: _ }) => Bytes::from_string(
+                reflexo_vec2svg::render_svg_html::<DefaultExportFeature>(paged_doc()?),
+            ),
+            ExportText(ExportTextTask { export: _ }) => {
+                Bytes::from_string(TextExport::run_on_doc(doc)?)
+            }
+            ExportMd(ExportMarkdownTask {
+                processor,
+                assets_path,
+                export: _,
+            }) => {
+                let conv = Typlite::new(Arc::new(graph.world().clone()))
+                    .with_format(Format::Md)
+                    .with_feature(typlite::TypliteFeat {
+                        processor,
+                        assets_path,
+                        ..Default::default()
+                    })
+                    .convert()
+                    .map_err(|e| anyhow::anyhow!("failed to convert to markdown: {e}"))?;
 
-                    let (is_first, merged_gap) = get_page_selection(&export)?;
+                Bytes::from_string(conv)
+            }
+            // todo: duplicated code with ExportMd
+            ExportTeX(ExportTeXTask {
+                processor,
+                assets_path,
+                export: _,
+            }) => {
+                log::info!("ExportTask({export_id}): exporting to TeX with processor {processor:?} and assets path {assets_path:?}");
+                let conv = Typlite::new(Arc::new(graph.world().clone()))
+                    .with_format(Format::LaTeX)
+                    .with_feature(typlite::TypliteFeat {
+                        processor,
+                        assets_path,
+                        ..Default::default()
+                    })
+                    .convert()
+                    .map_err(|e| anyhow::anyhow!("failed to convert to latex: {e}"))?;
 
-                    let pixmap = if is_first {
-                        typst_render::render(first_page()?, ppi / 72.)
-                    } else {
-                        typst_render::render_merged(paged_doc()?, ppi / 72., merged_gap, Some(fill))
-                    };
+                Bytes::from_string(conv)
+            }
+            ExportEpub(ExportEpubTask {
+                processor,
+                theme,
+                export: _,
+            }) => {
+                log::info!("ExportTask({export_id}): exporting to EPUB with processor {processor:?} and theme {theme:?}");
+                let conv = Typlite::new(Arc::new(graph.world().clone()))
+                    .with_format(Format::Epub)
+                    .with_feature(typlite::TypliteFeat {
+                        processor,
+                        theme,
+                        ..Default::default()
+                    })
+                    .to_epub()
+                    .map_err(|e| anyhow::anyhow!("failed to convert to epub: {e}"))?;
 
-                    Bytes::new(
-                        pixmap
-                            .encode_png()
-                            .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?,
-                    )
+                Bytes::new(conv)
+            }
+            ExportSvg(ExportSvgTask { export }) => {
+                let (is_first, merged_gap) = get_page_selection(&export)?;
+
+                Bytes::from_string(if is_first {
+                    typst_svg::svg(first_page()?)
+                } else {
+                    typst_svg::svg_merged(&select_pages(&export, paged_doc()?)?, merged_gap)
+                })
+            }
+            ExportPng(ExportPngTask { export, ppi, fill }) => {
+                let ppi = ppi.to_f32();
+                if ppi <= 1e-6 {
+                    bail!("invalid ppi: {ppi}");
                 }
-            })
-        })
-        .await??;
 
-        let to = write_to.clone();
-        tokio::task::spawn_blocking(move || write_atomic(to, data))
-            .await
-            .context_ut("failed to export")??;
+                let fill = if let Some(fill) = fill {
+                    parse_color(fill).map_err(|err| anyhow::anyhow!("invalid fill ({err})"))?
+                } else {
+                    Color::WHITE
+                };
 
-        log::debug!("ExportTask({export_id}): export complete");
-        Ok(Some(write_to))
+                let (is_first, merged_gap) = get_page_selection(&export)?;
+
+                let pixmap = if is_first {
+                    typst_render::render(first_page()?, ppi / 72.)
+                } else {
+                    let doc = select_pages(&export, paged_doc()?)?;
+                    typst_render::render_merged(&doc, ppi / 72., merged_gap, Some(fill))
+                };
+
+                Bytes::new(
+                    pixmap
+                        .encode_png()
+                        .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?,
+                )
+            }
+        })
     }
 }
 
@@ -499,6 +750,7 @@ impl Default for ExportUserConfig {
             task: ProjectTask::ExportPdf(ExportPdfTask {
                 export: ProjectExportTask {
                     when: TaskWhen::Never,
+                    when_script: None,
                     output: None,
                     transform: vec![],
                 },
@@ -646,7 +898,10 @@ impl FutureFolder {
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use clap::Parser;
+    use tinymist_task::EXPORT_MANIFEST_DIR;
 
     use super::*;
     use crate::export::ProjectCompilation;
@@ -723,6 +978,194 @@ mod tests {
         assert!(needs_run);
     }
 
+    /// Compiles the `main.typ` in the project and exports its pages to the
+    /// `out/page-{p}.svg` files.
+    fn export_pages(root: &Path, source: &str, manifest: bool) -> PathBuf {
+        export_svg(root, source, "$root/out/page-{p}", vec![], manifest)
+    }
+
+    /// Compiles the `main.typ` in the project and exports it to SVG files at
+    /// the output path.
+    fn export_svg(
+        root: &Path,
+        source: &str,
+        output: &str,
+        transform: Vec<ExportTransform>,
+        manifest: bool,
+    ) -> PathBuf {
+        use crate::project::{PathPattern, WorldProvider};
+
+        let main = root.join("main.typ");
+        std::fs::write(&main, source).unwrap();
+
+        let args = CompileOnceArgs::parse_from([
+            "tinymist",
+            main.to_str().unwrap(),
+            "--root",
+            root.to_str().unwrap(),
+            "--ignore-system-fonts",
+        ]);
+        let verse = args.resolve().expect("failed to resolve lsp universe");
+        let artifact =
+            CompiledArtifact::from_graph(WorldComputeGraph::from_world(verse.snapshot()), false);
+
+        let mut export = ProjectExportTask::new(TaskWhen::Never);
+        export.output = Some(PathPattern::new(output));
+        export.transform = transform;
+        let task = ProjectTask::ExportSvg(ExportSvgTask { export });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(ExportTask::do_export(task, artifact, None, manifest))
+            .expect("failed to export")
+            .expect("no output is exported")
+    }
+
+    fn with_project(f: impl FnOnce(&Path)) {
+        tinymist_std::fs::paths::temp_dir_in(std::env::temp_dir(), |root| {
+            f(root);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    const THREE_PAGES: &str = "One #pagebreak() Two #pagebreak() Three";
+
+    #[test]
+    fn test_export_skips_unchanged() {
+        with_project(|root| {
+            let output = export_pages(root, THREE_PAGES, true);
+            assert_eq!(output, root.join("out/page-1.svg"));
+            for number in 1..=3 {
+                assert!(root.join(format!("out/page-{number}.svg")).exists());
+            }
+            assert!(root.join(EXPORT_MANIFEST_DIR).exists());
+
+            // The outputs are not written again for the same document.
+            std::fs::write(root.join("out/page-2.svg"), "unchanged").unwrap();
+            let output = export_pages(root, THREE_PAGES, true);
+            assert_eq!(output, root.join("out/page-1.svg"));
+            let page = std::fs::read_to_string(root.join("out/page-2.svg")).unwrap();
+            assert_eq!(page, "unchanged");
+
+            // The removed outputs are exported again.
+            std::fs::remove_file(root.join("out/page-3.svg")).unwrap();
+            export_pages(root, THREE_PAGES, true);
+            assert!(root.join("out/page-3.svg").exists());
+        });
+    }
+
+    #[test]
+    fn test_export_skipped_reports_first_page() {
+        with_project(|root| {
+            let source = ["Page"; 11].join(" #pagebreak() ");
+            let output = export_pages(root, &source, true);
+            assert_eq!(output, root.join("out/page-1.svg"));
+
+            // The skipped export reports the first page rather than `page-10`.
+            let output = export_pages(root, &source, true);
+            assert_eq!(output, root.join("out/page-1.svg"));
+        });
+    }
+
+    #[test]
+    fn test_export_cleans_up_stale_pages() {
+        with_project(|root| {
+            export_pages(root, THREE_PAGES, true);
+
+            // Files not produced by the output path are never removed, even if
+            // they are listed in the manifest.
+            let write_to = root.join("out/page-{p}.svg");
+            let manifest_path = ExportManifest::path(root, &write_to);
+            let mut manifest = ExportManifest::read(&manifest_path);
+            assert_eq!(manifest.outputs.len(), 3);
+            std::fs::write(root.join("out/notes.svg"), "notes").unwrap();
+            manifest.add_output(&root.join("out/notes.svg"), "notes".into());
+            manifest.add_output(&root.join("main.typ"), "main".into());
+            manifest.write(&manifest_path).unwrap();
+
+            export_pages(root, "One", true);
+            assert!(root.join("out/page-1.svg").exists());
+            assert!(!root.join("out/page-2.svg").exists());
+            assert!(!root.join("out/page-3.svg").exists());
+            assert!(root.join("out/notes.svg").exists());
+            assert!(root.join("main.typ").exists());
+
+            let manifest = ExportManifest::read(&manifest_path);
+            let outputs = manifest
+                .outputs
+                .keys()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>();
+            assert_eq!(outputs, [root.join("out/page-1.svg")]);
+        });
+    }
+
+    #[test]
+    fn test_export_without_manifest() {
+        with_project(|root| {
+            export_pages(root, THREE_PAGES, false);
+            assert!(!root.join("target").exists());
+
+            // The outputs of the removed pages are kept without a manifest.
+            export_pages(root, "One", false);
+            assert!(root.join("out/page-1.svg").exists());
+            assert!(root.join("out/page-3.svg").exists());
+            assert!(!root.join("target").exists());
+        });
+    }
+
+    #[test]
+    fn test_export_page_ranges() {
+        let pages = |ranges: &[&str]| ExportTransform::Pages {
+            ranges: ranges.iter().map(|r| r.parse().unwrap()).collect(),
+        };
+
+        with_project(|root| {
+            let export = |transform| {
+                let output = export_svg(root, THREE_PAGES, "$root/out/doc", transform, false);
+                std::fs::read(output).unwrap()
+            };
+
+            // The ranges are applied without a page number template.
+            let all = export(vec![]);
+            let first = export(vec![ExportTransform::FirstPage]);
+            let middle = export(vec![pages(&["2-3"])]);
+            assert_ne!(middle, all);
+            assert_ne!(middle, first);
+            assert_ne!(first, all);
+            // `1-` selects all pages rather than the first page.
+            assert_eq!(export(vec![pages(&["1-"])]), all);
+        });
+
+        with_project(|root| {
+            let output = "$root/out/page-{p}";
+            export_svg(root, THREE_PAGES, output, vec![pages(&["2-3"])], false);
+            assert!(!root.join("out/page-1.svg").exists());
+            assert!(root.join("out/page-2.svg").exists());
+            assert!(root.join("out/page-3.svg").exists());
+        });
+
+        with_project(|root| {
+            let output = "$root/out/page-{p}";
+            export_svg(root, THREE_PAGES, output, vec![pages(&["1-"])], false);
+            for number in 1..=3 {
+                assert!(root.join(format!("out/page-{number}.svg")).exists());
+            }
+        });
+
+        with_project(|root| {
+            let output = "$root/out/page-{p}";
+            let first = vec![ExportTransform::FirstPage];
+            export_svg(root, THREE_PAGES, output, first, false);
+            assert!(root.join("out/page-1.svg").exists());
+            assert!(!root.join("out/page-2.svg").exists());
+        });
+    }
+
     use chrono::{DateTime, Utc};
     use tinymist_std::time::*;
 
@@ -7,12 +7,12 @@ use reflexo_typst::{Bytes, CompilerFeat, EntryReader, ExportWebSvgHtmlTask, WebS
 use reflexo_vec2svg::DefaultExportFeature;
 use tinymist_std::error::prelude::*;
 use tinymist_std::typst::TypstPagedDocument;
-use tinymist_task::{ExportTimings, TextExport};
+use tinymist_task::{ExportTask, ExportTimings, TextExport};
 use typlite::{Format, Typlite};
 
 use crate::project::{
-    ExportTeXTask, HtmlExport, LspCompilerFeat, PdfExport, PngExport, ProjectTask, SvgExport,
-    TaskWhen,
+    ExportEpubTask, ExportTeXTask, HtmlExport, LspCompilerFeat, PdfExport, PngExport, ProjectTask,
+    SvgExport, TaskWhen,
 };
 use crate::world::base::{
     ConfigTask, DiagnosticsTask, ExportComputation, FlagTask, HtmlCompilationTask,
@@ -87,19 +87,33 @@ impl<F: CompilerFeat> WorldComputable<F> for ProjectCompilation {
 pub struct ProjectExport;
 
 impl ProjectExport {
+    /// Checks whether the export is needed, which also evaluates the `when`
+    /// script of the task in the same way as the export task of the language
+    /// server.
+    fn needs_run<D: typst::Document>(
+        graph: &Arc<WorldComputeGraph<LspCompilerFeat>>,
+        task: Option<&ExportTask>,
+        doc: Option<&D>,
+    ) -> bool {
+        let needs_run = match task {
+            Some(task) => ExportTimings::needs_run_task(&graph.snap, task, doc),
+            None => ExportTimings::needs_run(&graph.snap, None, doc),
+        };
+        needs_run.unwrap_or(true)
+    }
+
     /// Exports the document to bytes artifact.
     fn export_bytes<
         D: typst::Document + Send + Sync + 'static,
         T: ExportComputation<LspCompilerFeat, D, Output = Bytes>,
     >(
         graph: &Arc<WorldComputeGraph<LspCompilerFeat>>,
-        when: Option<&TaskWhen>,
+        task: Option<&ExportTask>,
         config: &T::Config,
     ) -> Result<Option<Bytes>> {
         let doc = graph.compute::<OptionDocumentTask<D>>()?;
         let doc = doc.as_ref();
-        let n = ExportTimings::needs_run(&graph.snap, when, doc.as_deref()).unwrap_or(true);
-        if !n {
+        if !Self::needs_run(graph, task, doc.as_deref()) {
             return Ok(None);
         }
 
@@ -113,13 +127,12 @@ impl ProjectExport {
         T: ExportComputation<LspCompilerFeat, D, Output = String>,
     >(
         graph: &Arc<WorldComputeGraph<LspCompilerFeat>>,
-        when: Option<&TaskWhen>,
+        task: Option<&ExportTask>,
         config: &T::Config,
     ) -> Result<Option<Bytes>> {
         let doc = graph.compute::<OptionDocumentTask<D>>()?;
         let doc = doc.as_ref();
-        let n = ExportTimings::needs_run(&graph.snap, when, doc.as_deref()).unwrap_or(true);
-        if !n {
+        if !Self::needs_run(graph, task, doc.as_deref()) {
             return Ok(None);
         }
 
@@ -139,29 +152,27 @@ impl WorldComputable<LspCompilerFeat> for ProjectExport {
                 .as_ref()
                 .and_then(|o| o.substitute(&graph.snap.world.entry_state()))
         });
-        let when = config.when();
+        let task = config.as_export();
 
         let output = || -> Result<Option<Bytes>> {
             use ProjectTask::*;
             match config.as_ref() {
                 Preview(..) => todo!(),
-                ExportPdf(config) => Self::export_bytes::<_, PdfExport>(graph, when, config),
-                ExportPng(config) => Self::export_bytes::<_, PngExport>(graph, when, config),
-                ExportSvg(config) => Self::export_string::<_, SvgExport>(graph, when, config),
-                ExportHtml(config) => Self::export_string::<_, HtmlExport>(graph, when, config),
+                ExportPdf(config) => Self::export_bytes::<_, PdfExport>(graph, task, config),
+                ExportPng(config) => Self::export_bytes::<_, PngExport>(graph, task, config),
+                ExportSvg(config) => Self::export_string::<_, SvgExport>(graph, task, config),
+                ExportHtml(config) => Self::export_string::<_, HtmlExport>(graph, task, config),
                 // todo: configuration
                 ExportSvgHtml(_config) => Self::export_string::<
                     _,
                     WebSvgHtmlExport<DefaultExportFeature>,
                 >(
-                    graph, when, &ExportWebSvgHtmlTask::default()
+                    graph, task, &ExportWebSvgHtmlTask::default()
                 ),
                 ExportMd(..) => {
                     let doc = graph.compute::<OptionDocumentTask<TypstPagedDocument>>()?;
                     let doc = doc.as_ref();
-                    let n =
-                        ExportTimings::needs_run(&graph.snap, when, doc.as_deref()).unwrap_or(true);
-                    if !n {
+                    if !Self::needs_run(graph, task, doc.as_deref()) {
                         return Ok(None);
                     }
 
@@ -170,15 +181,22 @@ impl WorldComputable<LspCompilerFeat> for ProjectExport {
                 ExportTeX(..) => {
                     let doc = graph.compute::<OptionDocumentTask<TypstPagedDocument>>()?;
                     let doc = doc.as_ref();
-                    let n =
-                        ExportTimings::needs_run(&graph.snap, when, doc.as_deref()).unwrap_or(true);
-                    if !n {
+                    if !Self::needs_run(graph, task, doc.as_deref()) {
                         return Ok(None);
                     }
 
                     Ok(TypliteTeXExport::run(graph)?.map(Bytes::from_string))
                 }
-                ExportText(config) => Self::export_string::<_, TextExport>(graph, when, config),
+                ExportText(config) => Self::export_string::<_, TextExport>(graph, task, config),
+                ExportEpub(config) => {
+                    let doc = graph.compute::<OptionDocumentTask<TypstPagedDocument>>()?;
+                    let doc = doc.as_ref();
+                    if !Self::needs_run(graph, task, doc.as_deref()) {
+                        return Ok(None);
+                    }
+
+                    TypliteEpubExport::run(graph, config)
+                }
                 Query(..) => todo!(),
             }
         };
@@ -237,3 +255,34 @@ impl<const F: char> WorldComputable<LspCompilerFeat> for TypliteExport<F> {
 pub type TypliteMdExport = TypliteExport<'m'>;
 /// A task that exports the document to LaTeX.
 pub type TypliteTeXExport = TypliteExport<'x'>;
+
+/// A task that exports the document to EPUB by typlite.
+pub struct TypliteEpubExport;
+
+impl TypliteEpubExport {
+    fn run(
+        graph: &Arc<WorldComputeGraph<LspCompilerFeat>>,
+        config: &ExportEpubTask,
+    ) -> Result<Option<Bytes>> {
+        let conv = Typlite::new(Arc::new(graph.snap.world.clone()))
+            .with_format(Format::Epub)
+            .with_feature(typlite::TypliteFeat {
+                processor: config.processor.clone(),
+                theme: config.theme.clone(),
+                ..Default::default()
+            })
+            .to_epub()
+            .map_err(|e| anyhow::anyhow!("failed to convert to EPUB: {e}"))?;
+
+        Ok(Some(Bytes::new(conv)))
+    }
+}
+
+impl WorldComputable<LspCompilerFeat> for TypliteEpubExport {
+    type Output = Option<Bytes>;
+
+    fn compute(graph: &Arc<WorldComputeGraph<LspCompilerFeat>>) -> Result<Self::Output> {
+        let config = graph.must_get::<ConfigTask<ExportEpubTask>>()?;
+        Self::run(graph, &config)
+    }
+}
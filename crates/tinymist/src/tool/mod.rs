@@ -3,6 +3,7 @@
 pub mod ast;
 pub mod package;
 pub mod project;
+pub mod testing;
 pub mod word_count;
 
 #[cfg(feature = "preview")]
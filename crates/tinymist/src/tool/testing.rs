@@ -0,0 +1,324 @@
+//! Runs the test cases of a document in the language server.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use lsp_types::notification::Notification;
+use serde::{Deserialize, Serialize};
+use tinymist_project::LspWorld;
+use tinymist_query::testing::{
+    check_call, check_diagnostics, example_file, example_name, image_tolerance, is_html_example,
+    test_suites, ExampleRef, ImageTolerance, RefOutcome, TestCase, TestCaseKind,
+};
+use tinymist_query::{
+    convert_diagnostics, DiagnosticsMap, LocalContext, PositionEncoding, TestItemKind,
+};
+use tinymist_std::error::prelude::*;
+use tinymist_std::typst::{TypstDocument, TypstHtmlDocument, TypstPagedDocument};
+use typst::diag::SourceDiagnostic;
+use typst::foundations::Context;
+use typst::syntax::Source;
+use typst_shim::eval::TypstEngine;
+
+use crate::project::EntryReader;
+use crate::world::with_main;
+
+/// The options to run the test cases.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTestsOpts {
+    /// The names of the test items to run. All test items are run if it is
+    /// empty.
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Whether to update the reference images of the examples.
+    #[serde(default)]
+    pub update: bool,
+    /// The pixels per inch of the reference images, which is `144` by default.
+    pub ppi: Option<f32>,
+}
+
+/// The status of a test item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+    /// The test item is started.
+    Running,
+    /// The test item passed.
+    Passed,
+    /// The test item failed.
+    Failed,
+}
+
+/// The result of a test item, sent as a `tinymist/testResult` notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    /// The name of the test item.
+    pub name: String,
+    /// The kind of the test item.
+    pub kind: TestItemKind,
+    /// The status of the test item.
+    pub status: TestStatus,
+    /// The reason of the failure.
+    pub message: Option<String>,
+    /// The duration of the test item in milliseconds, if it is finished.
+    pub duration: Option<f64>,
+    /// The diagnostics of the test item.
+    pub diagnostics: DiagnosticsMap,
+}
+
+/// The notification of the result of a test item.
+pub struct TestResultNotification;
+
+impl Notification for TestResultNotification {
+    type Params = TestResult;
+    const METHOD: &'static str = "tinymist/testResult";
+}
+
+/// The summary of a test run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSummary {
+    /// The number of the passed test items.
+    pub passed: usize,
+    /// The number of the failed test items.
+    pub failed: usize,
+}
+
+/// Runs the test cases and test documents that the main document depends on,
+/// reporting the result of each test item.
+pub fn run_tests(
+    ctx: &mut LocalContext,
+    opts: &RunTestsOpts,
+    position_encoding: PositionEncoding,
+    report: impl Fn(TestResult),
+) -> Result<TestSummary> {
+    let world = ctx.world.clone();
+    let root = world
+        .entry_state()
+        .root()
+        .context("root must be determined to run tests")?;
+
+    let doc = typst::compile::<TypstPagedDocument>(&world).output?;
+    let suites = test_suites(ctx, &TypstDocument::from(Arc::new(doc)))
+        .context("failed to discover tests")?;
+
+    let mut runner = TestRunner {
+        world: &world,
+        root: &root,
+        opts,
+        position_encoding,
+        report: &report,
+        examples: HashSet::new(),
+        summary: TestSummary::default(),
+    };
+
+    for test in &suites.tests {
+        if runner.is_selected(&test.name) {
+            runner.run_test(test);
+        }
+    }
+    for example in &suites.examples {
+        if runner.is_selected(&example_name(example.id())) {
+            runner.run_example(example);
+        }
+    }
+    for source in &suites.diagnostics {
+        if runner.is_selected(&example_name(source.id())) {
+            runner.run_diagnostic(source);
+        }
+    }
+
+    Ok(runner.summary)
+}
+
+struct TestRunner<'a> {
+    world: &'a LspWorld,
+    root: &'a Path,
+    opts: &'a RunTestsOpts,
+    position_encoding: PositionEncoding,
+    report: &'a dyn Fn(TestResult),
+    /// The examples that have been run, which may be both included by an
+    /// `example-*` function and discovered as a document.
+    examples: HashSet<String>,
+    summary: TestSummary,
+}
+
+impl TestRunner<'_> {
+    fn is_selected(&self, name: &str) -> bool {
+        self.opts.names.is_empty() || self.opts.names.iter().any(|n| n == name)
+    }
+
+    fn running(&self, kind: TestItemKind, name: &str) {
+        (self.report)(TestResult {
+            name: name.to_owned(),
+            kind,
+            status: TestStatus::Running,
+            message: None,
+            duration: None,
+            diagnostics: DiagnosticsMap::default(),
+        });
+    }
+
+    fn finish(
+        &mut self,
+        kind: TestItemKind,
+        name: &str,
+        instant: std::time::Instant,
+        result: Result<(), (String, Vec<SourceDiagnostic>)>,
+    ) {
+        let (status, message, diagnostics) = match result {
+            Ok(()) => {
+                self.summary.passed += 1;
+                (TestStatus::Passed, None, DiagnosticsMap::default())
+            }
+            Err((message, diags)) => {
+                self.summary.failed += 1;
+                let diagnostics = convert_diagnostics(self.world, &diags, self.position_encoding);
+                (TestStatus::Failed, Some(message), diagnostics)
+            }
+        };
+
+        (self.report)(TestResult {
+            name: name.to_owned(),
+            kind,
+            status,
+            message,
+            duration: Some(instant.elapsed().as_secs_f64() * 1000.0),
+            diagnostics,
+        });
+    }
+
+    fn run_test(&mut self, test: &TestCase) {
+        let name = &test.name;
+        let kind = TestItemKind::from(test.kind);
+
+        let world = with_main(self.world, test.location);
+        if test.kind == TestCaseKind::Example {
+            match example_file(&world, name, test.location, test.function.span()) {
+                Ok(example) => self.run_example(&example),
+                Err(err) => {
+                    self.running(kind, name);
+                    let err = format!("not found: {err}");
+                    self.finish(kind, name, std::time::Instant::now(), Err((err, vec![])));
+                }
+            }
+            return;
+        }
+
+        self.running(kind, name);
+        let instant = std::time::Instant::now();
+        let mut engine = TypstEngine::new(&world);
+        let result = engine.call(&test.function, Context::default());
+
+        let result = check_call(test, result)
+            .map_err(|failure| (failure.message, failure.diagnostics.to_vec()));
+        self.finish(kind, name, instant, result);
+    }
+
+    fn run_example(&mut self, example: &Source) {
+        let name = &example_name(example.id());
+        if !self.examples.insert(name.clone()) {
+            return;
+        }
+
+        self.running(TestItemKind::Example, name);
+        let instant = std::time::Instant::now();
+        let result = self.check_example(name, example);
+        self.finish(TestItemKind::Example, name, instant, result);
+    }
+
+    /// Compiles an example and compares the rendered image to the reference
+    /// image at `refs/paged/<name>.png`, and the rendered HTML to
+    /// `refs/html/<name>.html` if it is labelled `<test-html-example>`, which
+    /// is compatible with the `tinymist test` command.
+    fn check_example(
+        &self,
+        name: &str,
+        example: &Source,
+    ) -> Result<(), (String, Vec<SourceDiagnostic>)> {
+        let world = with_main(self.world, example.id());
+        let doc = match typst::compile::<TypstPagedDocument>(&world).output {
+            Ok(doc) => doc,
+            Err(err) => return Err(("has error".into(), err.to_vec())),
+        };
+
+        let ppp = self.opts.ppi.unwrap_or(144.0) / 72.0;
+        let pixmap = typst_render::render_merged(&doc, ppp, Default::default(), None);
+        let result = pixmap
+            .encode_png()
+            .context_ut("cannot encode pixmap")
+            .and_then(|data| {
+                let tolerance = image_tolerance(&doc, ImageTolerance::default())?;
+                self.update_ref(name, &data, "paged", Some(tolerance))
+            });
+        self.check_ref(result, "paged")?;
+
+        if is_html_example(&doc) {
+            let doc = match typst::compile::<TypstHtmlDocument>(&world).output {
+                Ok(doc) => doc,
+                Err(err) => return Err(("has error".into(), err.to_vec())),
+            };
+            let output = match typst_html::html(&doc) {
+                Ok(output) => output,
+                Err(err) => return Err(("cannot render html".into(), err.to_vec())),
+            };
+            let result = self.update_ref(name, output.as_bytes(), "html", None);
+            self.check_ref(result, "html")?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the reference of an example, returning whether the rendered
+    /// example matches the reference.
+    fn update_ref(
+        &self,
+        name: &str,
+        data: &[u8],
+        kind: &str,
+        tolerance: Option<ImageTolerance>,
+    ) -> Result<bool> {
+        let refs = ExampleRef::new(self.root, kind, name);
+        let outcome = refs.update(data, tolerance, self.opts.update)?;
+        Ok(!matches!(outcome, RefOutcome::Mismatched(..)))
+    }
+
+    fn check_ref(
+        &self,
+        result: Result<bool>,
+        kind: &str,
+    ) -> Result<(), (String, Vec<SourceDiagnostic>)> {
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => Err((
+                format!("mismatch {kind}, update the reference to accept the changes"),
+                vec![],
+            )),
+            Err(err) => Err((err.to_string(), vec![])),
+        }
+    }
+
+    fn run_diagnostic(&mut self, source: &Source) {
+        let name = &example_name(source.id());
+        self.running(TestItemKind::Diagnostic, name);
+        let instant = std::time::Instant::now();
+
+        let world = with_main(self.world, source.id());
+        let result = typst::compile::<TypstPagedDocument>(&world);
+        let mut diagnostics = result.warnings.to_vec();
+        if let Err(errors) = result.output {
+            diagnostics.extend(errors);
+        }
+
+        let mismatches = check_diagnostics(self.world, source, &diagnostics);
+        let result = if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err((mismatches.join("; "), diagnostics))
+        };
+        self.finish(TestItemKind::Diagnostic, name, instant, result);
+    }
+}
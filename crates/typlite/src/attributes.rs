@@ -32,6 +32,12 @@ pub mod md_attr {
         value -> value
         caption -> caption
         class -> class
+        label -> label
+        supplement -> supplement
+        prefix -> prefix
+        key -> key
+        style -> style
+        tex -> tex
     }
 }
 
@@ -44,6 +50,7 @@ pub struct IdocAttr {
 #[derive(TypliteAttr, Default)]
 pub struct HeadingAttr {
     pub level: usize,
+    pub label: EcoString,
 }
 
 #[derive(TypliteAttr, Default)]
@@ -55,6 +62,7 @@ pub struct ImageAttr {
 #[derive(TypliteAttr, Default)]
 pub struct FigureAttr {
     pub caption: EcoString,
+    pub label: EcoString,
 }
 
 #[derive(TypliteAttr, Default)]
@@ -62,6 +70,46 @@ pub struct LinkAttr {
     pub dest: EcoString,
 }
 
+#[derive(TypliteAttr, Default)]
+pub struct LabelAttr {
+    pub dest: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct RefAttr {
+    pub dest: EcoString,
+    pub supplement: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct OutlineEntryAttr {
+    pub level: usize,
+    pub dest: EcoString,
+    pub prefix: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct CiteAttr {
+    pub key: EcoString,
+    pub supplement: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct BibliographyAttr {
+    pub src: EcoString,
+    pub style: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct BibItemAttr {
+    pub prefix: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct MathAttr {
+    pub tex: EcoString,
+}
+
 #[derive(TypliteAttr, Default)]
 pub struct RawAttr {
     pub lang: EcoString,
@@ -8,7 +8,7 @@ use cmark_writer::HtmlWriter;
 use cmark_writer::HtmlWriterOptions;
 use cmark_writer::WriteResult;
 use cmark_writer::WriterOptions;
-use cmark_writer::ast::Node;
+use cmark_writer::ast::{ListItem, Node};
 use cmark_writer::custom_node;
 use ecow::EcoString;
 use ecow::eco_format;
@@ -31,6 +31,10 @@ pub enum Format {
     Text,
     #[cfg(feature = "docx")]
     Docx,
+    #[cfg(feature = "epub")]
+    Epub,
+    Rst,
+    AsciiDoc,
 }
 
 /// Figure node implementation for all formats
@@ -228,6 +232,316 @@ impl VerbatimNode {
     }
 }
 
+/// Anchor node marking a target of intra-document links, e.g. a labelled
+/// element
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = true)]
+pub struct AnchorNode {
+    /// The identifier of the anchor
+    pub id: EcoString,
+}
+
+impl AnchorNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        writer.write_str(&format!("<a id=\"{}\"></a>", self.id))?;
+        Ok(())
+    }
+
+    fn write_html_custom(&self, writer: &mut HtmlWriter) -> HtmlWriteResult<()> {
+        let node = Node::HtmlElement(HtmlElement {
+            tag: EcoString::inline("a"),
+            attributes: vec![HtmlAttribute {
+                name: EcoString::inline("id"),
+                value: self.id.clone(),
+            }],
+            children: vec![],
+            self_closing: false,
+        });
+        writer.write_node(&node)?;
+        Ok(())
+    }
+}
+
+/// Reference node for a resolved reference to a labelled element
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = true)]
+pub struct ReferenceNode {
+    /// The identifier of the referenced anchor
+    pub target: EcoString,
+    /// The supplement of the referenced element, e.g. `Figure`
+    pub supplement: EcoString,
+    /// The resolved content of the reference, e.g. `Figure 3`
+    pub content: Vec<Node>,
+}
+
+impl ReferenceNode {
+    /// Converts the reference to a link to the anchor
+    pub fn to_link(&self) -> Node {
+        Node::Link {
+            url: eco_format!("#{}", self.target),
+            title: None,
+            content: self.content.clone(),
+        }
+    }
+
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        writer.write(&self.to_link())?;
+        Ok(())
+    }
+
+    fn write_html_custom(&self, writer: &mut HtmlWriter) -> HtmlWriteResult<()> {
+        writer.write_node(&self.to_link())?;
+        Ok(())
+    }
+}
+
+/// An entry in the outline
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutlineEntry {
+    /// The heading level of the outlined element
+    pub level: u8,
+    /// The identifier of the anchor of the outlined element
+    pub target: EcoString,
+    /// The numbering of the outlined element, e.g. `2.1`
+    pub prefix: EcoString,
+    /// The content of the entry
+    pub content: Vec<Node>,
+}
+
+impl OutlineEntry {
+    /// Converts the entry to a link to the outlined element
+    pub fn to_link(&self) -> Node {
+        let mut content = Vec::new();
+        if !self.prefix.is_empty() {
+            content.push(Node::Text(eco_format!("{} ", self.prefix)));
+        }
+        content.extend(self.content.iter().cloned());
+        Node::Link {
+            url: eco_format!("#{}", self.target),
+            title: None,
+            content,
+        }
+    }
+}
+
+/// Outline node for a table of contents
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = true)]
+pub struct OutlineNode {
+    /// The title heading of the outline
+    pub title: Option<Node>,
+    /// The entries of the outline, in document order
+    pub entries: Vec<OutlineEntry>,
+}
+
+impl OutlineNode {
+    /// Converts the entries to a nested list of links
+    pub fn to_list(&self) -> Node {
+        Node::UnorderedList(Self::list_items(&self.entries))
+    }
+
+    fn list_items(entries: &[OutlineEntry]) -> Vec<ListItem> {
+        let Some(level) = entries.iter().map(|entry| entry.level).min() else {
+            return Vec::new();
+        };
+
+        let mut items = Vec::new();
+        let mut rest = entries;
+        while let Some((entry, tail)) = rest.split_first() {
+            // The deeper entries following the entry are nested in its item
+            let end = tail
+                .iter()
+                .position(|next| next.level <= level)
+                .unwrap_or(tail.len());
+            let (children, next) = tail.split_at(end);
+
+            let mut content = vec![Node::Paragraph(vec![entry.to_link()])];
+            if !children.is_empty() {
+                content.push(Node::UnorderedList(Self::list_items(children)));
+            }
+            items.push(ListItem::Unordered { content });
+            rest = next;
+        }
+        items
+    }
+
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        let mut blocks = Vec::new();
+        blocks.extend(self.title.clone());
+        blocks.push(self.to_list());
+        let mut temp_writer = CommonMarkWriter::with_options(writer.options.clone());
+        temp_writer.write(&Node::Document(blocks))?;
+        let content = temp_writer.into_string();
+        writer.write_str(content.trim_end())?;
+        writer.write_str("\n")?;
+        Ok(())
+    }
+
+    fn write_html_custom(&self, writer: &mut HtmlWriter) -> HtmlWriteResult<()> {
+        let mut children = Vec::new();
+        children.extend(self.title.clone());
+        children.push(self.to_list());
+        let node = Node::HtmlElement(HtmlElement {
+            tag: EcoString::inline("nav"),
+            attributes: vec![],
+            children,
+            self_closing: false,
+        });
+        writer.write_node(&node)?;
+        Ok(())
+    }
+}
+
+/// Footnote node for a footnote reference and its content
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = true)]
+pub struct FootnoteNode {
+    /// The number of the footnote, starting from 1
+    pub number: usize,
+    /// The inline content of the footnote
+    pub content: Vec<Node>,
+}
+
+impl FootnoteNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        writer.write_str(&format!("[^{}]", self.number))?;
+        Ok(())
+    }
+
+    fn write_html_custom(&self, writer: &mut HtmlWriter) -> HtmlWriteResult<()> {
+        let node = Node::HtmlElement(HtmlElement {
+            tag: EcoString::inline("sup"),
+            attributes: vec![],
+            children: vec![Node::Text(eco_format!("{}", self.number))],
+            self_closing: false,
+        });
+        writer.write_node(&node)?;
+        Ok(())
+    }
+}
+
+/// Footnotes node for the definitions of the footnotes at the end of the
+/// document
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = false)]
+pub struct FootnotesNode {
+    /// The footnotes, in document order
+    pub footnotes: Vec<FootnoteNode>,
+}
+
+impl FootnotesNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        for (idx, footnote) in self.footnotes.iter().enumerate() {
+            if idx > 0 {
+                writer.write_str("\n")?;
+            }
+            writer.write_str(&format!("[^{}]: ", footnote.number))?;
+            for node in &footnote.content {
+                writer.write(node)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Citation node for a citation of a bibliography entry
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = true)]
+pub struct CiteNode {
+    /// The key of the cited entry
+    pub key: EcoString,
+    /// The supplement of the citation, e.g. `p. 7`
+    pub supplement: EcoString,
+    /// The citation rendered by the citation style, e.g. `[1]`
+    pub content: Vec<Node>,
+}
+
+impl CiteNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        for node in &self.content {
+            writer.write(node)?;
+        }
+        Ok(())
+    }
+
+    fn write_html_custom(&self, writer: &mut HtmlWriter) -> HtmlWriteResult<()> {
+        for node in &self.content {
+            writer.write_node(node)?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry in the bibliography
+#[derive(Debug, PartialEq, Clone)]
+pub struct BibEntry {
+    /// The prefix of the entry, e.g. `[1]`
+    pub prefix: EcoString,
+    /// The reference rendered by the citation style
+    pub content: Vec<Node>,
+}
+
+impl BibEntry {
+    /// Converts the entry to a paragraph
+    pub fn to_paragraph(&self) -> Node {
+        let mut content = Vec::new();
+        if !self.prefix.is_empty() {
+            content.push(Node::Text(eco_format!("{} ", self.prefix)));
+        }
+        content.extend(self.content.iter().cloned());
+        Node::Paragraph(content)
+    }
+}
+
+/// Bibliography node for the reference list
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = false)]
+pub struct BibliographyNode {
+    /// The title heading of the bibliography
+    pub title: Option<Node>,
+    /// The entries rendered by the citation style
+    pub entries: Vec<BibEntry>,
+    /// The paths of the bibliography files, e.g. `refs.bib`
+    pub sources: Vec<EcoString>,
+    /// The name of the citation style, e.g. `ieee`
+    pub style: EcoString,
+}
+
+impl BibliographyNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        let mut blocks = Vec::new();
+        blocks.extend(self.title.clone());
+        blocks.extend(self.entries.iter().map(BibEntry::to_paragraph));
+        let mut temp_writer = CommonMarkWriter::with_options(writer.options.clone());
+        temp_writer.write(&Node::Document(blocks))?;
+        let content = temp_writer.into_string();
+        writer.write_str(content.trim_end())?;
+        writer.write_str("\n")?;
+        Ok(())
+    }
+}
+
+/// Math node for an equation written by the native math markup of a format
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct MathNode {
+    /// Whether the equation is displayed as a block
+    pub block: bool,
+    /// The TeX source of the equation
+    pub tex: EcoString,
+}
+
+impl MathNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        if self.block {
+            writer.write_str(&format!("$$\n{}\n$$", self.tex))?;
+        } else {
+            writer.write_str(&format!("${}$", self.tex))?;
+        }
+        Ok(())
+    }
+}
+
 /// Alert node for alert messages
 #[derive(Debug, PartialEq, Clone)]
 #[custom_node(block = true, html_impl = false)]
@@ -0,0 +1,13 @@
+/// path: fig.svg
+<svg></svg>
+-----
+#set document(title: "Book")
+#set heading(numbering: "1.")
+
+= First <first>
+
+#image("./fig.svg", alt: "Diagram")
+
+= Second
+
+Back to @first.
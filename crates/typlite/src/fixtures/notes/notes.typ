@@ -0,0 +1,16 @@
+/// path: refs.bib
+@article{netwok2020,
+  title={At-scale impact of the {Net Wok}: A culinarically holistic investigation of distributed dumplings},
+  author={Astley, Rick and Morris, Linda},
+  journal={Armenian Journal of Proceedings},
+  volume={61},
+  pages={192--219},
+  year=2020,
+  publisher={Automatic Publishing Inc.}
+}
+-----
+Typst has footnotes#footnote[They are numbered in order.] and citations @netwok2020.
+
+See also #cite(<netwok2020>, supplement: [p. 7])#footnote[Another note.].
+
+#bibliography("refs.bib")
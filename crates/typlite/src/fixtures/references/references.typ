@@ -0,0 +1,16 @@
+/// path: fig.svg
+<svg></svg>
+-----
+#set heading(numbering: "1.")
+
+#outline()
+
+= Introduction <intro>
+
+See @results and @fig-diagram.
+
+= Results <results>
+
+#figure(image("./fig.svg", alt: "Diagram"), caption: "Diagram") <fig-diagram>
+
+Back to @intro.
@@ -0,0 +1,607 @@
+//! Markdown importer implementation, converting CommonMark and GFM to typst
+//! markup
+
+use std::collections::HashMap;
+
+use ecow::EcoString;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, LinkType, Options, Parser, Tag, TagEnd};
+
+use crate::Result;
+
+use super::FormatImporter;
+
+/// The package rendering LaTeX math, which is the math syntax of Markdown
+const MITEX_PACKAGE: &str = "@preview/mitex:0.2.4";
+
+/// Markdown importer implementation
+#[derive(Default)]
+pub struct MarkdownImporter {}
+
+impl MarkdownImporter {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl FormatImporter for MarkdownImporter {
+    fn import(&mut self, source: &str) -> Result<EcoString> {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_MATH
+            | Options::ENABLE_HEADING_ATTRIBUTES;
+
+        // Footnote definitions are taken out, as typst footnotes are written
+        // in place of their references
+        let mut definitions = Vec::new();
+        let mut body = Vec::new();
+        let mut events = Parser::new_ext(source, options);
+        while let Some(event) = events.next() {
+            match event {
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    let content = events
+                        .by_ref()
+                        .take_while(|event| {
+                            !matches!(event, Event::End(TagEnd::FootnoteDefinition))
+                        })
+                        .collect::<Vec<_>>();
+                    definitions.push((label, content));
+                }
+                event => body.push(event),
+            }
+        }
+
+        let mut math = false;
+        let mut footnotes = HashMap::new();
+        for (label, content) in definitions {
+            let mut emitter = Emitter::new(&footnotes);
+            content.into_iter().for_each(|event| emitter.event(event));
+            math |= emitter.math;
+            let content = emitter.out.trim().to_owned();
+            footnotes.insert(label.to_string(), content);
+        }
+
+        let mut emitter = Emitter::new(&footnotes);
+        body.into_iter().for_each(|event| emitter.event(event));
+        math |= emitter.math;
+
+        let mut output = EcoString::new();
+        if math {
+            output.push_str(&format!("#import \"{MITEX_PACKAGE}\": mi, mitex\n\n"));
+        }
+        output.push_str(emitter.out.trim());
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// The state of a list being emitted
+struct ListState {
+    ordered: bool,
+    /// The number of the first item, if it is not 1
+    start: Option<u64>,
+}
+
+/// The state of a table being emitted, whose cells are rendered separately
+#[derive(Default)]
+struct TableState {
+    alignments: Vec<Alignment>,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+}
+
+/// The state of an image being emitted, whose content is the alternative text
+struct ImageState {
+    url: String,
+    alt: String,
+}
+
+/// The emitting state saved when rendering content separately, e.g. a cell
+struct Capture {
+    out: String,
+    indent: String,
+    line_start: bool,
+    fresh: bool,
+}
+
+/// Emitter of typst markup from Markdown events
+struct Emitter<'a> {
+    /// The rendered footnote definitions by their labels
+    footnotes: &'a HashMap<String, String>,
+    out: String,
+    /// The indentation of the lines in list items and quotes
+    indent: String,
+    /// Whether the output is at the start of a line
+    line_start: bool,
+    /// Whether the output is at the start of a container, e.g. a list item,
+    /// which needs no paragraph break before its first block
+    fresh: bool,
+    /// Whether the text is skipped, e.g. the text of an autolink
+    skip_text: bool,
+    /// Whether the document uses math
+    math: bool,
+    captures: Vec<Capture>,
+    closers: Vec<&'static str>,
+    lists: Vec<ListState>,
+    heading_label: Option<String>,
+    code: Option<(String, String)>,
+    image: Option<ImageState>,
+    table: Option<TableState>,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(footnotes: &'a HashMap<String, String>) -> Self {
+        Self {
+            footnotes,
+            out: String::new(),
+            indent: String::new(),
+            line_start: true,
+            fresh: false,
+            skip_text: false,
+            math: false,
+            captures: Vec::new(),
+            closers: Vec::new(),
+            lists: Vec::new(),
+            heading_label: None,
+            code: None,
+            image: None,
+            table: None,
+        }
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        // Images and code blocks take their content as plain text
+        if let Some(image) = &mut self.image {
+            match event {
+                Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                    image.alt.push_str(&text)
+                }
+                Event::End(TagEnd::Image) => self.end_image(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some((_, code)) = &mut self.code {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.end_code_block(),
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => self.inline_code(&code),
+            Event::InlineMath(math) => self.math_equation(&math, false),
+            Event::DisplayMath(math) => self.math_equation(&math, true),
+            Event::Html(html) => {
+                self.html(&html);
+                self.end_line();
+            }
+            Event::InlineHtml(html) => self.html(&html),
+            Event::FootnoteReference(label) => self.footnote(&label),
+            Event::SoftBreak => self.end_line(),
+            Event::HardBreak => {
+                self.write(" \\");
+                self.end_line();
+            }
+            Event::Rule => {
+                self.blank_line();
+                self.write("#line(length: 100%)");
+                self.end_line();
+            }
+            Event::TaskListMarker(checked) => self.write(if checked { "☒ " } else { "☐ " }),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.blank_line(),
+            Tag::Heading { level, id, .. } => {
+                self.blank_line();
+                self.write(&"=".repeat(level as usize));
+                self.write(" ");
+                self.heading_label = id.map(|id| id.to_string());
+            }
+            Tag::BlockQuote(_) => {
+                self.blank_line();
+                self.write("#quote(block: true)[");
+                self.end_line();
+                self.indent.push_str("  ");
+                self.fresh = true;
+            }
+            Tag::CodeBlock(kind) => {
+                self.blank_line();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split([' ', ',', '{'])
+                        .next()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.blank_line();
+                } else {
+                    self.end_line();
+                }
+                self.lists.push(ListState {
+                    ordered: start.is_some(),
+                    start: start.filter(|start| *start != 1),
+                });
+            }
+            Tag::Item => {
+                self.end_line();
+                let marker = match self.lists.last_mut() {
+                    Some(ListState {
+                        ordered: true,
+                        start,
+                    }) => match start.take() {
+                        Some(number) => format!("{number}. "),
+                        None => "+ ".to_owned(),
+                    },
+                    _ => "- ".to_owned(),
+                };
+                self.write(&marker);
+                self.indent.push_str("  ");
+                self.fresh = true;
+            }
+            Tag::Table(alignments) => {
+                self.blank_line();
+                self.table = Some(TableState {
+                    alignments,
+                    ..Default::default()
+                });
+            }
+            Tag::TableCell => self.begin_capture(),
+            Tag::Emphasis => self.open_inline("_", "#emph["),
+            Tag::Strong => self.open_inline("*", "#strong["),
+            Tag::Strikethrough => {
+                self.write("#strike[");
+                self.closers.push("]");
+            }
+            Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            } => {
+                let url = match link_type {
+                    LinkType::Email => format!("mailto:{dest_url}"),
+                    _ => dest_url.to_string(),
+                };
+                self.write(&format!("#link({})", string_literal(&url)));
+                // The text of an autolink is the url itself
+                if matches!(link_type, LinkType::Autolink | LinkType::Email) {
+                    self.skip_text = true;
+                    self.closers.push("");
+                } else {
+                    self.write("[");
+                    self.closers.push("]");
+                }
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image = Some(ImageState {
+                    url: dest_url.to_string(),
+                    alt: String::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::HtmlBlock => self.end_line(),
+            TagEnd::Heading(_) => {
+                if let Some(label) = self.heading_label.take() {
+                    self.write(&format!(" <{label}>"));
+                }
+                self.end_line();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.dedent();
+                self.end_line();
+                self.write("]");
+                self.end_line();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                self.end_line();
+            }
+            TagEnd::Item => {
+                self.dedent();
+                self.end_line();
+                self.fresh = false;
+            }
+            TagEnd::TableCell => {
+                let cell = self.end_capture();
+                if let Some(table) = &mut self.table {
+                    table.row.push(cell);
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = std::mem::take(&mut table.row);
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::Table => self.end_table(),
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+                self.skip_text = false;
+                if let Some(closer) = self.closers.pop() {
+                    self.write(closer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip_text {
+            return;
+        }
+
+        // Text following a function call could be taken as its arguments
+        let after_call = self.out.ends_with([')', ']']);
+        let escaped = escape(text, self.line_start || self.fresh, after_call);
+        self.write(&escaped);
+    }
+
+    fn inline_code(&mut self, code: &str) {
+        if code.contains('`') {
+            self.write(&format!("#raw({})", string_literal(code)));
+        } else {
+            self.write(&format!("`{code}`"));
+        }
+    }
+
+    fn math_equation(&mut self, math: &str, block: bool) {
+        self.math = true;
+        let func = if block { "mitex" } else { "mi" };
+        let math = math.trim();
+        if math.contains('`') {
+            self.write(&format!("#{func}({})", string_literal(math)));
+        } else {
+            self.write(&format!("#{func}(`{math}`)"));
+        }
+    }
+
+    /// Keep HTML as comments, which cannot be rendered by typst
+    fn html(&mut self, html: &str) {
+        let html = html.trim_end_matches('\n');
+        if matches!(html.trim(), "<br>" | "<br/>" | "<br />") {
+            self.write(" \\");
+            self.end_line();
+            return;
+        }
+        self.write(&format!("/* {} */", html.replace("*/", "* /")));
+    }
+
+    fn footnote(&mut self, label: &str) {
+        match self.footnotes.get(label) {
+            Some(content) => self.write(&format!("#footnote[{content}]")),
+            None => {
+                let text = escape(&format!("[^{label}]"), false, false);
+                self.write(&text);
+            }
+        }
+    }
+
+    /// Open strong or emphasis, whose delimiters do not work inside words
+    fn open_inline(&mut self, delim: &'static str, func: &'static str) {
+        if self.out.ends_with(|ch: char| ch.is_alphanumeric()) {
+            self.write(func);
+            self.closers.push("]");
+        } else {
+            self.write(delim);
+            self.closers.push(delim);
+        }
+    }
+
+    fn end_image(&mut self) {
+        let Some(image) = self.image.take() else {
+            return;
+        };
+
+        let mut args = string_literal(&image.url);
+        if !image.alt.is_empty() {
+            args.push_str(&format!(", alt: {}", string_literal(&image.alt)));
+        }
+        self.write(&format!("#image({args})"));
+    }
+
+    fn end_code_block(&mut self) {
+        let Some((lang, code)) = self.code.take() else {
+            return;
+        };
+
+        // The fence must be longer than the backticks in the code
+        let mut longest = 0;
+        let mut run = 0;
+        for ch in code.chars() {
+            run = if ch == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat(longest.max(2) + 1);
+
+        self.write(&fence);
+        self.write(&lang);
+        self.end_line();
+        self.write(code.trim_end_matches('\n'));
+        self.end_line();
+        self.write(&fence);
+        self.end_line();
+    }
+
+    fn end_table(&mut self) {
+        let Some(table) = self.table.take() else {
+            return;
+        };
+
+        let columns = table.alignments.len().max(table.header.len());
+        self.write("#table(");
+        self.indent.push_str("  ");
+        self.end_line();
+        self.write(&format!("columns: {columns},"));
+        if table
+            .alignments
+            .iter()
+            .any(|align| *align != Alignment::None)
+        {
+            let alignments = table
+                .alignments
+                .iter()
+                .map(|align| match align {
+                    Alignment::None => "auto",
+                    Alignment::Left => "left",
+                    Alignment::Center => "center",
+                    Alignment::Right => "right",
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            // A single alignment is written as an array by a trailing comma
+            let trailing = if table.alignments.len() == 1 { "," } else { "" };
+            self.end_line();
+            self.write(&format!("align: ({alignments}{trailing}),"));
+        }
+        if !table.header.is_empty() {
+            self.end_line();
+            self.write(&format!("table.header({}),", table_cells(&table.header)));
+        }
+        for row in &table.rows {
+            self.end_line();
+            self.write(&format!("{},", table_cells(row)));
+        }
+        self.dedent();
+        self.end_line();
+        self.write(")");
+        self.end_line();
+    }
+
+    /// Render the following content separately until [`Self::end_capture`]
+    fn begin_capture(&mut self) {
+        self.captures.push(Capture {
+            out: std::mem::take(&mut self.out),
+            indent: std::mem::take(&mut self.indent),
+            line_start: std::mem::replace(&mut self.line_start, true),
+            fresh: std::mem::replace(&mut self.fresh, false),
+        });
+    }
+
+    fn end_capture(&mut self) -> String {
+        let Some(capture) = self.captures.pop() else {
+            return String::new();
+        };
+
+        let content = std::mem::replace(&mut self.out, capture.out);
+        self.indent = capture.indent;
+        self.line_start = capture.line_start;
+        self.fresh = capture.fresh;
+        content.trim().to_owned()
+    }
+
+    /// Write text, indenting each line in the current container
+    fn write(&mut self, text: &str) {
+        for (idx, line) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.out.push('\n');
+                self.line_start = true;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.line_start {
+                self.out.push_str(&self.indent);
+                self.line_start = false;
+            }
+            self.out.push_str(line);
+            self.fresh = false;
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.line_start {
+            self.out.push('\n');
+            self.line_start = true;
+        }
+    }
+
+    /// Separate the following block from the previous one by a blank line
+    fn blank_line(&mut self) {
+        if std::mem::take(&mut self.fresh) || self.out.is_empty() {
+            return;
+        }
+        self.end_line();
+        if !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn dedent(&mut self) {
+        let len = self.indent.len().saturating_sub(2);
+        self.indent.truncate(len);
+    }
+}
+
+/// Escape the characters of text that have meanings in typst markup
+fn escape(text: &str, line_start: bool, after_call: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    // Markers of headings, lists and enums at the start of a line, e.g. `1.`
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let enum_dot = (line_start && digits > 0 && text[digits..].starts_with('.')).then_some(digits);
+    // Arguments or fields following a function call, e.g. `(` or `.field`
+    let mut rest = text.chars();
+    let continues_call = match rest.next() {
+        Some('(' | '[') => true,
+        Some('.') => rest
+            .next()
+            .is_some_and(|ch| ch.is_alphabetic() || ch == '_'),
+        _ => false,
+    };
+    let escape_first =
+        (line_start && text.starts_with(['=', '-', '+', '/'])) || (after_call && continues_call);
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let special = match ch {
+            '\\' | '#' | '*' | '_' | '`' | '$' | '<' | '@' | '[' | ']' | '~' => true,
+            '/' => matches!(next, Some('/' | '*')),
+            '-' => next == Some('-'),
+            '.' => enum_dot == Some(idx),
+            _ => false,
+        };
+        if special || (idx == 0 && escape_first) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Create a typst string literal
+fn string_literal(text: &str) -> String {
+    format!("{text:?}")
+}
+
+fn table_cells(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| format!("[{cell}]"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
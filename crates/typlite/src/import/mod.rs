@@ -0,0 +1,23 @@
+//! Importer implementations converting other formats to typst markup
+
+pub mod markdown;
+
+pub use markdown::MarkdownImporter;
+
+use ecow::EcoString;
+
+use crate::Result;
+use crate::common::Format;
+
+/// Importer converting a document of some format to typst markup
+pub trait FormatImporter {
+    fn import(&mut self, source: &str) -> Result<EcoString>;
+}
+
+/// Create an importer instance based on the specified format
+pub fn create_importer(format: Format) -> Result<Box<dyn FormatImporter>> {
+    match format {
+        Format::Md => Ok(Box::new(MarkdownImporter::new())),
+        _ => Err(format!("importing from {format:?} is not supported").into()),
+    }
+}
@@ -6,6 +6,7 @@
 pub mod attributes;
 pub mod common;
 mod error;
+pub mod import;
 pub mod parser;
 pub mod tags;
 pub mod writer;
@@ -123,6 +124,32 @@ impl MarkdownDocument {
         Ok(output)
     }
 
+    /// Convert the content to a reStructuredText string.
+    pub fn to_rst_string(&self) -> tinymist_std::Result<ecow::EcoString> {
+        let mut output = ecow::EcoString::new();
+        let ast = self.parse()?;
+
+        let mut writer = WriterFactory::create(Format::Rst);
+        writer
+            .write_eco(&ast, &mut output)
+            .context_ut("failed to write")?;
+
+        Ok(output)
+    }
+
+    /// Convert the content to an AsciiDoc string.
+    pub fn to_asciidoc_string(&self) -> tinymist_std::Result<ecow::EcoString> {
+        let mut output = ecow::EcoString::new();
+        let ast = self.parse()?;
+
+        let mut writer = WriterFactory::create(Format::AsciiDoc);
+        writer
+            .write_eco(&ast, &mut output)
+            .context_ut("failed to write")?;
+
+        Ok(output)
+    }
+
     /// Convert the content to a DOCX document
     #[cfg(feature = "docx")]
     pub fn to_docx(&self) -> tinymist_std::Result<Vec<u8>> {
@@ -131,6 +158,36 @@ impl MarkdownDocument {
         let mut writer = WriterFactory::create(Format::Docx);
         writer.write_vec(&ast).context_ut("failed to write")
     }
+
+    /// Convert the content to an EPUB document
+    #[cfg(feature = "epub")]
+    pub fn to_epub(&self) -> tinymist_std::Result<Vec<u8>> {
+        use crate::attributes::md_attr;
+        use crate::common::FormatWriter;
+        use crate::writer::epub::EpubMetadata;
+
+        let ast = self.parse()?;
+
+        let info = &self.base.info;
+        let language = self.base.root.attrs.0.iter().find_map(|(name, value)| {
+            if *name == md_attr::lang {
+                Some(value.clone())
+            } else {
+                None
+            }
+        });
+        let metadata = EpubMetadata {
+            title: info.title.clone().unwrap_or_default(),
+            authors: info.author.clone(),
+            language: language.unwrap_or_default(),
+        };
+
+        let mut writer = crate::writer::EpubWriter::new()
+            .with_metadata(metadata)
+            .with_theme(self.feat.theme.clone())
+            .with_world(self.world.clone());
+        writer.write_vec(&ast).context_ut("failed to write")
+    }
 }
 
 /// A color theme for rendering the content. The valid values can be checked in [color-scheme](https://developer.mozilla.org/en-US/docs/Web/CSS/color-scheme).
@@ -178,6 +235,9 @@ pub struct TypliteFeat {
     /// It resembles the regular typst show rule function, like `#show:
     /// article`.
     pub processor: Option<String>,
+    /// The theme of e-books, which is a stylesheet or a directory containing
+    /// a `style.css` and fonts.
+    pub theme: Option<PathBuf>,
 }
 
 impl TypliteFeat {
@@ -214,6 +274,9 @@ impl TypliteFeat {
         if format == Format::Text || self.remove_html {
             dict.insert("x-remove-html".into(), Str("true".into()));
         }
+        if matches!(format, Format::Rst | Format::AsciiDoc) {
+            dict.insert("x-math-tex".into(), Str("true".into()));
+        }
 
         let task_inputs = TaskInputs {
             entry: Some(entry.select_in_workspace(main_id.vpath().as_rooted_path())),
@@ -304,11 +367,21 @@ impl Typlite {
             Format::Md => self.convert_doc(Format::Md)?.to_md_string(),
             Format::LaTeX => self.convert_doc(Format::LaTeX)?.to_tex_string(),
             Format::Text => self.convert_doc(Format::Text)?.to_text_string(),
+            Format::Rst => self.convert_doc(Format::Rst)?.to_rst_string(),
+            Format::AsciiDoc => self.convert_doc(Format::AsciiDoc)?.to_asciidoc_string(),
             #[cfg(feature = "docx")]
             Format::Docx => bail!("docx format is not supported"),
+            #[cfg(feature = "epub")]
+            Format::Epub => bail!("epub format is not supported"),
         }
     }
 
+    /// Convert a document of the format to typst markup.
+    pub fn import(source: &str, format: Format) -> tinymist_std::Result<ecow::EcoString> {
+        let mut importer = import::create_importer(format).context_ut("failed to import")?;
+        importer.import(source).context_ut("failed to import")
+    }
+
     /// Convert the content to a DOCX document
     #[cfg(feature = "docx")]
     pub fn to_docx(self) -> tinymist_std::Result<Vec<u8>> {
@@ -318,6 +391,15 @@ impl Typlite {
         self.convert_doc(Format::Docx)?.to_docx()
     }
 
+    /// Convert the content to an EPUB document
+    #[cfg(feature = "epub")]
+    pub fn to_epub(self) -> tinymist_std::Result<Vec<u8>> {
+        if self.format != Format::Epub {
+            bail!("format is not EPUB");
+        }
+        self.convert_doc(Format::Epub)?.to_epub()
+    }
+
     /// Convert the content to a markdown document.
     pub fn convert_doc(self, format: Format) -> tinymist_std::Result<MarkdownDocument> {
         let world = Arc::new(self.feat.prepare_world(&self.world, format)?);
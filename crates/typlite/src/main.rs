@@ -49,12 +49,22 @@ pub struct CompileArgs {
     /// ```
     #[clap(long = "processor", default_value = None, value_name = "PACKAGE_SPEC")]
     pub processor: Option<String>,
+
+    /// Configures the theme of e-books, which is a stylesheet or a directory
+    /// containing a `style.css` and fonts
+    #[clap(long, default_value = None, value_name = "THEME")]
+    pub theme: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
     // Parse command line arguments
     let args = CompileArgs::parse();
 
+    // Markdown documents are imported to typst, which needs no compilation
+    if let Some(format) = args.compile.input.as_deref().and_then(import_format) {
+        return import(args, format);
+    }
+
     let verse = args.compile.resolve()?;
     let world = Arc::new(verse.snapshot());
 
@@ -76,8 +86,12 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
     let output_format = match output_path.extension().and_then(std::ffi::OsStr::to_str) {
         Some("tex") => Format::LaTeX,
         Some("txt") => Format::Text,
+        Some("rst") => Format::Rst,
+        Some("adoc" | "asciidoc") => Format::AsciiDoc,
         #[cfg(feature = "docx")]
         Some("docx") => Format::Docx,
+        #[cfg(feature = "epub")]
+        Some("epub") => Format::Epub,
         _ => Format::Md,
     };
 
@@ -91,6 +105,7 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
         .with_feature(TypliteFeat {
             assets_path: args.assets_path,
             processor: args.processor,
+            theme: args.theme,
             ..Default::default()
         })
         .convert_doc(output_format)?;
@@ -99,8 +114,12 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
         Format::Md => Bytes::from_string(doc.to_md_string()?),
         Format::LaTeX => Bytes::from_string(doc.to_tex_string()?),
         Format::Text => Bytes::from_string(doc.to_text_string()?),
+        Format::Rst => Bytes::from_string(doc.to_rst_string()?),
+        Format::AsciiDoc => Bytes::from_string(doc.to_asciidoc_string()?),
         #[cfg(feature = "docx")]
         Format::Docx => Bytes::new(doc.to_docx()?),
+        #[cfg(feature = "epub")]
+        Format::Epub => Bytes::new(doc.to_epub()?),
     };
 
     if is_stdout {
@@ -114,6 +133,46 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
     Ok(())
 }
 
+/// Get the format of the input file to import to typst
+fn import_format(input: &str) -> Option<Format> {
+    match Path::new(input)
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+    {
+        Some("md" | "markdown") => Some(Format::Md),
+        _ => None,
+    }
+}
+
+fn import(args: CompileArgs, format: Format) -> Result<()> {
+    let input = args
+        .compile
+        .input
+        .context("Missing required argument: INPUT")?;
+
+    let is_stdout = args.output.as_deref() == Some("-");
+    let output_path = args
+        .output
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&input).with_extension("typ"));
+
+    let source = match std::fs::read_to_string(&input) {
+        Ok(source) => source,
+        Err(err) => bail!("failed to read file {input:?}: {err}"),
+    };
+    let result = Typlite::import(&source, format)?;
+
+    if is_stdout {
+        std::io::stdout()
+            .write_all(result.as_bytes())
+            .context("failed to write to stdout")?;
+    } else if let Err(err) = std::fs::write(&output_path, result.as_bytes()) {
+        bail!("failed to write file {output_path:?}: {err}");
+    }
+
+    Ok(())
+}
+
 fn print_diag_or_error<T>(world: &impl SourceWorld, result: Result<T>) -> Result<T> {
     match result {
         Ok(v) => Ok(v),
@@ -6,6 +6,106 @@
   }
 }
 
+// Gets the plain text of a content, e.g., the supplement of a figure.
+#let plain-text(it) = {
+  if type(it) == str {
+    it
+  } else if type(it) != content {
+    ""
+  } else if it.has("text") {
+    it.text
+  } else if it.has("children") {
+    it.children.map(plain-text).sum(default: "")
+  } else if it.has("body") {
+    plain-text(it.body)
+  } else if it == [ ] {
+    " "
+  } else {
+    ""
+  }
+}
+// Converts the body of an equation to TeX, e.g., for the math directives of
+// reStructuredText and AsciiDoc.
+#let math-tex(it) = {
+  let group(it) = "{" + math-tex(it) + "}"
+  let join(items, sep) = items.map(math-tex).intersperse(sep).sum(default: "")
+  if type(it) == str {
+    it
+  } else if type(it) == array {
+    join(it, " ")
+  } else if type(it) != content {
+    ""
+  } else if it.has("text") and type(it.text) == str {
+    // Both texts and symbols, e.g., `x` or `gamma`, have their text
+    let body = it.text.replace(regex("[{}%#&]"), m => "\\" + m.text)
+    if it.text.clusters().len() > 1 and it.text.contains(regex("\\p{L}")) {
+      "\\text{" + body + "}"
+    } else {
+      body
+    }
+  } else if it.func() == math.attach {
+    let tex = group(it.base)
+    for (script, key) in (("_", "b"), ("^", "t"), ("_", "br"), ("^", "tr")) {
+      let attach = it.at(key, default: none)
+      if attach != none {
+        tex += script + group(attach)
+      }
+    }
+    tex
+  } else if it.func() == math.frac {
+    "\\frac" + group(it.num) + group(it.denom)
+  } else if it.func() == math.binom {
+    "\\binom" + group(it.upper) + group(it.lower)
+  } else if it.func() == math.root {
+    if it.index == none {
+      "\\sqrt" + group(it.radicand)
+    } else {
+      "\\sqrt[" + math-tex(it.index) + "]" + group(it.radicand)
+    }
+  } else if it.func() == math.op {
+    "\\operatorname{" + plain-text(it.text) + "}"
+  } else if it.func() == math.primes {
+    "'" * it.count
+  } else if it.func() == math.vec {
+    "\\begin{pmatrix}" + join(it.children, " \\\\ ") + "\\end{pmatrix}"
+  } else if it.func() == math.mat {
+    let rows = it.rows.map(row => join(row, " & "))
+    "\\begin{pmatrix}" + rows.intersperse(" \\\\ ").sum(default: "") + "\\end{pmatrix}"
+  } else if it.func() == math.cases {
+    "\\begin{cases}" + join(it.children, " \\\\ ") + "\\end{cases}"
+  } else if it.func() == math.underline or it.func() == math.overline {
+    "\\" + repr(it.func()) + group(it.body)
+  } else if it.func() == math.align-point {
+    " & "
+  } else if it.func() == linebreak {
+    " \\\\ "
+  } else if it.func() == h or it == [ ] {
+    " "
+  } else if it.has("children") {
+    join(it.children, "")
+  } else if it.has("child") {
+    math-tex(it.child)
+  } else if it.has("body") {
+    math-tex(it.body)
+  } else if it.has("base") {
+    math-tex(it.base)
+  } else {
+    ""
+  }
+}
+// The TeX source of equations is only needed by the formats with native math
+// markups.
+#let math-attrs(it) = if sys.inputs.at("x-math-tex", default: none) == "true" {
+  (tex: math-tex(it.body))
+} else {
+  (:)
+}
+#let label-of(it) = if it.has("label") {
+  str(it.label)
+} else {
+  none
+}
+
 // typst doesn't allow things like `typParbreak`.
 #let md-parbreak = html.elem("m1parbreak", "")
 #let md-linebreak = html.elem("m1linebreak", "")
@@ -47,24 +147,68 @@
   attrs: (dest: dest),
   body,
 )
-#let md-ref(body) = html.elem(
+#let md-ref(dest: none, supplement: none, body) = html.elem(
   "span",
   html.elem(
     "m1ref",
+    attrs: if dest == none {
+      (:)
+    } else {
+      (dest: dest, supplement: supplement)
+    },
     body,
   ),
 )
-#let md-heading(level: int, body) = html.elem(
+#let md-heading(level: int, label: none, body) = html.elem(
   "m1heading",
-  attrs: (level: str(level)),
+  attrs: if label == none {
+    (level: str(level))
+  } else {
+    (level: str(level), label: label)
+  },
   box(body),
 )
 #let md-outline = html.elem.with("m1outline")
-#let md-outline-entry(level: int, body) = html.elem(
-  "m1outentry",
-  attrs: (level: str(level)),
-  body,
+#let md-outline-entry(level: int, dest: none, prefix: none, body) = {
+  let attrs = (level: str(level))
+  if dest != none {
+    attrs.insert("dest", dest)
+  }
+  if prefix != none and prefix != "" {
+    attrs.insert("prefix", prefix)
+  }
+  html.elem("m1outentry", attrs: attrs, body)
+}
+#let md-footnote(body) = html.elem(
+  "span",
+  html.elem("m1footnote", body),
+)
+#let md-cite(key: none, supplement: none, body) = html.elem(
+  "span",
+  html.elem(
+    "m1cite",
+    attrs: (key: key, supplement: supplement),
+    body,
+  ),
 )
+#let md-bibliography(src: (), style: "", body) = {
+  // The entries are rendered in a grid of prefixes and references if the style
+  // has prefixes, e.g. `[1]`.
+  show grid: it => {
+    for (prefix, entry) in it.children.chunks(2, exact: true) {
+      html.elem(
+        "m1bibitem",
+        attrs: (prefix: plain-text(prefix.at("body", default: prefix))),
+        entry.at("body", default: entry),
+      )
+    }
+  }
+  html.elem(
+    "m1bibliography",
+    attrs: (src: src.join(","), style: style),
+    body,
+  )
+}
 #let md-quote(/* attribution: none, */ body) = html.elem(
   "m1quote",
   // attrs: (attribution: attribution),
@@ -106,9 +250,8 @@
   ),
   "",
 )
-#let md-figure(body, caption: none) = html.elem(
-  "m1figure",
-  attrs: (
+#let md-figure(body, caption: none, label: none) = {
+  let attrs = (
     caption: if caption == none {
       ""
     } else {
@@ -118,9 +261,12 @@
         ""
       }
     },
-  ),
-  body,
-)
+  )
+  if label != none {
+    attrs.insert("label", label)
+  }
+  html.elem("m1figure", attrs: attrs, body)
+}
 
 #let if-not-paged(it, act) = {
   if target() == "html" {
@@ -191,12 +337,72 @@
   show image: it => if-not-paged(it, md-image(src: it.source, alt: it.alt))
 
   show raw: it => if-not-paged(it, md-raw(lang: it.lang, block: it.block, it.text))
-  show link: it => if-not-paged(it, md-link(dest: it.dest, it.body))
-  show ref: it => if-not-paged(it, md-ref(it))
+  // Only the links to urls are kept, since the links to locations are made by
+  // the references, which are linked by their targets instead.
+  show link: it => if-not-paged(
+    it,
+    if type(it.dest) == str {
+      md-link(dest: it.dest, it.body)
+    } else {
+      it.body
+    },
+  )
+  show ref: it => if-not-paged(
+    it,
+    if it.element == none {
+      md-ref(it)
+    } else {
+      let supplement = if type(it.supplement) == content {
+        it.supplement
+      } else {
+        it.element.at("supplement", default: none)
+      }
+      md-ref(dest: str(it.target), supplement: plain-text(supplement), it)
+    },
+  )
+
+  show footnote: it => if-not-paged(
+    it,
+    if type(it.body) == content {
+      md-footnote(it.body)
+    } else {
+      it
+    },
+  )
+  show cite: it => if-not-paged(
+    it,
+    md-cite(key: str(it.key), supplement: plain-text(it.supplement), it),
+  )
+  show bibliography: it => if-not-paged(
+    it,
+    {
+      let sources = it.at("sources", default: it.at("path", default: ()))
+      if type(sources) != array {
+        sources = (sources,)
+      }
+      md-bibliography(
+        src: sources.filter(source => type(source) == str),
+        style: if type(it.style) == str {
+          it.style
+        } else {
+          ""
+        },
+        it,
+      )
+    },
+  )
 
-  show heading: it => if-not-paged(it, md-heading(level: it.level, it.body))
+  show heading: it => if-not-paged(it, md-heading(level: it.level, label: label-of(it), it.body))
   show outline: it => if-not-paged(it, md-outline(it))
-  show outline.entry: it => if-not-paged(it, md-outline-entry(level: it.level, it.element))
+  show outline.entry: it => if-not-paged(
+    it,
+    md-outline-entry(
+      level: it.level,
+      dest: label-of(it.element),
+      prefix: plain-text(it.prefix()),
+      it.body(),
+    ),
+  )
   show quote: it => if-not-paged(it, md-quote(it.body))
   show table: it => if-not-paged(it, md-table(it))
   show grid: it => if-not-paged(it, md-grid(columns: it.columns, ..it.children))
@@ -205,6 +411,7 @@
     it,
     html.elem(
       "m1eqinline",
+      attrs: math-attrs(it),
       if sys.inputs.at("x-remove-html", default: none) != "true" { html.frame(box(inset: 0.5em, it)) } else {
         process-math-eq(it.body).flatten().join()
       },
@@ -212,21 +419,29 @@
   )
   show math.equation.where(block: true): it => if-not-paged(
     it,
-    if sys.inputs.at("x-remove-html", default: none) != "true" {
-      html.elem(
-        "m1eqblock",
-        html.frame(block(inset: 0.5em, it)),
-      )
-    } else {
-      html.elem(
-        "m1eqinline",
-        process-math-eq(it.body).flatten().join(),
-      )
+    {
+      let body = if sys.inputs.at("x-remove-html", default: none) != "true" {
+        html.elem(
+          "m1eqblock",
+          attrs: math-attrs(it),
+          html.frame(block(inset: 0.5em, it)),
+        )
+      } else {
+        html.elem(
+          "m1eqinline",
+          process-math-eq(it.body).flatten().join(),
+        )
+      }
+      if it.has("label") {
+        md-label(dest: str(it.label), body)
+      } else {
+        body
+      }
     },
   )
 
   show linebreak: it => if-not-paged(it, md-linebreak)
-  show figure: it => if-not-paged(it, md-figure(it.body, caption: it.caption))
+  show figure: it => if-not-paged(it, md-figure(it.body, caption: it.caption, label: label-of(it)))
 
   html.elem("m1document", body)
 }
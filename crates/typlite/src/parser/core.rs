@@ -1,5 +1,6 @@
 //! HTML parser core, containing main structures and general parsing logic
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use cmark_writer::ast::{CustomNode, HtmlAttribute, HtmlElement as CmarkHtmlElement, Node};
@@ -10,8 +11,10 @@ use typst::html::{HtmlElement, HtmlNode, tag};
 
 use crate::Result;
 use crate::TypliteFeat;
-use crate::attributes::{AlertsAttr, HeadingAttr, RawAttr, TypliteAttrsParser, md_attr};
-use crate::common::{AlertNode, CenterNode, VerbatimNode};
+use crate::attributes::{AlertsAttr, HeadingAttr, MathAttr, RawAttr, TypliteAttrsParser, md_attr};
+use crate::common::{
+    AlertNode, CenterNode, FootnoteNode, FootnotesNode, Format, MathNode, VerbatimNode,
+};
 use crate::tags::md_tag;
 
 use super::{list::ListParser, table::TableParser};
@@ -24,6 +27,12 @@ pub struct HtmlToAstParser {
     pub list_level: usize,
     pub blocks: Vec<Node>,
     pub inline_buffer: Vec<Node>,
+    /// Whether the document has an outline linking to its headings
+    pub outlined: bool,
+    pub heading_anchors: HashMap<EcoString, usize>,
+    pub outline_anchors: HashMap<EcoString, usize>,
+    /// The footnotes of the document, in document order
+    pub footnotes: Vec<FootnoteNode>,
 }
 
 impl HtmlToAstParser {
@@ -35,6 +44,10 @@ impl HtmlToAstParser {
             list_level: 0,
             blocks: Vec::new(),
             inline_buffer: Vec::new(),
+            outlined: false,
+            heading_anchors: HashMap::new(),
+            outline_anchors: HashMap::new(),
+            footnotes: Vec::new(),
         }
     }
 
@@ -86,6 +99,9 @@ impl HtmlToAstParser {
                 self.flush_inline_buffer();
                 let attrs = HeadingAttr::parse(&element.attrs)?;
                 self.convert_children(element)?;
+                if let Some(anchor) = self.heading_anchor(&attrs) {
+                    self.inline_buffer.insert(0, anchor);
+                }
                 self.flush_inline_buffer_as_block(|content| {
                     Node::heading(attrs.level as u8 + 1, content)
                 });
@@ -121,6 +137,12 @@ impl HtmlToAstParser {
             md_tag::highlight => self.convert_highlight(element),
             md_tag::strike => self.convert_strikethrough(element),
             md_tag::link => self.convert_link(element),
+            md_tag::label => self.convert_label(element),
+            md_tag::reference => self.convert_reference(element),
+            md_tag::outline => self.convert_outline(element),
+            md_tag::footnote => self.convert_footnote(element),
+            md_tag::cite => self.convert_cite(element),
+            md_tag::bibliography => self.convert_bibliography(element),
             md_tag::image => self.convert_image(element),
 
             md_tag::linebreak => {
@@ -148,6 +170,25 @@ impl HtmlToAstParser {
                 Ok(())
             }
 
+            md_tag::math_equation_inline | md_tag::math_equation_block
+                if matches!(self.feat.target, Format::Rst | Format::AsciiDoc) =>
+            {
+                // The formats have native math markups of TeX
+                let attrs = MathAttr::parse(&element.attrs)?;
+                let block = element.tag == md_tag::math_equation_block;
+                let math = Node::Custom(Box::new(MathNode {
+                    block,
+                    tex: attrs.tex,
+                }));
+                if block {
+                    self.flush_inline_buffer();
+                    self.blocks.push(math);
+                } else {
+                    self.inline_buffer.push(math);
+                }
+                Ok(())
+            }
+
             md_tag::math_equation_inline | md_tag::math_equation_block => {
                 if element.tag == md_tag::math_equation_block {
                     self.flush_inline_buffer();
@@ -343,6 +384,8 @@ impl HtmlToAstParser {
                 | md_tag::table
                 | md_tag::grid
                 | md_tag::figure
+                | md_tag::outline
+                | md_tag::bibliography
         )
     }
 
@@ -381,10 +424,17 @@ impl HtmlToAstParser {
     pub fn parse(mut self, root: &HtmlElement) -> Result<Node> {
         self.blocks.clear();
         self.inline_buffer.clear();
+        self.outlined = Self::has_outline(root);
 
         self.convert_element(root)?;
         self.flush_inline_buffer();
 
+        if !self.footnotes.is_empty() {
+            let footnotes = std::mem::take(&mut self.footnotes);
+            self.blocks
+                .push(Node::Custom(Box::new(FootnotesNode { footnotes })));
+        }
+
         Ok(Node::Document(self.blocks))
     }
 }
@@ -5,7 +5,7 @@ use typst::html::HtmlElement;
 
 use crate::Result;
 use crate::attributes::{FigureAttr, ImageAttr, LinkAttr, TypliteAttrsParser};
-use crate::common::{CenterNode, FigureNode, HighlightNode};
+use crate::common::{AnchorNode, CenterNode, FigureNode, HighlightNode};
 
 use super::core::HtmlToAstParser;
 
@@ -77,6 +77,9 @@ impl HtmlToAstParser {
 
         // Find image and body content
         let mut body_content = Vec::new();
+        if !attrs.label.is_empty() {
+            body_content.push(Node::Custom(Box::new(AnchorNode { id: attrs.label })));
+        }
         self.convert_children_into(&mut body_content, element)?;
         let body = Box::new(Node::Paragraph(body_content));
 
@@ -132,6 +132,18 @@ impl HtmlToAstParser {
     }
 
     fn convert_svg(&mut self, svg: String) -> Node {
+        // Frames are embedded in the package by the EPUB writer
+        #[cfg(feature = "epub")]
+        if self.feat.target == crate::Format::Epub {
+            let file_id = self.asset_counter;
+            self.asset_counter += 1;
+            return Node::Custom(Box::new(ExternalFrameNode {
+                file_path: PathBuf::from(format!("frame_{file_id}.svg")),
+                alt_text: EcoString::inline("typst-frame"),
+                svg,
+            }));
+        }
+
         let frame_url = self.create_asset_url(&svg);
 
         match frame_url {
@@ -4,6 +4,8 @@ mod core;
 mod inline;
 mod list;
 mod media;
+mod note;
+mod reference;
 mod table;
 
 pub use core::HtmlToAstParser;
@@ -0,0 +1,137 @@
+//! Note processing module, handles footnotes, citations and bibliographies
+
+use std::path::Path;
+
+use cmark_writer::ast::Node;
+use ecow::EcoString;
+use typst::World;
+use typst::html::{HtmlElement, HtmlNode, tag};
+
+use crate::Result;
+use crate::attributes::{BibItemAttr, BibliographyAttr, CiteAttr, HeadingAttr, TypliteAttrsParser};
+use crate::common::{BibEntry, BibliographyNode, CiteNode, FootnoteNode, Format};
+use crate::tags::md_tag;
+
+use super::core::HtmlToAstParser;
+
+impl HtmlToAstParser {
+    /// Convert footnote element, which is numbered in document order
+    pub fn convert_footnote(&mut self, element: &HtmlElement) -> Result<()> {
+        let mut content = Vec::new();
+        self.convert_children_into(&mut content, element)?;
+        let footnote = FootnoteNode {
+            number: self.footnotes.len() + 1,
+            content,
+        };
+        self.footnotes.push(footnote.clone());
+        self.inline_buffer.push(Node::Custom(Box::new(footnote)));
+        Ok(())
+    }
+
+    /// Convert citation element
+    pub fn convert_cite(&mut self, element: &HtmlElement) -> Result<()> {
+        let attrs = CiteAttr::parse(&element.attrs)?;
+        let mut content = Vec::new();
+        self.convert_children_into(&mut content, element)?;
+        self.inline_buffer.push(Node::Custom(Box::new(CiteNode {
+            key: attrs.key,
+            supplement: attrs.supplement,
+            content,
+        })));
+        Ok(())
+    }
+
+    /// Convert bibliography element to a reference list
+    pub fn convert_bibliography(&mut self, element: &HtmlElement) -> Result<()> {
+        self.flush_inline_buffer();
+
+        let attrs = BibliographyAttr::parse(&element.attrs)?;
+        let sources = attrs
+            .src
+            .split(',')
+            .filter(|path| !path.is_empty())
+            .map(|path| self.bibliography_source(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut bibliography = BibliographyNode {
+            title: None,
+            entries: Vec::new(),
+            sources,
+            style: attrs.style,
+        };
+        self.collect_bibliography(&mut bibliography, element)?;
+        self.blocks.push(Node::Custom(Box::new(bibliography)));
+        Ok(())
+    }
+
+    fn collect_bibliography(
+        &mut self,
+        bibliography: &mut BibliographyNode,
+        element: &HtmlElement,
+    ) -> Result<()> {
+        for child in &element.children {
+            let HtmlNode::Element(child) = child else {
+                continue;
+            };
+
+            match child.tag {
+                md_tag::heading
+                    if bibliography.title.is_none() && bibliography.entries.is_empty() =>
+                {
+                    let attrs = HeadingAttr::parse(&child.attrs)?;
+                    let mut content = Vec::new();
+                    self.convert_children_into(&mut content, child)?;
+                    bibliography.title = Some(Node::heading(attrs.level as u8 + 1, content));
+                }
+                md_tag::bib_item => {
+                    let attrs = BibItemAttr::parse(&child.attrs)?;
+                    let mut content = Vec::new();
+                    self.convert_children_into(&mut content, child)?;
+                    bibliography.entries.push(BibEntry {
+                        prefix: attrs.prefix,
+                        content,
+                    });
+                }
+                // Styles without prefixes render each entry as a paragraph
+                tag::p => {
+                    let mut content = Vec::new();
+                    self.convert_children_into(&mut content, child)?;
+                    if !content.is_empty() {
+                        bibliography.entries.push(BibEntry {
+                            prefix: EcoString::new(),
+                            content,
+                        });
+                    }
+                }
+                _ => self.collect_bibliography(bibliography, child)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the path of a bibliography file to be referenced by the output.
+    ///
+    /// The file is copied to the assets directory for LaTeX output, so that
+    /// the document can be compiled along with its bibliography.
+    fn bibliography_source(&self, path: &str) -> Result<EcoString> {
+        let Some(assets_path) = &self.feat.assets_path else {
+            return Ok(path.into());
+        };
+        if self.feat.target != Format::LaTeX {
+            return Ok(path.into());
+        }
+        let Some(file_name) = Path::new(path).file_name() else {
+            return Ok(path.into());
+        };
+
+        let id = self.world.main().join(path);
+        let data = self
+            .world
+            .file(id)
+            .map_err(|err| format!("failed to read bibliography file {path}: {err}"))?;
+
+        let file_path = assets_path.join(file_name);
+        std::fs::write(&file_path, data.as_slice())?;
+        Ok(file_path.to_string_lossy().replace('\\', "/").into())
+    }
+}
@@ -0,0 +1,171 @@
+//! Cross-reference processing module, handles labels, references and outlines
+
+use std::collections::HashMap;
+
+use cmark_writer::ast::Node;
+use ecow::{EcoString, eco_format};
+use typst::html::{HtmlElement, HtmlNode};
+
+use crate::Result;
+use crate::attributes::{HeadingAttr, LabelAttr, OutlineEntryAttr, RefAttr, TypliteAttrsParser};
+use crate::common::{AnchorNode, OutlineEntry, OutlineNode, ReferenceNode};
+use crate::tags::md_tag;
+
+use super::core::HtmlToAstParser;
+
+impl HtmlToAstParser {
+    /// Convert label element, which marks its content as a link target
+    pub fn convert_label(&mut self, element: &HtmlElement) -> Result<()> {
+        let attrs = LabelAttr::parse(&element.attrs)?;
+        if !attrs.dest.is_empty() {
+            self.inline_buffer.push(anchor(attrs.dest));
+        }
+        self.convert_children(element)
+    }
+
+    /// Convert reference element
+    pub fn convert_reference(&mut self, element: &HtmlElement) -> Result<()> {
+        let attrs = RefAttr::parse(&element.attrs)?;
+        // References to bibliography entries are not resolved to elements
+        if attrs.dest.is_empty() {
+            return self.convert_children(element);
+        }
+
+        let mut content = Vec::new();
+        self.convert_children_into(&mut content, element)?;
+        self.inline_buffer
+            .push(Node::Custom(Box::new(ReferenceNode {
+                target: attrs.dest,
+                supplement: attrs.supplement,
+                content,
+            })));
+        Ok(())
+    }
+
+    /// Convert outline element to a table of contents
+    pub fn convert_outline(&mut self, element: &HtmlElement) -> Result<()> {
+        self.flush_inline_buffer();
+
+        let mut outline = OutlineNode {
+            title: None,
+            entries: Vec::new(),
+        };
+        self.collect_outline(&mut outline, element)?;
+        self.blocks.push(Node::Custom(Box::new(outline)));
+        Ok(())
+    }
+
+    fn collect_outline(&mut self, outline: &mut OutlineNode, element: &HtmlElement) -> Result<()> {
+        for child in &element.children {
+            let HtmlNode::Element(child) = child else {
+                continue;
+            };
+
+            match child.tag {
+                md_tag::heading if outline.title.is_none() && outline.entries.is_empty() => {
+                    let attrs = HeadingAttr::parse(&child.attrs)?;
+                    let mut content = Vec::new();
+                    self.convert_children_into(&mut content, child)?;
+                    outline.title = Some(Node::heading(attrs.level as u8 + 1, content));
+                }
+                md_tag::outline_entry => {
+                    let attrs = OutlineEntryAttr::parse(&child.attrs)?;
+                    let mut content = Vec::new();
+                    self.convert_children_into(&mut content, child)?;
+                    let target = if attrs.dest.is_empty() {
+                        let slug = slugify(&plain_text(&content));
+                        unique_anchor(&mut self.outline_anchors, slug)
+                    } else {
+                        attrs.dest
+                    };
+                    outline.entries.push(OutlineEntry {
+                        level: attrs.level as u8 + 1,
+                        target,
+                        prefix: attrs.prefix,
+                        content,
+                    });
+                }
+                _ => self.collect_outline(outline, child)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the anchor of the heading in the inline buffer, which is its
+    /// label, or a slug of its content if the document has an outline linking
+    /// to it
+    pub(crate) fn heading_anchor(&mut self, attrs: &HeadingAttr) -> Option<Node> {
+        if !attrs.label.is_empty() {
+            return Some(anchor(attrs.label.clone()));
+        }
+        if !self.outlined {
+            return None;
+        }
+
+        let slug = slugify(&plain_text(&self.inline_buffer));
+        Some(anchor(unique_anchor(&mut self.heading_anchors, slug)))
+    }
+
+    /// Check whether the element contains an outline
+    pub(crate) fn has_outline(element: &HtmlElement) -> bool {
+        element.children.iter().any(|child| match child {
+            HtmlNode::Element(child) => child.tag == md_tag::outline || Self::has_outline(child),
+            _ => false,
+        })
+    }
+}
+
+fn anchor(id: EcoString) -> Node {
+    Node::Custom(Box::new(AnchorNode { id }))
+}
+
+/// Deduplicate a slug by appending the count of its previous occurrences
+fn unique_anchor(counts: &mut HashMap<EcoString, usize>, slug: EcoString) -> EcoString {
+    let count = counts.entry(slug.clone()).or_default();
+    let id = if *count == 0 {
+        slug
+    } else {
+        eco_format!("{slug}-{count}")
+    };
+    *count += 1;
+    id
+}
+
+/// Create a slug like `section-title` from the text of a heading
+fn slugify(text: &str) -> EcoString {
+    let mut slug = EcoString::new();
+    for ch in text.trim().chars() {
+        if ch.is_alphanumeric() {
+            for ch in ch.to_lowercase() {
+                slug.push(ch);
+            }
+        } else if (ch.is_whitespace() || ch == '-' || ch == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".into()
+    } else {
+        slug.into()
+    }
+}
+
+/// Get the plain text of inline nodes
+fn plain_text(nodes: &[Node]) -> EcoString {
+    let mut text = EcoString::new();
+    for node in nodes {
+        match node {
+            Node::Text(content) | Node::InlineCode(content) => text.push_str(content),
+            Node::Strong(content)
+            | Node::Emphasis(content)
+            | Node::Strikethrough(content)
+            | Node::Link { content, .. } => text.push_str(&plain_text(content)),
+            Node::HtmlElement(element) => text.push_str(&plain_text(&element.children)),
+            Node::SoftBreak | Node::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
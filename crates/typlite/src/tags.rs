@@ -36,6 +36,10 @@ pub mod md_tag {
         grid -> m1grid
         // grid_cell -> m1gridcell
         figure -> m1figure
+        footnote -> m1footnote
+        cite -> m1cite
+        bibliography -> m1bibliography
+        bib_item -> m1bibitem
 
         math_equation_inline -> m1eqinline
         math_equation_block -> m1eqblock
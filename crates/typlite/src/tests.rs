@@ -5,6 +5,7 @@ use typst::html::{HtmlNode, HtmlTag};
 use typst_syntax::Span;
 
 use super::*;
+use crate::common::FormatWriter;
 
 pub fn snapshot_testing(name: &str, f: &impl Fn(LspWorld, PathBuf)) {
     tinymist_tests::snapshot_testing!(name, |verse, path| {
@@ -26,6 +27,48 @@ fn convert_tex() {
     });
 }
 
+#[test]
+fn convert_rst() {
+    snapshot_testing("integration", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::Rst));
+    });
+}
+
+#[test]
+fn convert_asciidoc() {
+    snapshot_testing("integration", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::AsciiDoc));
+    });
+}
+
+#[test]
+fn convert_references() {
+    snapshot_testing("references", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::Md { for_docs: false }));
+    });
+}
+
+#[test]
+fn convert_references_tex() {
+    snapshot_testing("references", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::LaTeX));
+    });
+}
+
+#[test]
+fn convert_notes() {
+    snapshot_testing("notes", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::Md { for_docs: false }));
+    });
+}
+
+#[test]
+fn convert_notes_tex() {
+    snapshot_testing("notes", &|world, _path| {
+        insta::assert_snapshot!(conv(world, ConvKind::LaTeX));
+    });
+}
+
 #[test]
 fn convert_docs() {
     snapshot_testing("docs", &|world, _path| {
@@ -69,16 +112,294 @@ fn test_docx_generation() {
     });
 }
 
+#[test]
+#[cfg(feature = "docx")]
+fn test_docx_references() {
+    snapshot_testing("references", &|world, _path| {
+        let document = docx_part(world, "word/document.xml");
+
+        // Labels become bookmarks, with `-` replaced as Word requires.
+        for name in ["intro", "results", "fig_diagram"] {
+            let bookmark = format!(r#"w:name="{name}""#);
+            assert!(document.contains(&bookmark), "missing bookmark {name}");
+        }
+        // References link to the bookmarks of their targets.
+        assert!(document.contains(r#"w:anchor="results""#));
+        assert!(document.contains(r#"w:anchor="fig_diagram""#));
+        // The outline is a TOC field updated by Word.
+        assert!(document.contains(r"TOC \o"));
+    });
+}
+
+#[test]
+#[cfg(feature = "docx")]
+fn test_docx_notes() {
+    snapshot_testing("notes", &|world, _path| {
+        let document = docx_part(world.clone(), "word/document.xml");
+        assert!(document.contains("w:footnoteReference"));
+        assert!(document.contains("[1, p. 7]"));
+        assert!(document.contains(r#"w:val="Bibliography""#));
+
+        let footnotes = docx_part(world, "word/footnotes.xml");
+        assert!(footnotes.contains("They are numbered in order."));
+        assert!(footnotes.contains("Another note."));
+    });
+}
+
+/// Converts the document to DOCX and reads a part of the archive.
+#[cfg(feature = "docx")]
+fn docx_part(world: LspWorld, name: &str) -> String {
+    let converter = Typlite::new(Arc::new(world)).with_format(Format::Docx);
+    let docx_data = converter.to_docx().expect("failed to generate DOCX");
+    archive_part(docx_data, name)
+}
+
+#[test]
+#[cfg(feature = "epub")]
+fn test_epub_generation() {
+    snapshot_testing("epub", &|world, _path| {
+        let converter = Typlite::new(Arc::new(world)).with_format(Format::Epub);
+        let epub_data = converter.to_epub().expect("failed to generate EPUB");
+
+        // The mimetype is the first entry, which is not compressed
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(epub_data.clone()))
+            .expect("invalid EPUB archive");
+        let mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), zip::CompressionMethod::Stored);
+        assert_eq!(
+            archive_part(epub_data.clone(), "mimetype"),
+            "application/epub+zip"
+        );
+
+        let opf = archive_part(epub_data.clone(), "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Book</dc:title>"));
+        assert!(
+            opf.contains(r#"href="nav.xhtml" media-type="application/xhtml+xml" properties="nav""#)
+        );
+        assert!(opf.contains(r#"href="chapter_1.xhtml""#));
+        assert!(opf.contains(r#"href="chapter_2.xhtml""#));
+        assert!(!opf.contains("chapter_3.xhtml"));
+        assert!(opf.contains(r#"href="images/image_0.svg" media-type="image/svg+xml""#));
+        assert_eq!(opf.matches("<itemref ").count(), 2);
+
+        let nav = archive_part(epub_data.clone(), "OEBPS/nav.xhtml");
+        assert!(nav.contains(r##"<a href="chapter_1.xhtml#first">First</a>"##));
+        assert!(nav.contains(r##"<a href="chapter_2.xhtml#heading-1">Second</a>"##));
+
+        // The document is split into chapters at its top-level headings
+        let first = archive_part(epub_data.clone(), "OEBPS/chapter_1.xhtml");
+        assert!(first.contains("First"));
+        assert!(!first.contains("Second"));
+        assert!(first.contains(r#"src="images/image_0.svg""#));
+
+        // References to other chapters link to their files
+        let second = archive_part(epub_data, "OEBPS/chapter_2.xhtml");
+        assert!(second.contains("Second"));
+        assert!(second.contains(r##"href="chapter_1.xhtml#first""##));
+    });
+}
+
+/// Reads a part of a zip archive as a string.
+#[cfg(any(feature = "docx", feature = "epub"))]
+fn archive_part(data: Vec<u8>, name: &str) -> String {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).expect("invalid archive");
+    let mut part = String::new();
+    archive
+        .by_name(name)
+        .unwrap_or_else(|err| panic!("missing {name}: {err}"))
+        .read_to_string(&mut part)
+        .unwrap();
+    part
+}
+
+#[test]
+fn import_markdown() {
+    let source = r#"# Title
+
+Some *emph* and **strong** text with `code` and [a link](https://example.com).
+
+- First
+- Second
+  1. Nested
+
+| A | B |
+|:--|--:|
+| 1 | 2 |
+"#;
+
+    let typst = Typlite::import(source, Format::Md).unwrap();
+    assert_eq!(
+        typst,
+        r#"= Title
+
+Some _emph_ and *strong* text with `code` and #link("https://example.com")[a link].
+
+- First
+- Second
+  + Nested
+
+#table(
+  columns: 2,
+  align: (left, right),
+  table.header([A], [B]),
+  [1], [2],
+)
+"#
+    );
+}
+
+#[test]
+fn import_markdown_footnote_math() {
+    let source = r#"Euler's identity $e^{i\pi} + 1 = 0$.[^1]
+
+[^1]: See *Wikipedia*.
+"#;
+
+    let typst = Typlite::import(source, Format::Md).unwrap();
+    assert_eq!(
+        typst,
+        r#"#import "@preview/mitex:0.2.4": mi, mitex
+
+Euler's identity #mi(`e^{i\pi} + 1 = 0`).#footnote[See _Wikipedia_.]
+"#
+    );
+}
+
+/// A document covering the blocks written natively by the reST and AsciiDoc
+/// writers
+fn native_markup_document() -> Node {
+    use crate::common::{AlertNode, AnchorNode, MathNode, ReferenceNode};
+    use cmark_writer::ast::ListItem;
+
+    let text = |text: &str| Node::Text(text.into());
+    Node::Document(vec![
+        Node::heading(
+            2,
+            vec![
+                text("Intro"),
+                Node::Custom(Box::new(AnchorNode { id: "intro".into() })),
+            ],
+        ),
+        Node::Paragraph(vec![
+            text("See "),
+            Node::Custom(Box::new(ReferenceNode {
+                target: "intro".into(),
+                supplement: "Section".into(),
+                content: vec![text("Section 1")],
+            })),
+            text("."),
+        ]),
+        Node::Paragraph(vec![
+            text("Inline "),
+            Node::Custom(Box::new(MathNode {
+                block: false,
+                tex: "x_{1}".into(),
+            })),
+        ]),
+        Node::UnorderedList(vec![
+            ListItem::Unordered {
+                content: vec![Node::Paragraph(vec![text("First")])],
+            },
+            ListItem::Unordered {
+                content: vec![Node::Paragraph(vec![text("Second")])],
+            },
+        ]),
+        Node::code_block(Some("rust".into()), "fn main() {}".into()),
+        Node::Custom(Box::new(AlertNode {
+            content: vec![Node::Paragraph(vec![text("Careful")])],
+            class: "warning".into(),
+        })),
+        Node::Custom(Box::new(MathNode {
+            block: true,
+            tex: "a^{2}".into(),
+        })),
+    ])
+}
+
+#[test]
+fn write_rst() {
+    let mut output = ecow::EcoString::new();
+    writer::RstWriter::new()
+        .write_eco(&native_markup_document(), &mut output)
+        .unwrap();
+    assert_eq!(
+        output.trim_end(),
+        r#".. _intro:
+
+Intro
+=====
+
+See :ref:`Section 1 <intro>`.
+
+Inline :math:`x_{1}`
+
+- First
+- Second
+
+.. code-block:: rust
+
+   fn main() {}
+
+.. warning::
+
+   Careful
+
+.. math::
+
+   a^{2}"#
+    );
+}
+
+#[test]
+fn write_asciidoc() {
+    let mut output = ecow::EcoString::new();
+    writer::AsciiDocWriter::new()
+        .write_eco(&native_markup_document(), &mut output)
+        .unwrap();
+    assert_eq!(
+        output.trim_end(),
+        r#"[[intro]]
+== Intro
+
+See <<intro,Section 1>>.
+
+Inline latexmath:[x_{1}]
+
+* First
+* Second
+
+[source,rust]
+----
+fn main() {}
+----
+
+[WARNING]
+====
+Careful
+====
+
+[latexmath]
+++++
+a^{2}
+++++"#
+    );
+}
+
 enum ConvKind {
     Md { for_docs: bool },
     LaTeX,
+    Rst,
+    AsciiDoc,
 }
 
 impl ConvKind {
     fn for_docs(&self) -> bool {
         match self {
             ConvKind::Md { for_docs } => *for_docs,
-            ConvKind::LaTeX => false,
+            ConvKind::LaTeX | ConvKind::Rst | ConvKind::AsciiDoc => false,
         }
     }
 
@@ -86,6 +407,8 @@ impl ConvKind {
         match self {
             ConvKind::Md { .. } => Format::Md,
             ConvKind::LaTeX => Format::LaTeX,
+            ConvKind::Rst => Format::Rst,
+            ConvKind::AsciiDoc => Format::AsciiDoc,
         }
     }
 }
@@ -105,6 +428,8 @@ fn conv(world: LspWorld, kind: ConvKind) -> String {
     let res = match kind {
         ConvKind::Md { .. } => doc.to_md_string().unwrap(),
         ConvKind::LaTeX => doc.to_tex_string().unwrap(),
+        ConvKind::Rst => doc.to_rst_string().unwrap(),
+        ConvKind::AsciiDoc => doc.to_asciidoc_string().unwrap(),
     };
 
     static REG: OnceLock<Regex> = OnceLock::new();
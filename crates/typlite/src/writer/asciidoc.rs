@@ -0,0 +1,557 @@
+//! AsciiDoc writer implementation
+
+use cmark_writer::WriterOptions;
+use cmark_writer::ast::{HtmlElement, ListItem, Node};
+use cmark_writer::writer::CommonMarkWriter;
+use ecow::EcoString;
+use tinymist_std::path::unix_slash;
+
+use crate::Result;
+use crate::common::{
+    AlertNode, AnchorNode, BibliographyNode, CenterNode, CiteNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FormatWriter, HighlightNode, InlineNode, MathNode, OutlineNode, ReferenceNode,
+    VerbatimNode,
+};
+
+/// AsciiDoc writer implementation, which targets Asciidoctor
+#[derive(Default)]
+pub struct AsciiDocWriter {
+    /// The depth of the lists being written
+    list_depth: usize,
+}
+
+impl AsciiDocWriter {
+    pub fn new() -> Self {
+        Self { list_depth: 0 }
+    }
+
+    fn write_inline_nodes(&mut self, nodes: &[Node], output: &mut EcoString) -> Result<()> {
+        for node in nodes {
+            self.write_node(node, output)?;
+        }
+        Ok(())
+    }
+
+    /// Write a paragraph, whose anchors become the anchors of the following
+    /// block if the paragraph is empty and whose images become block images
+    fn write_paragraph(&mut self, inlines: &[Node], output: &mut EcoString) -> Result<()> {
+        let content = inlines
+            .iter()
+            .filter(|node| !matches!(node, Node::Text(text) if text.trim().is_empty()))
+            .collect::<Vec<_>>();
+        if content.is_empty() {
+            return Ok(());
+        }
+
+        if content
+            .iter()
+            .all(|node| node.is_custom_type::<AnchorNode>())
+        {
+            for node in content {
+                self.write_node(node, output)?;
+                output.push('\n');
+            }
+            return Ok(());
+        }
+
+        let (anchors, rest): (Vec<_>, Vec<_>) = content
+            .iter()
+            .partition(|node| node.is_custom_type::<AnchorNode>());
+        if rest.iter().all(|node| image_source(node).is_some()) {
+            for anchor in anchors {
+                self.write_node(anchor, output)?;
+                output.push('\n');
+            }
+            for node in rest {
+                if let Some((src, alt)) = image_source(node) {
+                    write_block_image(&src, &alt, output);
+                }
+            }
+            return Ok(());
+        }
+
+        for node in content {
+            self.write_node(node, output)?;
+        }
+        output.push_str("\n\n");
+        Ok(())
+    }
+
+    fn write_list(
+        &mut self,
+        items: &[ListItem],
+        start: Option<u32>,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        self.list_depth += 1;
+        let marker = match start {
+            Some(_) => ".".repeat(self.list_depth),
+            None => "*".repeat(self.list_depth),
+        };
+        // The first item may be numbered explicitly, e.g. `2. A`
+        let start = start.map(|start| match items.first() {
+            Some(ListItem::Ordered {
+                number: Some(value),
+                ..
+            }) => *value,
+            _ => start,
+        });
+        if let Some(start) = start.filter(|start| *start != 1) {
+            output.push_str(&format!("[start={start}]\n"));
+        }
+
+        for item in items {
+            let content = match item {
+                ListItem::Ordered { content, .. } | ListItem::Unordered { content } => content,
+                _ => continue,
+            };
+
+            output.push_str(&marker);
+            output.push(' ');
+            for (idx, block) in content.iter().enumerate() {
+                match block {
+                    // The first paragraph is the text of the item
+                    Node::Paragraph(inlines) if idx == 0 => {
+                        self.write_inline_nodes(inlines, output)?;
+                        output.push('\n');
+                    }
+                    // Nested lists are attached to the item without a blank line
+                    Node::OrderedList { .. } | Node::UnorderedList(..) => {
+                        let mut nested = EcoString::new();
+                        self.write_node(block, &mut nested)?;
+                        output.push_str(nested.trim_end());
+                        output.push('\n');
+                    }
+                    // Other blocks are attached by list continuations
+                    _ => {
+                        let mut nested = EcoString::new();
+                        self.write_node(block, &mut nested)?;
+                        if idx > 0 {
+                            output.push_str("+\n");
+                        }
+                        output.push_str(nested.trim_end());
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+
+        self.list_depth -= 1;
+        if self.list_depth == 0 {
+            output.push('\n');
+        }
+        Ok(())
+    }
+
+    fn write_table(
+        &mut self,
+        headers: &[Node],
+        rows: &[Vec<Node>],
+        output: &mut EcoString,
+    ) -> Result<()> {
+        let col_count = headers
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        if col_count == 0 {
+            return Ok(());
+        }
+
+        if headers.is_empty() {
+            output.push_str(&format!("[cols=\"{col_count}*\"]\n"));
+        } else {
+            output.push_str(&format!("[cols=\"{col_count}*\",options=\"header\"]\n"));
+        }
+        output.push_str("|===\n");
+        for row in std::iter::once(headers)
+            .filter(|headers| !headers.is_empty())
+            .chain(rows.iter().map(Vec::as_slice))
+        {
+            for cell in row {
+                let mut text = EcoString::new();
+                self.write_node(cell, &mut text)?;
+                output.push_str("| ");
+                output.push_str(&text.trim().replace('|', "\\|"));
+                output.push('\n');
+            }
+            output.push('\n');
+        }
+        output.push_str("|===\n\n");
+        Ok(())
+    }
+
+    fn write_figure(&mut self, figure: &FigureNode, output: &mut EcoString) -> Result<()> {
+        // The caption is the title of the block
+        let title = |output: &mut EcoString| {
+            if !figure.caption.is_empty() {
+                output.push('.');
+                output.push_str(&escape_adoc(&figure.caption));
+                output.push('\n');
+            }
+        };
+
+        match &*figure.body {
+            Node::Paragraph(content) => {
+                let (anchors, content): (Vec<_>, Vec<_>) = content
+                    .iter()
+                    .partition(|node| node.is_custom_type::<AnchorNode>());
+                for anchor in anchors {
+                    self.write_node(anchor, output)?;
+                    output.push('\n');
+                }
+
+                let image = content.iter().find_map(|node| image_source(node));
+                title(output);
+                match image {
+                    Some((src, alt)) => write_block_image(&src, &alt, output),
+                    None => {
+                        let content = content.into_iter().cloned().collect::<Vec<_>>();
+                        self.write_paragraph(&content, output)?;
+                    }
+                }
+            }
+            node => {
+                title(output);
+                self.write_node(node, output)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_bibliography(
+        &mut self,
+        bibliography: &BibliographyNode,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        if let Some(title) = &bibliography.title {
+            self.write_node(title, output)?;
+        }
+        for entry in &bibliography.entries {
+            self.write_node(&entry.to_paragraph(), output)?;
+        }
+        Ok(())
+    }
+
+    /// Write the document to AsciiDoc format
+    fn write_node(&mut self, node: &Node, output: &mut EcoString) -> Result<()> {
+        match node {
+            Node::Document(blocks) => {
+                for block in blocks {
+                    match block {
+                        // HTML blocks, e.g. tables with merged cells, are passed through
+                        Node::HtmlElement(..) => {
+                            output.push_str("++++\n");
+                            output.push_str(&html_source(block)?);
+                            output.push_str("\n++++\n\n");
+                        }
+                        _ => self.write_node(block, output)?,
+                    }
+                }
+            }
+            Node::Paragraph(inlines) => self.write_paragraph(inlines, output)?,
+            Node::Heading {
+                level,
+                content,
+                heading_type: _,
+            } => {
+                // Labels are the anchors of the section
+                let (anchors, content): (Vec<_>, Vec<_>) = content
+                    .iter()
+                    .partition(|node| node.is_custom_type::<AnchorNode>());
+                for anchor in anchors {
+                    self.write_node(anchor, output)?;
+                    output.push('\n');
+                }
+
+                output.push_str(&"=".repeat((*level).clamp(1, 6) as usize));
+                output.push(' ');
+                let mut title = EcoString::new();
+                for node in content {
+                    self.write_node(node, &mut title)?;
+                }
+                output.push_str(title.trim());
+                output.push_str("\n\n");
+            }
+            Node::BlockQuote(content) => {
+                output.push_str("____\n");
+                let mut nested = EcoString::new();
+                for block in content {
+                    self.write_node(block, &mut nested)?;
+                }
+                output.push_str(nested.trim_end());
+                output.push_str("\n____\n\n");
+            }
+            Node::CodeBlock {
+                language,
+                content,
+                block_type: _,
+            } => {
+                if let Some(lang) = language.as_deref().filter(|lang| !lang.is_empty()) {
+                    output.push_str("[source,");
+                    output.push_str(lang);
+                    output.push_str("]\n");
+                }
+
+                // The delimiter must not appear in the content
+                let delimiter = std::iter::successors(Some("----".to_owned()), |delimiter| {
+                    Some(format!("{delimiter}-"))
+                })
+                .find(|delimiter| !content.lines().any(|line| line.trim_end() == delimiter))
+                .unwrap_or_default();
+
+                output.push_str(&delimiter);
+                output.push('\n');
+                output.push_str(content.trim_end_matches('\n'));
+                output.push('\n');
+                output.push_str(&delimiter);
+                output.push_str("\n\n");
+            }
+            Node::OrderedList { start, items } => self.write_list(items, Some(*start), output)?,
+            Node::UnorderedList(items) => self.write_list(items, None, output)?,
+            Node::Table {
+                headers,
+                rows,
+                alignments: _,
+            } => self.write_table(headers, rows, output)?,
+            Node::ThematicBreak => {
+                output.push_str("'''\n\n");
+            }
+            node if node.is_custom_type::<FigureNode>() => {
+                let figure = node.as_custom_type::<FigureNode>().unwrap();
+                self.write_figure(figure, output)?;
+            }
+            node if node.is_custom_type::<ExternalFrameNode>() => {
+                let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+                let path = unix_slash(&frame.file_path);
+                write_block_image(&path, &frame.alt_text, output);
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let center = node.as_custom_type::<CenterNode>().unwrap();
+                match &center.node {
+                    Node::HtmlElement(element) => {
+                        self.write_paragraph(&element.children, output)?
+                    }
+                    node => self.write_node(node, output)?,
+                }
+            }
+            node if node.is_custom_type::<AlertNode>() => {
+                let alert = node.as_custom_type::<AlertNode>().unwrap();
+                let class = alert.class.to_ascii_uppercase();
+                match class.as_str() {
+                    "NOTE" | "TIP" | "IMPORTANT" | "WARNING" | "CAUTION" => {
+                        output.push_str(&format!("[{class}]\n"));
+                    }
+                    _ => {
+                        output.push_str(&format!(".{}\n[NOTE]\n", escape_adoc(&alert.class)));
+                    }
+                }
+                output.push_str("====\n");
+                let mut nested = EcoString::new();
+                for block in &alert.content {
+                    self.write_node(block, &mut nested)?;
+                }
+                output.push_str(nested.trim_end());
+                output.push_str("\n====\n\n");
+            }
+            node if node.is_custom_type::<MathNode>() => {
+                let math = node.as_custom_type::<MathNode>().unwrap();
+                if math.block {
+                    output.push_str("[latexmath]\n++++\n");
+                    output.push_str(&math.tex);
+                    output.push_str("\n++++\n\n");
+                } else {
+                    output.push_str("latexmath:[");
+                    output.push_str(&math.tex.replace(']', "\\]"));
+                    output.push(']');
+                }
+            }
+            node if node.is_custom_type::<AnchorNode>() => {
+                let anchor = node.as_custom_type::<AnchorNode>().unwrap();
+                output.push_str("[[");
+                output.push_str(&anchor.id);
+                output.push_str("]]");
+            }
+            node if node.is_custom_type::<ReferenceNode>() => {
+                let reference = node.as_custom_type::<ReferenceNode>().unwrap();
+                self.write_node(&reference.to_link(), output)?;
+            }
+            node if node.is_custom_type::<OutlineNode>() => {
+                let outline = node.as_custom_type::<OutlineNode>().unwrap();
+                if let Some(title) = &outline.title {
+                    self.write_node(title, output)?;
+                }
+                self.write_node(&outline.to_list(), output)?;
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                let mut content = EcoString::new();
+                self.write_inline_nodes(&footnote.content, &mut content)?;
+                output.push_str("footnote:[");
+                output.push_str(&content.replace(']', "\\]"));
+                output.push(']');
+            }
+            node if node.is_custom_type::<CiteNode>() => {
+                let cite = node.as_custom_type::<CiteNode>().unwrap();
+                self.write_inline_nodes(&cite.content, output)?;
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                self.write_bibliography(bibliography, output)?;
+            }
+            node if node.is_custom_type::<HighlightNode>() => {
+                let highlight = node.as_custom_type::<HighlightNode>().unwrap();
+                output.push_str("##");
+                self.write_inline_nodes(&highlight.content, output)?;
+                output.push_str("##");
+            }
+            node if node.is_custom_type::<InlineNode>() => {
+                let inline = node.as_custom_type::<InlineNode>().unwrap();
+                self.write_inline_nodes(&inline.content, output)?;
+            }
+            node if node.is_custom_type::<VerbatimNode>() => {
+                let verbatim = node.as_custom_type::<VerbatimNode>().unwrap();
+                output.push_str(&verbatim.content);
+            }
+            Node::Text(text) => {
+                output.push_str(&escape_adoc(text));
+            }
+            Node::Emphasis(content) => {
+                output.push_str("__");
+                self.write_inline_nodes(content, output)?;
+                output.push_str("__");
+            }
+            Node::Strong(content) => {
+                output.push_str("**");
+                self.write_inline_nodes(content, output)?;
+                output.push_str("**");
+            }
+            Node::Strikethrough(content) => {
+                output.push_str("[.line-through]##");
+                self.write_inline_nodes(content, output)?;
+                output.push_str("##");
+            }
+            Node::Link {
+                url,
+                title: _,
+                content,
+            } => {
+                let mut text = EcoString::new();
+                self.write_inline_nodes(content, &mut text)?;
+                // Links to labels are cross-references
+                match url.strip_prefix('#') {
+                    Some(target) => output.push_str(&format!("<<{target},{text}>>")),
+                    None => output.push_str(&format!("link:{url}[{}]", text.replace(']', "\\]"))),
+                }
+            }
+            Node::Image { url, alt, .. } => {
+                let mut text = EcoString::new();
+                self.write_inline_nodes(alt, &mut text)?;
+                output.push_str(&format!("image:{url}[{}]", text.replace(']', "\\]")));
+            }
+            Node::InlineCode(code) => {
+                // The passthrough keeps the code from substitutions
+                output.push_str("`+");
+                output.push_str(code);
+                output.push_str("+`");
+            }
+            Node::HardBreak => {
+                output.push_str(" +\n");
+            }
+            Node::SoftBreak => {
+                output.push(' ');
+            }
+            Node::HtmlElement(element) => {
+                self.write_inline_nodes(&element.children, output)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn write_block_image(src: &str, alt: &str, output: &mut EcoString) {
+    output.push_str("image::");
+    output.push_str(src);
+    output.push('[');
+    output.push_str(&alt.replace(']', "\\]"));
+    output.push_str("]\n\n");
+}
+
+/// Write an HTML element as its source
+fn html_source(node: &Node) -> Result<String> {
+    let mut writer = CommonMarkWriter::with_options(WriterOptions {
+        strict: false,
+        ..Default::default()
+    });
+    writer
+        .write(node)
+        .map_err(|err| format!("failed to write HTML: {err}"))?;
+    Ok(writer.into_string().trim().to_owned())
+}
+
+/// Get the source and the alternative text of an image
+fn image_source(node: &Node) -> Option<(String, String)> {
+    match node {
+        Node::Image { url, alt, .. } => {
+            let alt = alt
+                .iter()
+                .filter_map(|node| match node {
+                    Node::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            Some((url.to_string(), alt))
+        }
+        Node::HtmlElement(HtmlElement {
+            tag, attributes, ..
+        }) if tag == "img" => {
+            let attr = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|attr| attr.name == name)
+                    .map(|attr| attr.value.to_string())
+            };
+            Some((attr("src")?, attr("alt").unwrap_or_default()))
+        }
+        node if node.is_custom_type::<ExternalFrameNode>() => {
+            let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+            Some((unix_slash(&frame.file_path), frame.alt_text.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Escape the text by passthroughs if it contains markup characters of
+/// AsciiDoc
+fn escape_adoc(text: &str) -> String {
+    const SPECIAL: &[char] = &['*', '_', '`', '#', '^', '~', '{', '[', '<', '\\'];
+    if !text.contains(SPECIAL) && !text.contains('+') {
+        return text.to_owned();
+    }
+
+    // The plus signs are written by attribute references, since they delimit
+    // the passthroughs
+    text.split('+')
+        .map(|part| {
+            if part.contains(SPECIAL) {
+                format!("++{part}++")
+            } else {
+                part.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("{plus}")
+}
+
+impl FormatWriter for AsciiDocWriter {
+    fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
+        self.write_node(document, output)?;
+        Ok(())
+    }
+
+    fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
+        let mut output = EcoString::new();
+        self.write_eco(document, &mut output)?;
+        Ok(output.as_str().as_bytes().to_vec())
+    }
+}
@@ -34,6 +34,7 @@ impl DocxStyles {
         let heading4 = Self::create_heading_style("Heading4", "Heading 4", 24);
         let heading5 = Self::create_heading_style("Heading5", "Heading 5", 22);
         let heading6 = Self::create_heading_style("Heading6", "Heading 6", 20);
+        let toc_heading = Self::create_heading_style("TOCHeading", "TOC Heading", 32);
 
         let courier_fonts = RunFonts::new()
             .ascii("Courier New")
@@ -83,6 +84,14 @@ impl DocxStyles {
             .size(16)
             .align(AlignmentType::Center);
 
+        let footnote_text = Style::new("FootnoteText", StyleType::Paragraph)
+            .name("Footnote Text")
+            .size(18);
+
+        let bibliography = Style::new("Bibliography", StyleType::Paragraph)
+            .name("Bibliography")
+            .indent(Some(720), Some(SpecialIndentType::Hanging(720)), None, None);
+
         let table = Style::new("Table", StyleType::Table)
             .name("Table")
             .table_align(TableAlignmentType::Center);
@@ -93,6 +102,7 @@ impl DocxStyles {
             .add_style(heading4)
             .add_style(heading5)
             .add_style(heading6)
+            .add_style(toc_heading)
             .add_style(code_block)
             .add_style(code_inline)
             .add_style(math_block)
@@ -102,6 +112,8 @@ impl DocxStyles {
             .add_style(hyperlink)
             .add_style(blockquote)
             .add_style(caption)
+            .add_style(footnote_text)
+            .add_style(bibliography)
             .add_style(table)
     }
 }
@@ -9,7 +9,8 @@ use std::io::Cursor;
 
 use crate::Result;
 use crate::common::{
-    CenterNode, FigureNode, FormatWriter, HighlightNode, InlineNode, VerbatimNode,
+    AnchorNode, BibliographyNode, CenterNode, CiteNode, FigureNode, FootnoteNode, FormatWriter,
+    HighlightNode, InlineNode, OutlineNode, ReferenceNode, VerbatimNode,
 };
 
 use super::image_processor::DocxImageProcessor;
@@ -23,6 +24,7 @@ pub struct DocxWriter {
     numbering: DocxNumbering,
     list_level: usize,
     list_numbering_count: usize,
+    bookmark_count: usize,
     image_processor: DocxImageProcessor,
 }
 
@@ -39,6 +41,7 @@ impl DocxWriter {
             numbering: DocxNumbering::new(),
             list_level: 0,
             list_numbering_count: 0,
+            bookmark_count: 0,
             image_processor: DocxImageProcessor::new(),
         }
     }
@@ -73,10 +76,13 @@ impl DocxWriter {
     /// Process figure node (image with caption)
     fn process_figure(&mut self, mut docx: Docx, figure_node: &FigureNode) -> Result<Docx> {
         // First handle the figure body (typically an image)
+        let mut anchors = Vec::new();
         match &*figure_node.body {
             Node::Paragraph(content) => {
                 for node in content {
-                    if let Node::Image {
+                    if let Some(anchor) = node.as_custom_type::<AnchorNode>() {
+                        anchors.push(anchor.id.clone());
+                    } else if let Node::Image {
                         url,
                         title: _,
                         alt: _,
@@ -150,9 +156,91 @@ impl DocxWriter {
             }
         }
 
+        // Bookmark the caption, which is the last paragraph of the figure
+        if let Some(DocumentChild::Paragraph(para)) = docx.document.children.last_mut() {
+            for id in anchors {
+                *para = self.add_bookmark(Paragraph::clone(para), &id).into();
+            }
+        }
+
         Ok(docx)
     }
 
+    /// Add a bookmark as the target of intra-document links
+    fn add_bookmark(&mut self, para: Paragraph, id: &str) -> Paragraph {
+        let bookmark_id = self.bookmark_count;
+        self.bookmark_count += 1;
+        para.add_bookmark_start(bookmark_id, bookmark_name(id))
+            .add_bookmark_end(bookmark_id)
+    }
+
+    /// Process outline node as a table of contents field
+    fn process_outline(&mut self, mut docx: Docx, outline: &OutlineNode) -> Result<Docx> {
+        if let Some(Node::Heading { content, .. }) = &outline.title {
+            docx = self.process_paragraph(docx, content, Some("TOCHeading"))?;
+        }
+
+        let levels = outline.entries.iter().map(|entry| entry.level as usize);
+        let min_level = levels.clone().min().unwrap_or(1).clamp(1, 6);
+        let max_level = levels.max().unwrap_or(6).clamp(min_level, 6);
+
+        // The entries are filled in advance, and the field is updated with page
+        // numbers when the document is opened
+        let mut toc = TableOfContents::new()
+            .heading_styles_range(min_level, max_level)
+            .hyperlink()
+            .dirty();
+        for entry in &outline.entries {
+            let mut text = String::new();
+            if !entry.prefix.is_empty() {
+                text.push_str(&entry.prefix);
+                text.push(' ');
+            }
+            for node in &entry.content {
+                if let Node::Text(content) = node {
+                    text.push_str(content);
+                }
+            }
+
+            let item = TableOfContentsItem::new()
+                .text(text)
+                .toc_key(bookmark_name(&entry.target))
+                .level(entry.level as usize);
+            toc = toc.add_item(item);
+        }
+
+        Ok(docx.add_table_of_contents(toc))
+    }
+
+    /// Process bibliography node as its title and the rendered reference list
+    fn process_bibliography(
+        &mut self,
+        mut docx: Docx,
+        bibliography: &BibliographyNode,
+    ) -> Result<Docx> {
+        if let Some(title) = &bibliography.title {
+            docx = self.process_node(docx, title)?;
+        }
+        for entry in &bibliography.entries {
+            if let Node::Paragraph(content) = entry.to_paragraph() {
+                docx = self.process_paragraph(docx, &content, Some("Bibliography"))?;
+            }
+        }
+        Ok(docx)
+    }
+
+    /// Create a footnote part from the content of the footnote
+    fn create_footnote(&self, footnote: &FootnoteNode) -> Result<Footnote> {
+        let mut para = Paragraph::new().style("FootnoteText");
+        for child in &footnote.content {
+            let run = self.process_inline_to_run(Run::new(), child)?;
+            if !run.children.is_empty() {
+                para = para.add_run(run);
+            }
+        }
+        Ok(Footnote::new().add_content(para))
+    }
+
     /// Process inline element and add to Run
     fn process_inline_to_run(&self, mut run: Run, node: &Node) -> Result<Run> {
         match node {
@@ -250,6 +338,27 @@ impl DocxWriter {
                     run = self.process_inline_to_run(run, child)?;
                 }
             }
+            node if node.is_custom_type::<ReferenceNode>() => {
+                // References need to be processed at paragraph level, only handle content here
+                let reference = node.as_custom_type::<ReferenceNode>().unwrap();
+                run = run.style("Hyperlink");
+                for child in &reference.content {
+                    run = self.process_inline_to_run(run, child)?;
+                }
+            }
+            node if node.is_custom_type::<AnchorNode>() => {
+                // Bookmarks are added at paragraph level
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                run = run.add_footnote_reference(self.create_footnote(footnote)?);
+            }
+            node if node.is_custom_type::<CiteNode>() => {
+                let cite = node.as_custom_type::<CiteNode>().unwrap();
+                for child in &cite.content {
+                    run = self.process_inline_to_run(run, child)?;
+                }
+            }
             node if node.is_custom_type::<VerbatimNode>() => {
                 let node = node.as_custom_type::<VerbatimNode>().unwrap();
                 eprintln!("Warning: `m1verbatim` is ignored {:?}.", node.content);
@@ -265,7 +374,7 @@ impl DocxWriter {
 
     /// Process paragraph and add to document
     fn process_paragraph(
-        &self,
+        &mut self,
         mut docx: Docx,
         content: &[Node],
         style: Option<&str>,
@@ -277,49 +386,15 @@ impl DocxWriter {
             para = para.style(style_name);
         }
 
-        // Extract all link nodes
-        let mut links = Vec::new();
-        for (i, node) in content.iter().enumerate() {
-            if let Node::Link {
-                url,
-                title: _,
-                content: _,
-            } = node
-            {
-                links.push((i, url.clone()));
-            }
-        }
-
-        // If no links, process paragraph normally
-        if links.is_empty() {
-            // Process paragraph content
-            for node in content {
-                let run = Run::new();
-                let run = self.process_inline_to_run(run, node)?;
-                if !run.children.is_empty() {
-                    para = para.add_run(run);
-                }
-            }
-        } else {
-            // If links exist, we need to process in segments
-            let mut last_idx = 0;
-            for (idx, url) in links {
-                // Process content before the link
-                for item in content.iter().take(idx).skip(last_idx) {
-                    let run = Run::new();
-                    let run = self.process_inline_to_run(run, item)?;
-                    if !run.children.is_empty() {
-                        para = para.add_run(run);
-                    }
-                }
-
-                // Process link
-                if let Node::Link {
-                    url: _,
+        // Links, references and anchors are paragraph children, while other
+        // nodes are processed as runs
+        for node in content {
+            match node {
+                Node::Link {
+                    url,
                     title: _,
                     content: link_content,
-                } = &content[idx]
-                {
+                } => {
                     let mut hyperlink_run = Run::new().style("Hyperlink");
                     for child in link_content {
                         hyperlink_run = self.process_inline_to_run(hyperlink_run, child)?;
@@ -327,21 +402,36 @@ impl DocxWriter {
 
                     // Create and add hyperlink
                     if !hyperlink_run.children.is_empty() {
-                        let hyperlink =
-                            Hyperlink::new(&url, HyperlinkType::External).add_run(hyperlink_run);
+                        let hyperlink = Hyperlink::new(url.as_str(), HyperlinkType::External)
+                            .add_run(hyperlink_run);
                         para = para.add_hyperlink(hyperlink);
                     }
                 }
+                node if node.is_custom_type::<ReferenceNode>() => {
+                    let reference = node.as_custom_type::<ReferenceNode>().unwrap();
+                    let mut hyperlink_run = Run::new().style("Hyperlink");
+                    for child in &reference.content {
+                        hyperlink_run = self.process_inline_to_run(hyperlink_run, child)?;
+                    }
 
-                last_idx = idx + 1;
-            }
-
-            // Process content after the last link
-            for item in content.iter().skip(last_idx) {
-                let run = Run::new();
-                let run = self.process_inline_to_run(run, item)?;
-                if !run.children.is_empty() {
-                    para = para.add_run(run);
+                    // Link to the bookmark of the referenced element
+                    if !hyperlink_run.children.is_empty() {
+                        let hyperlink =
+                            Hyperlink::new(bookmark_name(&reference.target), HyperlinkType::Anchor)
+                                .add_run(hyperlink_run);
+                        para = para.add_hyperlink(hyperlink);
+                    }
+                }
+                node if node.is_custom_type::<AnchorNode>() => {
+                    let anchor = node.as_custom_type::<AnchorNode>().unwrap();
+                    para = self.add_bookmark(para, &anchor.id);
+                }
+                node => {
+                    let run = Run::new();
+                    let run = self.process_inline_to_run(run, node)?;
+                    if !run.children.is_empty() {
+                        para = para.add_run(run);
+                    }
                 }
             }
         }
@@ -435,6 +525,14 @@ impl DocxWriter {
                 let figure_node = node.as_custom_type::<FigureNode>().unwrap();
                 docx = self.process_figure(docx, figure_node)?;
             }
+            node if node.is_custom_type::<OutlineNode>() => {
+                let outline = node.as_custom_type::<OutlineNode>().unwrap();
+                docx = self.process_outline(docx, outline)?;
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                docx = self.process_bibliography(docx, bibliography)?;
+            }
             node if node.is_custom_type::<CenterNode>() => {
                 let center_node = node.as_custom_type::<CenterNode>().unwrap();
                 // Handle regular node but with center alignment
@@ -700,6 +798,7 @@ impl FormatWriter for DocxWriter {
     fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
         self.list_level = 0;
         self.list_numbering_count = 0;
+        self.bookmark_count = 0;
         self.generate_docx(document)
     }
 
@@ -707,3 +806,10 @@ impl FormatWriter for DocxWriter {
         Err("DOCX format does not support EcoString output".into())
     }
 }
+
+/// Get a valid bookmark name from the identifier of an anchor
+fn bookmark_name(id: &str) -> String {
+    id.chars()
+        .map(|ch| if ch.is_alphanumeric() { ch } else { '_' })
+        .collect()
+}
@@ -0,0 +1,13 @@
+//! EPUB converter implementation
+//!
+//! This module is organized into two main components:
+//! - Writer: Functionality for splitting the AST into chapters and rendering
+//!   them to XHTML documents with their embedded resources
+//! - Package: The package document, navigation document and the zip container
+//!   of an EPUB 3 publication
+
+mod package;
+mod writer;
+
+pub use package::EpubMetadata;
+pub use writer::EpubWriter;
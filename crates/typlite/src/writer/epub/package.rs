@@ -0,0 +1,256 @@
+//! EPUB package, i.e. the zip container of the publication resources
+
+use std::io::{Cursor, Write};
+
+use ecow::EcoString;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::Result;
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// Metadata of the publication
+#[derive(Debug, Clone, Default)]
+pub struct EpubMetadata {
+    /// The title of the publication
+    pub title: EcoString,
+    /// The authors of the publication
+    pub authors: Vec<EcoString>,
+    /// The language of the publication, e.g. `en`
+    pub language: EcoString,
+}
+
+/// A resource of the publication
+pub struct EpubResource {
+    /// The path relative to the package document, e.g. `images/frame_0.svg`
+    pub href: String,
+    /// The media type of the resource, e.g. `image/svg+xml`
+    pub media_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// An entry of the navigation document
+pub struct NavEntry {
+    /// The heading level of the entry
+    pub level: u8,
+    /// The link to the heading, e.g. `chapter_1.xhtml#introduction`
+    pub href: String,
+    pub title: String,
+}
+
+/// An EPUB 3 publication
+pub struct EpubPackage {
+    pub metadata: EpubMetadata,
+    /// The unique identifier of the publication
+    pub identifier: String,
+    /// The last modification time, e.g. `2025-01-01T00:00:00Z`
+    pub modified: String,
+    /// The XHTML documents of the chapters, in reading order
+    pub chapters: Vec<EpubResource>,
+    /// The other resources, e.g. stylesheets, images and fonts
+    pub resources: Vec<EpubResource>,
+    pub nav: Vec<NavEntry>,
+}
+
+impl EpubPackage {
+    /// Write the publication to a zip container
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        // The mimetype must be the first entry, which is not compressed
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        add_file(&mut zip, "mimetype", b"application/epub+zip", stored)?;
+        add_file(
+            &mut zip,
+            "META-INF/container.xml",
+            CONTAINER.as_bytes(),
+            deflated,
+        )?;
+        add_file(
+            &mut zip,
+            "OEBPS/content.opf",
+            self.package_document().as_bytes(),
+            deflated,
+        )?;
+        add_file(
+            &mut zip,
+            "OEBPS/nav.xhtml",
+            self.nav_document().as_bytes(),
+            deflated,
+        )?;
+        for resource in self.chapters.iter().chain(&self.resources) {
+            let path = format!("OEBPS/{}", resource.href);
+            add_file(&mut zip, &path, &resource.data, deflated)?;
+        }
+
+        let cursor = zip
+            .finish()
+            .map_err(|err| format!("failed to write epub: {err}"))?;
+        Ok(cursor.into_inner())
+    }
+
+    fn package_document(&self) -> String {
+        let metadata = &self.metadata;
+        let mut opf = String::new();
+        opf.push_str(&format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="pub-id" xml:lang="{lang}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="pub-id">{identifier}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>{lang}</dc:language>
+"#,
+            lang = escape_xml(&metadata.language),
+            identifier = escape_xml(&self.identifier),
+            title = escape_xml(&metadata.title),
+        ));
+        for author in &metadata.authors {
+            opf.push_str(&format!(
+                "    <dc:creator>{}</dc:creator>\n",
+                escape_xml(author)
+            ));
+        }
+        opf.push_str(&format!(
+            "    <meta property=\"dcterms:modified\">{}</meta>\n  </metadata>\n  <manifest>\n",
+            self.modified
+        ));
+
+        opf.push_str(
+            "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+        );
+        for (idx, resource) in self.chapters.iter().enumerate() {
+            opf.push_str(&manifest_item(&format!("chapter-{idx}"), resource));
+        }
+        for (idx, resource) in self.resources.iter().enumerate() {
+            opf.push_str(&manifest_item(&format!("resource-{idx}"), resource));
+        }
+
+        opf.push_str("  </manifest>\n  <spine>\n");
+        for idx in 0..self.chapters.len() {
+            opf.push_str(&format!("    <itemref idref=\"chapter-{idx}\"/>\n"));
+        }
+        opf.push_str("  </spine>\n</package>\n");
+        opf
+    }
+
+    fn nav_document(&self) -> String {
+        let title = escape_xml(&self.metadata.title);
+        let mut body = format!("<nav epub:type=\"toc\" id=\"toc\">\n<h1>{title}</h1>\n");
+        write_nav_list(&self.nav, &mut body);
+        body.push_str("</nav>");
+        xhtml_document(&self.metadata.title, &self.metadata.language, &body)
+    }
+}
+
+/// Create an XHTML content document
+pub fn xhtml_document(title: &str, language: &str, body: &str) -> String {
+    let title = escape_xml(title);
+    let language = escape_xml(language);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="{language}" xml:lang="{language}">
+<head>
+<meta charset="UTF-8" />
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="style.css" />
+</head>
+<body>
+{body}
+</body>
+</html>
+"#
+    )
+}
+
+/// Get the media type of a resource from its extension
+pub fn media_type(extension: &str) -> Option<&'static str> {
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "css" => "text/css",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => return None,
+    })
+}
+
+/// Escape the text to be used in XML text and attributes
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn manifest_item(id: &str, resource: &EpubResource) -> String {
+    format!(
+        "    <item id=\"{id}\" href=\"{}\" media-type=\"{}\"/>\n",
+        escape_xml(&resource.href),
+        resource.media_type
+    )
+}
+
+/// Write the entries as nested lists, where the deeper entries following an
+/// entry are nested in its item
+fn write_nav_list(entries: &[NavEntry], out: &mut String) {
+    let Some(level) = entries.iter().map(|entry| entry.level).min() else {
+        return;
+    };
+
+    out.push_str("<ol>\n");
+    let mut rest = entries;
+    while let Some((entry, tail)) = rest.split_first() {
+        let end = tail
+            .iter()
+            .position(|next| next.level <= level)
+            .unwrap_or(tail.len());
+        let (children, next) = tail.split_at(end);
+
+        out.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            escape_xml(&entry.href),
+            escape_xml(&entry.title)
+        ));
+        if !children.is_empty() {
+            out.push('\n');
+            write_nav_list(children, out);
+        }
+        out.push_str("</li>\n");
+        rest = next;
+    }
+    out.push_str("</ol>\n");
+}
+
+fn add_file(
+    zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+    path: &str,
+    data: &[u8],
+    options: FileOptions,
+) -> Result<()> {
+    zip.start_file(path, options)
+        .map_err(|err| format!("failed to write epub: {err}"))?;
+    zip.write_all(data)?;
+    Ok(())
+}
@@ -0,0 +1,589 @@
+//! EPUB document writer implementation
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+
+use base64::Engine;
+use cmark_writer::ast::{HtmlAttribute, HtmlElement, ListItem, Node};
+use cmark_writer::{HtmlWriter, HtmlWriterOptions};
+use ecow::{EcoString, eco_format};
+use regex::{Captures, Regex};
+use tinymist_project::LspWorld;
+use typst::World;
+
+use crate::Result;
+use crate::common::{
+    AlertNode, AnchorNode, BibliographyNode, CenterNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FootnotesNode, FormatWriter, InlineNode, VerbatimNode,
+};
+
+use super::package::{
+    EpubMetadata, EpubPackage, EpubResource, NavEntry, media_type, xhtml_document,
+};
+
+const DEFAULT_CSS: &str = r#"body {
+  margin: 0 5%;
+  line-height: 1.5;
+}
+img {
+  max-width: 100%;
+}
+figure {
+  margin: 1em 0;
+  text-align: center;
+}
+pre, code {
+  font-family: monospace;
+}
+pre {
+  white-space: pre-wrap;
+}
+blockquote {
+  margin: 1em 2em;
+  font-style: italic;
+}
+table {
+  margin: 1em auto;
+  border-collapse: collapse;
+}
+th, td {
+  padding: 0.25em 0.5em;
+  border: 1px solid #888;
+}
+aside[epub|type~="footnote"] {
+  font-size: 0.9em;
+}
+"#;
+
+/// EPUB writer that splits the document into chapters at its top-level
+/// headings
+pub struct EpubWriter {
+    metadata: EpubMetadata,
+    /// The theme, which is a stylesheet or a directory of a `style.css` and
+    /// fonts
+    theme: Option<PathBuf>,
+    /// The world to read the images from, whose relative paths are resolved
+    /// against the main file
+    world: Option<Arc<LspWorld>>,
+    resources: Vec<EpubResource>,
+    /// The hrefs of the embedded files by their source paths
+    embedded: HashMap<EcoString, String>,
+    nav: Vec<NavEntry>,
+    heading_count: usize,
+}
+
+impl Default for EpubWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EpubWriter {
+    pub fn new() -> Self {
+        Self {
+            metadata: EpubMetadata::default(),
+            theme: None,
+            world: None,
+            resources: Vec::new(),
+            embedded: HashMap::new(),
+            nav: Vec::new(),
+            heading_count: 0,
+        }
+    }
+
+    /// Sets the metadata of the publication
+    pub fn with_metadata(mut self, metadata: EpubMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the theme providing the stylesheet and fonts
+    pub fn with_theme(mut self, theme: Option<PathBuf>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the world to read the images from
+    pub fn with_world(mut self, world: Arc<LspWorld>) -> Self {
+        self.world = Some(world);
+        self
+    }
+
+    /// Load the stylesheet and embed the fonts of the theme
+    fn load_theme(&mut self) -> Result<String> {
+        let Some(theme) = self.theme.clone() else {
+            return Ok(DEFAULT_CSS.to_owned());
+        };
+        if theme.is_file() {
+            return Ok(fs::read_to_string(&theme)?);
+        }
+
+        let css = match fs::read_to_string(theme.join("style.css")) {
+            Ok(css) => css,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => DEFAULT_CSS.to_owned(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut fonts = Vec::new();
+        for entry in fs::read_dir(&theme)? {
+            let path = entry?.path();
+            let media_type = extension(&path).and_then(media_type);
+            if let Some(media_type) = media_type.filter(|ty| ty.starts_with("font/")) {
+                fonts.push((path, media_type));
+            }
+        }
+        fonts.sort();
+
+        // Fonts are referenced by `fonts/<file name>` in the stylesheet
+        for (path, media_type) in fonts {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            self.resources.push(EpubResource {
+                href: format!("fonts/{}", name.to_string_lossy()),
+                media_type,
+                data: fs::read(&path)?,
+            });
+        }
+        Ok(css)
+    }
+
+    /// Embed an image, returning its href in the publication
+    fn add_image(&mut self, stem: &str, ext: &str, data: Vec<u8>) -> Option<String> {
+        let media_type = media_type(ext)?;
+        let href = format!("images/{stem}_{}.{ext}", self.resources.len());
+        self.resources.push(EpubResource {
+            href: href.clone(),
+            media_type,
+            data,
+        });
+        Some(href)
+    }
+
+    /// Embed the image of a url, which is a local file or a data url
+    ///
+    /// Remote images are left as is, while the other images that cannot be
+    /// embedded are reported as errors.
+    fn embed_url(&mut self, url: &str) -> Result<Option<String>> {
+        if let Some(href) = self.embedded.get(url) {
+            return Ok(Some(href.clone()));
+        }
+
+        let href = if let Some(data_url) = url.strip_prefix("data:") {
+            let (mime, data) = data_url
+                .split_once(";base64,")
+                .ok_or("data url of image is not base64 encoded")?;
+            let ext = match mime {
+                "image/svg+xml" => "svg",
+                "image/jpeg" => "jpg",
+                mime => mime.strip_prefix("image/").unwrap_or(mime),
+            };
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|err| format!("failed to decode data url of image: {err}"))?;
+            self.add_image("image", ext, data)
+                .ok_or_else(|| format!("unsupported image type: {mime}"))?
+        } else if url.contains("://") {
+            // Remote resources are left as is
+            return Ok(None);
+        } else {
+            let data = match &self.world {
+                Some(world) => world
+                    .file(world.main().join(url))
+                    .map(|data| data.to_vec())
+                    .map_err(|err| format!("failed to read image {url}: {err}"))?,
+                None => {
+                    fs::read(url).map_err(|err| format!("failed to read image {url}: {err}"))?
+                }
+            };
+            extension(Path::new(url))
+                .and_then(|ext| self.add_image("image", ext, data))
+                .ok_or_else(|| format!("unsupported image type: {url}"))?
+        };
+
+        self.embedded.insert(url.into(), href.clone());
+        Ok(Some(href))
+    }
+
+    /// Prepare the nodes to be written as XHTML, embedding the images and
+    /// converting the nodes without HTML representation
+    fn prepare_all(&mut self, nodes: &mut [Node], chapter: &str) -> Result<()> {
+        for node in nodes {
+            self.prepare(node, chapter)?;
+        }
+        Ok(())
+    }
+
+    fn prepare(&mut self, node: &mut Node, chapter: &str) -> Result<()> {
+        match node {
+            Node::Document(children)
+            | Node::Paragraph(children)
+            | Node::BlockQuote(children)
+            | Node::Strong(children)
+            | Node::Emphasis(children)
+            | Node::Strikethrough(children)
+            | Node::Link {
+                content: children, ..
+            } => self.prepare_all(children, chapter)?,
+            Node::Heading { level, content, .. } => {
+                self.prepare_all(content, chapter)?;
+
+                // Headings are linked by the navigation document
+                let anchor = content
+                    .iter()
+                    .find_map(|node| node.as_custom_type::<AnchorNode>())
+                    .map(|anchor| anchor.id.clone());
+                let id = match anchor {
+                    Some(id) => id,
+                    None => {
+                        self.heading_count += 1;
+                        let id = eco_format!("heading-{}", self.heading_count);
+                        content.insert(0, Node::Custom(Box::new(AnchorNode { id: id.clone() })));
+                        id
+                    }
+                };
+                self.nav.push(NavEntry {
+                    level: *level,
+                    href: format!("{chapter}#{id}"),
+                    title: plain_text(content),
+                });
+            }
+            Node::OrderedList { items, .. } | Node::UnorderedList(items) => {
+                for item in items {
+                    match item {
+                        ListItem::Ordered { content, .. } | ListItem::Unordered { content } => {
+                            self.prepare_all(content, chapter)?
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Node::Table { headers, rows, .. } => {
+                self.prepare_all(headers, chapter)?;
+                for row in rows {
+                    self.prepare_all(row, chapter)?;
+                }
+            }
+            Node::Image { url, .. } => {
+                if let Some(href) = self.embed_url(url)? {
+                    *url = href.into();
+                }
+            }
+            Node::HtmlElement(element) => {
+                if element.tag == "img" {
+                    for attr in &mut element.attributes {
+                        if attr.name == "src" {
+                            if let Some(href) = self.embed_url(&attr.value)? {
+                                attr.value = href.into();
+                            }
+                        }
+                    }
+                }
+                self.prepare_all(&mut element.children, chapter)?;
+            }
+            node if node.is_custom_type::<ExternalFrameNode>() => {
+                let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+                let alt = frame.alt_text.clone();
+                let data = frame.svg.clone().into_bytes();
+                if let Some(href) = self.add_image("frame", "svg", data) {
+                    *node = Node::Image {
+                        url: href.into(),
+                        title: None,
+                        alt: vec![Node::Text(alt)],
+                    };
+                }
+            }
+            node if node.is_custom_type::<FigureNode>() => {
+                let mut figure = node.as_custom_type::<FigureNode>().unwrap().clone();
+                self.prepare(&mut figure.body, chapter)?;
+                *node = Node::Custom(Box::new(figure));
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let mut center = node.as_custom_type::<CenterNode>().unwrap().clone();
+                self.prepare(&mut center.node, chapter)?;
+                *node = Node::Custom(Box::new(center));
+            }
+            node if node.is_custom_type::<InlineNode>() => {
+                let mut inline = node.as_custom_type::<InlineNode>().unwrap().clone();
+                self.prepare_all(&mut inline.content, chapter)?;
+                *node = Node::Custom(Box::new(inline));
+            }
+            node if node.is_custom_type::<AlertNode>() => {
+                let alert = node.as_custom_type::<AlertNode>().unwrap();
+                *node = Node::BlockQuote(alert.content.clone());
+                self.prepare(node, chapter)?;
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                *node = note_reference(footnote.number);
+            }
+            node if node.is_custom_type::<FootnotesNode>() => {
+                let footnotes = node.as_custom_type::<FootnotesNode>().unwrap();
+                let notes = footnotes.footnotes.iter().map(note).collect();
+                *node = html_element("section", vec![epub_type("footnotes")], notes);
+                self.prepare(node, chapter)?;
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                let mut children = Vec::new();
+                children.extend(bibliography.title.clone());
+                children.extend(
+                    bibliography
+                        .entries
+                        .iter()
+                        .map(|entry| entry.to_paragraph()),
+                );
+                *node = html_element("section", vec![epub_type("bibliography")], children);
+                self.prepare(node, chapter)?;
+            }
+            node if node.is_custom_type::<VerbatimNode>() => {
+                *node = Node::Text(EcoString::new());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Render the chapter to the body of an XHTML document
+    fn render_chapter(chapter: &[Node]) -> Result<String> {
+        let mut writer = HtmlWriter::with_options(HtmlWriterOptions {
+            strict: false,
+            ..Default::default()
+        });
+        for node in chapter {
+            writer
+                .write_node(node)
+                .map_err(|err| format!("failed to write chapter: {err}"))?;
+        }
+
+        // Void elements must be closed in XHTML
+        static VOID_ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"<(area|br|col|hr|img|input|link|meta|source|wbr)\b([^>]*?)\s*/?>").unwrap()
+        });
+        let body = writer.into_string();
+        Ok(VOID_ELEMENT.replace_all(&body, "<$1$2 />").into_owned())
+    }
+
+    fn generate_epub(&mut self, document: &Node) -> Result<Vec<u8>> {
+        let css = self.load_theme()?;
+
+        let blocks = match document {
+            Node::Document(blocks) => blocks.as_slice(),
+            node => std::slice::from_ref(node),
+        };
+
+        // The content before the first heading is kept in its own chapter
+        let top_level = blocks
+            .iter()
+            .filter_map(|block| match block {
+                Node::Heading { level, .. } => Some(*level),
+                _ => None,
+            })
+            .min();
+        let mut chapters: Vec<Vec<Node>> = Vec::new();
+        for block in blocks {
+            let is_top = matches!(block, Node::Heading { level, .. } if Some(*level) == top_level);
+            if is_top || chapters.is_empty() {
+                chapters.push(Vec::new());
+            }
+
+            let file = chapter_file(chapters.len() - 1);
+            let mut block = block.clone();
+            self.prepare(&mut block, &file)?;
+            if let Some(chapter) = chapters.last_mut() {
+                chapter.push(block);
+            }
+        }
+        if chapters.is_empty() {
+            chapters.push(Vec::new());
+        }
+
+        if self.metadata.title.is_empty() {
+            self.metadata.title = match self.nav.first() {
+                Some(entry) => entry.title.as_str().into(),
+                None => EcoString::inline("Untitled"),
+            };
+        }
+        if self.metadata.language.is_empty() {
+            self.metadata.language = EcoString::inline("en");
+        }
+
+        let bodies = chapters
+            .iter()
+            .map(|chapter| Self::render_chapter(chapter))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Links to the anchors in other chapters are resolved to the files of
+        // the chapters
+        static ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\bid="([^"]+)""#).unwrap());
+        static HREF: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r##"\bhref="#([^"]+)""##).unwrap());
+        let mut anchors = HashMap::new();
+        for (idx, body) in bodies.iter().enumerate() {
+            for capture in ID.captures_iter(body) {
+                anchors.entry(capture[1].to_owned()).or_insert(idx);
+            }
+        }
+
+        let mut documents = Vec::new();
+        for (idx, body) in bodies.iter().enumerate() {
+            let body =
+                HREF.replace_all(body, |capture: &Captures| match anchors.get(&capture[1]) {
+                    Some(chapter) if *chapter != idx => {
+                        format!("href=\"{}#{}\"", chapter_file(*chapter), &capture[1])
+                    }
+                    _ => capture[0].to_owned(),
+                });
+
+            let title = self
+                .nav
+                .iter()
+                .find(|entry| entry.href.starts_with(&format!("{}#", chapter_file(idx))))
+                .map_or(self.metadata.title.as_str(), |entry| entry.title.as_str());
+            documents.push(xhtml_document(title, &self.metadata.language, &body));
+        }
+
+        // Every chapter is reachable from the navigation document
+        if self.nav.is_empty() {
+            self.nav = (0..documents.len())
+                .map(|idx| NavEntry {
+                    level: 1,
+                    href: chapter_file(idx),
+                    title: self.metadata.title.to_string(),
+                })
+                .collect();
+        }
+
+        let mut resources = vec![EpubResource {
+            href: "style.css".to_owned(),
+            media_type: "text/css",
+            data: css.into_bytes(),
+        }];
+        resources.append(&mut self.resources);
+
+        let identifier = format!(
+            "urn:typlite:{:032x}",
+            tinymist_std::hash::hash128(&documents)
+        );
+        let now = tinymist_std::time::utc_now();
+        let modified = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            now.year(),
+            now.month() as u8,
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+
+        let package = EpubPackage {
+            metadata: self.metadata.clone(),
+            identifier,
+            modified,
+            chapters: documents
+                .into_iter()
+                .enumerate()
+                .map(|(idx, document)| EpubResource {
+                    href: chapter_file(idx),
+                    media_type: "application/xhtml+xml",
+                    data: document.into_bytes(),
+                })
+                .collect(),
+            resources,
+            nav: std::mem::take(&mut self.nav),
+        };
+        package.to_zip()
+    }
+}
+
+impl FormatWriter for EpubWriter {
+    fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
+        self.resources.clear();
+        self.embedded.clear();
+        self.nav.clear();
+        self.heading_count = 0;
+        self.generate_epub(document)
+    }
+
+    fn write_eco(&mut self, _document: &Node, _output: &mut EcoString) -> Result<()> {
+        Err("EPUB format does not support EcoString output".into())
+    }
+}
+
+fn chapter_file(idx: usize) -> String {
+    format!("chapter_{}.xhtml", idx + 1)
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(std::ffi::OsStr::to_str)
+}
+
+fn html_element(tag: &str, attributes: Vec<HtmlAttribute>, children: Vec<Node>) -> Node {
+    Node::HtmlElement(HtmlElement {
+        tag: tag.into(),
+        attributes,
+        children,
+        self_closing: false,
+    })
+}
+
+fn html_attr(name: &str, value: EcoString) -> HtmlAttribute {
+    HtmlAttribute {
+        name: name.into(),
+        value,
+    }
+}
+
+fn epub_type(ty: &str) -> HtmlAttribute {
+    html_attr("epub:type", ty.into())
+}
+
+/// Create a reference to a footnote, which is shown as a popup by readers
+fn note_reference(number: usize) -> Node {
+    html_element(
+        "a",
+        vec![
+            html_attr("href", eco_format!("#fn-{number}")),
+            html_attr("id", eco_format!("fnref-{number}")),
+            epub_type("noteref"),
+        ],
+        vec![html_element(
+            "sup",
+            vec![],
+            vec![Node::Text(eco_format!("{number}"))],
+        )],
+    )
+}
+
+fn note(footnote: &FootnoteNode) -> Node {
+    let mut content = vec![Node::Text(eco_format!("{}. ", footnote.number))];
+    content.extend(footnote.content.iter().cloned());
+    html_element(
+        "aside",
+        vec![
+            html_attr("id", eco_format!("fn-{}", footnote.number)),
+            epub_type("footnote"),
+        ],
+        vec![Node::Paragraph(content)],
+    )
+}
+
+/// Get the plain text of inline nodes
+fn plain_text(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(content) | Node::InlineCode(content) => text.push_str(content),
+            Node::Strong(content)
+            | Node::Emphasis(content)
+            | Node::Strikethrough(content)
+            | Node::Link { content, .. } => text.push_str(&plain_text(content)),
+            Node::HtmlElement(element) => text.push_str(&plain_text(&element.children)),
+            Node::SoftBreak | Node::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text.trim().to_owned()
+}
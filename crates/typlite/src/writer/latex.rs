@@ -8,7 +8,8 @@ use tinymist_std::path::unix_slash;
 
 use crate::Result;
 use crate::common::{
-    CenterNode, ExternalFrameNode, FigureNode, FormatWriter, HighlightNode, InlineNode, ListState,
+    AnchorNode, BibliographyNode, CenterNode, CiteNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FormatWriter, HighlightNode, InlineNode, ListState, OutlineNode, ReferenceNode,
     VerbatimNode,
 };
 
@@ -35,6 +36,61 @@ impl LaTeXWriter {
         Ok(())
     }
 
+    /// Write the bibliography, which is generated by BibTeX from the original
+    /// bibliography files if possible
+    fn write_bibliography(
+        &mut self,
+        bibliography: &BibliographyNode,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        let title = match &bibliography.title {
+            Some(Node::Heading { content, .. }) => Some(content),
+            _ => None,
+        };
+
+        let sources = bibliography
+            .sources
+            .iter()
+            .filter(|source| source.ends_with(".bib"))
+            .map(|source| source.trim_end_matches(".bib"))
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            // Falls back to the reference list rendered by typst
+            if let Some(title) = title {
+                output.push_str("\\section*{");
+                self.write_inline_nodes(title, output)?;
+                output.push_str("}\n\n");
+            }
+            for entry in &bibliography.entries {
+                if !entry.prefix.is_empty() {
+                    output.push_str(&escape_latex(&entry.prefix));
+                    output.push(' ');
+                }
+                self.write_inline_nodes(&entry.content, output)?;
+                output.push_str("\n\n");
+            }
+            return Ok(());
+        }
+
+        if let Some(title) = title {
+            output.push_str("\\renewcommand{\\refname}{");
+            self.write_inline_nodes(title, output)?;
+            output.push_str("}\n");
+        }
+        let style = match bibliography.style.as_str() {
+            "ieee" => "IEEEtran",
+            "apa" | "chicago-author-date" | "harvard-cite-them-right" => "apalike",
+            "alphanumeric" => "alpha",
+            _ => "plain",
+        };
+        output.push_str("\\bibliographystyle{");
+        output.push_str(style);
+        output.push_str("}\n\\bibliography{");
+        output.push_str(&sources.join(","));
+        output.push_str("}\n\n");
+        Ok(())
+    }
+
     /// Write the document to LaTeX format
     fn write_node(&mut self, node: &Node, output: &mut EcoString) -> Result<()> {
         match node {
@@ -65,8 +121,18 @@ impl LaTeXWriter {
                     _ => return Err(format!("Heading level {level} is not supported").into()),
                 }
 
-                self.write_inline_nodes(content, output)?;
-                output.push_str("}\n\n");
+                // Labels are placed after the heading to refer to its numbering
+                let (anchors, content): (Vec<_>, Vec<_>) = content
+                    .iter()
+                    .partition(|node| node.is_custom_type::<AnchorNode>());
+                for node in content {
+                    self.write_node(node, output)?;
+                }
+                output.push('}');
+                for anchor in anchors {
+                    self.write_node(anchor, output)?;
+                }
+                output.push_str("\n\n");
             }
             Node::BlockQuote(content) => {
                 output.push_str("\\begin{quote}\n");
@@ -212,11 +278,14 @@ impl LaTeXWriter {
                 output.push_str("\\begin{figure}[htbp]\n\\centering\n");
 
                 // Handle the body content (typically an image)
+                let mut anchors = Vec::new();
                 match &*figure_node.body {
                     Node::Paragraph(content) => {
                         for node in content {
-                            // Special handling for image nodes in figures
-                            if let Node::Image {
+                            // Labels are placed after the caption to refer to its numbering
+                            if node.is_custom_type::<AnchorNode>() {
+                                anchors.push(node);
+                            } else if let Node::Image {
                                 url,
                                 title: _,
                                 alt: _,
@@ -245,6 +314,10 @@ impl LaTeXWriter {
                     output.push_str(&escape_latex(&figure_node.caption));
                     output.push_str("}\n");
                 }
+                for anchor in anchors {
+                    self.write_node(anchor, output)?;
+                    output.push('\n');
+                }
 
                 // Close figure environment
                 output.push_str("\\end{figure}\n\n");
@@ -289,6 +362,54 @@ impl LaTeXWriter {
                     self.write_node(child, output)?;
                 }
             }
+            node if node.is_custom_type::<AnchorNode>() => {
+                let anchor = node.as_custom_type::<AnchorNode>().unwrap();
+                output.push_str("\\label{");
+                output.push_str(&anchor.id);
+                output.push('}');
+            }
+            node if node.is_custom_type::<ReferenceNode>() => {
+                let reference = node.as_custom_type::<ReferenceNode>().unwrap();
+                // LaTeX numbers the referenced element by itself
+                if !reference.supplement.is_empty() {
+                    output.push_str(&escape_latex(&reference.supplement));
+                    output.push('~');
+                }
+                output.push_str("\\ref{");
+                output.push_str(&reference.target);
+                output.push('}');
+            }
+            node if node.is_custom_type::<OutlineNode>() => {
+                let outline = node.as_custom_type::<OutlineNode>().unwrap();
+                if let Some(Node::Heading { content, .. }) = &outline.title {
+                    output.push_str("\\renewcommand{\\contentsname}{");
+                    self.write_inline_nodes(content, output)?;
+                    output.push_str("}\n");
+                }
+                output.push_str("\\tableofcontents\n\n");
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                output.push_str("\\footnote{");
+                self.write_inline_nodes(&footnote.content, output)?;
+                output.push('}');
+            }
+            node if node.is_custom_type::<CiteNode>() => {
+                let cite = node.as_custom_type::<CiteNode>().unwrap();
+                output.push_str("\\cite");
+                if !cite.supplement.is_empty() {
+                    output.push('[');
+                    output.push_str(&escape_latex(&cite.supplement));
+                    output.push(']');
+                }
+                output.push('{');
+                output.push_str(&cite.key);
+                output.push('}');
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                self.write_bibliography(bibliography, output)?;
+            }
             node if node.is_custom_type::<VerbatimNode>() => {
                 let inline_node = node.as_custom_type::<VerbatimNode>().unwrap();
                 output.push_str(&inline_node.content);
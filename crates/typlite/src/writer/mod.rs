@@ -1,15 +1,23 @@
 //! Writer implementations for different output formats
 
+pub mod asciidoc;
 #[cfg(feature = "docx")]
 pub mod docx;
+#[cfg(feature = "epub")]
+pub mod epub;
 pub mod latex;
 pub mod markdown;
+pub mod rst;
 pub mod text;
 
 #[cfg(feature = "docx")]
 pub use self::docx::DocxWriter;
+#[cfg(feature = "epub")]
+pub use self::epub::EpubWriter;
+pub use asciidoc::AsciiDocWriter;
 pub use latex::LaTeXWriter;
 pub use markdown::MarkdownWriter;
+pub use rst::RstWriter;
 pub use text::TextWriter;
 
 use crate::common::{Format, FormatWriter};
@@ -20,8 +28,12 @@ pub fn create_writer(format: Format) -> Box<dyn FormatWriter> {
         Format::Md => Box::new(markdown::MarkdownWriter::new()),
         Format::LaTeX => Box::new(latex::LaTeXWriter::new()),
         Format::Text => Box::new(text::TextWriter::new()),
+        Format::Rst => Box::new(rst::RstWriter::new()),
+        Format::AsciiDoc => Box::new(asciidoc::AsciiDocWriter::new()),
         #[cfg(feature = "docx")]
         Format::Docx => Box::new(docx::DocxWriter::new()),
+        #[cfg(feature = "epub")]
+        Format::Epub => Box::new(epub::EpubWriter::new()),
     }
 }
 
@@ -0,0 +1,571 @@
+//! reStructuredText writer implementation
+
+use cmark_writer::WriterOptions;
+use cmark_writer::ast::{HtmlElement, ListItem, Node};
+use cmark_writer::writer::CommonMarkWriter;
+use ecow::EcoString;
+use tinymist_std::path::unix_slash;
+
+use crate::Result;
+use crate::common::{
+    AlertNode, AnchorNode, BibliographyNode, CenterNode, CiteNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FootnotesNode, FormatWriter, HighlightNode, InlineNode, MathNode, OutlineNode,
+    ReferenceNode, VerbatimNode,
+};
+
+/// reStructuredText writer implementation, which targets Sphinx
+pub struct RstWriter {}
+
+impl Default for RstWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RstWriter {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn write_blocks(&mut self, blocks: &[Node], output: &mut EcoString) -> Result<()> {
+        for block in blocks {
+            match block {
+                // HTML blocks, e.g. tables with merged cells, are passed through
+                Node::HtmlElement(..) => {
+                    Self::write_directive(output, "raw", "html", &[], &html_source(block)?);
+                }
+                _ => self.write_node(block, output)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_inline_nodes(&mut self, nodes: &[Node], output: &mut EcoString) -> Result<()> {
+        for node in nodes {
+            self.write_node(node, output)?;
+        }
+        Ok(())
+    }
+
+    /// Write the blocks to be nested in a directive or a list item
+    fn nested_blocks(&mut self, blocks: &[Node]) -> Result<String> {
+        let mut content = EcoString::new();
+        self.write_blocks(blocks, &mut content)?;
+        Ok(content.trim_end().to_owned())
+    }
+
+    /// Write a directive, whose options and content are indented by three
+    /// spaces
+    fn write_directive(
+        output: &mut EcoString,
+        name: &str,
+        argument: &str,
+        options: &[(&str, &str)],
+        content: &str,
+    ) {
+        output.push_str(".. ");
+        output.push_str(name);
+        output.push_str("::");
+        if !argument.is_empty() {
+            output.push(' ');
+            output.push_str(argument);
+        }
+        output.push('\n');
+        for (name, value) in options {
+            output.push_str("   :");
+            output.push_str(name);
+            output.push(':');
+            if !value.is_empty() {
+                output.push(' ');
+                output.push_str(value);
+            }
+            output.push('\n');
+        }
+        if !content.is_empty() {
+            output.push('\n');
+            output.push_str(&indent(content, "   ", "   "));
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+
+    /// Write a paragraph, whose anchors become the targets of the paragraph and
+    /// whose images become image directives
+    fn write_paragraph(&mut self, inlines: &[Node], output: &mut EcoString) -> Result<()> {
+        let (anchors, content): (Vec<_>, Vec<_>) = inlines
+            .iter()
+            .partition(|node| node.is_custom_type::<AnchorNode>());
+        write_targets(&anchors, output);
+
+        let content = content
+            .into_iter()
+            .filter(|node| !matches!(node, Node::Text(text) if text.trim().is_empty()))
+            .collect::<Vec<_>>();
+        if content.is_empty() {
+            return Ok(());
+        }
+        if content.iter().all(|node| image_source(node).is_some()) {
+            for node in content {
+                if let Some((src, alt)) = image_source(node) {
+                    Self::write_directive(output, "image", &src, &[("alt", &alt)], "");
+                }
+            }
+            return Ok(());
+        }
+
+        for node in content {
+            self.write_node(node, output)?;
+        }
+        output.push_str("\n\n");
+        Ok(())
+    }
+
+    fn write_list(
+        &mut self,
+        items: &[ListItem],
+        start: Option<u32>,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        let mut number = start.unwrap_or(1);
+        for item in items {
+            let content = match item {
+                // Items may be numbered explicitly, e.g. `2. A`
+                ListItem::Ordered {
+                    number: Some(value),
+                    content,
+                } => {
+                    number = *value;
+                    content
+                }
+                ListItem::Ordered { content, .. } | ListItem::Unordered { content } => content,
+                _ => continue,
+            };
+
+            let marker = match start {
+                Some(_) => format!("{number}. "),
+                None => "- ".to_owned(),
+            };
+            number += 1;
+
+            let content = self.nested_blocks(content)?;
+            let rest = " ".repeat(marker.len());
+            output.push_str(&indent(&content, &marker, &rest));
+            output.push('\n');
+        }
+        output.push('\n');
+        Ok(())
+    }
+
+    fn write_table(
+        &mut self,
+        headers: &[Node],
+        rows: &[Vec<Node>],
+        title: &str,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        let mut content = String::new();
+        let header_rows = if headers.is_empty() { 0 } else { 1 };
+        for row in std::iter::once(headers)
+            .filter(|headers| !headers.is_empty())
+            .chain(rows.iter().map(Vec::as_slice))
+        {
+            for (idx, cell) in row.iter().enumerate() {
+                let mut text = EcoString::new();
+                self.write_node(cell, &mut text)?;
+                content.push_str(if idx == 0 { "* - " } else { "  - " });
+                content.push_str(text.trim());
+                content.push('\n');
+            }
+        }
+
+        let header_rows = header_rows.to_string();
+        Self::write_directive(
+            output,
+            "list-table",
+            title,
+            &[("header-rows", &header_rows)],
+            content.trim_end(),
+        );
+        Ok(())
+    }
+
+    fn write_figure(&mut self, figure: &FigureNode, output: &mut EcoString) -> Result<()> {
+        let caption = escape_rst(&figure.caption);
+        match &*figure.body {
+            Node::Paragraph(content) => {
+                // Labels are the targets of the figure
+                let (anchors, content): (Vec<_>, Vec<_>) = content
+                    .iter()
+                    .partition(|node| node.is_custom_type::<AnchorNode>());
+                write_targets(&anchors, output);
+
+                let image = content.iter().find_map(|node| image_source(node));
+                if let Some((src, alt)) = image {
+                    Self::write_directive(output, "figure", &src, &[("alt", &alt)], &caption);
+                } else {
+                    let content = content.into_iter().cloned().collect::<Vec<_>>();
+                    self.write_paragraph(&content, output)?;
+                    self.write_caption(&caption, output);
+                }
+            }
+            Node::Table { headers, rows, .. } => {
+                self.write_table(headers, rows, &caption, output)?;
+            }
+            Node::CodeBlock {
+                language: Some(lang),
+                content,
+                ..
+            } if !lang.is_empty() => {
+                let content = content.trim_end_matches('\n');
+                let options = [("caption", caption.as_str())];
+                let options = if caption.is_empty() {
+                    &[][..]
+                } else {
+                    &options[..]
+                };
+                Self::write_directive(output, "code-block", lang, options, content);
+            }
+            node => {
+                self.write_node(node, output)?;
+                self.write_caption(&caption, output);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_caption(&mut self, caption: &str, output: &mut EcoString) {
+        if !caption.is_empty() {
+            output.push('*');
+            output.push_str(caption);
+            output.push_str("*\n\n");
+        }
+    }
+
+    fn write_bibliography(
+        &mut self,
+        bibliography: &BibliographyNode,
+        output: &mut EcoString,
+    ) -> Result<()> {
+        if let Some(title) = &bibliography.title {
+            self.write_node(title, output)?;
+        }
+        for entry in &bibliography.entries {
+            self.write_node(&entry.to_paragraph(), output)?;
+        }
+        Ok(())
+    }
+
+    /// Write the document to reStructuredText format
+    fn write_node(&mut self, node: &Node, output: &mut EcoString) -> Result<()> {
+        match node {
+            Node::Document(blocks) => self.write_blocks(blocks, output)?,
+            Node::Paragraph(inlines) => self.write_paragraph(inlines, output)?,
+            Node::Heading {
+                level,
+                content,
+                heading_type: _,
+            } => {
+                // Labels are the targets of the section
+                let (anchors, content): (Vec<_>, Vec<_>) = content
+                    .iter()
+                    .partition(|node| node.is_custom_type::<AnchorNode>());
+                write_targets(&anchors, output);
+
+                let mut title = EcoString::new();
+                for node in content {
+                    self.write_node(node, &mut title)?;
+                }
+                let title = title.trim();
+
+                // The underline must be as long as the title, where wide
+                // characters take more than one byte
+                let adornment = match level {
+                    1 | 2 => '=',
+                    3 => '-',
+                    4 => '~',
+                    5 => '^',
+                    _ => '"',
+                };
+                let line = adornment.to_string().repeat(title.len().max(3));
+                if *level == 1 {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+                output.push_str(title);
+                output.push('\n');
+                output.push_str(&line);
+                output.push_str("\n\n");
+            }
+            Node::BlockQuote(content) => {
+                let content = self.nested_blocks(content)?;
+                output.push_str(&indent(&content, "   ", "   "));
+                output.push_str("\n\n");
+            }
+            Node::CodeBlock {
+                language,
+                content,
+                block_type: _,
+            } => {
+                let content = content.trim_end_matches('\n');
+                match language.as_deref() {
+                    Some(lang) if !lang.is_empty() => {
+                        Self::write_directive(output, "code-block", lang, &[], content);
+                    }
+                    _ => {
+                        output.push_str("::\n\n");
+                        output.push_str(&indent(content, "   ", "   "));
+                        output.push_str("\n\n");
+                    }
+                }
+            }
+            Node::OrderedList { start, items } => self.write_list(items, Some(*start), output)?,
+            Node::UnorderedList(items) => self.write_list(items, None, output)?,
+            Node::Table {
+                headers,
+                rows,
+                alignments: _,
+            } => self.write_table(headers, rows, "", output)?,
+            Node::ThematicBreak => {
+                output.push_str("----------\n\n");
+            }
+            node if node.is_custom_type::<FigureNode>() => {
+                let figure = node.as_custom_type::<FigureNode>().unwrap();
+                self.write_figure(figure, output)?;
+            }
+            node if node.is_custom_type::<ExternalFrameNode>() => {
+                let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+                let path = unix_slash(&frame.file_path);
+                Self::write_directive(output, "image", &path, &[("alt", &frame.alt_text)], "");
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let center = node.as_custom_type::<CenterNode>().unwrap();
+                match &center.node {
+                    Node::HtmlElement(element) => {
+                        self.write_paragraph(&element.children, output)?
+                    }
+                    node => self.write_node(node, output)?,
+                }
+            }
+            node if node.is_custom_type::<AlertNode>() => {
+                let alert = node.as_custom_type::<AlertNode>().unwrap();
+                let content = self.nested_blocks(&alert.content)?;
+                let class = alert.class.to_ascii_lowercase();
+                match class.as_str() {
+                    "note" | "tip" | "important" | "warning" | "caution" => {
+                        Self::write_directive(output, &class, "", &[], &content);
+                    }
+                    _ => Self::write_directive(output, "admonition", &alert.class, &[], &content),
+                }
+            }
+            node if node.is_custom_type::<MathNode>() => {
+                let math = node.as_custom_type::<MathNode>().unwrap();
+                if math.block {
+                    Self::write_directive(output, "math", "", &[], &math.tex);
+                } else {
+                    output.push_str(":math:`");
+                    output.push_str(&math.tex);
+                    output.push('`');
+                }
+            }
+            node if node.is_custom_type::<OutlineNode>() => {
+                let outline = node.as_custom_type::<OutlineNode>().unwrap();
+                let mut title = EcoString::new();
+                if let Some(Node::Heading { content, .. }) = &outline.title {
+                    self.write_inline_nodes(content, &mut title)?;
+                }
+                Self::write_directive(output, "contents", title.trim(), &[], "");
+            }
+            node if node.is_custom_type::<ReferenceNode>() => {
+                let reference = node.as_custom_type::<ReferenceNode>().unwrap();
+                self.write_node(&reference.to_link(), output)?;
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                // The escaped space separates the reference without a visible space
+                output.push_str(&format!("\\ [#fn-{}]_", footnote.number));
+            }
+            node if node.is_custom_type::<FootnotesNode>() => {
+                let footnotes = node.as_custom_type::<FootnotesNode>().unwrap();
+                for footnote in &footnotes.footnotes {
+                    let mut content = EcoString::new();
+                    self.write_inline_nodes(&footnote.content, &mut content)?;
+                    let marker = format!(".. [#fn-{}] ", footnote.number);
+                    output.push_str(&indent(content.trim(), &marker, "   "));
+                    output.push_str("\n\n");
+                }
+            }
+            node if node.is_custom_type::<CiteNode>() => {
+                let cite = node.as_custom_type::<CiteNode>().unwrap();
+                self.write_inline_nodes(&cite.content, output)?;
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                self.write_bibliography(bibliography, output)?;
+            }
+            node if node.is_custom_type::<HighlightNode>() => {
+                let highlight = node.as_custom_type::<HighlightNode>().unwrap();
+                self.write_inline_nodes(&highlight.content, output)?;
+            }
+            node if node.is_custom_type::<InlineNode>() => {
+                let inline = node.as_custom_type::<InlineNode>().unwrap();
+                self.write_inline_nodes(&inline.content, output)?;
+            }
+            node if node.is_custom_type::<VerbatimNode>() => {
+                let verbatim = node.as_custom_type::<VerbatimNode>().unwrap();
+                output.push_str(&verbatim.content);
+            }
+            Node::Text(text) => {
+                output.push_str(&escape_rst(text));
+            }
+            Node::Emphasis(content) => {
+                output.push('*');
+                self.write_inline_nodes(content, output)?;
+                output.push('*');
+            }
+            Node::Strong(content) => {
+                output.push_str("**");
+                self.write_inline_nodes(content, output)?;
+                output.push_str("**");
+            }
+            Node::Strikethrough(content) => {
+                self.write_inline_nodes(content, output)?;
+            }
+            Node::Link {
+                url,
+                title: _,
+                content,
+            } => {
+                let mut text = EcoString::new();
+                self.write_inline_nodes(content, &mut text)?;
+                // Links to labels are cross-references of Sphinx
+                match url.strip_prefix('#') {
+                    Some(target) => output.push_str(&format!(":ref:`{text} <{target}>`")),
+                    None => output.push_str(&format!("`{text} <{url}>`__")),
+                }
+            }
+            Node::Image { alt, .. } => {
+                // Images are written by directives, which cannot be inlined
+                self.write_inline_nodes(alt, output)?;
+            }
+            Node::InlineCode(code) => {
+                output.push_str("``");
+                output.push_str(code);
+                output.push_str("``");
+            }
+            Node::HardBreak | Node::SoftBreak => {
+                output.push(' ');
+            }
+            Node::HtmlElement(element) => {
+                self.write_inline_nodes(&element.children, output)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Write the anchors as the targets of the following element
+fn write_targets(anchors: &[&Node], output: &mut EcoString) {
+    for anchor in anchors {
+        if let Some(anchor) = anchor.as_custom_type::<AnchorNode>() {
+            output.push_str(".. _");
+            output.push_str(&anchor.id);
+            output.push_str(":\n\n");
+        }
+    }
+}
+
+/// Get the source and the alternative text of an image
+fn image_source(node: &Node) -> Option<(String, String)> {
+    match node {
+        Node::Image { url, alt, .. } => {
+            let alt = alt
+                .iter()
+                .filter_map(|node| match node {
+                    Node::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            Some((url.to_string(), alt))
+        }
+        Node::HtmlElement(HtmlElement {
+            tag, attributes, ..
+        }) if tag == "img" => {
+            let attr = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|attr| attr.name == name)
+                    .map(|attr| attr.value.to_string())
+            };
+            Some((attr("src")?, attr("alt").unwrap_or_default()))
+        }
+        node if node.is_custom_type::<ExternalFrameNode>() => {
+            let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+            Some((unix_slash(&frame.file_path), frame.alt_text.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Write an HTML element as its source
+fn html_source(node: &Node) -> Result<String> {
+    let mut writer = CommonMarkWriter::with_options(WriterOptions {
+        strict: false,
+        ..Default::default()
+    });
+    writer
+        .write(node)
+        .map_err(|err| format!("failed to write HTML: {err}"))?;
+    Ok(writer.into_string().trim().to_owned())
+}
+
+/// Prefix the first line and indent the other lines of the text
+fn indent(text: &str, first: &str, rest: &str) -> String {
+    if text.is_empty() {
+        return first.trim_end().to_owned();
+    }
+
+    let mut indented = String::new();
+    for (idx, line) in text.lines().enumerate() {
+        if idx > 0 {
+            indented.push('\n');
+        }
+        if idx == 0 {
+            indented.push_str(first);
+        } else if !line.is_empty() {
+            indented.push_str(rest);
+        }
+        indented.push_str(line);
+    }
+    indented.trim_end().to_owned()
+}
+
+/// Escape the inline markup characters of reStructuredText in a string
+fn escape_rst(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' | '*' | '`' | '|' => escaped.push('\\'),
+            // A trailing underscore makes a reference, e.g. `name_`
+            '_' if !chars.peek().is_some_and(|next| next.is_alphanumeric()) => escaped.push('\\'),
+            _ => {}
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+impl FormatWriter for RstWriter {
+    fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
+        self.write_node(document, output)?;
+        Ok(())
+    }
+
+    fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
+        let mut output = EcoString::new();
+        self.write_eco(document, &mut output)?;
+        Ok(output.as_str().as_bytes().to_vec())
+    }
+}
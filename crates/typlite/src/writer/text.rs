@@ -4,7 +4,10 @@ use cmark_writer::ast::Node;
 use ecow::EcoString;
 
 use crate::Result;
-use crate::common::{ExternalFrameNode, FigureNode, FormatWriter};
+use crate::common::{
+    BibliographyNode, CiteNode, ExternalFrameNode, FigureNode, FootnoteNode, FootnotesNode,
+    FormatWriter, OutlineNode, ReferenceNode,
+};
 
 /// Text writer implementation
 #[derive(Default)]
@@ -168,6 +171,58 @@ impl TextWriter {
                     }
                 }
             }
+            node if node.is_custom_type::<ReferenceNode>() => {
+                if let Some(reference) = node.as_custom_type::<ReferenceNode>() {
+                    for inline in &reference.content {
+                        Self::write_node(inline, output)?;
+                    }
+                }
+            }
+            node if node.is_custom_type::<OutlineNode>() => {
+                if let Some(outline) = node.as_custom_type::<OutlineNode>() {
+                    if let Some(title) = &outline.title {
+                        Self::write_node(title, output)?;
+                    }
+                    for entry in &outline.entries {
+                        Self::write_node(&entry.to_link(), output)?;
+                        output.push_str("\n");
+                    }
+                    output.push_str("\n");
+                }
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                if let Some(footnote) = node.as_custom_type::<FootnoteNode>() {
+                    output.push_str(&format!("[{}]", footnote.number));
+                }
+            }
+            node if node.is_custom_type::<FootnotesNode>() => {
+                if let Some(footnotes) = node.as_custom_type::<FootnotesNode>() {
+                    for footnote in &footnotes.footnotes {
+                        output.push_str(&format!("[{}] ", footnote.number));
+                        for child in &footnote.content {
+                            Self::write_node(child, output)?;
+                        }
+                        output.push_str("\n");
+                    }
+                }
+            }
+            node if node.is_custom_type::<CiteNode>() => {
+                if let Some(cite) = node.as_custom_type::<CiteNode>() {
+                    for child in &cite.content {
+                        Self::write_node(child, output)?;
+                    }
+                }
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                if let Some(bibliography) = node.as_custom_type::<BibliographyNode>() {
+                    if let Some(title) = &bibliography.title {
+                        Self::write_node(title, output)?;
+                    }
+                    for entry in &bibliography.entries {
+                        Self::write_node(&entry.to_paragraph(), output)?;
+                    }
+                }
+            }
             _ => {}
         }
         Ok(())
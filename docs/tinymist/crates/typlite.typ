@@ -47,6 +47,18 @@ Supported format:
 - `output.md`: Markdown
 - `output.tex`: LaTeX
 - `output.docx`: Word
+- `output.epub`: EPUB
+- `output.rst`: reStructuredText
+- `output.adoc`: AsciiDoc
+
+Markdown documents can be imported to typst, which converts CommonMark and GFM markups, e.g. tables and footnotes, to typst markups. LaTeX math is rendered by the `mitex` package.
+
+```shell
+# default output is README.typ
+typlite README.md
+# specify output
+typlite README.md output.typ
+```
 
 Todo: We may support custom format by typst scripting in future, like:
 
@@ -58,6 +70,10 @@ typlite main.typ --post-process @preview/typlite-mdx output.mdx
 = Feature
 
 - *Contexual Content Rendering*: Contents begin with `context` keyword will be rendered as svg output. The svg output will be embedded inline in the output file as *base64* by default, if the `--assets-path` parameter is not specified. Otherwise, the svg output will be saved in the specified folder and the path will be embedded in the output file.
+- *Cross References*: Labels become anchors, references become links to the labelled elements with the numbering shown in the document, e.g. "Figure 3" or "Section 2.1", and `outline` becomes a linked table of contents. They are converted natively by each format, i.e. HTML anchors in Markdown, `\label`, `\ref`, and `\tableofcontents` in LaTeX, and bookmarks and a table of contents field in Word.
+- *Footnotes and Citations*: Footnotes become GFM footnotes in Markdown, `\footnote` in LaTeX, and footnote parts in Word. Citations and the bibliography are rendered with the document's citation style, except for LaTeX, where they become `\cite` and `\bibliography` over the original `.bib` files, which are copied to the assets directory if it is specified.
+- *E-books*: The EPUB output is split into chapters at the top-level headings, with a navigation document built from the heading outline. Contextual contents are embedded as svg images. The `--theme` parameter specifies a stylesheet, or a directory containing a `style.css` and the fonts it uses, e.g. `typlite main.typ output.epub --theme theme/`.
+- *reStructuredText and AsciiDoc*: The outputs use the native markups of Sphinx and Asciidoctor, e.g. admonitions for alerts, `:ref:` and `<<id>>` for cross references, and the `math` directive and `latexmath` for equations, which are converted to TeX.
 
 = Typlite-Specific `sys.inputs`
 
@@ -48,6 +48,14 @@ The `tinymist compile` command is compatible with `typst compile`:
 tinymist compile path/to/main.typ
 ```
 
+To export one PNG or SVG file per page, use `{p}`, `{0p}`, or `{t}` in the output path, which are replaced by the page number, the zero-padded page number, and the page count. Combined with `--pages`, only the selected pages are exported:
+
+```bash
+tinymist compile --pages 2-4 path/to/main.typ 'page-{0p}-of-{t}.png'
+```
+
+Like `typst compile`, the command always writes all the outputs and leaves no other files in the project. The exports run by the language server are incremental instead. Each of them writes a manifest of its outputs to `target/tinymist/exports` under the project root, which records the hashes of the export task, the compiled document, and the outputs. An export is skipped if the document is unchanged, only the changed pages are rewritten when exporting one file per page, and the outputs of removed pages are deleted. Only the files matching the output path are ever deleted. Deleting the manifests forces a full export.
+
 To save the compilation command to the lock file:
 
 ```bash
@@ -62,6 +70,27 @@ tinymist compile --lockfile some/tinymist.lock path/to/main.typ
 
 The lock file feature is in development. It is to help the language server to understand the structure of your projects. See #github-link("/editors/vscode/Configuration.md#tinymistprojectresolution")[Configuration: tinymist.projectResolution].
 
+== Post-processing Exported Artifacts
+
+To run a Typst script over the exported artifact before it is written, use the `--transform-script` flag. The flag can be repeated, and the scripts run in order. They are also saved to the lock file as `script` transforms of the export task.
+
+```bash
+tinymist compile --transform-script 'document.labels.at("appendix", default: ())' path/to/main.typ
+```
+
+The script is evaluated in code mode with the following variables:
+- `artifact`: the exported artifact in bytes.
+- `format`: the extension of the exported artifact, e.g. `"pdf"` or `"json"`.
+- `document`: a dictionary containing `pages`, the number of pages, `labels`, the page numbers of the labelled elements keyed by label, and `metadata`, the values of the labelled `metadata` elements keyed by label.
+
+The script returns `none` to keep the artifact, `bytes` or `str` to replace it, or, for PDF, PNG, and SVG exports, an array of page numbers to export only these pages. For example, the following script injects the value of `#metadata(..) <version>` into the JSON output of a query task in the lock file:
+
+```typ
+let data = json(artifact)
+data.insert("version", document.metadata.at("version"))
+json.encode(data)
+```
+
 == Running Tests
 
 To run tests, you can use the `test` command, which is also compatible with `typst compile`:
@@ -78,6 +107,30 @@ The `test` command will defaultly run all the functions whose names are staring
 
 See #cross-link("/feature/testing.typ")[Docs: Testing Features] for more information.
 
+== Linting a Document
+
+To lint a document and all the files it depends on, use the `lint` command, which is also compatible with `typst compile`:
+
+```bash
+tinymist lint path/to/main.typ
+```
+
+The diagnostics can be printed as human-readable text (default), JSON or #link("https://sarifweb.azurewebsites.net/")[SARIF]:
+
+```bash
+tinymist lint --format sarif --output lint.sarif path/to/main.typ
+```
+
+The command exits with a nonzero code if any diagnostic reaches the severity given by `--fail-on`, which can be `hint`, `warning`, `error` (default) or `never`.
+
+With `--fix`, the machine-applicable fixes are written back to the files, for example, removing unused imports or creating missing files, and only the remaining diagnostics are reported:
+
+```bash
+tinymist lint --fix path/to/main.typ
+```
+
+See #cross-link("/feature/linting.typ")[Docs: Linting Features] for more information.
+
 == Generating shell completion script
 
 To generate a bash-compatible completion script:
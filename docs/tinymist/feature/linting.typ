@@ -22,4 +22,76 @@ If enabled, the linter will run on save or on type, depending on your configurat
   - (Default) `onSave` run linting when you save the file.
   - `onType` run linting as you type.
 
+= Linting in CLI
 
+The linter can also run without an editor, for example, in CI:
+
+```bash
+tinymist lint --fail-on warning path/to/main.typ
+```
+
+See #cross-link("/feature/cli.typ")[Docs: Command Line Interface] for more information.
+
+= Removing Unused Code
+
+The `unused-*` rules report bindings, parameters and import items that are never used. Names starting with an underscore, such as `_x`, are not reported. Each of the diagnostics comes with a quick fix that removes the unused code.
+
+= Fixing Problems Automatically
+
+Some diagnostics carry machine-applicable fixes, which are provided as quick fixes in the editor:
+
+- `type-compare`: compares with the type instead of its name, e.g. `type(x) == "string"` becomes `type(x) == str`.
+- `buggy-block`: moves an ineffective set rule out of its block, e.g. `if cond { set text(red) }` becomes `set text(red) if (cond)`.
+- `unused-*`: removes the unused code.
+- A missing file imported or included by a string path: creates the file.
+
+All the fixes of a document can be applied at once by the `source.fixAll.tinymist` code action. For example, to apply them on save in VS Code:
+
+```json
+{ "editor.codeActionsOnSave": { "source.fixAll.tinymist": "explicit" } }
+```
+
+They can also be applied to all the files in CLI by `tinymist lint --fix`. Removing an unused parameter or export is not applied in bulk, since it may break the callers of the function or the modules importing it, and is only provided as a quick fix.
+
+= Configuring Lint Rules
+
+Each check is a lint rule with a stable ID:
+
+- `bad-branch-stmt`: `break` or `continue` statements in a non-loop context.
+- `bad-return-stmt`: `return` statements in a non-function context.
+- `buggy-block`: set or show rules that don't take effect.
+- `type-compare`: comparisons between strings and types, which are deprecated.
+- `variable-font`: variable fonts, which are not supported by typst yet.
+- `implicit-discard`: values that are implicitly discarded by a function return.
+- `unused-binding`: local bindings that are never used.
+- `unused-param`: closure parameters that are never used.
+- `unused-import`: import items that are never used.
+- `unused-export`: `#let` exports of a module imported by other project files that are used neither by the module itself nor by other modules. The entries of documents, the entrypoints of packages and the modules that no project file imports are skipped, since their exports are used outside the project.
+
+The severity of a rule can be `off`, `hint`, `warning` or `error`. The `unused-*` rules default to `hint`, and the others default to `warning`. It can be configured in the `[lint.rules]` table of `tinymist.lock`:
+
+```toml
+[lint.rules]
+type-compare = "error"
+variable-font = "off"
+```
+
+It can also be configured by `tinymist.lint.rules` in the editor settings, which overrides the lock file:
+
+```json
+{ "tinymist.lint.rules": { "type-compare": "error" } }
+```
+
+== Suppressing Lint Rules
+
+A comment in the form of `// tinymist-lint: allow(rule-a, rule-b)` suppresses the listed rules in a scope determined by its position:
+
+- at the start of a file, the whole file;
+- at the start of a code or content block, the whole block;
+- at the end of a line, the statement on that line;
+- otherwise, the next statement.
+
+```typ
+// tinymist-lint: allow(variable-font)
+#set text(font: "Noto Sans VF")
+```
@@ -8,6 +8,12 @@ The testing feature is available since `tinymist` v0.13.10.
 
 You can run tests and check coverage in the IDE or CLI.
 
+In the editor, the language server shows a "Run test" code lens above each `test-*`, `panic-on-*` and `example-*` function, and an "Update reference" code lens above each `example-*` function. The prefixes follow the test configuration of the last compiled document, e.g., `#metadata((test_pattern: "check-")) <test-config>`. The examples are checked in the same way as the CLI, i.e., the references are stored at `refs/paged/<name>.png` and `refs/html/<name>.html`, and the images are compared with the default tolerance unless the example overrides it.
+
+Editors can build a test explorer on the following commands of the language server:
+- `tinymist.discoverTests` with the path of a document returns the test cases and test documents that the document depends on, with their names, kinds, URIs and ranges.
+- `tinymist.runTests` with the path of a document and the options `{ names, update }` runs the selected test items, or all of them if `names` is empty. The result of each test item is sent as a `tinymist/testResult` notification once it starts and once it finishes, carrying the status, the failure message and the diagnostics. It returns the numbers of the passed and failed test items.
+
 = Test Discovery <tinymist-test-discovery>
 
 Given a file, tinymist will try to discover tests related to the file.
@@ -20,8 +26,10 @@ Given a file, tinymist will try to discover tests related to the file.
     - If the label `<test-html-example>` can be found in the example file, html export will be called.
   - Top-level functions will be checked for tests.
     - If a function is named `test-*`, it is considered a test function and will be called directly.
-    - If a function is named `bench-*`, it is considered a benchmark function and will be called once to collect coverage.
+    - If a function is named `bench-*`, it is considered a benchmark function and will be timed after the other test cases. When collecting coverage, it will be called once instead.
     - If a function is named `panic-on-*`, it will only pass the test if a panic occurs during execution.
+      - If the function is preceded by doc comments like `/// should-panic: <pattern>`, the test only passes if the panic message contains every pattern.
+  - If a file is named `diag-*.typ`, it is considered a *diagnostic document* and will be compiled without failing the run on its errors. Instead, its diagnostics are checked against the annotations in it. See @tinymist-test-diagnostics.
 
 Example Entry File:
 ```typ
@@ -47,6 +55,27 @@ Running test(panic-on-panic)
    Info All test cases passed...
 ```
 
+= Checking Panic Messages and Diagnostics <tinymist-test-diagnostics>
+
+A `panic-on-*` test passes on any panic by default. To check that it panics for the expected reason, add `should-panic` doc comments before the function. Each pattern must be contained in the panic message:
+
+```typ
+/// should-panic: index out of bounds
+#let panic-on-out-of-bounds() = {
+  (1, 2, 3).at(5)
+}
+```
+
+To check the errors and warnings of a whole document, name it `diag-*.typ` and annotate the lines where diagnostics are expected. An annotation `//~ error: <pattern>` expects an error at the same line, and `//~^ warning: <pattern>` expects a warning at the line above, with one `^` per line. The test passes if every diagnostic matches an annotation with the same severity and line, whose pattern is contained in the message, and every annotation is matched:
+
+```typ
+#let x = unknown //~ error: unknown variable
+#set text(fill: 1)
+//~^ error: expected color
+```
+
+Since a diagnostic document usually fails to compile, it cannot be imported. Instead, make it a dependency of the entry file by reading it, e.g. `#read("diag-scope.typ")`. The prefix of the diagnostic documents can be changed by the `diagnostic_pattern` field of the test configuration, e.g. `#metadata((diagnostic_pattern: "expect-")) <test-config>`.
+
 = Benchmarking
 
 Since it requires some heavy framework to run benchmarks, a standalone tool is provided to run benchmarks.
@@ -88,6 +117,22 @@ h/r/u/c/q: help/run/update/quit
 
 For example, according to the help message, update the reference files using the command `u` (update).
 
+= Selecting Tests with CLI
+
+Use `--filter` to run only the test cases and examples whose names contain a substring, or match a glob pattern with `*` and `?`. Use `--kind` to run only the test cases of a kind, i.e., `test`, `panic`, `example`, `bench` or `diagnostic`. Both options can be specified multiple times.
+
+```bash
+tinymist test tests/main.typ --filter "test-layout-*" --kind test --kind panic
+```
+
+Use `--list` to print the selected test cases and examples without running them, `--fail-fast` to stop after the first failure, and `--jobs` to limit the number of threads running the test cases in parallel.
+
+```bash
+tinymist test tests/main.typ --filter heading --list
+test(test-heading) at /tests/main.typ
+example(example-heading) at /tests/example-heading.typ
+```
+
 = Collecting Coverage with CLI
 
 You can collect coverage using the `--coverage` option.
@@ -97,9 +142,17 @@ tinymist test tests/main.typ --coverage
 ...
    Info Written coverage to target/coverage.json ...
     Cov Coverage Summary 9/10 (90.00%)
+    Cov Branch Summary 3/4 (75.00%)
    Info All test cases passed...
 ```
 
+The branch summary counts the arms of the branching expressions taken by the tests:
+- the `if` and `else` arms of a conditional, including the implicit `else` of a conditional without one.
+- the body of a `for` or `while` loop being entered or skipped.
+- the alternatives of an `or` selector of a show rule, e.g., `show heading.or(figure): ..`, matching the shown element. The selector is counted only if all of its alternatives are element functions, optionally filtered by `where`.
+
+VS Cod(e,ium) shows the arms of the branches in the editor as well.
+
 Use `--print-coverage=full` to print the coverage of each file.
 
 ```bash
@@ -108,9 +161,37 @@ tinymist test tests/main.typ --coverage --print-coverage=full
     Cov  6     / 6     (100.00%)  tests/example-hello-world.typ
     Cov  3     / 4     ( 75.00%)  tests/main.typ
     Cov Coverage Summary 9/10 (90.00%)
+    Cov Branch Summary 3/4 (75.00%)
    Info All test cases passed...
 ```
 
+Use `--coverage-format` to write the coverage in a format of the standard coverage tools, which is also accepted by `tinymist cov` as `--format`:
+- `json` (default): the format consumed by VS Cod(e,ium), written to `target/coverage.json`.
+- `lcov`: an LCOV tracefile, written to `target/lcov.info`.
+- `cobertura`: a Cobertura XML report, written to `target/cobertura.xml`.
+
+The LCOV and Cobertura reports contain line and branch hits, with paths relative to the project root. Files in packages are left out.
+
+```bash
+tinymist test tests/main.typ --coverage --coverage-format=lcov
+...
+   Info Written coverage to target/lcov.info ...
+```
+
+= Benchmarking with CLI
+
+Benchmark functions are called `--bench-warmup` times (3 by default) to warm up, and then `--bench-samples` times (20 by default) to collect timings. The caches of the compiler are evicted before each call, so every call does the full work. The mean, median and standard deviation of the timings are printed.
+
+```bash
+tinymist test tests/main.typ
+...
+Running test(bench-layout)
+  Bench bench-layout: mean 1.52ms, median 1.49ms, stddev 80.12µs (20 samples)
+ Passed test(bench-layout)
+```
+
+The timings are saved as baselines to `target/bench-baselines.json` under the project root on the first run. On the later runs, a benchmark fails if its median time is slower than the baseline by more than `--bench-threshold` percent (10 by default). Use `--update` to accept the new timings as the baselines.
+
 = Debugging tests with CLI
 
 If any test fails, the CLI will return a non-zero exit code.
@@ -131,12 +212,24 @@ To get image files to diff you can use grep to find the image files to update:
 
 ```bash
 tinymist test tests/main.typ 2> >(grep Hint) > >(grep "compare image")
-   Hint example(example-hello-world): compare image at refs/png/example-hello-world.png
-   Hint example(example-other): compare image at refs/png/example-other.png
+   Hint example(example-hello-world): compare image at refs/paged/example-hello-world.png
+   Hint example(example-other): compare image at refs/paged/example-other.png
 ```
 
 You can use your favorite image `diff` tool to compare the images, e.g. `magick compare`.
 
+= Comparing Images with Tolerance
+
+If the hash of a rendered image differs from the reference hash and the reference image exists, the images are compared pixel by pixel. Two pixels are regarded as equal if the perceptual distance between their colors is at most `--pixel-tolerance` (0.1 by default, from 0 to 1), which absorbs the subtle changes of anti-aliasing. The example passes if the ratio of the differing pixels is at most `--diff-tolerance` (0 by default, from 0 to 1).
+
+An example can override the tolerance with a dictionary in metadata:
+
+```typ
+#metadata((pixel: 0.2, ratio: 0.01)) <test-image-tolerance>
+```
+
+On a mismatch, the rendered image is written to `refs/paged/*.tmp.png`, and the differing pixels are highlighted in red over the faded reference in `refs/paged/*.diff.png`. The dashboard shows the reference, rendered and diff images side by side, or only the rendered image if the reference image is absent.
+
 = Tips: Reproducible Rendering
 
 To ensure that the rendering is reproducible, you can ignore system fonts.
@@ -153,11 +246,11 @@ tinymist test tests/main.typ --font-paths /path/to/fonts
 
 = Continuous Integration
 
-`tinymist test` only compares hash files to check whether content is changed. Therefore, you can ignore rendered files and only keep the hash files to compare them on CI. Putting the following content in `.gitignore` will help you to ignore the files:
+`tinymist test` compares hash files to check whether content is changed, and falls back to the tolerance-based comparison only if the reference images exist. Therefore, you can ignore rendered files and only keep the hash files to compare them exactly on CI, or keep the png files as well to allow for rendering differences across platforms. Putting the following content in `.gitignore` will help you to ignore the files:
 
 ```exclude
 # png files
-refs/png/**/*.png
+refs/paged/**/*.png
 # html files
 refs/html/**/*.html
 # hash files
@@ -166,6 +259,16 @@ refs/html/**/*.html
 
 Install `tinymist` on CI and run `tinymist test` to check whether the content is changed.
 
+Use `--report` to write the test results in a format of the CI tools, which can be specified multiple times:
+- `junit=<path>`: a JUnit XML report, which is shown in the merge request UI of GitLab, for example.
+- `json=<path>`: a JSON report.
+
+Each test case and example is recorded with its kind, file, duration, status, failure messages and diagnostics.
+
+```bash
+tinymist test tests/main.typ --report junit=target/junit.xml --report json=target/tests.json
+```
+
 ```yaml
 - name: Install tinymist
   env:
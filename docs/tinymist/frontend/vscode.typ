@@ -159,7 +159,8 @@ To configure when PDFs are compiled:
   - `onSave` makes a PDF after saving the Typst file.
   - `onType` makes PDF files live, as you type.
   - `never` disables PDF compilation.
-  - `onDocumentHasTitle` makes a PDF when the document has a title and, as you save.
+  - `onDocumentHasTitle` (deprecated) makes a PDF when the document has a title and, as you save.
+  - `script` makes a PDF as you save if the Typst script in the "Export PDF Script" setting evaluates to `true`. For example, `document.title != none` only exports documents having a title, and `"final" in document.labels` only exports documents containing a `#metadata(none) <final>` marker. The script can also read `sys.inputs`.
 
 To configure where PDFs are saved:
 
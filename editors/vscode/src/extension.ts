@@ -567,11 +567,32 @@ async function commandRunCodeLens(...args: string[]): Promise<void> {
     case "more": {
       return codeLensMore();
     }
+    case "run-test":
+    case "update-test": {
+      return codeLensRunTest(args[1], args[0] === "update-test");
+    }
     default: {
       console.error("unknown code lens command", args[0]);
     }
   }
 
+  async function codeLensRunTest(name: string, update: boolean): Promise<void> {
+    const activeEditor = window.activeTextEditor;
+    if (activeEditor === undefined) {
+      return;
+    }
+
+    const fsPath = activeEditor.document.uri.fsPath;
+    const summary = await tinymist.runTests(fsPath, { names: [name], update });
+    if (summary.failed > 0) {
+      void vscode.window.showErrorMessage(`Test ${name} failed, see the output for details`);
+    } else if (summary.passed === 0) {
+      void vscode.window.showWarningMessage(`Test ${name} is not found`);
+    } else {
+      void vscode.window.showInformationMessage(`Test ${name} passed`);
+    }
+  }
+
   async function codeLensMore(): Promise<void> {
     const kBrowsing = "Browsing Preview Documents";
     const kPreviewIn = "Preview in ..";
@@ -137,7 +137,14 @@ export function testingCovActivate(context: IContext, testController: vscode.Tes
       for (const [k, v] of Object.entries(cov_json)) {
         details.set(
           vscode.Uri.file(k).fsPath,
-          v.map((x) => new vscode.StatementCoverage(x.executed, x.location, x.branches)),
+          v.map(
+            (x) =>
+              new vscode.StatementCoverage(
+                x.executed,
+                x.location,
+                x.branches?.map((b) => new vscode.BranchCoverage(b.executed, b.location, b.label)),
+              ),
+          ),
         );
       }
 
@@ -322,6 +322,11 @@ export class LanguageState {
     client.onNotification("tinymist/compileStatus", (params: TinymistStatus) => {
       wordCountItemProcess(params);
     });
+    client.onNotification("tinymist/testResult", (result: TestResult) => {
+      if (result.status === "failed") {
+        client.outputChannel.appendLine(`Failed ${result.kind}(${result.name}): ${result.message}`);
+      }
+    });
     if (extensionState.features.preview) {
       this.registerPreviewNotifications(client);
     }
@@ -358,6 +363,14 @@ export class LanguageState {
     return tinymist.executeCommand<SymbolInformation[]>("tinymist.getWorkspaceLabels", []);
   }
 
+  discoverTests(fsPath: string) {
+    return tinymist.executeCommand<TestItem[] | null>("tinymist.discoverTests", [fsPath]);
+  }
+
+  runTests(fsPath: string, opts: RunTestsOpts) {
+    return tinymist.executeCommand<TestSummary>("tinymist.runTests", [fsPath, opts]);
+  }
+
   showLog() {
     if (this.client) {
       this.client.outputChannel.show();
@@ -779,6 +792,35 @@ export interface SymbolInfo {
   children: SymbolInfo[];
 }
 
+export type TestItemKind = "test" | "panic" | "bench" | "example" | "diagnostic";
+
+export interface TestItem {
+  name: string;
+  kind: TestItemKind;
+  uri: string;
+  range: lc.Range;
+}
+
+export interface RunTestsOpts {
+  names?: string[];
+  update?: boolean;
+  ppi?: number;
+}
+
+export interface TestResult {
+  name: string;
+  kind: TestItemKind;
+  status: "running" | "passed" | "failed";
+  message?: string;
+  duration?: number;
+  diagnostics: Record<string, lc.Diagnostic[]>;
+}
+
+export interface TestSummary {
+  passed: number;
+  failed: number;
+}
+
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 function isCodeActionWithoutEditsAndCommands(value: any): boolean {
   const candidate: lc.CodeAction = value;
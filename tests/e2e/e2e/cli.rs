@@ -1,4 +1,6 @@
-use tinymist_std::path::PathClean;
+use std::path::Path;
+
+use tinymist_std::path::{unix_slash, PathClean};
 
 use crate::artifact::{cli, GIT_ROOT};
 
@@ -72,3 +74,248 @@ fn test_compile() {
     })
     .expect("test should succeed");
 }
+
+/// Runs `tinymist lint` on the lint fixture and reads the written output.
+fn lint_output(name: &str, format: &str) -> String {
+    const INPUT_REL: &str = "tests/workspaces/individuals/lint.typ";
+
+    let cwd = GIT_ROOT.clone();
+    let root = cwd.join("target/e2e/tinymist-cli");
+
+    let mut content = None;
+    tinymist_std::fs::paths::temp_dir_in(root, |tmp| {
+        let output = tmp.clean().join(name);
+        let status = cli()
+            .arg("lint")
+            .arg("--format")
+            .arg(format)
+            .arg("--output")
+            .arg(&output)
+            .arg(INPUT_REL)
+            .status()
+            .expect("lint should run");
+        assert!(status.success(), "lint should not fail on warnings");
+        content = Some(std::fs::read_to_string(&output).expect("lint output should exist"));
+
+        Ok(())
+    })
+    .expect("test should succeed");
+
+    content.unwrap()
+}
+
+#[test]
+fn test_lint_json() {
+    let json = lint_output("lint.json", "json");
+    let lints: serde_json::Value = serde_json::from_str(&json).expect("output should be JSON");
+    let lints = lints.as_array().expect("output should be an array");
+
+    let find = |rule: &str| {
+        lints
+            .iter()
+            .find(|lint| lint["rule"] == rule)
+            .unwrap_or_else(|| panic!("{rule} should be reported in {json}"))
+    };
+
+    let unused = find("unused-binding");
+    assert_eq!(unused["severity"], "hint");
+    assert!(unused["path"].as_str().unwrap().ends_with("lint.typ"));
+    assert_eq!(unused["range"]["start"]["line"], 2);
+    assert_eq!(unused["range"]["start"]["column"], 7);
+
+    let compare = find("type-compare");
+    assert_eq!(compare["severity"], "warning");
+    assert_eq!(compare["range"]["start"]["line"], 3);
+}
+
+#[test]
+fn test_lint_sarif() {
+    let sarif = lint_output("lint.sarif", "sarif");
+    let sarif: serde_json::Value = serde_json::from_str(&sarif).expect("output should be JSON");
+
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "tinymist");
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert!(rules.iter().any(|rule| rule["id"] == "unused-binding"));
+
+    let results = run["results"].as_array().unwrap();
+    let compare = results
+        .iter()
+        .find(|result| result["ruleId"] == "type-compare")
+        .expect("type-compare should be reported");
+    assert_eq!(compare["level"], "warning");
+    let location = &compare["locations"][0]["physicalLocation"];
+    let uri = location["artifactLocation"]["uri"].as_str().unwrap();
+    assert!(uri.ends_with("lint.typ"), "{uri}");
+    assert_eq!(location["region"]["startLine"], 3);
+
+    let unused = results
+        .iter()
+        .find(|result| result["ruleId"] == "unused-binding")
+        .expect("unused-binding should be reported");
+    assert_eq!(unused["level"], "note");
+}
+
+#[test]
+fn test_lint_human_output() {
+    let human = lint_output("lint.txt", "human");
+
+    assert!(human.contains("unused-binding"), "{human}");
+    assert!(human.contains("type-compare"), "{human}");
+    assert!(human.contains("hint: unused binding `x`"), "{human}");
+    assert!(human.contains("warning: "), "{human}");
+}
+
+#[test]
+fn test_lint_fix() {
+    let cwd = GIT_ROOT.clone();
+    let root = cwd.join("target/e2e/tinymist-cli");
+
+    tinymist_std::fs::paths::temp_dir_in(root, |tmp| {
+        let dir = tmp.clean();
+        let input = dir.join("lint-fix.typ");
+        let output = dir.join("lint-fix.json");
+        std::fs::write(
+            &input,
+            "#let f() = {\n  let x = 1\n  let s = \"integer\"\n  if type(1) == s { \"yes\" } else { \"no\" }\n}\n\n#f()\n",
+        )
+        .expect("should write input");
+
+        let status = cli()
+            .arg("lint")
+            .arg("--fix")
+            .arg("--format")
+            .arg("json")
+            .arg("--output")
+            .arg(&output)
+            .arg(&input)
+            .status()
+            .expect("lint should run");
+        assert!(status.success(), "lint should not fail on warnings");
+
+        let fixed = std::fs::read_to_string(&input).expect("input should exist");
+        assert!(!fixed.contains("let x"), "{fixed}");
+
+        // The remaining diagnostics are located in the fixed file.
+        let json = std::fs::read_to_string(&output).expect("lint output should exist");
+        let lints: serde_json::Value = serde_json::from_str(&json).expect("output should be JSON");
+        let lints = lints.as_array().expect("output should be an array");
+        assert!(lints.iter().all(|lint| lint["rule"] != "unused-binding"), "{json}");
+        let compare = lints
+            .iter()
+            .find(|lint| lint["rule"] == "type-compare")
+            .unwrap_or_else(|| panic!("type-compare should be reported in {json}"));
+        assert_eq!(compare["range"]["start"]["line"], 3);
+
+        Ok(())
+    })
+    .expect("test should succeed");
+}
+
+/// Copies the files in the directory recursively.
+fn copy_dir(src: &Path, dst: &Path) {
+    std::fs::create_dir_all(dst).expect("should create directory");
+    for entry in std::fs::read_dir(src).expect("should read directory") {
+        let entry = entry.expect("should read entry");
+        let path = entry.path();
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            std::fs::copy(&path, &target).expect("should copy file");
+        }
+    }
+}
+
+/// Runs `tinymist cov` on a copy of the coverage fixture, which imports a
+/// local package, and reads the report written under the project root.
+fn cov_output(format: &str, report: &str) -> String {
+    let cwd = GIT_ROOT.clone();
+    let root = cwd.join("target/e2e/tinymist-cli");
+
+    let mut content = None;
+    tinymist_std::fs::paths::temp_dir_in(root, |tmp| {
+        let project = tmp.clean();
+        copy_dir(&cwd.join("tests/workspaces/cov"), &project);
+
+        let status = cli()
+            .arg("cov")
+            .arg("--root")
+            .arg(&project)
+            .arg("--package-path")
+            .arg(project.join("packages"))
+            .arg("--format")
+            .arg(format)
+            .arg(project.join("main.typ"))
+            .status()
+            .expect("cov should run");
+        assert!(status.success(), "cov should succeed");
+
+        let report = project.join("target").join(report);
+        let report = std::fs::read_to_string(&report).expect("report should exist");
+        content = Some(report.replace(&unix_slash(&project), "[root]"));
+
+        Ok(())
+    })
+    .expect("test should succeed");
+
+    content.unwrap()
+}
+
+#[test]
+fn test_cov_lcov() {
+    let lcov = cov_output("lcov", "lcov.info");
+    insta::assert_snapshot!(lcov, @r"
+    TN:
+    SF:main.typ
+    BRDA:4,0,0,0
+    BRDA:4,0,1,1
+    BRF:2
+    BRH:1
+    DA:3,1
+    DA:4,0
+    DA:6,1
+    DA:8,1
+    DA:9,1
+    LF:5
+    LH:4
+    end_of_record
+    ");
+}
+
+#[test]
+fn test_cov_cobertura() {
+    let cobertura = cov_output("cobertura", "cobertura.xml");
+    let start = cobertura
+        .find("timestamp=\"")
+        .expect("timestamp should exist")
+        + 11;
+    let end = start + cobertura[start..].find('"').unwrap();
+    let cobertura = format!("{}[timestamp]{}", &cobertura[..start], &cobertura[end..]);
+    insta::assert_snapshot!(cobertura, @r#"
+    <?xml version="1.0" ?>
+    <!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+    <coverage line-rate="0.8000" branch-rate="0.5000" lines-covered="4" lines-valid="5" branches-covered="1" branches-valid="2" complexity="0" version="tinymist" timestamp="[timestamp]">
+      <sources>
+        <source>[root]</source>
+      </sources>
+      <packages>
+        <package name="" line-rate="0.8000" branch-rate="0.5000" complexity="0">
+          <classes>
+            <class name="main.typ" filename="main.typ" line-rate="0.8000" branch-rate="0.5000" complexity="0">
+              <methods/>
+              <lines>
+                <line number="3" hits="1" branch="false"/>
+                <line number="4" hits="0" branch="true" condition-coverage="50% (1/2)"/>
+                <line number="6" hits="1" branch="false"/>
+                <line number="8" hits="1" branch="false"/>
+                <line number="9" hits="1" branch="false"/>
+              </lines>
+            </class>
+          </classes>
+        </package>
+      </packages>
+    </coverage>
+    "#);
+}
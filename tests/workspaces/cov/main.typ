@@ -0,0 +1,11 @@
+#import "@preview/cov-util:0.1.0": double
+
+#let describe(x) = {
+  if x > 2 {
+    "many"
+  } else {
+    "few"
+  }
+}
+
+#describe(double(1))
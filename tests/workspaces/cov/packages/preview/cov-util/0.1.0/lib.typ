@@ -0,0 +1,3 @@
+#let double(x) = {
+  x * 2
+}
@@ -0,0 +1,6 @@
+#let f() = {
+  let x = 1
+  if type(1) == "integer" { "yes" } else { "no" }
+}
+
+#f()